@@ -1,44 +1,59 @@
 extern crate alloc;
 
+use alloc::collections::BTreeMap;
 use alloc::format;
 use alloc::string::{String, ToString};
-use alloc::vec::Vec;
 use noli::net::TcpStream;
 use noli::net::{lookup_host, SocketAddr};
+use saba_core::constants::MAX_RESPONSE_BODY_SIZE;
 use saba_core::error::Error;
-use saba_core::http::HttpResponse;
+use saba_core::http::{HttpRequest, HttpResponse, HttpTransport, ResponseReader};
+use saba_core::url::Url;
 
-pub struct HttpClient {}
+#[derive(Debug)]
+pub struct HttpClient {
+    max_body_size: usize,
+}
 
 impl HttpClient {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            max_body_size: MAX_RESPONSE_BODY_SIZE,
+        }
+    }
+
+    pub fn max_body_size(&self) -> usize {
+        self.max_body_size
     }
 
-    pub fn get(&self, host: String, port: u16, path: String) -> Result<HttpResponse, Error> {
+    // レスポンスのボディがこの大きさ(バイト)を超えたら、読むのをやめてエラーにする
+    pub fn set_max_body_size(&mut self, max_body_size: usize) {
+        self.max_body_size = max_body_size;
+    }
+
+    pub fn get(
+        &self,
+        host: String,
+        port: u16,
+        path: String,
+        headers: BTreeMap<String, String>,
+    ) -> Result<HttpResponse, Error> {
+        let url = format!("http://{}:{}/{}", host, port, path);
         let ips = match lookup_host(&host) {
             Ok(ips) => ips,
-            Err(e) => {
-                return Err(Error::Network(format!(
-                    "Failed to find IP addresses: {:#?}",
-                    e
-                )))
-            }
+            Err(_) => return Err(Error::DnsResolutionFailed(host)),
         };
 
         if ips.len() < 1 {
-            return Err(Error::Network("Failed to find IP addresses".to_string()));
+            return Err(Error::DnsResolutionFailed(host));
         }
 
         let socket_addr: SocketAddr = (ips[0], port).into();
 
+        // noliは接続に失敗した理由を返さないので、相手から応答がなかったものとして扱う
         let mut stream = match TcpStream::connect(socket_addr) {
             Ok(stream) => stream,
-            Err(_) => {
-                return Err(Error::Network(
-                    "Failed to connect to TCP stream".to_string(),
-                ))
-            }
+            Err(_) => return Err(Error::ConnectionTimeout(url)),
         };
 
         let mut request = String::from("GET /");
@@ -49,37 +64,68 @@ impl HttpClient {
         request.push('\n');
         request.push_str("Accept: text/html\n");
         request.push_str("Connection: close\n");
+        for (name, value) in headers {
+            request.push_str(&name);
+            request.push_str(": ");
+            request.push_str(&value);
+            request.push('\n');
+        }
         request.push('\n');
 
         let _bytes_written = match stream.write(request.as_bytes()) {
             Ok(bytes) => bytes,
             Err(_) => {
-                return Err(Error::Network(
-                    "Failed to send a request to TCP stream".to_string(),
-                ))
+                return Err(Error::Network {
+                    url,
+                    message: "Failed to send a request to TCP stream".to_string(),
+                })
             }
         };
 
-        let mut received = Vec::new();
+        let mut reader = ResponseReader::new(url.clone(), self.max_body_size);
         loop {
             let mut buf = [0u8; 4096];
             let bytes_read = match stream.read(&mut buf) {
                 Ok(bytes) => bytes,
                 Err(_) => {
-                    return Err(Error::Network(
-                        "Failed to receive a request from TCP stream".to_string(),
-                    ))
+                    return Err(Error::Network {
+                        url,
+                        message: "Failed to receive a request from TCP stream".to_string(),
+                    })
                 }
             };
             if bytes_read == 0 {
                 break;
             }
-            received.extend_from_slice(&buf[..bytes_read]);
+            reader.push(&buf[..bytes_read])?;
         }
 
-        match core::str::from_utf8(&received) {
-            Ok(response) => HttpResponse::new(response.to_string()),
-            Err(e) => Err(Error::Network(format!("Invalid received response: {}", e))),
+        reader.finish()
+    }
+}
+
+// URLを解析してリクエストを送る
+// リダイレクトはたどらずに返し、移動先へのリクエストは呼び出し側でヘッダーを作り直して送る
+impl HttpTransport for HttpClient {
+    fn request(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+        // TLSには対応していないので、https://のURLには接続できない
+        if request.url().starts_with("https://") {
+            return Err(Error::TlsError(request.url()));
         }
+        let parsed_url = match Url::new(request.url()).parse() {
+            Ok(url) => url,
+            Err(_) => return Err(Error::InvalidUrl(request.url())),
+        };
+        let port = match parsed_url.port().parse::<u16>() {
+            Ok(port) => port,
+            Err(_) => return Err(Error::InvalidUrl(request.url())),
+        };
+
+        self.get(
+            parsed_url.host(),
+            port,
+            parsed_url.path(),
+            request.headers(),
+        )
     }
 }
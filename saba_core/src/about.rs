@@ -0,0 +1,99 @@
+// ネットワークを使わずに表示する組み込みのページ
+
+use crate::error::Error;
+use alloc::format;
+use alloc::string::{String, ToString};
+
+// ホームURLを設定しないときに起動時に開くページ
+pub static DEFAULT_HOME_URL: &str = "about:home";
+
+static HOME_PAGE_HTML: &str = "<html><head></head><body><h1>saba</h1><p>Enter a URL in the address bar to start browsing.</p></body></html>";
+
+static SABA_PAGE_HTML: &str = "<html><head></head><body><h1>About saba</h1><p>saba is a small web browser written in Rust.</p></body></html>";
+
+static BLANK_PAGE_HTML: &str = "<html><head></head><body></body></html>";
+
+// urlが組み込みのページを指すとき、そのHTMLを返す
+pub fn about_page(url: &str) -> Option<&'static str> {
+    match url {
+        "about:home" => Some(HOME_PAGE_HTML),
+        "about:saba" => Some(SABA_PAGE_HTML),
+        "about:blank" => Some(BLANK_PAGE_HTML),
+        _ => None,
+    }
+}
+
+// ページを読み込めなかったときに代わりに表示するページ
+pub fn error_page(error: &Error) -> String {
+    let (title, hint) = match error {
+        Error::DnsResolutionFailed(_) => (
+            "Server not found".to_string(),
+            "Check the address for typos.",
+        ),
+        Error::ConnectionTimeout(_) => (
+            "Connection timed out".to_string(),
+            "The server took too long to respond. Try again later.",
+        ),
+        Error::TlsError(_) => (
+            "Secure connection failed".to_string(),
+            "saba cannot open https:// pages yet.",
+        ),
+        Error::HttpError { status, .. } => (
+            format!("Error {}", status),
+            "The server could not return the page.",
+        ),
+        Error::InvalidUrl(_) => (
+            "Invalid address".to_string(),
+            "Enter an address that starts with http://.",
+        ),
+        _ => ("Failed to load the page".to_string(), "Try again later."),
+    };
+
+    // 文字参照はまだ解釈できないので、タグとして読まれないよう'<'を取り除く
+    let message = error.to_string().replace('<', "");
+    format!(
+        "<html><head><title>{}</title></head><body><h1>{}</h1><p>{}</p><p>{}</p></body></html>",
+        title, title, message, hint
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_about_page() {
+        assert!(about_page(DEFAULT_HOME_URL).is_some());
+        assert_eq!(Some(SABA_PAGE_HTML), about_page("about:saba"));
+        assert_eq!(Some(BLANK_PAGE_HTML), about_page("about:blank"));
+        assert_eq!(None, about_page("about:unknown"));
+        assert_eq!(None, about_page("http://example.com"));
+    }
+    #[test]
+    fn test_error_page() {
+        let page = error_page(&Error::DnsResolutionFailed("example.invalid".to_string()));
+        assert!(page.contains("<h1>Server not found</h1>"));
+        assert!(page.contains("could not resolve host example.invalid"));
+
+        let page = error_page(&Error::HttpError {
+            status: 503,
+            url: "http://example.com".to_string(),
+        });
+        assert!(page.contains("<h1>Error 503</h1>"));
+
+        assert!(
+            error_page(&Error::ConnectionTimeout("http://example.com".to_string()))
+                .contains("<h1>Connection timed out</h1>")
+        );
+        assert!(
+            error_page(&Error::TlsError("https://example.com".to_string()))
+                .contains("<h1>Secure connection failed</h1>")
+        );
+        assert!(error_page(&Error::Other("broken".to_string())).contains("<p>broken</p>"));
+
+        // URLに含まれる'<'はタグにしない
+        let page = error_page(&Error::InvalidUrl("http://<script>".to_string()));
+        assert!(page.contains("<h1>Invalid address</h1>"));
+        assert!(!page.contains("<script>"));
+    }
+}
@@ -1,12 +1,63 @@
+use crate::about::{about_page, error_page, DEFAULT_HOME_URL};
+use crate::clock::{Clock, NoClock};
+use crate::constants::{MAX_REDIRECTS, MAX_ZOOM, MIN_ZOOM, ZOOM_STEP};
+use crate::cookie::CookieJar;
+use crate::data_url::DataUrl;
+use crate::error::Error;
+use crate::file::{decode_text, file_path, FileReader, NoFileReader};
+use crate::hsts::HstsStore;
+use crate::http::{HttpRequest, HttpResponse, HttpTransport};
 use crate::renderer::page::Page;
+use crate::url::{host_of, strip_fragment, Url};
 use alloc::rc::Rc;
+use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use core::cell::RefCell;
 
+// ダークモードで既定のスタイルシートの前に加えるルール。作者のスタイルより優先度が低い
+// リンクの既定の青は暗い背景では読みにくいので、明るい色にする
+static DARK_MODE_STYLESHEET: &str =
+    "body { background-color: black; color: lightgray; } a { color: aqua; }";
+
+// リンクをクリックしたマウスのボタン
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseButton {
+    Left,
+    Middle,
+}
+
+// リンクのクリックで行うこと。URLは表示中のページを基準に解決済み
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkAction {
+    // 表示中のタブでページを移動する
+    Navigate(String),
+    // 新しいタブに読み込み、表示中のタブはそのままにする
+    OpenInBackgroundTab(String),
+}
+
 #[derive(Debug, Clone)]
 pub struct Browser {
     active_page_index: usize,
     pages: Vec<Rc<RefCell<Page>>>,
+    current_url: Option<String>,
+    transport: Option<Rc<dyn HttpTransport>>,
+    // file: URLの読み込みに使う
+    file_reader: Rc<dyn FileReader>,
+    // ページのスクリプトに渡す時計
+    clock: Rc<dyn Clock>,
+    // ページに適用する既定のスタイルシート。ページのスタイルより優先度が低い
+    default_stylesheet: String,
+    dark_mode: bool,
+    // falseのときは、ページのスクリプトを実行しない
+    js_enabled: bool,
+    // 文字の大きさの倍率(%)
+    zoom: i64,
+    hsts_store: HstsStore,
+    cookie_jar: CookieJar,
+    // HSTSの有効期限の判定に使う現在時刻(秒)
+    current_time: u64,
+    // 起動時に開くページのURL
+    home_url: String,
 }
 
 impl Browser {
@@ -16,6 +67,18 @@ impl Browser {
         let browser = Rc::new(RefCell::new(Self {
             active_page_index: 0,
             pages: Vec::new(),
+            current_url: None,
+            transport: None,
+            file_reader: Rc::new(NoFileReader),
+            clock: Rc::new(NoClock),
+            default_stylesheet: String::new(),
+            dark_mode: false,
+            js_enabled: true,
+            zoom: 100,
+            hsts_store: HstsStore::new(),
+            cookie_jar: CookieJar::new(),
+            current_time: 0,
+            home_url: DEFAULT_HOME_URL.to_string(),
         }));
 
         page.set_browser(Rc::downgrade(&browser));
@@ -27,4 +90,1144 @@ impl Browser {
     pub fn current_page(&self) -> Rc<RefCell<Page>> {
         self.pages[self.active_page_index].clone()
     }
+
+    pub fn tab_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    pub fn tab(&self, index: usize) -> Option<Rc<RefCell<Page>>> {
+        self.pages.get(index).cloned()
+    }
+
+    pub fn active_tab_index(&self) -> usize {
+        self.active_page_index
+    }
+
+    pub fn set_current_url(&mut self, url: String) {
+        self.current_url = Some(url);
+    }
+
+    pub fn current_url(&self) -> Option<String> {
+        self.current_url.clone()
+    }
+
+    // ナビゲーションとサブリソースの取得に使う通信層を設定する
+    pub fn set_transport(&mut self, transport: Rc<dyn HttpTransport>) {
+        self.transport = Some(transport);
+    }
+
+    // file: URLの読み込みに使うファイルシステムを設定する
+    pub fn set_file_reader(&mut self, file_reader: Rc<dyn FileReader>) {
+        self.file_reader = file_reader;
+    }
+
+    // ページのスクリプトのDate.now()に使う時計を設定する
+    pub fn set_clock(&mut self, clock: Rc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    // 要素ごとの既定の表示はレイアウトが持っているので、最初は空
+    // ここに書いたルールはそれより後、ページのスタイルより前に適用される
+    pub fn default_stylesheet(&self) -> String {
+        self.default_stylesheet.clone()
+    }
+
+    // 次に読み込むページから使われる
+    pub fn set_default_stylesheet(&mut self, css: String) {
+        self.default_stylesheet = css;
+    }
+
+    pub fn append_default_stylesheet(&mut self, css: &str) {
+        self.default_stylesheet.push_str(css);
+    }
+
+    pub fn js_enabled(&self) -> bool {
+        self.js_enabled
+    }
+
+    // 次に読み込むページから使われる。表示中のページはそのまま
+    pub fn set_js_enabled(&mut self, js_enabled: bool) {
+        self.js_enabled = js_enabled;
+    }
+
+    pub fn dark_mode(&self) -> bool {
+        self.dark_mode
+    }
+
+    // 開いているタブはすぐに描画し直す
+    pub fn set_dark_mode(&mut self, dark_mode: bool) {
+        if self.dark_mode == dark_mode {
+            return;
+        }
+        self.dark_mode = dark_mode;
+
+        let css = self.effective_default_stylesheet();
+        for page in &self.pages {
+            page.borrow_mut()
+                .restyle_with_default_stylesheet(css.clone());
+        }
+    }
+
+    // ダークモードのルールを先に置き、利用者が設定したルールで上書きできるようにする
+    fn effective_default_stylesheet(&self) -> String {
+        if self.dark_mode {
+            DARK_MODE_STYLESHEET.to_string() + &self.default_stylesheet
+        } else {
+            self.default_stylesheet.clone()
+        }
+    }
+
+    pub fn zoom(&self) -> i64 {
+        self.zoom
+    }
+
+    // 倍率はMIN_ZOOMからMAX_ZOOMの範囲に収める。開いているタブはすぐに描画し直す
+    pub fn set_zoom(&mut self, zoom: i64) {
+        self.zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        for page in &self.pages {
+            page.borrow_mut().set_zoom(self.zoom);
+        }
+    }
+
+    pub fn zoom_in(&mut self) {
+        self.set_zoom(self.zoom + ZOOM_STEP);
+    }
+
+    pub fn zoom_out(&mut self) {
+        self.set_zoom(self.zoom - ZOOM_STEP);
+    }
+
+    pub fn set_current_time(&mut self, current_time: u64) {
+        self.current_time = current_time;
+    }
+
+    pub fn set_home_url(&mut self, url: String) {
+        self.home_url = url;
+    }
+
+    pub fn home_url(&self) -> String {
+        self.home_url.clone()
+    }
+
+    // ホームURLのページを現在のページに読み込む
+    pub fn open_home_page(&mut self) -> Result<(), Error> {
+        self.navigate(self.home_url.clone())
+    }
+
+    pub fn hsts_store(&self) -> &HstsStore {
+        &self.hsts_store
+    }
+
+    pub fn cookie_jar(&self) -> &CookieJar {
+        &self.cookie_jar
+    }
+
+    // 設定された通信層でurlを取得し、現在のページに読み込む
+    pub fn navigate(&mut self, url: String) -> Result<(), Error> {
+        // 同じ文書内のフラグメントへの移動では、読み込み直さず、スクロール位置もそのままにする
+        if !is_local_url(&url) && url.contains('#') {
+            if let Some(current_url) = &self.current_url {
+                if strip_fragment(current_url) == strip_fragment(&url) {
+                    self.set_current_url(url);
+                    return Ok(());
+                }
+            }
+        }
+
+        let page = self.current_page();
+        let url = self.load(&page, url)?;
+        self.set_current_url(url);
+        Ok(())
+    }
+
+    // urlを読み込めなかったとき、その理由を説明するページを表示中のタブに出す
+    // アドレスバーには読み込もうとしたURLを残す
+    pub fn show_error_page(&mut self, url: String, error: &Error) {
+        let response = HttpResponse::builder()
+            .header("Content-Type", "text/html")
+            .body(error_page(error))
+            .build();
+        let page = self.current_page();
+        page.borrow_mut().set_url(url.clone());
+        page.borrow_mut().receive_response(response);
+        page.borrow_mut().set_scroll_offset(0);
+        self.set_current_url(url);
+    }
+
+    // 新しいタブにurlを読み込む。表示中のタブは切り替えない
+    // 読み込みに失敗したときはタブを追加しない
+    pub fn open_in_background_tab(&mut self, url: String) -> Result<(), Error> {
+        let mut page = Page::new();
+        page.set_browser(self.current_page().borrow().browser());
+        let page = Rc::new(RefCell::new(page));
+
+        self.load(&page, url)?;
+        self.pages.push(page);
+        Ok(())
+    }
+
+    // hrefを表示中のページのURLで解決する。解決できないときはそのまま返す
+    pub fn resolve_link(&self, href: &str) -> String {
+        match self
+            .current_url
+            .as_ref()
+            .map(|u| Url::new(u.clone()).parse())
+        {
+            Some(Ok(page_url)) => page_url.resolve(href),
+            _ => href.to_string(),
+        }
+    }
+
+    // hrefを解決し、クリックしたボタンに応じた動作を返す
+    // 左クリックは表示中のタブで移動し、中クリックは背景のタブに開く
+    pub fn link_action(&self, href: &str, button: MouseButton) -> LinkAction {
+        let url = self.resolve_link(href);
+
+        match button {
+            MouseButton::Left => LinkAction::Navigate(url),
+            MouseButton::Middle => LinkAction::OpenInBackgroundTab(url),
+        }
+    }
+
+    // urlを取得してpageに読み込み、実際に読み込んだURLを返す
+    // HSTSが有効なホストへはhttps://に書き換えてからリクエストを送る
+    // リダイレクトでは、CookieとRefererを移動先のURLに合わせて作り直してから送り直す
+    fn load(&mut self, page: &Rc<RefCell<Page>>, url: String) -> Result<String, Error> {
+        page.borrow_mut().set_clock(self.clock.clone());
+        page.borrow_mut().set_js_enabled(self.js_enabled);
+        page.borrow_mut().set_zoom(self.zoom);
+        page.borrow_mut()
+            .set_default_stylesheet(self.effective_default_stylesheet());
+
+        // 組み込みのページ、data: URL、file: URLは通信層を使わずに読み込む
+        if let Some(html) = local_document(&url, self.file_reader.as_ref())? {
+            let response = HttpResponse::builder()
+                .header("Content-Type", "text/html")
+                .body(html)
+                .build();
+            page.borrow_mut().set_url(url.clone());
+            page.borrow_mut().receive_response(response);
+            page.borrow_mut().set_scroll_offset(0);
+            return Ok(url);
+        }
+
+        let transport = match &self.transport {
+            Some(transport) => transport.clone(),
+            None => {
+                return Err(Error::Network {
+                    url,
+                    message: "no http transport is set".to_string(),
+                })
+            }
+        };
+
+        let mut url = self.hsts_store.upgrade(&url, self.current_time);
+        let mut redirects = 0;
+        let response = loop {
+            // フラグメントはサーバーに送らない
+            let response =
+                transport.request(self.create_request(strip_fragment(&url).to_string()))?;
+            // http://で受け取ったヘッダーは改ざんされている可能性があるので無視する
+            if url.starts_with("https://") {
+                if let Ok(header) = response.header_value("Strict-Transport-Security") {
+                    self.hsts_store
+                        .insert(&host_of(&url), &header, self.current_time);
+                }
+            }
+            // リダイレクトのレスポンスのCookieも、それを返したURLのものとして保存する
+            for header in response.header_values("Set-Cookie") {
+                self.cookie_jar.set_cookie(&url, &header, self.current_time);
+            }
+
+            match response.redirect_location(&url) {
+                Some(_) if redirects >= MAX_REDIRECTS => {
+                    return Err(Error::Network {
+                        url,
+                        message: "too many redirects".to_string(),
+                    })
+                }
+                Some(location) => {
+                    redirects += 1;
+                    url = self.hsts_store.upgrade(&location, self.current_time);
+                }
+                None => break response,
+            }
+        };
+        let response = response.error_for_status(&url)?;
+
+        page.borrow_mut().set_url(url.clone());
+        page.borrow_mut().set_transport(transport);
+        page.borrow_mut().receive_response(response);
+        // 新しいページは先頭から表示する
+        page.borrow_mut().set_scroll_offset(0);
+        Ok(url)
+    }
+
+    // 表示中のページがあれば、そのURLをRefererとして送る
+    // 保存されているCookieのうち、urlに一致するものも送る
+    pub fn create_request(&self, url: String) -> HttpRequest {
+        let cookie = self.cookie_jar.cookie_header(&url, self.current_time);
+        let mut builder = HttpRequest::builder(url);
+        if let Some(referrer) = self.current_url.as_deref().and_then(referrer) {
+            builder = builder.header("Referer", referrer);
+        }
+        if let Some(cookie) = cookie {
+            builder = builder.header("Cookie", &cookie);
+        }
+        builder.build()
+    }
+}
+
+// 通信層を使わずに読み込むURL
+fn is_local_url(url: &str) -> bool {
+    about_page(url).is_some() || url.starts_with("data:") || url.starts_with("file:")
+}
+
+// urlのページから送るRefererの値。フラグメントは取り除く
+// 組み込みのページやdata: URL、file: URLからはRefererを送らない
+pub fn referrer(url: &str) -> Option<&str> {
+    if is_local_url(url) {
+        return None;
+    }
+    Some(strip_fragment(url))
+}
+
+// 組み込みのページ、data: URL、file: URLのHTMLを返す。それ以外のURLにはNoneを返す
+fn local_document(url: &str, file_reader: &dyn FileReader) -> Result<Option<String>, Error> {
+    if let Some(html) = about_page(url) {
+        return Ok(Some(html.to_string()));
+    }
+    if url.starts_with("file:") {
+        let bytes = file_reader.read(&file_path(url)?)?;
+        return Ok(Some(decode_text(&bytes)));
+    }
+    if !url.starts_with("data:") {
+        return Ok(None);
+    }
+
+    let data_url = DataUrl::parse(url)?;
+    if data_url.media_type() != "text/html" {
+        return Err(Error::UnexpectedInput {
+            input: data_url.media_type(),
+            message: "data: URL media type is not supported".to_string(),
+        });
+    }
+    Ok(Some(data_url.body()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::CHAR_HEIGHT_WITH_PADDING;
+    use crate::display_item::DisplayItem;
+    use crate::http::HttpResponse;
+    use crate::test_support::{body_response, StubTransport};
+    use alloc::format;
+
+    fn mock_transport() -> Rc<StubTransport> {
+        let secure = HttpResponse::builder()
+            .header("Strict-Transport-Security", "max-age=100")
+            .body("<html><head></head><body><p>secure</p></body></html>".to_string())
+            .build();
+        Rc::new(StubTransport::new(
+            [
+                (
+                    "http://example.com/a",
+                    body_response("<html><head></head><body><p>page a</p></body></html>"),
+                ),
+                (
+                    "http://example.com/b",
+                    body_response("<html><head></head><body><p>page b</p></body></html>"),
+                ),
+                (
+                    "http://example.com/login",
+                    HttpResponse::builder()
+                        .header("Set-Cookie", "session=abc; Path=/")
+                        .body("<html><head></head><body><p>login</p></body></html>".to_string())
+                        .build(),
+                ),
+                (
+                    "http://example.com/gone",
+                    HttpResponse::builder().status(404).build(),
+                ),
+                (
+                    "http://other.com/",
+                    body_response("<html><head></head><body></body></html>"),
+                ),
+                ("http://secure.com/", secure.clone()),
+                ("https://secure.com/", secure),
+            ]
+            .to_vec(),
+        ))
+    }
+
+    fn texts(browser: &Rc<RefCell<Browser>>) -> Vec<String> {
+        browser
+            .borrow()
+            .current_page()
+            .borrow()
+            .display_items()
+            .iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text { text, .. } => Some(text.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_no_referer_on_first_navigation() {
+        let browser = Browser::new();
+        let request = browser
+            .borrow()
+            .create_request("http://example.com/a".to_string());
+        assert_eq!(None, request.header_value("Referer"));
+    }
+
+    #[test]
+    fn test_referer() {
+        let browser = Browser::new();
+        browser
+            .borrow_mut()
+            .set_current_url("http://example.com/a".to_string());
+        let request = browser
+            .borrow()
+            .create_request("http://example.com/b".to_string());
+        assert_eq!(
+            Some("http://example.com/a".to_string()),
+            request.header_value("Referer")
+        );
+    }
+
+    #[test]
+    fn test_navigate_with_mock_transport() {
+        let transport = mock_transport();
+        let browser = Browser::new();
+        browser.borrow_mut().set_transport(transport.clone());
+
+        assert!(browser
+            .borrow_mut()
+            .navigate("http://example.com/a".to_string())
+            .is_ok());
+        assert_eq!(["page a".to_string()].to_vec(), texts(&browser));
+
+        assert!(browser
+            .borrow_mut()
+            .navigate("http://example.com/b".to_string())
+            .is_ok());
+        assert_eq!(["page b".to_string()].to_vec(), texts(&browser));
+        assert_eq!(
+            Some("http://example.com/b".to_string()),
+            browser.borrow().current_url()
+        );
+
+        let requests = transport.requests();
+        assert_eq!(2, requests.len());
+        assert_eq!(None, requests[0].header_value("Referer"));
+        assert_eq!(
+            Some("http://example.com/a".to_string()),
+            requests[1].header_value("Referer")
+        );
+    }
+
+    #[test]
+    fn test_link_action_depends_on_button() {
+        let transport = mock_transport();
+        let browser = Browser::new();
+        browser.borrow_mut().set_transport(transport);
+        assert!(browser
+            .borrow_mut()
+            .navigate("http://example.com/a".to_string())
+            .is_ok());
+
+        assert_eq!(
+            LinkAction::Navigate("http://example.com:80/b".to_string()),
+            browser.borrow().link_action("b", MouseButton::Left)
+        );
+        assert_eq!(
+            LinkAction::OpenInBackgroundTab("http://example.com:80/b".to_string()),
+            browser.borrow().link_action("b", MouseButton::Middle)
+        );
+    }
+
+    #[test]
+    fn test_open_in_background_tab() {
+        let transport = mock_transport();
+        let browser = Browser::new();
+        browser.borrow_mut().set_transport(transport.clone());
+        assert!(browser
+            .borrow_mut()
+            .navigate("http://example.com/a".to_string())
+            .is_ok());
+
+        assert!(browser
+            .borrow_mut()
+            .open_in_background_tab("http://example.com/b".to_string())
+            .is_ok());
+
+        // 表示中のタブはそのまま
+        assert_eq!(2, browser.borrow().tab_count());
+        assert_eq!(0, browser.borrow().active_tab_index());
+        assert_eq!(["page a".to_string()].to_vec(), texts(&browser));
+        assert_eq!(
+            Some("http://example.com/a".to_string()),
+            browser.borrow().current_url()
+        );
+
+        let tab = browser.borrow().tab(1).expect("new tab should exist");
+        assert_eq!(Some("http://example.com/b".to_string()), tab.borrow().url());
+        assert_eq!(
+            Some("http://example.com/a".to_string()),
+            transport.requests()[1].header_value("Referer")
+        );
+    }
+
+    #[test]
+    fn test_failed_background_tab_is_not_added() {
+        let transport = mock_transport();
+        let browser = Browser::new();
+        browser.borrow_mut().set_transport(transport);
+
+        assert!(browser
+            .borrow_mut()
+            .open_in_background_tab("http://example.com/missing".to_string())
+            .is_err());
+        assert_eq!(1, browser.borrow().tab_count());
+    }
+
+    #[test]
+    fn test_navigation_resets_scroll_offset() {
+        let transport = mock_transport();
+        let browser = Browser::new();
+        browser.borrow_mut().set_transport(transport.clone());
+
+        assert!(browser
+            .borrow_mut()
+            .navigate("http://example.com/a".to_string())
+            .is_ok());
+        let page = browser.borrow().current_page();
+        page.borrow_mut().set_scroll_offset(120);
+
+        // 同じ文書内のフラグメントへの移動では、スクロール位置を変えない
+        assert!(browser
+            .borrow_mut()
+            .navigate("http://example.com/a#section".to_string())
+            .is_ok());
+        assert_eq!(120, page.borrow().scroll_offset());
+        assert_eq!(1, transport.requests().len());
+
+        assert!(browser
+            .borrow_mut()
+            .navigate("http://example.com/b#section".to_string())
+            .is_ok());
+        assert_eq!(0, page.borrow().scroll_offset());
+        assert_eq!(["page b".to_string()].to_vec(), texts(&browser));
+        assert_eq!("http://example.com/b", transport.requests()[1].url());
+        assert_eq!(
+            Some("http://example.com/b#section".to_string()),
+            browser.borrow().current_url()
+        );
+    }
+
+    #[test]
+    fn test_open_default_home_page() {
+        let browser = Browser::new();
+        assert!(browser.borrow_mut().open_home_page().is_ok());
+        assert_eq!(
+            [
+                "saba".to_string(),
+                "Enter a URL in the address bar to start browsing.".to_string()
+            ]
+            .to_vec(),
+            texts(&browser)
+        );
+        assert_eq!(
+            Some(DEFAULT_HOME_URL.to_string()),
+            browser.borrow().current_url()
+        );
+
+        // 組み込みのページからの遷移ではRefererを送らない
+        let request = browser
+            .borrow()
+            .create_request("http://example.com/a".to_string());
+        assert_eq!(None, request.header_value("Referer"));
+    }
+
+    #[test]
+    fn test_open_configured_home_page() {
+        let transport = mock_transport();
+        let browser = Browser::new();
+        browser.borrow_mut().set_transport(transport.clone());
+        browser
+            .borrow_mut()
+            .set_home_url("http://example.com/a".to_string());
+
+        assert!(browser.borrow_mut().open_home_page().is_ok());
+        assert_eq!(["page a".to_string()].to_vec(), texts(&browser));
+        assert_eq!(1, transport.requests().len());
+    }
+
+    #[test]
+    fn test_navigate_to_about_pages_without_transport() {
+        let transport = mock_transport();
+        let browser = Browser::new();
+        browser.borrow_mut().set_transport(transport.clone());
+
+        assert!(browser
+            .borrow_mut()
+            .navigate("about:blank".to_string())
+            .is_ok());
+        // 空のbodyの背景だけが描画される
+        assert!(texts(&browser).is_empty());
+        assert_eq!(
+            Some("about:blank".to_string()),
+            browser.borrow().current_url()
+        );
+
+        assert!(browser
+            .borrow_mut()
+            .navigate("about:saba".to_string())
+            .is_ok());
+        assert_eq!(
+            [
+                "About saba".to_string(),
+                "saba is a small web browser written in Rust.".to_string()
+            ]
+            .to_vec(),
+            texts(&browser)
+        );
+
+        assert!(transport.requests().is_empty());
+    }
+
+    #[test]
+    fn test_navigate_to_data_url() {
+        let transport = mock_transport();
+        let browser = Browser::new();
+        browser.borrow_mut().set_transport(transport.clone());
+
+        let url = "data:text/html,<html><head></head><body><h1>hi</h1></body></html>";
+        assert!(browser.borrow_mut().navigate(url.to_string()).is_ok());
+        assert_eq!(["hi".to_string()].to_vec(), texts(&browser));
+        assert_eq!(Some(url.to_string()), browser.borrow().current_url());
+        assert!(transport.requests().is_empty());
+
+        // data: URLのページからの遷移ではRefererを送らない
+        assert!(browser
+            .borrow_mut()
+            .navigate("http://example.com/a".to_string())
+            .is_ok());
+        assert_eq!(None, transport.requests()[0].header_value("Referer"));
+    }
+
+    #[test]
+    fn test_navigate_to_base64_data_url() {
+        let browser = Browser::new();
+
+        // "<html><head></head><body><p>hello</p></body></html>"
+        let url = "data:text/html;base64,PGh0bWw+PGhlYWQ+PC9oZWFkPjxib2R5PjxwPmhlbGxvPC9wPjwvYm9keT48L2h0bWw+";
+        assert!(browser.borrow_mut().navigate(url.to_string()).is_ok());
+        assert_eq!(["hello".to_string()].to_vec(), texts(&browser));
+    }
+
+    #[test]
+    fn test_navigate_to_unsupported_data_url() {
+        let browser = Browser::new();
+        assert!(matches!(
+            browser
+                .borrow_mut()
+                .navigate("data:image/png;base64,iVBORw0K".to_string()),
+            Err(Error::UnexpectedInput { .. })
+        ));
+        assert_eq!(None, browser.borrow().current_url());
+    }
+
+    fn text_colors(browser: &Rc<RefCell<Browser>>) -> Vec<u32> {
+        browser
+            .borrow()
+            .current_page()
+            .borrow()
+            .display_items()
+            .iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text { style, .. } => Some(style.color().code_u32()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_default_stylesheet() {
+        let browser = Browser::new();
+        assert_eq!("", browser.borrow().default_stylesheet());
+        browser
+            .borrow_mut()
+            .set_default_stylesheet("body { color: red; }".to_string());
+
+        let url = "data:text/html,<html><head></head><body><p>hi</p></body></html>";
+        assert!(browser.borrow_mut().navigate(url.to_string()).is_ok());
+        assert_eq!([0xff0000].to_vec(), text_colors(&browser));
+    }
+
+    #[test]
+    fn test_author_style_wins_over_default_stylesheet() {
+        let browser = Browser::new();
+        browser
+            .borrow_mut()
+            .set_default_stylesheet("body { color: red; }".to_string());
+        // 詳細度が高くても、既定のスタイルシートのルールはページのルールに負ける
+        browser
+            .borrow_mut()
+            .append_default_stylesheet(".text { color: green; }");
+
+        let url = "data:text/html,<html><head><style>p { color: blue; }</style></head><body><p class=text>hi</p></body></html>";
+        assert!(browser.borrow_mut().navigate(url.to_string()).is_ok());
+        assert_eq!([0x0000ff].to_vec(), text_colors(&browser));
+    }
+
+    fn background_colors(browser: &Rc<RefCell<Browser>>) -> Vec<u32> {
+        browser
+            .borrow()
+            .current_page()
+            .borrow()
+            .display_items()
+            .iter()
+            .filter_map(|item| match item {
+                DisplayItem::Rect { style, .. } => Some(style.background_color().code_u32()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_disable_js() {
+        let browser = Browser::new();
+        assert!(browser.borrow().js_enabled());
+        let url = r#"data:text/html,<html><head><script>document.getElementById("t").textContent = "changed";</script></head><body><p id="t">original</p></body></html>"#;
+
+        assert!(browser.borrow_mut().navigate(url.to_string()).is_ok());
+        assert_eq!(["changed".to_string()].to_vec(), texts(&browser));
+
+        // スクリプトを無効にすると、元のHTMLのまま描画する
+        browser.borrow_mut().set_js_enabled(false);
+        assert!(browser.borrow_mut().navigate(url.to_string()).is_ok());
+        assert_eq!(["original".to_string()].to_vec(), texts(&browser));
+        assert!(browser
+            .borrow()
+            .current_page()
+            .borrow()
+            .eval_js_expression("1")
+            .is_err());
+    }
+
+    #[test]
+    fn test_dark_mode() {
+        let browser = Browser::new();
+        assert!(!browser.borrow().dark_mode());
+
+        let url =
+            "data:text/html,<html><head></head><body><p>hi <a href=/>link</a></p></body></html>";
+        assert!(browser.borrow_mut().navigate(url.to_string()).is_ok());
+        assert_eq!([0x000000, 0x0000ff].to_vec(), text_colors(&browser));
+        assert_eq!([0xffffff, 0xffffff].to_vec(), background_colors(&browser));
+
+        // 読み込み直さなくても、表示中のページに反映される
+        browser.borrow_mut().set_dark_mode(true);
+        assert!(browser.borrow().dark_mode());
+        assert_eq!([0xd3d3d3, 0x00ffff].to_vec(), text_colors(&browser));
+        assert_eq!([0x000000, 0x000000].to_vec(), background_colors(&browser));
+
+        browser.borrow_mut().set_dark_mode(false);
+        assert_eq!([0x000000, 0x0000ff].to_vec(), text_colors(&browser));
+        assert_eq!([0xffffff, 0xffffff].to_vec(), background_colors(&browser));
+    }
+
+    #[test]
+    fn test_dark_mode_applies_to_new_pages_beneath_author_rules() {
+        let browser = Browser::new();
+        browser.borrow_mut().set_dark_mode(true);
+        // 利用者の既定のスタイルシートはダークモードのルールより優先される
+        browser
+            .borrow_mut()
+            .set_default_stylesheet("a { color: yellow; }".to_string());
+
+        let url = "data:text/html,<html><head><style>.note { color: red; }</style></head><body><p>hi</p><p class=note>note <a href=/>link</a></p></body></html>";
+        assert!(browser.borrow_mut().navigate(url.to_string()).is_ok());
+        assert_eq!(
+            [0xd3d3d3, 0xff0000, 0xffff00].to_vec(),
+            text_colors(&browser)
+        );
+    }
+
+    fn text_ys(browser: &Rc<RefCell<Browser>>) -> Vec<i64> {
+        browser
+            .borrow()
+            .current_page()
+            .borrow()
+            .display_items()
+            .iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text { layout_point, .. } => Some(layout_point.y()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_zoom() {
+        let browser = Browser::new();
+        assert_eq!(100, browser.borrow().zoom());
+
+        let url = "data:text/html,<html><head></head><body><p>a</p><p>b</p></body></html>";
+        assert!(browser.borrow_mut().navigate(url.to_string()).is_ok());
+        assert_eq!([0, CHAR_HEIGHT_WITH_PADDING].to_vec(), text_ys(&browser));
+
+        // 読み込み直さなくても、表示中のページのレイアウトに反映される
+        for _ in 0..4 {
+            browser.borrow_mut().zoom_in();
+        }
+        assert_eq!(200, browser.borrow().zoom());
+        assert_eq!(
+            [0, CHAR_HEIGHT_WITH_PADDING * 2].to_vec(),
+            text_ys(&browser)
+        );
+
+        // 次に読み込むページにも同じ倍率を使う
+        assert!(browser.borrow_mut().navigate(url.to_string()).is_ok());
+        assert_eq!(
+            [0, CHAR_HEIGHT_WITH_PADDING * 2].to_vec(),
+            text_ys(&browser)
+        );
+
+        browser.borrow_mut().zoom_out();
+        assert_eq!(175, browser.borrow().zoom());
+    }
+
+    #[test]
+    fn test_zoom_is_clamped() {
+        let browser = Browser::new();
+        browser.borrow_mut().set_zoom(1000);
+        assert_eq!(MAX_ZOOM, browser.borrow().zoom());
+        browser.borrow_mut().zoom_in();
+        assert_eq!(MAX_ZOOM, browser.borrow().zoom());
+
+        browser.borrow_mut().set_zoom(0);
+        assert_eq!(MIN_ZOOM, browser.borrow().zoom());
+        browser.borrow_mut().zoom_out();
+        assert_eq!(MIN_ZOOM, browser.borrow().zoom());
+    }
+
+    // 決まったパスにだけHTMLを返すファイルシステム
+    #[derive(Debug)]
+    struct StubFileReader;
+
+    impl FileReader for StubFileReader {
+        fn read(&self, path: &str) -> Result<Vec<u8>, Error> {
+            match path {
+                "/home/user/index.html" => {
+                    // UTF-8のBOM付き
+                    let mut bytes = [0xef, 0xbb, 0xbf].to_vec();
+                    bytes.extend_from_slice(
+                        "<html><head></head><body><p>ローカル</p></body></html>".as_bytes(),
+                    );
+                    Ok(bytes)
+                }
+                _ => Err(Error::Other(format!("{} not found", path))),
+            }
+        }
+    }
+
+    #[test]
+    fn test_navigate_to_file_url() {
+        let transport = mock_transport();
+        let browser = Browser::new();
+        browser.borrow_mut().set_transport(transport.clone());
+        browser
+            .borrow_mut()
+            .set_file_reader(Rc::new(StubFileReader));
+
+        let url = "file:///home/user/index.html";
+        assert!(browser.borrow_mut().navigate(url.to_string()).is_ok());
+        assert_eq!(Some(url.to_string()), browser.borrow().current_url());
+        assert_eq!(["ローカル".to_string()].to_vec(), texts(&browser));
+        assert!(transport.requests().is_empty());
+
+        // ローカルのファイルからの遷移ではRefererを送らない
+        assert!(browser
+            .borrow_mut()
+            .navigate("http://example.com/a".to_string())
+            .is_ok());
+        assert_eq!(None, transport.requests()[0].header_value("Referer"));
+
+        assert!(browser
+            .borrow_mut()
+            .navigate("file:///home/user/missing.html".to_string())
+            .is_err());
+    }
+
+    #[test]
+    fn test_navigate_to_file_url_without_filesystem() {
+        let browser = Browser::new();
+        let result = browser
+            .borrow_mut()
+            .navigate("file:///home/user/index.html".to_string());
+        assert_eq!(
+            Err(Error::FileSystemUnavailable(
+                "/home/user/index.html".to_string()
+            )),
+            result
+        );
+        assert_eq!(None, browser.borrow().current_url());
+    }
+
+    #[test]
+    fn test_navigate_without_transport() {
+        let browser = Browser::new();
+        let result = browser
+            .borrow_mut()
+            .navigate("http://example.com/a".to_string());
+        assert!(matches!(
+            result,
+            Err(Error::Network { url, .. }) if url == "http://example.com/a"
+        ));
+        assert_eq!(None, browser.borrow().current_url());
+    }
+
+    #[test]
+    fn test_network_failure_is_distinguished_from_ui_failure() {
+        let transport = mock_transport();
+        let browser = Browser::new();
+        browser.borrow_mut().set_transport(transport);
+
+        let network_error = browser
+            .borrow_mut()
+            .navigate("http://example.com/missing".to_string())
+            .unwrap_err();
+        let ui_error = Error::InvalidUI {
+            component: "address bar".to_string(),
+            message: "failed to update".to_string(),
+        };
+
+        for (error, is_network) in [(network_error, true), (ui_error, false)] {
+            match error {
+                Error::Network { url, message } => {
+                    assert!(is_network);
+                    assert_eq!("http://example.com/missing", url);
+                    assert_eq!("not found", message);
+                }
+                Error::InvalidUI { component, .. } => {
+                    assert!(!is_network);
+                    assert_eq!("address bar", component);
+                }
+                e => panic!("unexpected error {:?}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn test_http_error_shows_error_page() {
+        let transport = mock_transport();
+        let browser = Browser::new();
+        browser.borrow_mut().set_transport(transport);
+        assert!(browser
+            .borrow_mut()
+            .navigate("http://example.com/a".to_string())
+            .is_ok());
+
+        let url = "http://example.com/gone".to_string();
+        let error = browser.borrow_mut().navigate(url.clone()).unwrap_err();
+        assert_eq!(
+            Error::HttpError {
+                status: 404,
+                url: url.clone()
+            },
+            error
+        );
+        // 失敗した時点では、前のページが表示されたまま
+        assert_eq!(["page a".to_string()].to_vec(), texts(&browser));
+
+        browser.borrow_mut().show_error_page(url.clone(), &error);
+        assert_eq!(Some(url), browser.borrow().current_url());
+        assert_eq!(
+            [
+                "Error 404".to_string(),
+                "http://example.com/gone returned status 404".to_string(),
+                "The server could not return the page.".to_string()
+            ]
+            .to_vec(),
+            texts(&browser)
+        );
+    }
+
+    #[test]
+    fn test_navigate_upgrades_to_https_with_hsts() {
+        let transport = mock_transport();
+        let browser = Browser::new();
+        browser.borrow_mut().set_transport(transport.clone());
+
+        // http://で受け取ったヘッダーは保存しない
+        assert!(browser
+            .borrow_mut()
+            .navigate("http://secure.com/".to_string())
+            .is_ok());
+        assert!(!browser.borrow().hsts_store().is_active("secure.com", 0));
+
+        // https://で受け取ったヘッダーは保存される
+        assert!(browser
+            .borrow_mut()
+            .navigate("https://secure.com/".to_string())
+            .is_ok());
+        assert!(browser.borrow().hsts_store().is_active("secure.com", 0));
+
+        assert!(browser
+            .borrow_mut()
+            .navigate("http://secure.com/".to_string())
+            .is_ok());
+        assert_eq!(
+            Some("https://secure.com/".to_string()),
+            browser.borrow().current_url()
+        );
+
+        // 有効期限が切れたら書き換えない
+        browser.borrow_mut().set_current_time(100);
+        assert!(browser
+            .borrow_mut()
+            .navigate("http://secure.com/".to_string())
+            .is_ok());
+
+        let urls = transport
+            .requests()
+            .iter()
+            .map(|request| request.url())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            [
+                "http://secure.com/".to_string(),
+                "https://secure.com/".to_string(),
+                "https://secure.com/".to_string(),
+                "http://secure.com/".to_string()
+            ]
+            .to_vec(),
+            urls
+        );
+    }
+
+    #[test]
+    fn test_cookie_is_sent_back_to_same_domain() {
+        let transport = mock_transport();
+        let browser = Browser::new();
+        browser.borrow_mut().set_transport(transport.clone());
+
+        for url in [
+            "http://example.com/login",
+            "http://example.com/a",
+            "http://other.com/",
+        ] {
+            assert!(browser.borrow_mut().navigate(url.to_string()).is_ok());
+        }
+
+        let cookies = transport
+            .requests()
+            .iter()
+            .map(|request| request.header_value("Cookie"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            [None, Some("session=abc".to_string()), None].to_vec(),
+            cookies
+        );
+    }
+
+    fn redirect_transport() -> Rc<StubTransport> {
+        Rc::new(StubTransport::new(
+            [
+                (
+                    "http://example.com/login",
+                    HttpResponse::builder()
+                        .header("Set-Cookie", "session=abc; Path=/")
+                        .body("<html><head></head><body></body></html>".to_string())
+                        .build(),
+                ),
+                (
+                    "http://example.com/moved",
+                    HttpResponse::builder()
+                        .status(302)
+                        .header("Location", "http://other.com/landing")
+                        .header("Set-Cookie", "hop=1; Path=/")
+                        .build(),
+                ),
+                (
+                    "http://other.com/landing",
+                    HttpResponse::builder()
+                        .header("Set-Cookie", "landed=2; Path=/")
+                        .body("<html><head></head><body><p>landing</p></body></html>".to_string())
+                        .build(),
+                ),
+                (
+                    "http://example.com/loop",
+                    HttpResponse::builder()
+                        .status(302)
+                        .header("Location", "http://example.com/loop")
+                        .build(),
+                ),
+            ]
+            .to_vec(),
+        ))
+    }
+
+    #[test]
+    fn test_redirect_rebuilds_request_for_new_url() {
+        let transport = redirect_transport();
+        let browser = Browser::new();
+        browser.borrow_mut().set_transport(transport.clone());
+        for url in ["http://example.com/login", "http://example.com/moved"] {
+            assert!(browser.borrow_mut().navigate(url.to_string()).is_ok());
+        }
+        assert_eq!(["landing".to_string()].to_vec(), texts(&browser));
+        assert_eq!(
+            Some("http://other.com/landing".to_string()),
+            browser.borrow().current_url()
+        );
+
+        // 移動先の別のホストには、元のホストのCookieを送らない
+        let requests = transport.requests();
+        assert_eq!(3, requests.len());
+        assert_eq!(
+            Some("session=abc".to_string()),
+            requests[1].header_value("Cookie")
+        );
+        assert_eq!(None, requests[2].header_value("Cookie"));
+        assert_eq!(
+            Some("http://example.com/login".to_string()),
+            requests[2].header_value("Referer")
+        );
+
+        // リダイレクトと移動先のCookieは、それぞれを返したURLのものとして保存する
+        let jar = browser.borrow().cookie_jar().clone();
+        assert_eq!(
+            Some("session=abc; hop=1".to_string()),
+            jar.cookie_header("http://example.com/", 0)
+        );
+        assert_eq!(
+            Some("landed=2".to_string()),
+            jar.cookie_header("http://other.com/", 0)
+        );
+    }
+
+    #[test]
+    fn test_too_many_redirects() {
+        let transport = redirect_transport();
+        let browser = Browser::new();
+        browser.borrow_mut().set_transport(transport.clone());
+
+        assert_eq!(
+            Err(Error::Network {
+                url: "http://example.com/loop".to_string(),
+                message: "too many redirects".to_string(),
+            }),
+            browser
+                .borrow_mut()
+                .navigate("http://example.com/loop".to_string())
+        );
+        assert_eq!(MAX_REDIRECTS + 1, transport.requests().len());
+    }
 }
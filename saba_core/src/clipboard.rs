@@ -0,0 +1,86 @@
+use alloc::string::String;
+use core::fmt::Debug;
+
+// クリップボードのテキストを読み書きする抽象
+// OSがクリップボードを提供しない環境ではNoClipboardを使い、テストではスタブに差し替えられる
+pub trait Clipboard: Debug {
+    fn read_text(&self) -> Option<String>;
+    // 書き込めたときにtrueを返す
+    fn write_text(&self, text: &str) -> bool;
+}
+
+// クリップボードが使えない環境向けの実装。常に何も返さず、書き込みもしない
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoClipboard;
+
+impl Clipboard for NoClipboard {
+    fn read_text(&self) -> Option<String> {
+        None
+    }
+
+    fn write_text(&self, _text: &str) -> bool {
+        false
+    }
+}
+
+// クリップボードのテキストを入力欄の末尾に追加する
+// アドレスバーは1行なので制御文字（改行など）は取り除く
+// 入力欄が変化したときにtrueを返す
+pub fn paste_into(input: &mut String, clipboard: &dyn Clipboard) -> bool {
+    let text = match clipboard.read_text() {
+        Some(text) => text,
+        None => return false,
+    };
+
+    let before = input.len();
+    input.extend(text.chars().filter(|c| !c.is_control()));
+    input.len() != before
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[derive(Debug)]
+    struct StubClipboard {
+        text: Option<String>,
+    }
+
+    impl Clipboard for StubClipboard {
+        fn read_text(&self) -> Option<String> {
+            self.text.clone()
+        }
+
+        fn write_text(&self, _text: &str) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn test_paste_appends_text() {
+        let clipboard = StubClipboard {
+            text: Some("example.com/index.html".to_string()),
+        };
+        let mut input = "http://".to_string();
+        assert!(paste_into(&mut input, &clipboard));
+        assert_eq!("http://example.com/index.html", input);
+    }
+
+    #[test]
+    fn test_paste_strips_control_characters() {
+        let clipboard = StubClipboard {
+            text: Some("http://example.com\n".to_string()),
+        };
+        let mut input = String::new();
+        assert!(paste_into(&mut input, &clipboard));
+        assert_eq!("http://example.com", input);
+    }
+
+    #[test]
+    fn test_paste_without_clipboard() {
+        let mut input = "http://".to_string();
+        assert!(!paste_into(&mut input, &NoClipboard));
+        assert_eq!("http://", input);
+    }
+}
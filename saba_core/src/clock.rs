@@ -0,0 +1,18 @@
+use core::fmt::Debug;
+
+// スクリプトから参照する時計の抽象
+// OSが時計を提供しない環境ではNoClockを使い、テストではスタブに差し替えられる
+pub trait Clock: Debug {
+    // 単調増加するミリ秒
+    fn now_millis(&self) -> u64;
+}
+
+// 時計が使えない環境向けの実装。常に0を返す
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoClock;
+
+impl Clock for NoClock {
+    fn now_millis(&self) -> u64 {
+        0
+    }
+}
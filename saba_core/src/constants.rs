@@ -23,3 +23,21 @@ pub static CONTENT_AREA_HEIGHT: i64 =
 pub static CHAR_WIDTH: i64 = 8;
 pub static CHAR_HEIGHT: i64 = 16;
 pub static CHAR_HEIGHT_WITH_PADDING: i64 = CHAR_HEIGHT + 4;
+
+pub static ALERT_WIDTH: i64 = 300;
+pub static ALERT_HEIGHT: i64 = 80;
+pub static ALERT_BUTTON_WIDTH: i64 = 40;
+pub static ALERT_BUTTON_HEIGHT: i64 = 20;
+pub static CONTEXT_MENU_WIDTH: i64 = 160;
+pub static CONTEXT_MENU_ITEM_HEIGHT: i64 = CHAR_HEIGHT_WITH_PADDING;
+pub static FLIP_FRAMES: usize = 8;
+pub static MAX_JS_CALL_DEPTH: usize = 64;
+pub static MAX_STYLESHEET_CACHE_ENTRIES: usize = 32;
+// 1回の読み込みでたどるリダイレクトの回数の上限
+pub static MAX_REDIRECTS: usize = 20;
+// 受け取るレスポンスのボディの大きさ(バイト)の上限の既定値
+pub static MAX_RESPONSE_BODY_SIZE: usize = 8 * 1024 * 1024;
+// 文字の大きさの倍率(%)の範囲と、1回の拡大・縮小で変える量
+pub static MIN_ZOOM: i64 = 50;
+pub static MAX_ZOOM: i64 = 300;
+pub static ZOOM_STEP: i64 = 25;
@@ -0,0 +1,198 @@
+use crate::browser::Browser;
+use crate::clipboard::Clipboard;
+use crate::constants::{CONTEXT_MENU_ITEM_HEIGHT, CONTEXT_MENU_WIDTH};
+use crate::error::Error;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextMenuItem {
+    OpenInNewTab,
+    CopyLinkAddress,
+}
+
+impl ContextMenuItem {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ContextMenuItem::OpenInNewTab => "Open in new tab",
+            ContextMenuItem::CopyLinkAddress => "Copy link address",
+        }
+    }
+}
+
+// リンクの上で右クリックしたときに開くメニュー
+// 描画はUIに任せ、ここでは項目、位置、選ばれたときの動作だけを扱う
+// 位置はコンテンツ領域の左上を原点とする
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextMenu {
+    url: String,
+    position: (i64, i64),
+    items: Vec<ContextMenuItem>,
+}
+
+impl ContextMenu {
+    // urlは解決済みのリンク先
+    pub fn for_link(url: String, position: (i64, i64)) -> Self {
+        Self {
+            url,
+            position,
+            items: [
+                ContextMenuItem::OpenInNewTab,
+                ContextMenuItem::CopyLinkAddress,
+            ]
+            .to_vec(),
+        }
+    }
+
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    pub fn position(&self) -> (i64, i64) {
+        self.position
+    }
+
+    pub fn items(&self) -> &[ContextMenuItem] {
+        &self.items
+    }
+
+    // メニュー全体の幅と高さ
+    pub fn size(&self) -> (i64, i64) {
+        (
+            CONTEXT_MENU_WIDTH,
+            CONTEXT_MENU_ITEM_HEIGHT * self.items.len() as i64,
+        )
+    }
+
+    // index番目の項目の左上の位置
+    pub fn item_position(&self, index: usize) -> (i64, i64) {
+        (
+            self.position.0,
+            self.position.1 + CONTEXT_MENU_ITEM_HEIGHT * index as i64,
+        )
+    }
+
+    // positionにある項目を返す。メニューの外ならNone
+    pub fn item_at(&self, position: (i64, i64)) -> Option<ContextMenuItem> {
+        let (width, height) = self.size();
+        let (x, y) = (position.0 - self.position.0, position.1 - self.position.1);
+        if x < 0 || x >= width || y < 0 || y >= height {
+            return None;
+        }
+
+        self.items
+            .get((y / CONTEXT_MENU_ITEM_HEIGHT) as usize)
+            .copied()
+    }
+
+    // 選ばれた項目の動作を実行する
+    pub fn select(
+        &self,
+        item: ContextMenuItem,
+        browser: &mut Browser,
+        clipboard: &dyn Clipboard,
+    ) -> Result<(), Error> {
+        match item {
+            ContextMenuItem::OpenInNewTab => browser.open_in_background_tab(self.url.clone()),
+            ContextMenuItem::CopyLinkAddress => {
+                if clipboard.write_text(&self.url) {
+                    Ok(())
+                } else {
+                    Err(Error::Other("clipboard is not available".to_string()))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clipboard::NoClipboard;
+    use core::cell::RefCell;
+
+    #[derive(Debug)]
+    struct StubClipboard {
+        text: RefCell<Option<String>>,
+    }
+
+    impl Clipboard for StubClipboard {
+        fn read_text(&self) -> Option<String> {
+            self.text.borrow().clone()
+        }
+
+        fn write_text(&self, text: &str) -> bool {
+            *self.text.borrow_mut() = Some(text.to_string());
+            true
+        }
+    }
+
+    #[test]
+    fn test_item_at() {
+        let menu = ContextMenu::for_link("about:saba".to_string(), (100, 50));
+        assert_eq!(Some(ContextMenuItem::OpenInNewTab), menu.item_at((101, 51)));
+        assert_eq!(
+            Some(ContextMenuItem::CopyLinkAddress),
+            menu.item_at((100 + CONTEXT_MENU_WIDTH - 1, 50 + CONTEXT_MENU_ITEM_HEIGHT))
+        );
+        // メニューの外
+        assert_eq!(None, menu.item_at((99, 51)));
+        assert_eq!(None, menu.item_at((101, 50 + CONTEXT_MENU_ITEM_HEIGHT * 2)));
+    }
+
+    #[test]
+    fn test_select_open_in_new_tab() {
+        let browser = Browser::new();
+        let clipboard = StubClipboard {
+            text: RefCell::new(None),
+        };
+        let menu = ContextMenu::for_link("about:saba".to_string(), (0, 0));
+
+        assert!(menu
+            .select(
+                ContextMenuItem::OpenInNewTab,
+                &mut browser.borrow_mut(),
+                &clipboard
+            )
+            .is_ok());
+        assert_eq!(2, browser.borrow().tab_count());
+        assert_eq!(0, browser.borrow().active_tab_index());
+        assert_eq!(None, clipboard.read_text());
+    }
+
+    #[test]
+    fn test_select_copy_link_address() {
+        let browser = Browser::new();
+        let clipboard = StubClipboard {
+            text: RefCell::new(None),
+        };
+        let menu = ContextMenu::for_link("http://example.com:80/a".to_string(), (0, 0));
+
+        assert!(menu
+            .select(
+                ContextMenuItem::CopyLinkAddress,
+                &mut browser.borrow_mut(),
+                &clipboard
+            )
+            .is_ok());
+        assert_eq!(
+            Some("http://example.com:80/a".to_string()),
+            clipboard.read_text()
+        );
+        assert_eq!(1, browser.borrow().tab_count());
+    }
+
+    #[test]
+    fn test_copy_without_clipboard() {
+        let browser = Browser::new();
+        let menu = ContextMenu::for_link("http://example.com:80/a".to_string(), (0, 0));
+
+        assert!(menu
+            .select(
+                ContextMenuItem::CopyLinkAddress,
+                &mut browser.borrow_mut(),
+                &NoClipboard
+            )
+            .is_err());
+    }
+}
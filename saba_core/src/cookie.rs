@@ -0,0 +1,271 @@
+use crate::url::{host_of, path_of};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
+// HttpOnlyやSecure、Expires(日付の解析が必要)は扱わない
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cookie {
+    name: String,
+    value: String,
+    domain: String,
+    // Domain属性がない場合は、設定したホストにだけ送る
+    host_only: bool,
+    path: String,
+    expiry: Option<u64>,
+}
+
+impl Cookie {
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    pub fn value(&self) -> String {
+        self.value.clone()
+    }
+
+    pub fn domain(&self) -> String {
+        self.domain.clone()
+    }
+
+    pub fn path(&self) -> String {
+        self.path.clone()
+    }
+
+    fn matches(&self, host: &str, path: &str, now: u64) -> bool {
+        if self.expiry.is_some_and(|expiry| expiry <= now) {
+            return false;
+        }
+        let domain_matched = if self.host_only {
+            host == self.domain
+        } else {
+            domain_matches(host, &self.domain)
+        };
+        domain_matched && path_matches(path, &self.path)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CookieJar {
+    cookies: Vec<Cookie>,
+}
+
+impl Default for CookieJar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CookieJar {
+    pub fn new() -> Self {
+        Self {
+            cookies: Vec::new(),
+        }
+    }
+
+    pub fn cookies(&self) -> Vec<Cookie> {
+        self.cookies.clone()
+    }
+
+    // urlへのリクエストのレスポンスに含まれていたSet-Cookieヘッダーを保存する
+    pub fn set_cookie(&mut self, url: &str, header: &str, now: u64) {
+        let host = host_of(url);
+        let mut attributes = header.split(';');
+        let (name, value) = match attributes.next().and_then(|pair| pair.split_once('=')) {
+            Some((name, value)) => (name.trim().to_string(), value.trim().to_string()),
+            None => return,
+        };
+        if name.is_empty() {
+            return;
+        }
+
+        let mut cookie = Cookie {
+            name,
+            value,
+            domain: host.clone(),
+            host_only: true,
+            path: default_path(url),
+            expiry: None,
+        };
+
+        for attribute in attributes {
+            let (key, value) = match attribute.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => (attribute.trim(), ""),
+            };
+            if key.eq_ignore_ascii_case("domain") {
+                let domain = value.trim_start_matches('.').to_ascii_lowercase();
+                // 他のドメインのCookieは設定できない
+                if !domain_matches(&host, &domain) {
+                    return;
+                }
+                cookie.domain = domain;
+                cookie.host_only = false;
+            } else if key.eq_ignore_ascii_case("path") && value.starts_with('/') {
+                cookie.path = value.to_string();
+            } else if key.eq_ignore_ascii_case("max-age") {
+                cookie.expiry = match value.parse::<i64>() {
+                    Ok(max_age) if max_age <= 0 => Some(now),
+                    Ok(max_age) => Some(now.saturating_add(max_age as u64)),
+                    Err(_) => cookie.expiry,
+                };
+            }
+        }
+
+        self.cookies.retain(|c| {
+            !(c.name == cookie.name && c.domain == cookie.domain && c.path == cookie.path)
+        });
+        // Max-Ageが0以下の場合は削除するだけ
+        if cookie.expiry.is_some_and(|expiry| expiry <= now) {
+            return;
+        }
+        self.cookies.push(cookie);
+    }
+
+    // urlへのリクエストに付けるCookieヘッダーの値を返す
+    // パスが長いものから順に並べる
+    pub fn cookie_header(&self, url: &str, now: u64) -> Option<String> {
+        let host = host_of(url);
+        let path = path_of(url);
+
+        let mut cookies = self
+            .cookies
+            .iter()
+            .filter(|c| c.matches(&host, &path, now))
+            .collect::<Vec<_>>();
+        if cookies.is_empty() {
+            return None;
+        }
+        cookies.sort_by_key(|cookie| Reverse(cookie.path.len()));
+
+        Some(
+            cookies
+                .iter()
+                .map(|c| {
+                    let mut pair = c.name.clone();
+                    pair.push('=');
+                    pair.push_str(&c.value);
+                    pair
+                })
+                .collect::<Vec<_>>()
+                .join("; "),
+        )
+    }
+}
+
+fn domain_matches(host: &str, domain: &str) -> bool {
+    if host == domain {
+        return true;
+    }
+    match host.strip_suffix(domain) {
+        Some(prefix) => prefix.ends_with('.'),
+        None => false,
+    }
+}
+
+fn path_matches(request_path: &str, cookie_path: &str) -> bool {
+    if request_path == cookie_path {
+        return true;
+    }
+    match request_path.strip_prefix(cookie_path) {
+        Some(rest) => cookie_path.ends_with('/') || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+// Path属性がない場合は、リクエストしたURLのディレクトリを使う
+fn default_path(url: &str) -> String {
+    let path = path_of(url);
+    match path.rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(index) => path[..index].to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_cookie() {
+        let mut jar = CookieJar::new();
+        jar.set_cookie("http://example.com/a/index.html", "id=123; HttpOnly", 0);
+
+        let cookies = jar.cookies();
+        assert_eq!(1, cookies.len());
+        assert_eq!("id", cookies[0].name());
+        assert_eq!("123", cookies[0].value());
+        assert_eq!("example.com", cookies[0].domain());
+        assert_eq!("/a", cookies[0].path());
+    }
+
+    #[test]
+    fn test_overwrite_cookie() {
+        let mut jar = CookieJar::new();
+        jar.set_cookie("http://example.com/", "id=1", 0);
+        jar.set_cookie("http://example.com/", "id=2", 0);
+        assert_eq!(
+            Some("id=2".to_string()),
+            jar.cookie_header("http://example.com/", 0)
+        );
+    }
+
+    #[test]
+    fn test_cookie_header_domain() {
+        let mut jar = CookieJar::new();
+        jar.set_cookie("http://example.com/", "a=1", 0);
+        jar.set_cookie("http://example.com/", "b=2; Domain=.example.com", 0);
+
+        assert_eq!(
+            Some("a=1; b=2".to_string()),
+            jar.cookie_header("http://example.com:8000/index.html", 0)
+        );
+        assert_eq!(
+            Some("b=2".to_string()),
+            jar.cookie_header("http://www.example.com/", 0)
+        );
+        assert_eq!(None, jar.cookie_header("http://other.com/", 0));
+        assert_eq!(None, jar.cookie_header("http://badexample.com/", 0));
+    }
+
+    #[test]
+    fn test_cannot_set_cookie_for_other_domain() {
+        let mut jar = CookieJar::new();
+        jar.set_cookie("http://example.com/", "a=1; Domain=other.com", 0);
+        assert!(jar.cookies().is_empty());
+    }
+
+    #[test]
+    fn test_cookie_header_path() {
+        let mut jar = CookieJar::new();
+        jar.set_cookie("http://example.com/", "a=1; Path=/docs", 0);
+        jar.set_cookie("http://example.com/", "b=2; Path=/", 0);
+
+        assert_eq!(
+            Some("a=1; b=2".to_string()),
+            jar.cookie_header("http://example.com/docs/index.html", 0)
+        );
+        assert_eq!(
+            Some("b=2".to_string()),
+            jar.cookie_header("http://example.com/docsets", 0)
+        );
+    }
+
+    #[test]
+    fn test_cookie_expiry() {
+        let mut jar = CookieJar::new();
+        jar.set_cookie("http://example.com/", "a=1; Max-Age=10", 0);
+        assert_eq!(
+            Some("a=1".to_string()),
+            jar.cookie_header("http://example.com/", 9)
+        );
+        assert_eq!(None, jar.cookie_header("http://example.com/", 10));
+
+        jar.set_cookie("http://example.com/", "b=2", 0);
+        jar.set_cookie("http://example.com/", "b=2; Max-Age=0", 0);
+        assert_eq!(
+            Some("a=1".to_string()),
+            jar.cookie_header("http://example.com/", 0)
+        );
+    }
+}
@@ -0,0 +1,261 @@
+use crate::url::Url;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+// Content-Security-Policyヘッダーのうち、script-src、style-src、frame-ancestorsだけをサポートする
+// ディレクティブがない場合はdefault-srcを使い、それもなければすべて許可する
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CspPolicy {
+    default_src: Option<Vec<String>>,
+    script_src: Option<Vec<String>>,
+    style_src: Option<Vec<String>>,
+    frame_ancestors: Option<Vec<String>>,
+}
+
+impl CspPolicy {
+    pub fn parse(header: &str) -> Self {
+        let mut policy = Self {
+            default_src: None,
+            script_src: None,
+            style_src: None,
+            frame_ancestors: None,
+        };
+
+        for directive in header.split(';') {
+            let mut tokens = directive.split_whitespace();
+            let name = match tokens.next() {
+                Some(name) => name.to_ascii_lowercase(),
+                None => continue,
+            };
+            let sources = tokens.map(|t| t.to_string()).collect::<Vec<String>>();
+
+            // 同じディレクティブが複数ある場合は最初のものを使う
+            match name.as_str() {
+                "default-src" if policy.default_src.is_none() => policy.default_src = Some(sources),
+                "script-src" if policy.script_src.is_none() => policy.script_src = Some(sources),
+                "style-src" if policy.style_src.is_none() => policy.style_src = Some(sources),
+                "frame-ancestors" if policy.frame_ancestors.is_none() => {
+                    policy.frame_ancestors = Some(sources)
+                }
+                _ => {}
+            }
+        }
+
+        policy
+    }
+
+    pub fn allows_inline_script(&self) -> bool {
+        Self::allows_inline(self.script_sources())
+    }
+
+    pub fn allows_script(&self, url: &str, page_url: Option<&str>) -> bool {
+        Self::allows_url(self.script_sources(), url, page_url)
+    }
+
+    pub fn allows_inline_style(&self) -> bool {
+        Self::allows_inline(self.style_sources())
+    }
+
+    pub fn allows_style(&self, url: &str, page_url: Option<&str>) -> bool {
+        Self::allows_url(self.style_sources(), url, page_url)
+    }
+
+    // frame-ancestorsはdefault-srcにフォールバックしない
+    pub fn allows_frame_ancestor(&self, embedder_url: &str, page_url: Option<&str>) -> bool {
+        Self::allows_url(self.frame_ancestors.as_ref(), embedder_url, page_url)
+    }
+
+    fn script_sources(&self) -> Option<&Vec<String>> {
+        self.script_src.as_ref().or(self.default_src.as_ref())
+    }
+
+    fn style_sources(&self) -> Option<&Vec<String>> {
+        self.style_src.as_ref().or(self.default_src.as_ref())
+    }
+
+    // 'self'が指定されている場合も、インラインのスクリプトやスタイルは許可する
+    fn allows_inline(sources: Option<&Vec<String>>) -> bool {
+        let sources = match sources {
+            Some(sources) => sources,
+            None => return true,
+        };
+
+        sources
+            .iter()
+            .any(|s| s == "'self'" || s == "'unsafe-inline'" || s == "*")
+    }
+
+    fn allows_url(sources: Option<&Vec<String>>, url: &str, page_url: Option<&str>) -> bool {
+        let sources = match sources {
+            Some(sources) => sources,
+            None => return true,
+        };
+
+        let target = match Url::new(url.to_string()).parse() {
+            Ok(target) => target,
+            Err(_) => return false,
+        };
+
+        sources.iter().any(|source| match source.as_str() {
+            "*" => true,
+            "'self'" => match page_url.map(|u| Url::new(u.to_string()).parse()) {
+                Some(Ok(page)) => page.host() == target.host() && page.port() == target.port(),
+                _ => false,
+            },
+            "'none'" | "'unsafe-inline'" => false,
+            _ => {
+                if source.starts_with("http://") {
+                    match Url::new(source.to_string()).parse() {
+                        Ok(s) => s.host() == target.host() && s.port() == target.port(),
+                        Err(_) => false,
+                    }
+                } else {
+                    *source == target.host()
+                }
+            }
+        })
+    }
+}
+
+// X-Frame-Optionsヘッダーの値
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XFrameOptions {
+    Deny,
+    SameOrigin,
+    AllowFrom(String),
+}
+
+impl XFrameOptions {
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut tokens = value.split_whitespace();
+        let directive = tokens.next()?.to_ascii_uppercase();
+        match directive.as_str() {
+            "DENY" => Some(Self::Deny),
+            "SAMEORIGIN" => Some(Self::SameOrigin),
+            "ALLOW-FROM" => tokens.next().map(|url| Self::AllowFrom(url.to_string())),
+            _ => None,
+        }
+    }
+
+    // page_urlのページをembedder_urlのページのフレームに埋め込めるかどうか
+    pub fn allows_embedding(&self, embedder_url: &str, page_url: Option<&str>) -> bool {
+        let origin = match self {
+            Self::Deny => return false,
+            Self::SameOrigin => match page_url {
+                Some(page_url) => page_url,
+                None => return false,
+            },
+            Self::AllowFrom(url) => url.as_str(),
+        };
+
+        match (
+            Url::new(origin.to_string()).parse(),
+            Url::new(embedder_url.to_string()).parse(),
+        ) {
+            (Ok(origin), Ok(embedder)) => {
+                origin.host() == embedder.host() && origin.port() == embedder.port()
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static PAGE_URL: Option<&str> = Some("http://example.com/index.html");
+
+    #[test]
+    fn test_no_policy() {
+        let policy = CspPolicy::parse("");
+        assert!(policy.allows_inline_script());
+        assert!(policy.allows_script("http://other.com/a.js", PAGE_URL));
+        assert!(policy.allows_inline_style());
+    }
+
+    #[test]
+    fn test_script_src_none() {
+        let policy = CspPolicy::parse("script-src 'none'");
+        assert!(!policy.allows_inline_script());
+        assert!(!policy.allows_script("http://example.com/a.js", PAGE_URL));
+        // style-srcには影響しない
+        assert!(policy.allows_inline_style());
+        assert!(policy.allows_style("http://other.com/a.css", PAGE_URL));
+    }
+
+    #[test]
+    fn test_script_src_self() {
+        let policy = CspPolicy::parse("script-src 'self'");
+        assert!(policy.allows_inline_script());
+        assert!(policy.allows_script("http://example.com/js/a.js", PAGE_URL));
+        assert!(!policy.allows_script("http://other.com/a.js", PAGE_URL));
+        assert!(!policy.allows_script("http://example.com:8000/a.js", PAGE_URL));
+    }
+
+    #[test]
+    fn test_host_source() {
+        let policy = CspPolicy::parse("script-src 'self' cdn.com; style-src http://style.com");
+        assert!(policy.allows_script("http://cdn.com/a.js", PAGE_URL));
+        assert!(!policy.allows_script("http://other.com/a.js", PAGE_URL));
+        assert!(!policy.allows_inline_style());
+        assert!(policy.allows_style("http://style.com/a.css", PAGE_URL));
+        assert!(!policy.allows_style("http://example.com/a.css", PAGE_URL));
+    }
+
+    #[test]
+    fn test_default_src_fallback() {
+        let policy = CspPolicy::parse("default-src 'none'; style-src *");
+        assert!(!policy.allows_inline_script());
+        assert!(!policy.allows_script("http://example.com/a.js", PAGE_URL));
+        assert!(policy.allows_inline_style());
+        assert!(policy.allows_style("http://other.com/a.css", PAGE_URL));
+    }
+
+    #[test]
+    fn test_frame_ancestors() {
+        let policy = CspPolicy::parse("default-src 'none'");
+        assert!(policy.allows_frame_ancestor("http://other.com/", PAGE_URL));
+
+        let policy = CspPolicy::parse("frame-ancestors 'self'");
+        assert!(policy.allows_frame_ancestor("http://example.com/top.html", PAGE_URL));
+        assert!(!policy.allows_frame_ancestor("http://other.com/", PAGE_URL));
+
+        let policy = CspPolicy::parse("frame-ancestors 'none'");
+        assert!(!policy.allows_frame_ancestor("http://example.com/top.html", PAGE_URL));
+    }
+
+    #[test]
+    fn test_x_frame_options_deny() {
+        let options = XFrameOptions::parse("DENY");
+        assert_eq!(Some(XFrameOptions::Deny), options);
+        assert!(!XFrameOptions::Deny.allows_embedding("http://example.com/", PAGE_URL));
+    }
+
+    #[test]
+    fn test_x_frame_options_sameorigin() {
+        let options = XFrameOptions::parse("sameorigin").expect("failed to parse");
+        assert_eq!(XFrameOptions::SameOrigin, options);
+        assert!(options.allows_embedding("http://example.com/top.html", PAGE_URL));
+        assert!(!options.allows_embedding("http://other.com/", PAGE_URL));
+    }
+
+    #[test]
+    fn test_x_frame_options_allow_from() {
+        let options =
+            XFrameOptions::parse("ALLOW-FROM http://other.com/").expect("failed to parse");
+        assert_eq!(
+            XFrameOptions::AllowFrom("http://other.com/".to_string()),
+            options
+        );
+        assert!(options.allows_embedding("http://other.com/top.html", PAGE_URL));
+        assert!(!options.allows_embedding("http://example.com/", PAGE_URL));
+    }
+
+    #[test]
+    fn test_x_frame_options_invalid() {
+        assert_eq!(None, XFrameOptions::parse(""));
+        assert_eq!(None, XFrameOptions::parse("ALLOW-FROM"));
+        assert_eq!(None, XFrameOptions::parse("ALLOWALL"));
+    }
+}
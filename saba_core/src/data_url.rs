@@ -0,0 +1,160 @@
+use crate::error::Error;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+// data:[<mediatype>][;base64],<data> の形式で、URLそのものに内容を埋め込む
+// https://www.rfc-editor.org/rfc/rfc2397
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataUrl {
+    media_type: String,
+    body: String,
+}
+
+impl DataUrl {
+    pub fn parse(url: &str) -> Result<Self, Error> {
+        let invalid = |message: &str| Error::UnexpectedInput {
+            input: url.to_string(),
+            message: message.to_string(),
+        };
+
+        let rest = match url.strip_prefix("data:") {
+            Some(rest) => rest,
+            None => return Err(invalid("not a data: URL")),
+        };
+        let (meta, data) = match rest.split_once(',') {
+            Some(pair) => pair,
+            None => return Err(invalid("data: URL has no comma")),
+        };
+
+        let (meta, is_base64) = match meta.strip_suffix(";base64") {
+            Some(meta) => (meta, true),
+            None => (meta, false),
+        };
+        // charsetなどのパラメータは無視し、メディアタイプだけを取り出す
+        let media_type = match meta.split(';').next().unwrap_or("").trim() {
+            "" => "text/plain".to_string(),
+            media_type => media_type.to_ascii_lowercase(),
+        };
+
+        let bytes = percent_decode(data);
+        let bytes = if is_base64 {
+            match base64_decode(&bytes) {
+                Some(bytes) => bytes,
+                None => return Err(invalid("invalid base64 in data: URL")),
+            }
+        } else {
+            bytes
+        };
+        let body = match String::from_utf8(bytes) {
+            Ok(body) => body,
+            Err(_) => return Err(invalid("data: URL is not valid UTF-8")),
+        };
+
+        Ok(Self { media_type, body })
+    }
+
+    pub fn media_type(&self) -> String {
+        self.media_type.clone()
+    }
+
+    pub fn body(&self) -> String {
+        self.body.clone()
+    }
+}
+
+// %XXの形式でエスケープされたバイトを元に戻す。不正なエスケープはそのまま残す
+fn percent_decode(input: &str) -> Vec<u8> {
+    let bytes = input.as_bytes();
+    let mut result = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = core::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                result.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        result.push(bytes[i]);
+        i += 1;
+    }
+    result
+}
+
+fn base64_value(c: u8) -> Option<u32> {
+    match c {
+        b'A'..=b'Z' => Some((c - b'A') as u32),
+        b'a'..=b'z' => Some((c - b'a') as u32 + 26),
+        b'0'..=b'9' => Some((c - b'0') as u32 + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+// 空白は読み飛ばし、末尾の=は省略されていてもよい
+fn base64_decode(input: &[u8]) -> Option<Vec<u8>> {
+    let chars: Vec<u8> = input
+        .iter()
+        .copied()
+        .filter(|c| !c.is_ascii_whitespace())
+        .collect();
+    let chars = match chars.iter().position(|c| *c == b'=') {
+        Some(padding) => &chars[..padding],
+        None => &chars[..],
+    };
+
+    let mut result = Vec::new();
+    for chunk in chars.chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+        let mut n = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            n |= base64_value(*c)? << (18 - 6 * i);
+        }
+        for i in 0..chunk.len() - 1 {
+            result.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_plain() {
+        let url = DataUrl::parse("data:text/html,<h1>hi</h1>").expect("should parse");
+        assert_eq!("text/html", url.media_type());
+        assert_eq!("<h1>hi</h1>", url.body());
+    }
+
+    #[test]
+    fn test_parse_percent_encoded() {
+        let url = DataUrl::parse("data:text/html;charset=utf-8,%3Cp%3Ea%20b%3C/p%3E")
+            .expect("should parse");
+        assert_eq!("text/html", url.media_type());
+        assert_eq!("<p>a b</p>", url.body());
+    }
+
+    #[test]
+    fn test_parse_base64() {
+        // "<p>hello</p>"
+        let url = DataUrl::parse("data:text/html;base64,PHA+aGVsbG88L3A+").expect("should parse");
+        assert_eq!("<p>hello</p>", url.body());
+        // 末尾の=を含む
+        let url = DataUrl::parse("data:;base64,aGk=").expect("should parse");
+        assert_eq!("text/plain", url.media_type());
+        assert_eq!("hi", url.body());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(DataUrl::parse("http://example.com").is_err());
+        assert!(DataUrl::parse("data:text/html").is_err());
+        assert!(DataUrl::parse("data:text/html;base64,!!!!").is_err());
+    }
+}
@@ -1,6 +1,8 @@
 use crate::renderer::layout::computed_style::ComputedStyle;
 use crate::renderer::layout::layout_object::{LayoutPoint, LayoutSize};
+use crate::renderer::layout::text::{line_height, text_width};
 use alloc::string::String;
+use core::cmp::{max, min};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum DisplayItem {
@@ -15,3 +17,148 @@ pub enum DisplayItem {
         layout_point: LayoutPoint,
     },
 }
+
+impl DisplayItem {
+    // 描画される範囲
+    pub fn bounds(&self) -> (LayoutPoint, LayoutSize) {
+        match self {
+            DisplayItem::Rect {
+                layout_point,
+                layout_size,
+                ..
+            } => (*layout_point, *layout_size),
+            DisplayItem::Text {
+                text,
+                style,
+                layout_point,
+            } => (
+                *layout_point,
+                LayoutSize::new(
+                    text_width(text, style.font_size()),
+                    line_height(style.font_size()),
+                ),
+            ),
+        }
+    }
+}
+
+// 前回と今回の表示項目を比べ、描画し直す必要のある範囲を返す
+pub fn dirty_rect(old: &[DisplayItem], new: &[DisplayItem]) -> Option<(LayoutPoint, LayoutSize)> {
+    let mut dirty = None;
+
+    for i in 0..max(old.len(), new.len()) {
+        let (old_item, new_item) = (old.get(i), new.get(i));
+        if old_item == new_item {
+            continue;
+        }
+
+        for item in [old_item, new_item].into_iter().flatten() {
+            dirty = match dirty {
+                Some(rect) => Some(union_rect(rect, item.bounds())),
+                None => Some(item.bounds()),
+            };
+        }
+    }
+
+    dirty
+}
+
+pub fn union_rect(
+    a: (LayoutPoint, LayoutSize),
+    b: (LayoutPoint, LayoutSize),
+) -> (LayoutPoint, LayoutSize) {
+    let x = min(a.0.x(), b.0.x());
+    let y = min(a.0.y(), b.0.y());
+    let right = max(a.0.x() + a.1.width(), b.0.x() + b.1.width());
+    let bottom = max(a.0.y() + a.1.height(), b.0.y() + b.1.height());
+
+    (
+        LayoutPoint::new(x, y),
+        LayoutSize::new(right - x, bottom - y),
+    )
+}
+
+pub fn intersect_rect(
+    a: (LayoutPoint, LayoutSize),
+    b: (LayoutPoint, LayoutSize),
+) -> Option<(LayoutPoint, LayoutSize)> {
+    let x = max(a.0.x(), b.0.x());
+    let y = max(a.0.y(), b.0.y());
+    let right = min(a.0.x() + a.1.width(), b.0.x() + b.1.width());
+    let bottom = min(a.0.y() + a.1.height(), b.0.y() + b.1.height());
+
+    if right <= x || bottom <= y {
+        return None;
+    }
+
+    Some((
+        LayoutPoint::new(x, y),
+        LayoutSize::new(right - x, bottom - y),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{CHAR_HEIGHT_WITH_PADDING, CHAR_WIDTH};
+    use crate::renderer::dom::node::{Node, NodeKind};
+    use alloc::rc::Rc;
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+    use core::cell::RefCell;
+
+    fn create_style() -> ComputedStyle {
+        let node = Rc::new(RefCell::new(Node::new(NodeKind::Text("".to_string()))));
+        let mut style = ComputedStyle::new();
+        style.defaulting(&node, None);
+        style
+    }
+
+    fn create_items(text: &str) -> Vec<DisplayItem> {
+        [
+            DisplayItem::Rect {
+                style: create_style(),
+                layout_point: LayoutPoint::new(0, 0),
+                layout_size: LayoutSize::new(100, 100),
+            },
+            DisplayItem::Text {
+                text: text.to_string(),
+                style: create_style(),
+                layout_point: LayoutPoint::new(10, 20),
+            },
+        ]
+        .to_vec()
+    }
+
+    #[test]
+    fn test_no_change() {
+        assert_eq!(
+            None,
+            dirty_rect(&create_items("hello"), &create_items("hello"))
+        );
+    }
+
+    #[test]
+    fn test_single_text_change() {
+        let dirty = dirty_rect(&create_items("hello"), &create_items("hi"));
+        assert_eq!(
+            Some((
+                LayoutPoint::new(10, 20),
+                LayoutSize::new(CHAR_WIDTH * 5, CHAR_HEIGHT_WITH_PADDING)
+            )),
+            dirty
+        );
+    }
+
+    #[test]
+    fn test_intersect_rect() {
+        let a = (LayoutPoint::new(0, 0), LayoutSize::new(10, 10));
+        let b = (LayoutPoint::new(5, 5), LayoutSize::new(10, 10));
+        let c = (LayoutPoint::new(20, 20), LayoutSize::new(10, 10));
+        assert_eq!(
+            Some((LayoutPoint::new(5, 5), LayoutSize::new(5, 5))),
+            intersect_rect(a, b)
+        );
+        assert_eq!(None, intersect_rect(a, c));
+    }
+}
@@ -1,9 +1,140 @@
 use alloc::string::String;
+use core::fmt;
 
+// 各バリアントは原因を特定するための文脈（URLや入力値、UI部品の名前）を持つ
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Error {
-    Network(String),
-    UnexpectedInput(String),
-    InvalidUI(String),
+    // リクエスト先のURLと失敗の内容
+    Network { url: String, message: String },
+    // IPアドレスが見つからなかったホスト名
+    DnsResolutionFailed(String),
+    // 接続を確立できなかったか、応答が返ってこなかったリクエスト先のURL
+    ConnectionTimeout(String),
+    // 安全な接続を確立できなかったURL
+    TlsError(String),
+    // エラーを示すステータスコードと、それを返したURL
+    HttpError { status: u16, url: String },
+    // 解析できなかったURL
+    InvalidUrl(String),
+    // ボディが上限(バイト)を超えたレスポンスを返したURL
+    BodyTooLarge { url: String, limit: usize },
+    // ファイルシステムが使えない環境で、読み書きしようとしたパス
+    FileSystemUnavailable(String),
+    // 受け付けられなかった入力値とその理由
+    UnexpectedInput { input: String, message: String },
+    // 失敗したUI部品の名前と失敗の内容
+    InvalidUI { component: String, message: String },
     Other(String),
 }
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Network { url, message } => write!(f, "network error for {}: {}", url, message),
+            Error::DnsResolutionFailed(host) => write!(f, "could not resolve host {}", host),
+            Error::ConnectionTimeout(url) => write!(f, "connection to {} timed out", url),
+            Error::TlsError(url) => {
+                write!(f, "could not establish a secure connection to {}", url)
+            }
+            Error::HttpError { status, url } => write!(f, "{} returned status {}", url, status),
+            Error::InvalidUrl(url) => write!(f, "invalid URL: {}", url),
+            Error::BodyTooLarge { url, limit } => {
+                write!(f, "response body from {} exceeds {} bytes", url, limit)
+            }
+            Error::FileSystemUnavailable(path) => {
+                write!(
+                    f,
+                    "cannot access {}: filesystem access is not available",
+                    path
+                )
+            }
+            Error::UnexpectedInput { input, message } => write!(f, "{}: {:?}", message, input),
+            Error::InvalidUI { component, message } => write!(f, "{}: {}", component, message),
+            Error::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_display_network() {
+        let e = Error::Network {
+            url: "http://example.com".to_string(),
+            message: "connection refused".to_string(),
+        };
+        assert_eq!(
+            "network error for http://example.com: connection refused",
+            e.to_string()
+        );
+    }
+
+    #[test]
+    fn test_display_dns_resolution_failed() {
+        let e = Error::DnsResolutionFailed("example.invalid".to_string());
+        assert_eq!("could not resolve host example.invalid", e.to_string());
+    }
+
+    #[test]
+    fn test_display_connection_timeout() {
+        let e = Error::ConnectionTimeout("http://example.com".to_string());
+        assert_eq!("connection to http://example.com timed out", e.to_string());
+    }
+
+    #[test]
+    fn test_display_tls_error() {
+        let e = Error::TlsError("https://example.com".to_string());
+        assert_eq!(
+            "could not establish a secure connection to https://example.com",
+            e.to_string()
+        );
+    }
+
+    #[test]
+    fn test_display_http_error() {
+        let e = Error::HttpError {
+            status: 503,
+            url: "http://example.com".to_string(),
+        };
+        assert_eq!("http://example.com returned status 503", e.to_string());
+    }
+
+    #[test]
+    fn test_display_invalid_url() {
+        let e = Error::InvalidUrl("http://".to_string());
+        assert_eq!("invalid URL: http://", e.to_string());
+    }
+
+    #[test]
+    fn test_display_body_too_large() {
+        let e = Error::BodyTooLarge {
+            url: "http://example.com".to_string(),
+            limit: 1024,
+        };
+        assert_eq!(
+            "response body from http://example.com exceeds 1024 bytes",
+            e.to_string()
+        );
+    }
+
+    #[test]
+    fn test_display_file_system_unavailable() {
+        let e = Error::FileSystemUnavailable("/index.html".to_string());
+        assert_eq!(
+            "cannot access /index.html: filesystem access is not available",
+            e.to_string()
+        );
+    }
+
+    #[test]
+    fn test_display_unexpected_input() {
+        let e = Error::UnexpectedInput {
+            input: "blink".to_string(),
+            message: "display is not supported yet".to_string(),
+        };
+        assert_eq!("display is not supported yet: \"blink\"", e.to_string());
+    }
+}
@@ -0,0 +1,129 @@
+use crate::error::Error;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Debug;
+
+// file: URLのファイルを読み込む抽象
+// OSがファイルシステムを提供しない環境ではNoFileReaderを使い、テストではスタブに差し替えられる
+pub trait FileReader: Debug {
+    // pathは"/"から始まる絶対パス
+    fn read(&self, path: &str) -> Result<Vec<u8>, Error>;
+}
+
+// ファイルシステムが使えない環境向けの実装。常にエラーを返す
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoFileReader;
+
+impl FileReader for NoFileReader {
+    fn read(&self, path: &str) -> Result<Vec<u8>, Error> {
+        Err(Error::FileSystemUnavailable(path.to_string()))
+    }
+}
+
+// スクリーンショットなどをファイルに書き出す抽象
+pub trait FileWriter: Debug {
+    // pathは"/"から始まる絶対パス
+    fn write(&self, path: &str, bytes: &[u8]) -> Result<(), Error>;
+}
+
+// ファイルシステムが使えない環境向けの実装。常にエラーを返す
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoFileWriter;
+
+impl FileWriter for NoFileWriter {
+    fn write(&self, path: &str, _bytes: &[u8]) -> Result<(), Error> {
+        Err(Error::FileSystemUnavailable(path.to_string()))
+    }
+}
+
+// file:///path または file://localhost/path からパスを取り出す
+pub fn file_path(url: &str) -> Result<String, Error> {
+    let invalid = |message: &str| Error::UnexpectedInput {
+        input: url.to_string(),
+        message: message.to_string(),
+    };
+
+    let rest = match url.strip_prefix("file://") {
+        Some(rest) => rest,
+        None => return Err(invalid("not a file: URL")),
+    };
+    let path = match rest.strip_prefix("localhost") {
+        Some(path) => path,
+        None => rest,
+    };
+    if !path.starts_with('/') {
+        return Err(invalid("file: URL must have an absolute path"));
+    }
+
+    // クエリとフラグメントはファイルの読み込みには使わない
+    let end = path.find(['?', '#']).unwrap_or(path.len());
+    Ok(path[..end].to_string())
+}
+
+// ファイルの内容を文字列にする
+// BOMがあればそれに従い、なければUTF-8として読む。UTF-8として不正ならLatin-1とみなす
+pub fn decode_text(bytes: &[u8]) -> String {
+    if let Some(rest) = bytes.strip_prefix(&[0xef, 0xbb, 0xbf]) {
+        return String::from_utf8_lossy(rest).to_string();
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xff, 0xfe]) {
+        return decode_utf16(rest, u16::from_le_bytes);
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xfe, 0xff]) {
+        return decode_utf16(rest, u16::from_be_bytes);
+    }
+
+    match core::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => bytes.iter().map(|b| *b as char).collect(),
+    }
+}
+
+fn decode_utf16(bytes: &[u8], to_u16: fn([u8; 2]) -> u16) -> String {
+    let units = bytes.chunks_exact(2).map(|pair| to_u16([pair[0], pair[1]]));
+    char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_path() {
+        assert_eq!(
+            Ok("/home/user/index.html".to_string()),
+            file_path("file:///home/user/index.html")
+        );
+        assert_eq!(
+            Ok("/index.html".to_string()),
+            file_path("file://localhost/index.html#top")
+        );
+        assert!(file_path("file://index.html").is_err());
+        assert!(file_path("http://example.com/").is_err());
+    }
+
+    #[test]
+    fn test_decode_text() {
+        assert_eq!("あ", decode_text("あ".as_bytes()));
+        // UTF-8のBOMは取り除く
+        assert_eq!("a", decode_text(&[0xef, 0xbb, 0xbf, b'a']));
+        assert_eq!("ab", decode_text(&[0xff, 0xfe, b'a', 0, b'b', 0]));
+        assert_eq!("ab", decode_text(&[0xfe, 0xff, 0, b'a', 0, b'b']));
+        // UTF-8として不正なバイト列はLatin-1として読む
+        assert_eq!("café", decode_text(&[b'c', b'a', b'f', 0xe9]));
+    }
+
+    #[test]
+    fn test_no_file_reader() {
+        assert_eq!(
+            Err(Error::FileSystemUnavailable("/index.html".to_string())),
+            NoFileReader.read("/index.html")
+        );
+        assert_eq!(
+            Err(Error::FileSystemUnavailable("/screenshot.ppm".to_string())),
+            NoFileWriter.write("/screenshot.ppm", b"P6")
+        );
+    }
+}
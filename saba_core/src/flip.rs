@@ -0,0 +1,234 @@
+use crate::display_item::DisplayItem;
+use crate::renderer::layout::layout_object::LayoutPoint;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// FLIP(First, Last, Invert, Play)アニメーションの状態
+// 同じテキストを持つ表示項目を同じ要素とみなし、変更前の位置から変更後の位置へ動かす
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlipState {
+    text: String,
+    // 同じテキストが複数ある場合に、何番目の項目かを区別する
+    occurrence: usize,
+    // 変更後の位置から変更前の位置へのずれ(Invert)
+    invert_x: i64,
+    invert_y: i64,
+    frame: usize,
+    total_frames: usize,
+}
+
+impl FlipState {
+    pub fn new(
+        text: String,
+        occurrence: usize,
+        first: LayoutPoint,
+        last: LayoutPoint,
+        total_frames: usize,
+    ) -> Self {
+        Self {
+            text,
+            occurrence,
+            invert_x: first.x() - last.x(),
+            invert_y: first.y() - last.y(),
+            frame: 0,
+            total_frames,
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.text.clone()
+    }
+
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.total_frames
+    }
+
+    // 次のフレームに進める(Play)
+    pub fn advance(&mut self) {
+        if !self.is_finished() {
+            self.frame += 1;
+        }
+    }
+
+    // 現在のフレームで変更後の位置に加えるずれ
+    // ease-out(3次)で、残りの割合は(1 - t)^3になる
+    pub fn offset(&self) -> (i64, i64) {
+        if self.is_finished() {
+            return (0, 0);
+        }
+        let total = self.total_frames as i64;
+        let remaining = (self.total_frames - self.frame) as i64;
+        let numerator = remaining * remaining * remaining;
+        let denominator = total * total * total;
+        (
+            self.invert_x * numerator / denominator,
+            self.invert_y * numerator / denominator,
+        )
+    }
+}
+
+// 変更前(First)と変更後(Last)の表示項目を比べ、位置が変わったテキストのFLIPの状態を作る
+pub fn flip_states(
+    first: &[DisplayItem],
+    last: &[DisplayItem],
+    total_frames: usize,
+) -> Vec<FlipState> {
+    if total_frames == 0 {
+        return Vec::new();
+    }
+
+    let first_points = text_points(first);
+    let mut states = Vec::new();
+
+    for (text, occurrence, last_point) in text_points(last) {
+        let first_point = first_points
+            .iter()
+            .find(|(t, o, _)| *t == text && *o == occurrence)
+            .map(|(_, _, point)| *point);
+        match first_point {
+            Some(first_point) if first_point != last_point => states.push(FlipState::new(
+                text,
+                occurrence,
+                first_point,
+                last_point,
+                total_frames,
+            )),
+            _ => {}
+        }
+    }
+
+    states
+}
+
+// FLIPの状態に従って、表示項目を現在のフレームの位置にずらしたものを返す
+pub fn apply_flip(items: &[DisplayItem], states: &[FlipState]) -> Vec<DisplayItem> {
+    let mut occurrences: Vec<(String, usize)> = Vec::new();
+
+    items
+        .iter()
+        .map(|item| match item {
+            DisplayItem::Text {
+                text,
+                style,
+                layout_point,
+            } => {
+                let occurrence = next_occurrence(&mut occurrences, text);
+                let (dx, dy) = states
+                    .iter()
+                    .find(|s| s.text == *text && s.occurrence == occurrence)
+                    .map(|s| s.offset())
+                    .unwrap_or((0, 0));
+                DisplayItem::Text {
+                    text: text.clone(),
+                    style: style.clone(),
+                    layout_point: LayoutPoint::new(layout_point.x() + dx, layout_point.y() + dy),
+                }
+            }
+            _ => item.clone(),
+        })
+        .collect()
+}
+
+fn text_points(items: &[DisplayItem]) -> Vec<(String, usize, LayoutPoint)> {
+    let mut occurrences: Vec<(String, usize)> = Vec::new();
+    let mut points = Vec::new();
+
+    for item in items {
+        if let DisplayItem::Text {
+            text, layout_point, ..
+        } = item
+        {
+            let occurrence = next_occurrence(&mut occurrences, text);
+            points.push((text.clone(), occurrence, *layout_point));
+        }
+    }
+
+    points
+}
+
+fn next_occurrence(occurrences: &mut Vec<(String, usize)>, text: &str) -> usize {
+    match occurrences.iter_mut().find(|(t, _)| t == text) {
+        Some((_, count)) => {
+            *count += 1;
+            *count - 1
+        }
+        None => {
+            occurrences.push((String::from(text), 1));
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::layout::computed_style::ComputedStyle;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    fn text_item(text: &str, x: i64, y: i64) -> DisplayItem {
+        DisplayItem::Text {
+            text: text.to_string(),
+            style: ComputedStyle::new(),
+            layout_point: LayoutPoint::new(x, y),
+        }
+    }
+
+    fn points(items: &[DisplayItem]) -> Vec<(i64, i64)> {
+        items
+            .iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text { layout_point, .. } => {
+                    Some((layout_point.x(), layout_point.y()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_no_movement() {
+        let items = vec![text_item("a", 0, 0)];
+        assert!(flip_states(&items, &items, 4).is_empty());
+    }
+
+    #[test]
+    fn test_transition_two_elements() {
+        let first = vec![text_item("a", 0, 0), text_item("b", 0, 20)];
+        // "a"と"b"の順番が入れ替わる
+        let last = vec![text_item("b", 0, 0), text_item("a", 40, 20)];
+
+        let mut states = flip_states(&first, &last, 4);
+        assert_eq!(2, states.len());
+
+        // 残りの割合: (4/4)^3, (3/4)^3, (2/4)^3, (1/4)^3, 0
+        let expected = [
+            vec![(0, 20), (0, 0)],
+            vec![(0, 8), (24, 12)],
+            vec![(0, 2), (35, 18)],
+            vec![(0, 0), (40, 20)],
+            vec![(0, 0), (40, 20)],
+        ];
+        for positions in expected {
+            assert_eq!(positions, points(&apply_flip(&last, &states)));
+            for state in states.iter_mut() {
+                state.advance();
+            }
+        }
+        assert!(states.iter().all(|s| s.is_finished()));
+    }
+
+    #[test]
+    fn test_same_text_occurrence() {
+        let first = vec![text_item("x", 0, 0), text_item("x", 0, 20)];
+        let last = vec![text_item("x", 0, 0), text_item("x", 0, 40)];
+
+        let states = flip_states(&first, &last, 2);
+        assert_eq!(1, states.len());
+        assert_eq!(vec![(0, 0), (0, 20)], points(&apply_flip(&last, &states)));
+    }
+}
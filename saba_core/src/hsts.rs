@@ -0,0 +1,119 @@
+use crate::url::host_of;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+
+// Strict-Transport-Securityヘッダーを受け取ったホストと、その有効期限(秒)を保存する
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HstsStore {
+    entries: BTreeMap<String, u64>,
+}
+
+impl Default for HstsStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HstsStore {
+    pub fn new() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+        }
+    }
+
+    // max-age=0の場合は登録を削除する
+    pub fn insert(&mut self, host: &str, header: &str, now: u64) {
+        let max_age = match parse_max_age(header) {
+            Some(max_age) => max_age,
+            None => return,
+        };
+
+        if max_age == 0 {
+            self.entries.remove(host);
+        } else {
+            self.entries
+                .insert(host.to_string(), now.saturating_add(max_age));
+        }
+    }
+
+    pub fn is_active(&self, host: &str, now: u64) -> bool {
+        match self.entries.get(host) {
+            Some(expiry) => now < *expiry,
+            None => false,
+        }
+    }
+
+    // 有効な登録があるホストへのhttp://のURLをhttps://に書き換える
+    pub fn upgrade(&self, url: &str, now: u64) -> String {
+        match url.strip_prefix("http://") {
+            Some(rest) if self.is_active(&host_of(url), now) => {
+                let mut upgraded = String::from("https://");
+                upgraded.push_str(rest);
+                upgraded
+            }
+            _ => url.to_string(),
+        }
+    }
+}
+
+fn parse_max_age(header: &str) -> Option<u64> {
+    for directive in header.split(';') {
+        let (name, value) = match directive.split_once('=') {
+            Some((name, value)) => (name.trim(), value.trim().trim_matches('"')),
+            None => continue,
+        };
+        if name.eq_ignore_ascii_case("max-age") {
+            return value.parse::<u64>().ok();
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert() {
+        let mut store = HstsStore::new();
+        store.insert("example.com", "max-age=100; includeSubDomains", 10);
+        assert!(store.is_active("example.com", 10));
+        assert!(store.is_active("example.com", 109));
+        assert!(!store.is_active("example.com", 110));
+        assert!(!store.is_active("other.com", 10));
+    }
+
+    #[test]
+    fn test_max_age_zero_removes_entry() {
+        let mut store = HstsStore::new();
+        store.insert("example.com", "max-age=100", 0);
+        store.insert("example.com", "max-age=0", 1);
+        assert!(!store.is_active("example.com", 1));
+    }
+
+    #[test]
+    fn test_invalid_header() {
+        let mut store = HstsStore::new();
+        store.insert("example.com", "includeSubDomains", 0);
+        store.insert("example.com", "max-age=abc", 0);
+        assert!(!store.is_active("example.com", 0));
+    }
+
+    #[test]
+    fn test_upgrade() {
+        let mut store = HstsStore::new();
+        store.insert("example.com", "max-age=31536000", 0);
+        assert_eq!(
+            "https://example.com:8000/index.html",
+            store.upgrade("http://example.com:8000/index.html", 0)
+        );
+        assert_eq!(
+            "http://other.com/index.html",
+            store.upgrade("http://other.com/index.html", 0)
+        );
+        assert_eq!(
+            "http://example.com/",
+            store.upgrade("http://example.com/", 31536000)
+        );
+    }
+}
@@ -1,10 +1,73 @@
 use crate::alloc::string::ToString;
 use crate::error::Error;
+use crate::url::Url;
+use alloc::collections::BTreeMap;
 use alloc::format;
 use alloc::string::String;
 use alloc::vec::Vec;
+use core::fmt::Debug;
+
+// HTTPリクエストを送ってレスポンスを受け取る通信層
+// 実際のネットワーク実装はnet_wasabiにあり、テストではモックに差し替えられる
+pub trait HttpTransport: Debug {
+    fn request(&self, request: HttpRequest) -> Result<HttpResponse, Error>;
+}
+
+// ナビゲーションやサブリソースの取得で送るリクエスト
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpRequest {
+    url: String,
+    headers: BTreeMap<String, String>,
+}
+
+impl HttpRequest {
+    pub fn new(url: String) -> Self {
+        Self {
+            url,
+            headers: BTreeMap::new(),
+        }
+    }
+
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    pub fn headers(&self) -> BTreeMap<String, String> {
+        self.headers.clone()
+    }
+
+    pub fn set_header(&mut self, name: &str, value: String) {
+        self.headers.insert(name.to_string(), value);
+    }
+
+    pub fn header_value(&self, name: &str) -> Option<String> {
+        self.headers.get(name).cloned()
+    }
+
+    pub fn builder(url: String) -> HttpRequestBuilder {
+        HttpRequestBuilder {
+            request: Self::new(url),
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
+pub struct HttpRequestBuilder {
+    request: HttpRequest,
+}
+
+impl HttpRequestBuilder {
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.request.set_header(name, value.to_string());
+        self
+    }
+
+    pub fn build(self) -> HttpRequest {
+        self.request
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct HttpResponse {
     version: String,
     status_code: u32,
@@ -13,7 +76,7 @@ pub struct HttpResponse {
     body: String,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Header {
     name: String,
     value: String,
@@ -31,10 +94,10 @@ impl HttpResponse {
         let (status_line, remaining) = match preprocessed_response.split_once('\n') {
             Some((s, r)) => (s, r),
             None => {
-                return Err(Error::Network(format!(
-                    "invalid http response: {}",
-                    preprocessed_response
-                )))
+                return Err(Error::UnexpectedInput {
+                    input: preprocessed_response,
+                    message: "invalid http response".to_string(),
+                })
             }
         };
         let (headers, body) = match remaining.split_once("\n\n") {
@@ -61,6 +124,15 @@ impl HttpResponse {
         })
     }
 
+    // 生のHTTPメッセージを組み立ててパースする代わりに使う。ステータスは200から始まる
+    pub fn builder() -> HttpResponseBuilder {
+        HttpResponseBuilder {
+            status_code: 200,
+            headers: Vec::new(),
+            body: String::new(),
+        }
+    }
+
     pub fn version(&self) -> String {
         self.version.clone()
     }
@@ -85,6 +157,444 @@ impl HttpResponse {
 
         Err(format!("failed to find {} in headers", name))
     }
+
+    // Content-Typeヘッダーのメディアタイプ。charsetなどのパラメータは取り除き、小文字にする
+    pub fn content_type(&self) -> Option<String> {
+        let value = self.header_value("Content-Type").ok()?;
+        let media_type = value.split(';').next().unwrap_or("").trim();
+        if media_type.is_empty() {
+            return None;
+        }
+        Some(media_type.to_ascii_lowercase())
+    }
+
+    // Set-Cookieのように複数回現れるヘッダーの値をすべて返す
+    pub fn header_values(&self, name: &str) -> Vec<String> {
+        self.headers
+            .iter()
+            .filter(|h| h.name.eq_ignore_ascii_case(name))
+            .map(|h| h.value.clone())
+            .collect()
+    }
+
+    // リダイレクトのレスポンスなら、urlを基準に解決したLocationの移動先を返す
+    pub fn redirect_location(&self, url: &str) -> Option<String> {
+        if !matches!(self.status_code, 301 | 302 | 303 | 307 | 308) {
+            return None;
+        }
+        let location = self.header_value("Location").ok()?;
+        if location.starts_with("https://") {
+            return Some(location);
+        }
+        match Url::new(url.to_string()).parse() {
+            Ok(base) => Some(base.resolve(&location)),
+            Err(_) => Some(location),
+        }
+    }
+
+    // エラーを示すステータスで、表示できるボディもないレスポンスをエラーにする
+    // ボディがあれば、サーバーが用意したエラーページとしてそのまま表示する
+    pub fn error_for_status(self, url: &str) -> Result<Self, Error> {
+        if self.status_code >= 400 && self.body.trim().is_empty() {
+            return Err(Error::HttpError {
+                status: self.status_code as u16,
+                url: url.to_string(),
+            });
+        }
+        Ok(self)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpResponseBuilder {
+    status_code: u16,
+    headers: Vec<Header>,
+    body: String,
+}
+
+impl HttpResponseBuilder {
+    pub fn status(mut self, code: u16) -> Self {
+        self.status_code = code;
+        self
+    }
+
+    // 同じ名前のヘッダーを複数回追加できる
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers
+            .push(Header::new(name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn body(mut self, text: String) -> Self {
+        self.body = text;
+        self
+    }
+
+    pub fn build(self) -> HttpResponse {
+        HttpResponse {
+            version: "HTTP/1.1".to_string(),
+            status_code: self.status_code as u32,
+            reason: reason_phrase(self.status_code).to_string(),
+            headers: self.headers,
+            body: self.body,
+        }
+    }
+}
+
+// よく使うステータスコードの理由句。それ以外は空にする
+fn reason_phrase(status_code: u16) -> &'static str {
+    match status_code {
+        200 => "OK",
+        204 => "No Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        500 => "Internal Server Error",
+        _ => "",
+    }
+}
+
+// Content-Typeのないレスポンスのメディアタイプをボディの先頭から推測する
+// https://mimesniff.spec.whatwg.org/#identifying-a-resource-with-an-unknown-mime-type
+pub fn sniff_mime_type(body: &str) -> &'static str {
+    let head = body.trim_start();
+
+    if starts_with_ignore_case(head, "<!doctype html") || starts_with_ignore_case(head, "<!--") {
+        return "text/html";
+    }
+    // タグ名の後に空白か'>'が続くときだけHTMLとみなす
+    for tag in HTML_SNIFF_TAGS {
+        if let Some(rest) = strip_prefix_ignore_case(head, tag) {
+            if rest.starts_with(|c: char| c == '>' || c.is_ascii_whitespace()) {
+                return "text/html";
+            }
+        }
+    }
+
+    if head.starts_with('{') || head.starts_with('[') {
+        return "application/json";
+    }
+    "text/plain"
+}
+
+static HTML_SNIFF_TAGS: [&str; 15] = [
+    "<html", "<head", "<script", "<iframe", "<h1", "<div", "<font", "<table", "<a", "<style",
+    "<title", "<b", "<body", "<br", "<p",
+];
+
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = s.get(..prefix.len())?;
+    if head.eq_ignore_ascii_case(prefix) {
+        Some(&s[prefix.len()..])
+    } else {
+        None
+    }
+}
+
+fn starts_with_ignore_case(s: &str, prefix: &str) -> bool {
+    strip_prefix_ignore_case(s, prefix).is_some()
+}
+
+// multipart/form-dataの境界文字列を作る。乱数源がないので、呼び出し側が渡すseedから作る
+// 同じseedからは同じ境界文字列ができる
+pub fn multipart_boundary(seed: u64) -> String {
+    // xorshift64。0のままだと0しか出ないので、0は別の値に置き換える
+    let mut x = if seed == 0 {
+        0x2545_f491_4f6c_dd1d
+    } else {
+        seed
+    };
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    format!("----SabaFormBoundary{:016x}", x)
+}
+
+// フォームのフィールドをmultipart/form-dataでエンコードする
+// https://www.rfc-editor.org/rfc/rfc7578
+pub fn encode_multipart(fields: &[(String, String)], boundary: &str) -> Vec<u8> {
+    let mut body = String::new();
+    for (name, value) in fields {
+        body.push_str(&format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+            boundary,
+            escape_field_name(name),
+            value
+        ));
+    }
+    body.push_str(&format!("--{}--\r\n", boundary));
+    body.into_bytes()
+}
+
+// nameの引用符と改行はヘッダーを壊すので、HTMLの仕様と同じようにパーセントエンコードする
+fn escape_field_name(name: &str) -> String {
+    name.replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+// application/x-www-form-urlencodedの文字列をパースする
+// 同じ名前が複数回現れたときは、URLSearchParams.get()と同じように最初の値を使う
+// https://url.spec.whatwg.org/#urlencoded-parsing
+pub fn url_decode(s: &str) -> BTreeMap<String, String> {
+    let mut params = BTreeMap::new();
+    for pair in s.split('&').filter(|pair| !pair.is_empty()) {
+        let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+        params
+            .entry(decode_form_component(name))
+            .or_insert_with(|| decode_form_component(value));
+    }
+    params
+}
+
+// パラメータをapplication/x-www-form-urlencodedでエンコードする。名前の順に並べる
+// https://url.spec.whatwg.org/#urlencoded-serializing
+pub fn url_encode(params: &BTreeMap<String, String>) -> String {
+    url_encode_pairs(
+        &params
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect::<Vec<(String, String)>>(),
+    )
+}
+
+// 名前と値の組を、並んでいる順のままapplication/x-www-form-urlencodedでエンコードする
+pub fn url_encode_pairs(pairs: &[(String, String)]) -> String {
+    pairs
+        .iter()
+        .map(|(name, value)| {
+            format!(
+                "{}={}",
+                encode_form_component(name),
+                encode_form_component(value)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("&")
+}
+
+// '+'は空白に戻し、%HHはバイトに戻す。不正なエスケープはそのまま残す
+fn decode_form_component(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut result = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => result.push(b' '),
+            b'%' => match s.get(i + 1..i + 3).map(|hex| u8::from_str_radix(hex, 16)) {
+                Some(Ok(byte)) => {
+                    result.push(byte);
+                    i += 3;
+                    continue;
+                }
+                _ => result.push(b'%'),
+            },
+            b => result.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&result).to_string()
+}
+
+// 英数字と"*-._"以外はUTF-8のバイトごとに%HHにする。空白は'+'にする
+fn encode_form_component(s: &str) -> String {
+    let mut result = String::new();
+    for byte in s.bytes() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'*' | b'-' | b'.' | b'_' => {
+                result.push(byte as char)
+            }
+            b' ' => result.push('+'),
+            _ => result.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    result
+}
+
+// ステータス行とヘッダーに許す大きさ(バイト)
+static MAX_HEAD_SIZE: usize = 16 * 1024;
+
+// ソケットから受け取ったバイト列を溜めて、レスポンスにする
+// ボディが上限を超えたら、それ以上溜めずにエラーを返す
+// Content-Lengthやチャンクの大きさは、そのバイト列を受け取る前に上限と比べる
+// 宣言より多く送られてきた場合は、実際に受け取ったバイト数で比べる
+#[derive(Debug, Clone)]
+pub struct ResponseReader {
+    url: String,
+    max_body_size: usize,
+    received: Vec<u8>,
+}
+
+impl ResponseReader {
+    pub fn new(url: String, max_body_size: usize) -> Self {
+        Self {
+            url,
+            max_body_size,
+            received: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.received.extend_from_slice(bytes);
+
+        // ヘッダーを受け取り終わるまでは、ボディの大きさはわからない
+        // 空行が届かないままヘッダーだけが増え続けないように、ヘッダーの大きさも制限する
+        let (headers, body) = match split_head(&self.received) {
+            Some(split) => split,
+            None if self.received.len() > MAX_HEAD_SIZE => {
+                self.received = Vec::new();
+                return Err(Error::Network {
+                    url: self.url.clone(),
+                    message: format!("response headers exceed {} bytes", MAX_HEAD_SIZE),
+                });
+            }
+            None => return Ok(()),
+        };
+        let headers = String::from_utf8_lossy(headers);
+        let body_size = if let Some(length) = find_header(&headers, "Content-Length") {
+            length.parse::<usize>().unwrap_or(0).max(body.len())
+        } else if find_header(&headers, "Transfer-Encoding")
+            .is_some_and(|v| v.eq_ignore_ascii_case("chunked"))
+        {
+            chunked_body_size(body)
+        } else {
+            // 長さのわからないボディは、接続が閉じられるまで読む
+            body.len()
+        };
+
+        // チャンクの区切りやトレーラーを含めても、溜めるのはヘッダーとボディの上限の合計まで
+        if body_size > self.max_body_size
+            || self.received.len() > MAX_HEAD_SIZE.saturating_add(self.max_body_size)
+        {
+            self.received = Vec::new();
+            return Err(Error::BodyTooLarge {
+                url: self.url.clone(),
+                limit: self.max_body_size,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<HttpResponse, Error> {
+        match core::str::from_utf8(&self.received) {
+            Ok(response) => HttpResponse::new(response.to_string()),
+            Err(e) => Err(Error::Network {
+                url: self.url,
+                message: format!("Invalid received response: {}", e),
+            }),
+        }
+    }
+}
+
+// ヘッダーとボディの境目(空行)で分ける。HttpResponse::newと同じく、改行は\r\nでも\nでもよい
+fn split_head(received: &[u8]) -> Option<(&[u8], &[u8])> {
+    for i in 0..received.len() {
+        if received[i..].starts_with(b"\r\n\r\n") {
+            return Some((&received[..i], &received[i + 4..]));
+        }
+        if received[i..].starts_with(b"\n\n") {
+            return Some((&received[..i], &received[i + 2..]));
+        }
+    }
+    None
+}
+
+// ステータス行に続くヘッダーから、名前の一致するものの値を探す。名前の大文字と小文字は区別しない
+fn find_header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+    head.lines().skip(1).find_map(|line| {
+        let (n, v) = line.split_once(':')?;
+        if n.trim().eq_ignore_ascii_case(name) {
+            Some(v.trim())
+        } else {
+            None
+        }
+    })
+}
+
+// チャンク形式のボディで、これまでに受け取ったチャンクの大きさの合計
+// データがまだ届いていないチャンクも、宣言された大きさで数える
+// チャンクの大きさの行が読めないときは、そこから後に受け取ったバイト数をそのまま足す
+// https://www.rfc-editor.org/rfc/rfc9112#section-7.1
+fn chunked_body_size(body: &[u8]) -> usize {
+    let mut total: usize = 0;
+    let mut pos = 0;
+    while let Some(line_len) = body[pos..].iter().position(|b| *b == b'\n') {
+        let line = String::from_utf8_lossy(&body[pos..pos + line_len]);
+        // チャンク拡張(;の後)は無視する
+        let size = line.split(';').next().unwrap_or("").trim();
+        let size = match usize::from_str_radix(size, 16) {
+            Ok(size) => size,
+            Err(_) => return total.saturating_add(body.len() - pos),
+        };
+        if size == 0 {
+            break;
+        }
+        total = total.saturating_add(size);
+
+        // データの後の改行を飛ばして、次のチャンクの先頭に進む
+        pos += line_len + 1 + size;
+        if body[pos.min(body.len())..].starts_with(b"\r") {
+            pos += 1;
+        }
+        pos += 1;
+        if pos >= body.len() {
+            break;
+        }
+    }
+    total
+}
+
+// 取得したリソースの情報。開発者向けに表示する
+// 圧縮には対応していないので、raw_sizeはContent-Lengthヘッダー(なければボディの長さ)を使う
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourceStat {
+    url: String,
+    raw_size: usize,
+    decompressed_size: usize,
+    content_type: String,
+    status_code: u16,
+}
+
+impl ResourceStat {
+    pub fn new(url: String, response: &HttpResponse) -> Self {
+        let decompressed_size = response.body().len();
+        let raw_size = response
+            .header_value("Content-Length")
+            .ok()
+            .and_then(|length| length.parse::<usize>().ok())
+            .unwrap_or(decompressed_size);
+
+        Self {
+            url,
+            raw_size,
+            decompressed_size,
+            content_type: response.header_value("Content-Type").unwrap_or_default(),
+            status_code: response.status_code() as u16,
+        }
+    }
+
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    pub fn raw_size(&self) -> usize {
+        self.raw_size
+    }
+
+    pub fn decompressed_size(&self) -> usize {
+        self.decompressed_size
+    }
+
+    pub fn content_type(&self) -> String {
+        self.content_type.clone()
+    }
+
+    pub fn status_code(&self) -> u16 {
+        self.status_code
+    }
 }
 
 #[cfg(test)]
@@ -134,9 +644,369 @@ mod tests {
         assert_eq!(res.body(), "body message".to_string());
     }
 
+    #[test]
+    fn test_header_values() {
+        let raw = "HTTP/1.1 200 OK\nSet-Cookie: a=1\nset-cookie: b=2\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+        assert_eq!(
+            ["a=1".to_string(), "b=2".to_string()].to_vec(),
+            res.header_values("Set-Cookie")
+        );
+        assert!(res.header_values("Date").is_empty());
+    }
+
+    #[test]
+    fn test_content_type() {
+        let raw = "HTTP/1.1 200 OK\nContent-Type: Text/HTML; charset=utf-8\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+        assert_eq!(Some("text/html".to_string()), res.content_type());
+
+        let raw = "HTTP/1.1 200 OK\nDate: xx\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+        assert_eq!(None, res.content_type());
+    }
+
+    #[test]
+    fn test_sniff_mime_type() {
+        assert_eq!("text/html", sniff_mime_type("<!DOCTYPE html><html></html>"));
+        assert_eq!("text/html", sniff_mime_type("\n  <HTML lang=\"ja\">"));
+        assert_eq!("text/html", sniff_mime_type("<p>text</p>"));
+        assert_eq!("application/json", sniff_mime_type("{\"a\": 1}"));
+        assert_eq!("application/json", sniff_mime_type(" [1, 2]"));
+        assert_eq!("text/plain", sniff_mime_type("hello <html>"));
+        // タグ名の途中で終わっているものはHTMLとみなさない
+        assert_eq!("text/plain", sniff_mime_type("<pre>"));
+        assert_eq!("text/plain", sniff_mime_type(""));
+    }
+
     #[test]
     fn test_invalid() {
         let raw = "HTTP/1.1 200 OK".to_string();
         assert!(HttpResponse::new(raw).is_err());
     }
+
+    fn params(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_url_encode() {
+        assert_eq!(
+            "a=1&q=hello+world%21",
+            url_encode(&params(&[("q", "hello world!"), ("a", "1")]))
+        );
+        assert_eq!(
+            "name=%E3%81%95%E3%81%B0",
+            url_encode(&params(&[("name", "さば")]))
+        );
+        assert_eq!("", url_encode(&BTreeMap::new()));
+    }
+
+    #[test]
+    fn test_url_decode() {
+        assert_eq!(
+            params(&[("q", "hello world!"), ("a", "1")]),
+            url_decode("q=hello+world%21&a=1")
+        );
+        // 値のない名前、空のペア、不正なエスケープ
+        assert_eq!(
+            params(&[("flag", ""), ("p", "100%"), ("x", "%zz")]),
+            url_decode("flag&&p=100%&x=%zz")
+        );
+        // 同じ名前が複数回現れたときは最初の値を使う
+        assert_eq!(params(&[("tag", "a")]), url_decode("tag=a&tag=b"));
+    }
+
+    #[test]
+    fn test_url_encoding_round_trip() {
+        let original = params(&[
+            ("name", "さば 🐟"),
+            ("special", "a&b=c+d%e/?#"),
+            ("empty", ""),
+            ("a b", "x\ny"),
+        ]);
+        assert_eq!(original, url_decode(&url_encode(&original)));
+    }
+
+    // テスト用の最小限のmultipart/form-dataのパーサー
+    fn parse_multipart(body: &[u8], boundary: &str) -> Vec<(String, String)> {
+        let body = core::str::from_utf8(body).expect("body should be UTF-8");
+        let delimiter = format!("--{}", boundary);
+        let rest = body
+            .strip_suffix(&format!("{}--\r\n", delimiter))
+            .expect("body should end with the close delimiter");
+
+        rest.split(&delimiter)
+            .filter(|part| !part.is_empty())
+            .map(|part| {
+                let part = part
+                    .strip_prefix("\r\n")
+                    .expect("part should start with CRLF");
+                let (headers, value) = part
+                    .split_once("\r\n\r\n")
+                    .expect("part should have headers");
+                let name = headers
+                    .strip_prefix("Content-Disposition: form-data; name=\"")
+                    .and_then(|h| h.strip_suffix('"'))
+                    .expect("part should have a name");
+                let value = value
+                    .strip_suffix("\r\n")
+                    .expect("value should end with CRLF");
+                (name.to_string(), value.to_string())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_encode_multipart() {
+        let fields = [("a".to_string(), "1".to_string())];
+        assert_eq!(
+            b"--XX\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\n1\r\n--XX--\r\n".to_vec(),
+            encode_multipart(&fields, "XX")
+        );
+        assert_eq!(b"--XX--\r\n".to_vec(), encode_multipart(&[], "XX"));
+    }
+
+    #[test]
+    fn test_multipart_round_trip() {
+        let fields = [
+            ("name".to_string(), "saba".to_string()),
+            ("comment".to_string(), "line1\r\nline2".to_string()),
+            ("empty".to_string(), String::new()),
+            ("file".to_string(), "<html></html>".to_string()),
+        ];
+        let boundary = multipart_boundary(1);
+
+        let body = encode_multipart(&fields, &boundary);
+        assert_eq!(fields.to_vec(), parse_multipart(&body, &boundary));
+    }
+
+    #[test]
+    fn test_multipart_escapes_field_name() {
+        let fields = [("a\"b\nc".to_string(), "v".to_string())];
+        assert_eq!(
+            [("a%22b%0Ac".to_string(), "v".to_string())].to_vec(),
+            parse_multipart(&encode_multipart(&fields, "XX"), "XX")
+        );
+    }
+
+    #[test]
+    fn test_multipart_boundary() {
+        assert_eq!(multipart_boundary(1), multipart_boundary(1));
+        assert_ne!(multipart_boundary(1), multipart_boundary(2));
+        assert!(multipart_boundary(0).starts_with("----SabaFormBoundary"));
+        assert_ne!(
+            multipart_boundary(0),
+            "----SabaFormBoundary0000000000000000"
+        );
+    }
+
+    #[test]
+    fn test_request_headers() {
+        let mut request = HttpRequest::new("http://example.com".to_string());
+        assert_eq!(None, request.header_value("Referer"));
+
+        request.set_header("Referer", "http://example.com/a".to_string());
+        assert_eq!(
+            Some("http://example.com/a".to_string()),
+            request.header_value("Referer")
+        );
+        assert_eq!("http://example.com", request.url());
+    }
+
+    #[test]
+    fn test_request_builder() {
+        let mut request = HttpRequest::new("http://example.com".to_string());
+        request.set_header("Referer", "http://example.com/a".to_string());
+        assert_eq!(
+            request,
+            HttpRequest::builder("http://example.com".to_string())
+                .header("Referer", "http://example.com/a")
+                .build()
+        );
+    }
+
+    #[test]
+    fn test_response_builder() {
+        let raw =
+            "HTTP/1.1 302 Found\nContent-Type: text/html\nSet-Cookie: a=1\nSet-Cookie: b=2\n\nbody"
+                .to_string();
+        let expected = HttpResponse::new(raw).expect("failed to parse http response");
+        let built = HttpResponse::builder()
+            .status(302)
+            .header("Content-Type", "text/html")
+            .header("Set-Cookie", "a=1")
+            .header("Set-Cookie", "b=2")
+            .body("body".to_string())
+            .build();
+        assert_eq!(expected, built);
+        assert_eq!(
+            ["a=1".to_string(), "b=2".to_string()].to_vec(),
+            built.header_values("Set-Cookie")
+        );
+    }
+
+    #[test]
+    fn test_response_builder_defaults() {
+        let response = HttpResponse::builder().build();
+        assert_eq!("HTTP/1.1", response.version());
+        assert_eq!(200, response.status_code());
+        assert_eq!("OK", response.reason());
+        assert!(response.headers().is_empty());
+        assert_eq!("", response.body());
+    }
+
+    #[test]
+    fn test_error_for_status() {
+        let url = "http://example.com/a.html";
+        assert!(HttpResponse::builder()
+            .build()
+            .error_for_status(url)
+            .is_ok());
+        assert_eq!(
+            Err(Error::HttpError {
+                status: 404,
+                url: url.to_string()
+            }),
+            HttpResponse::builder()
+                .status(404)
+                .body("\n".to_string())
+                .build()
+                .error_for_status(url)
+        );
+        // サーバーのエラーページは表示する
+        let response = HttpResponse::builder()
+            .status(500)
+            .body("<html><body>oops</body></html>".to_string())
+            .build();
+        assert_eq!(Ok(response.clone()), response.error_for_status(url));
+    }
+
+    #[test]
+    fn test_response_builder_reason_phrase() {
+        let response = HttpResponse::builder().status(404).build();
+        assert_eq!(404, response.status_code());
+        assert_eq!("Not Found", response.reason());
+        assert_eq!("", HttpResponse::builder().status(299).build().reason());
+    }
+
+    #[test]
+    fn test_resource_stat() {
+        let raw =
+            "HTTP/1.1 404 NotFound\nContent-Type: text/css\nContent-Length: 20\n\nbody".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+        let stat = ResourceStat::new("http://example.com/a.css".to_string(), &res);
+        assert_eq!("http://example.com/a.css", stat.url());
+        assert_eq!(20, stat.raw_size());
+        assert_eq!(4, stat.decompressed_size());
+        assert_eq!("text/css", stat.content_type());
+        assert_eq!(404, stat.status_code());
+    }
+
+    fn read_response(chunks: &[&str], max_body_size: usize) -> Result<HttpResponse, Error> {
+        let mut reader = ResponseReader::new("http://example.com".to_string(), max_body_size);
+        for chunk in chunks {
+            reader.push(chunk.as_bytes())?;
+        }
+        reader.finish()
+    }
+
+    #[test]
+    fn test_response_reader() {
+        let res = read_response(
+            &["HTTP/1.1 200 OK\r\nContent-", "Length: 5\r\n\r\nhe", "llo"],
+            5,
+        )
+        .expect("response within the limit should be read");
+        assert_eq!("hello", res.body());
+    }
+
+    #[test]
+    fn test_response_reader_content_length_over_limit() {
+        // ボディを受け取る前に、Content-Lengthで上限を超えるとわかる
+        assert_eq!(
+            Err(Error::BodyTooLarge {
+                url: "http://example.com".to_string(),
+                limit: 10,
+            }),
+            read_response(&["HTTP/1.1 200 OK\ncontent-length: 11\n\n"], 10)
+        );
+    }
+
+    #[test]
+    fn test_response_reader_chunked_over_limit() {
+        let head = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n";
+        let res = read_response(
+            &[head, "4\r\nabcd\r\n", "6;ext=1\r\nefghij\r\n0\r\n\r\n"],
+            10,
+        );
+        assert!(res.is_ok());
+
+        // 2つ目のチャンクの大きさを受け取った時点でエラーになる
+        assert_eq!(
+            Err(Error::BodyTooLarge {
+                url: "http://example.com".to_string(),
+                limit: 8,
+            }),
+            read_response(&[head, "4\r\nabcd\r\n", "5\r\n"], 8)
+        );
+    }
+
+    #[test]
+    fn test_response_reader_without_length_over_limit() {
+        // 長さのわからないボディは、受け取った分で判定する
+        let head = "HTTP/1.1 200 OK\n\n";
+        let body = "x".repeat(100);
+        assert!(read_response(&[head, &body], 100).is_ok());
+        assert_eq!(
+            Err(Error::BodyTooLarge {
+                url: "http://example.com".to_string(),
+                limit: 100,
+            }),
+            read_response(&[head, &body, "y"], 100)
+        );
+    }
+
+    #[test]
+    fn test_response_reader_understated_content_length() {
+        // Content-Lengthより多く送られてきたら、受け取った分で判定する
+        let head = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n";
+        assert_eq!(
+            Err(Error::BodyTooLarge {
+                url: "http://example.com".to_string(),
+                limit: 10,
+            }),
+            read_response(&[head, "ab", &"c".repeat(9)], 10)
+        );
+    }
+
+    #[test]
+    fn test_response_reader_bad_chunk_size() {
+        // 大きさの行が読めないチャンクの後のバイト列も数える
+        let head = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n";
+        assert_eq!(
+            Err(Error::BodyTooLarge {
+                url: "http://example.com".to_string(),
+                limit: 10,
+            }),
+            read_response(&[head, "4\r\nabcd\r\n", "zz\r\n", &"x".repeat(10)], 10)
+        );
+    }
+
+    #[test]
+    fn test_response_reader_headers_without_end() {
+        // 空行が届かないまま、ヘッダーが上限を超えた
+        let header = "X-Padding: aaaaaaaaaaaaaaaa\r\n";
+        let headers = header.repeat(MAX_HEAD_SIZE / header.len() + 1);
+        assert_eq!(
+            Err(Error::Network {
+                url: "http://example.com".to_string(),
+                message: format!("response headers exceed {} bytes", MAX_HEAD_SIZE),
+            }),
+            read_response(&["HTTP/1.1 200 OK\r\n", &headers], 10)
+        );
+    }
 }
@@ -2,11 +2,25 @@
 
 extern crate alloc;
 
+pub mod about;
 pub mod browser;
+pub mod clipboard;
+pub mod clock;
 pub mod constants;
+pub mod context_menu;
+pub mod cookie;
+pub mod csp;
+pub mod data_url;
 pub mod display_item;
 pub mod error;
+pub mod file;
+pub mod flip;
+pub mod hsts;
 pub mod http;
 pub mod renderer;
+pub mod screenshot;
+pub mod sri;
+#[cfg(test)]
+pub mod test_support;
 pub mod url;
 pub mod util;
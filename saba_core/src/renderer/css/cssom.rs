@@ -1,25 +1,144 @@
 use crate::renderer::css::token::CssToken;
 use crate::renderer::css::token::CssTokenizer;
+use crate::renderer::dom::node::Element;
+use alloc::format;
+use alloc::rc::Rc;
 use alloc::string::{String, ToString};
 use alloc::vec;
 use alloc::vec::Vec;
+use core::cell::Cell;
+use core::fmt::{Display, Formatter};
 use core::iter::Peekable;
 
+// レイアウトで解釈するプロパティ。これ以外のプロパティはエラーとして記録する
+static SUPPORTED_PROPERTIES: [&str; 18] = [
+    "background-color",
+    "color",
+    "display",
+    "font-style",
+    "white-space",
+    "overflow",
+    "visibility",
+    "float",
+    "word-wrap",
+    "overflow-wrap",
+    "width",
+    "content",
+    "grid-template-columns",
+    "grid-template-rows",
+    "gap",
+    "column-gap",
+    "row-gap",
+    "flex-wrap",
+];
+
+// 継承されないプロパティ。これらに`inherit`を指定しても、このブラウザは解釈しない
+static NON_INHERITED_PROPERTIES: [&str; 16] = [
+    "background-color",
+    "display",
+    "overflow",
+    "float",
+    "width",
+    "height",
+    "content",
+    "margin",
+    "padding",
+    "border",
+    "grid-template-columns",
+    "grid-template-rows",
+    "gap",
+    "column-gap",
+    "row-gap",
+    "flex-wrap",
+];
+
+// CSSのパースで見つかったエラー。行と列は1から数える
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CssParseError {
+    message: String,
+    line: u32,
+    column: u32,
+}
+
+impl CssParseError {
+    pub fn new(message: String, (line, column): (u32, u32)) -> Self {
+        Self {
+            message,
+            line,
+            column,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    pub fn column(&self) -> u32 {
+        self.column
+    }
+}
+
+impl Display for CssParseError {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        write!(
+            f,
+            "{} (line {}, column {})",
+            self.message, self.line, self.column
+        )
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct CssParser {
     t: Peekable<CssTokenizer>,
+    // 次の宣言に付けるソース上の順番
+    next_source_index: usize,
+    // 最後に読み込んだトークンの位置
+    location: Rc<Cell<(u32, u32)>>,
+    errors: Vec<CssParseError>,
 }
 
 impl CssParser {
     pub fn new(t: CssTokenizer) -> Self {
-        Self { t: t.peekable() }
+        let location = t.location();
+        Self {
+            t: t.peekable(),
+            next_source_index: 0,
+            location,
+            errors: Vec::new(),
+        }
     }
 
-    pub fn parse_stylesheet(&mut self) -> StyleSheet {
+    // エラーが1つでもあればErrを返す
+    pub fn parse_stylesheet(&mut self) -> Result<StyleSheet, Vec<CssParseError>> {
+        let (sheet, errors) = self.parse_stylesheet_with_errors();
+        if errors.is_empty() {
+            Ok(sheet)
+        } else {
+            Err(errors)
+        }
+    }
+
+    // ブラウザは不正な部分を読み飛ばして描画を続けるため、エラーがあってもスタイルシートを返す
+    pub fn parse_stylesheet_with_errors(&mut self) -> (StyleSheet, Vec<CssParseError>) {
         let mut sheet = StyleSheet::new();
 
         sheet.set_rules(self.consume_list_of_rules());
-        sheet
+        (sheet, self.errors.clone())
+    }
+
+    // 次のトークンの先頭の位置
+    fn next_location(&mut self) -> (u32, u32) {
+        self.t.peek();
+        self.location.get()
+    }
+
+    fn report_error(&mut self, message: String, location: (u32, u32)) {
+        self.errors.push(CssParseError::new(message, location));
     }
 
     fn consume_list_of_rules(&mut self) -> Vec<QualifiedRule> {
@@ -61,6 +180,9 @@ impl CssParser {
                     rule.set_declarations(self.consume_list_of_declarations());
                     return Some(rule);
                 }
+                CssToken::Colon => {
+                    rule.set_pseudo_element(self.consume_pseudo_element());
+                }
                 _ => {
                     rule.set_selector(self.consume_selector());
                 }
@@ -68,7 +190,44 @@ impl CssParser {
         }
     }
 
+    // `::before`と`::after`以外の疑似要素・疑似クラスは無視する
+    fn consume_pseudo_element(&mut self) -> Option<PseudoElement> {
+        while self.t.peek() == Some(&CssToken::Colon) {
+            self.t.next();
+        }
+
+        match self.t.peek() {
+            Some(CssToken::Ident(_)) => PseudoElement::from_str(&self.consume_ident()),
+            _ => None,
+        }
+    }
+
+    // element.matches()などに渡される、1つのセレクタだけの文字列をパースする
+    pub fn parse_selector(&mut self) -> Result<Selector, Vec<CssParseError>> {
+        let location = self.next_location();
+        if self.t.peek().is_none() {
+            self.report_error("empty selector".to_string(), location);
+            return Err(self.errors.clone());
+        }
+
+        let selector = self.consume_selector();
+        let location = self.next_location();
+        if let Some(token) = self.t.next() {
+            self.report_error(format!("unexpected {:?} after selector", token), location);
+        }
+        if selector == Selector::UnknownSelector && self.errors.is_empty() {
+            self.report_error("unsupported selector".to_string(), location);
+        }
+
+        if self.errors.is_empty() {
+            Ok(selector)
+        } else {
+            Err(self.errors.clone())
+        }
+    }
+
     fn consume_selector(&mut self) -> Selector {
+        let location = self.next_location();
         let token = match self.t.next() {
             Some(t) => t,
             None => panic!("should have a token but got None"),
@@ -78,18 +237,19 @@ impl CssParser {
             CssToken::HashToken(value) => Selector::IdSelector(value[1..].to_string()),
             CssToken::Delim(delim) => {
                 if delim == '.' {
-                    return Selector::ClassSelector(self.consume_ident());
-                }
-                panic!("Parse error: {:?} is an unexpected token.", token);
-            }
-            CssToken::Ident(ident) => {
-                if self.t.peek() == Some(&CssToken::Colon) {
-                    while self.t.peek() != Some(&CssToken::OpenCurly) {
-                        self.t.next();
+                    if let Some(CssToken::Ident(_)) = self.t.peek() {
+                        return Selector::ClassSelector(self.consume_ident());
                     }
+                    self.report_error("expected class name after `.`".to_string(), location);
+                    return Selector::UnknownSelector;
                 }
-                Selector::TypeSelector(ident.to_string())
+                if delim == '[' {
+                    return self.consume_attribute_selector(location);
+                }
+                self.report_error(format!("unexpected {:?} in selector", delim), location);
+                Selector::UnknownSelector
             }
+            CssToken::Ident(ident) => Selector::TypeSelector(ident.to_string()),
             CssToken::AtKeyword(_keyword) => {
                 // @から始まるルールを無視する
                 while self.t.peek() != Some(&CssToken::OpenCurly) {
@@ -104,6 +264,43 @@ impl CssParser {
         }
     }
 
+    // `[name]`または`[name=value]`。開き括弧は読み込み済み
+    fn consume_attribute_selector(&mut self, location: (u32, u32)) -> Selector {
+        let name = match self.t.next() {
+            Some(CssToken::Ident(name)) => name,
+            _ => {
+                self.report_error("expected attribute name after `[`".to_string(), location);
+                return Selector::UnknownSelector;
+            }
+        };
+
+        let value = match self.t.next() {
+            Some(CssToken::Delim(']')) => return Selector::AttributeSelector { name, value: None },
+            Some(CssToken::Delim('=')) => match self.t.next() {
+                Some(CssToken::Ident(value)) | Some(CssToken::StringToken(value)) => value,
+                _ => {
+                    self.report_error("expected attribute value after `=`".to_string(), location);
+                    return Selector::UnknownSelector;
+                }
+            },
+            _ => {
+                self.report_error("unsupported attribute selector".to_string(), location);
+                return Selector::UnknownSelector;
+            }
+        };
+
+        match self.t.next() {
+            Some(CssToken::Delim(']')) => Selector::AttributeSelector {
+                name,
+                value: Some(value),
+            },
+            _ => {
+                self.report_error("expected `]` in attribute selector".to_string(), location);
+                Selector::UnknownSelector
+            }
+        }
+    }
+
     fn consume_list_of_declarations(&mut self) -> Vec<Declaration> {
         let mut declarations = Vec::new();
 
@@ -127,7 +324,9 @@ impl CssParser {
                     }
                 }
                 _ => {
-                    self.t.next();
+                    let location = self.next_location();
+                    let token = self.t.next();
+                    self.report_error(format!("unexpected {:?} in declarations", token), location);
                 }
             }
         }
@@ -138,17 +337,40 @@ impl CssParser {
             return None;
         }
 
+        let location = self.next_location();
         let mut declaration = Declaration::new();
         declaration.set_property(self.consume_ident());
+        let colon_location = self.next_location();
         match self.t.next() {
-            Some(token) => match token {
-                CssToken::Colon => {}
-                _ => return None,
-            },
-            None => return None,
+            Some(CssToken::Colon) => {}
+            _ => {
+                self.report_error(
+                    format!("expected `:` after property {:?}", declaration.property),
+                    colon_location,
+                );
+                return None;
+            }
+        }
+
+        // 解釈できないプロパティも宣言としては残し、レイアウトで無視する
+        if !SUPPORTED_PROPERTIES.contains(&declaration.property.as_str()) {
+            self.report_error(
+                format!("unknown property {:?}", declaration.property),
+                location,
+            );
         }
 
         declaration.set_value(self.consume_component_value());
+        // `1fr 2fr`のように空白で区切られた値は、残りの値として持つ
+        while !matches!(
+            self.t.peek(),
+            Some(CssToken::SemiColon) | Some(CssToken::CloseCurly) | None
+        ) {
+            let value = self.consume_component_value();
+            declaration.rest.push(value);
+        }
+        declaration.set_source_index(self.next_source_index);
+        self.next_source_index += 1;
         Some(declaration)
     }
 
@@ -186,29 +408,160 @@ impl StyleSheet {
     pub fn set_rules(&mut self, rules: Vec<QualifiedRule>) {
         self.rules = rules;
     }
+
+    pub fn set_origin(&mut self, origin: CascadeOrigin) {
+        for rule in &mut self.rules {
+            rule.set_origin(origin);
+        }
+    }
+
+    // 正しくパースできても、書き方に問題のありそうな宣言を報告する
+    pub fn lint(&self) -> Vec<LintWarning> {
+        let mut warnings = Vec::new();
+
+        for (rule_index, rule) in self.rules.iter().enumerate() {
+            let mut seen: Vec<&str> = Vec::new();
+            for declaration in &rule.declarations {
+                let property = declaration.property.as_str();
+
+                if seen.contains(&property) {
+                    warnings.push(LintWarning::new(
+                        format!("duplicate declaration of {:?}", property),
+                        rule_index,
+                    ));
+                }
+                seen.push(property);
+
+                match declaration.value {
+                    ComponentValue::Dimension(value, ref unit) => {
+                        if value == 0.0 {
+                            warnings.push(LintWarning::new(
+                                format!("unit is redundant in `0{}` for {:?}", unit, property),
+                                rule_index,
+                            ));
+                        }
+                    }
+                    ComponentValue::Ident(ref value)
+                        if value == "inherit" && NON_INHERITED_PROPERTIES.contains(&property) =>
+                    {
+                        warnings.push(LintWarning::new(
+                            format!("`inherit` is not supported for {:?}", property),
+                            rule_index,
+                        ));
+                    }
+                    _ => {}
+                }
+
+                if let Some(standard) = unprefixed_property(property) {
+                    if !rule.declarations.iter().any(|d| d.property == standard) {
+                        warnings.push(LintWarning::new(
+                            format!("{:?} has no standard {:?} fallback", property, standard),
+                            rule_index,
+                        ));
+                    }
+                }
+            }
+        }
+
+        warnings
+    }
+}
+
+// `-webkit-transform`のようなベンダープレフィックスつきのプロパティから、標準の名前を返す
+fn unprefixed_property(property: &str) -> Option<&str> {
+    let rest = property.strip_prefix('-')?;
+    let (_, standard) = rest.split_once('-')?;
+    if standard.is_empty() {
+        return None;
+    }
+    Some(standard)
+}
+
+// lintで見つかった問題。rule_indexはスタイルシートの何番目のルールか
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintWarning {
+    message: String,
+    rule_index: usize,
+}
+
+impl LintWarning {
+    pub fn new(message: String, rule_index: usize) -> Self {
+        Self {
+            message,
+            rule_index,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+
+    pub fn rule_index(&self) -> usize {
+        self.rule_index
+    }
+}
+
+impl Display for LintWarning {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        write!(f, "{} (rule {})", self.message, self.rule_index)
+    }
+}
+
+// ルールを書いたのは誰か。カスケードでは詳細度より先に比べ、UserAgentのルールは常に負ける
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CascadeOrigin {
+    UserAgent,
+    Author,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct QualifiedRule {
     pub selector: Selector,
+    pub pseudo_element: Option<PseudoElement>,
     pub declarations: Vec<Declaration>,
+    pub origin: CascadeOrigin,
 }
 
 impl QualifiedRule {
     pub fn new() -> Self {
         Self {
             selector: Selector::TypeSelector("".to_string()),
+            pseudo_element: None,
             declarations: Vec::new(),
+            origin: CascadeOrigin::Author,
         }
     }
 
+    pub fn set_origin(&mut self, origin: CascadeOrigin) {
+        self.origin = origin;
+    }
+
     pub fn set_selector(&mut self, selector: Selector) {
         self.selector = selector;
     }
 
+    pub fn set_pseudo_element(&mut self, pseudo_element: Option<PseudoElement>) {
+        self.pseudo_element = pseudo_element;
+    }
+
     pub fn set_declarations(&mut self, declarations: Vec<Declaration>) {
         self.declarations = declarations;
     }
+
+    // (IDセレクタの数, クラスセレクタの数, 型セレクタと疑似要素の数)
+    // タプルの比較で詳細度の大小を比べられる
+    pub fn specificity(&self) -> (usize, usize, usize) {
+        let (a, b, c) = match self.selector {
+            Selector::IdSelector(_) => (1, 0, 0),
+            Selector::ClassSelector(_) | Selector::AttributeSelector { .. } => (0, 1, 0),
+            Selector::TypeSelector(_) => (0, 0, 1),
+            Selector::UnknownSelector => (0, 0, 0),
+        };
+        match self.pseudo_element {
+            Some(_) => (a, b, c + 1),
+            None => (a, b, c),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -216,14 +569,57 @@ pub enum Selector {
     TypeSelector(String),
     ClassSelector(String),
     IdSelector(String),
+    // `[name]`は属性があるとき、`[name=value]`は属性の値が一致するときに選ばれる
+    AttributeSelector { name: String, value: Option<String> },
     // パース中にエラーが起こったときに使用される
     UnknownSelector,
 }
 
+impl Selector {
+    pub fn matches(&self, element: &Element) -> bool {
+        match self {
+            Selector::TypeSelector(type_name) => element.kind().to_string() == *type_name,
+            Selector::ClassSelector(class_name) => element
+                .attributes()
+                .iter()
+                .any(|attr| attr.name() == "class" && attr.value() == *class_name),
+            Selector::IdSelector(id_name) => element
+                .attributes()
+                .iter()
+                .any(|attr| attr.name() == "id" && attr.value() == *id_name),
+            Selector::AttributeSelector { name, value } => match value {
+                Some(value) => element.get_attribute(name).as_ref() == Some(value),
+                None => element.has_attribute(name),
+            },
+            Selector::UnknownSelector => false,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PseudoElement {
+    Before,
+    After,
+}
+
+impl PseudoElement {
+    fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "before" => Some(PseudoElement::Before),
+            "after" => Some(PseudoElement::After),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Declaration {
     pub property: String,
     pub value: ComponentValue,
+    // 値が空白で区切られて複数あるときの、2つ目以降の値
+    pub rest: Vec<ComponentValue>,
+    // スタイルシート内で何番目の宣言か。詳細度が同じときは後のものを優先する
+    pub source_index: usize,
 }
 
 impl Declaration {
@@ -231,9 +627,15 @@ impl Declaration {
         Self {
             property: String::new(),
             value: ComponentValue::Ident(String::new()),
+            rest: Vec::new(),
+            source_index: 0,
         }
     }
 
+    pub fn set_source_index(&mut self, source_index: usize) {
+        self.source_index = source_index;
+    }
+
     pub fn set_property(&mut self, property: String) {
         self.property = property;
     }
@@ -241,6 +643,13 @@ impl Declaration {
     pub fn set_value(&mut self, value: ComponentValue) {
         self.value = value;
     }
+
+    // 最初の値と残りの値を合わせた、すべての値
+    pub fn values(&self) -> Vec<ComponentValue> {
+        let mut values = vec![self.value.clone()];
+        values.extend(self.rest.iter().cloned());
+        values
+    }
 }
 
 pub type ComponentValue = CssToken;
@@ -254,7 +663,9 @@ mod tests {
     fn test_empty() {
         let style = "".to_string();
         let t = CssTokenizer::new(style);
-        let cssom = CssParser::new(t).parse_stylesheet();
+        let cssom = CssParser::new(t)
+            .parse_stylesheet()
+            .expect("stylesheet should be valid");
 
         assert_eq!(cssom.rules.len(), 0);
     }
@@ -263,7 +674,9 @@ mod tests {
     fn test_one_rule() {
         let style = "p { color: red; }".to_string();
         let t = CssTokenizer::new(style);
-        let cssom = CssParser::new(t).parse_stylesheet();
+        let cssom = CssParser::new(t)
+            .parse_stylesheet()
+            .expect("stylesheet should be valid");
 
         let mut rule = QualifiedRule::new();
         rule.set_selector(Selector::TypeSelector("p".to_string()));
@@ -286,7 +699,9 @@ mod tests {
     fn test_id_selector() {
         let style = "#id { color: red; }".to_string();
         let t = CssTokenizer::new(style);
-        let cssom = CssParser::new(t).parse_stylesheet();
+        let cssom = CssParser::new(t)
+            .parse_stylesheet()
+            .expect("stylesheet should be valid");
 
         let mut rule = QualifiedRule::new();
         rule.set_selector(Selector::IdSelector("id".to_string()));
@@ -309,7 +724,9 @@ mod tests {
     fn test_class_selector() {
         let style = ".class { color: red; }".to_string();
         let t = CssTokenizer::new(style);
-        let cssom = CssParser::new(t).parse_stylesheet();
+        let cssom = CssParser::new(t)
+            .parse_stylesheet()
+            .expect("stylesheet should be valid");
 
         let mut rule = QualifiedRule::new();
         rule.set_selector(Selector::ClassSelector("class".to_string()));
@@ -332,7 +749,16 @@ mod tests {
     fn test_multiple_rules() {
         let style = "p { color: red; } h1 { font-size: 40; color: blue; }".to_string();
         let t = CssTokenizer::new(style);
-        let cssom = CssParser::new(t).parse_stylesheet();
+        let (cssom, errors) = CssParser::new(t).parse_stylesheet_with_errors();
+        // font-sizeはレイアウトで解釈しないが、宣言としては残る
+        assert_eq!(
+            [CssParseError::new(
+                "unknown property \"font-size\"".to_string(),
+                (1, 24)
+            )]
+            .to_vec(),
+            errors
+        );
 
         let mut rule1 = QualifiedRule::new();
         rule1.set_selector(Selector::TypeSelector("p".to_string()));
@@ -346,9 +772,11 @@ mod tests {
         let mut declaration2 = Declaration::new();
         declaration2.set_property("font-size".to_string());
         declaration2.set_value(ComponentValue::Number(40.0));
+        declaration2.set_source_index(1);
         let mut declaration3 = Declaration::new();
         declaration3.set_property("color".to_string());
         declaration3.set_value(ComponentValue::Ident("blue".to_string()));
+        declaration3.set_source_index(2);
         rule2.set_declarations(vec![declaration2, declaration3]);
 
         let expected = [rule1, rule2];
@@ -360,4 +788,204 @@ mod tests {
             i += 1;
         }
     }
+
+    #[test]
+    fn test_pseudo_element() {
+        let style = "p::before { content: \"> \"; } .note::after { content: \"!\"; }".to_string();
+        let t = CssTokenizer::new(style);
+        let cssom = CssParser::new(t)
+            .parse_stylesheet()
+            .expect("stylesheet should be valid");
+
+        let mut rule1 = QualifiedRule::new();
+        rule1.set_selector(Selector::TypeSelector("p".to_string()));
+        rule1.set_pseudo_element(Some(PseudoElement::Before));
+        let mut declaration1 = Declaration::new();
+        declaration1.set_property("content".to_string());
+        declaration1.set_value(ComponentValue::StringToken("> ".to_string()));
+        rule1.set_declarations(vec![declaration1]);
+
+        let mut rule2 = QualifiedRule::new();
+        rule2.set_selector(Selector::ClassSelector("note".to_string()));
+        rule2.set_pseudo_element(Some(PseudoElement::After));
+        let mut declaration2 = Declaration::new();
+        declaration2.set_property("content".to_string());
+        declaration2.set_value(ComponentValue::StringToken("!".to_string()));
+        declaration2.set_source_index(1);
+        rule2.set_declarations(vec![declaration2]);
+
+        assert_eq!([rule1, rule2].to_vec(), cssom.rules);
+    }
+
+    #[test]
+    fn test_specificity() {
+        let style = "#id { color: red; } .class { color: red; } p { color: red; } p::before { content: \"\"; }"
+            .to_string();
+        let t = CssTokenizer::new(style);
+        let cssom = CssParser::new(t)
+            .parse_stylesheet()
+            .expect("stylesheet should be valid");
+
+        let specificities = cssom
+            .rules
+            .iter()
+            .map(|rule| rule.specificity())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            [(1, 0, 0), (0, 1, 0), (0, 0, 1), (0, 0, 2)].to_vec(),
+            specificities
+        );
+        assert!(cssom.rules[0].specificity() > cssom.rules[1].specificity());
+        assert!(cssom.rules[1].specificity() > cssom.rules[2].specificity());
+    }
+
+    #[test]
+    fn test_parse_errors_have_locations() {
+        let style = "p {\n  color: red;\n  margin: 0;\n}\n.note {\n  color blue;\n  width: 50%;\n}"
+            .to_string();
+        let t = CssTokenizer::new(style);
+        let (cssom, errors) = CssParser::new(t).parse_stylesheet_with_errors();
+
+        assert_eq!(
+            [
+                CssParseError::new("unknown property \"margin\"".to_string(), (3, 3)),
+                CssParseError::new("expected `:` after property \"color\"".to_string(), (6, 9)),
+            ]
+            .to_vec(),
+            errors
+        );
+        assert_eq!(
+            "unknown property \"margin\" (line 3, column 3)",
+            errors[0].to_string()
+        );
+
+        // エラーのある宣言を読み飛ばし、残りの宣言はパースする
+        assert_eq!(2, cssom.rules.len());
+        let width = &cssom.rules[1].declarations[0];
+        assert_eq!("width", width.property);
+        assert_eq!(ComponentValue::Percentage(50.0), width.value);
+    }
+
+    #[test]
+    fn test_parse_stylesheet_returns_errors() {
+        let t = CssTokenizer::new("p { colour: red; }".to_string());
+        let errors = CssParser::new(t)
+            .parse_stylesheet()
+            .expect_err("colour should be reported");
+        assert_eq!(1, errors.len());
+        assert_eq!((1, 5), (errors[0].line(), errors[0].column()));
+    }
+
+    fn lint(style: &str) -> Vec<LintWarning> {
+        let t = CssTokenizer::new(style.to_string());
+        let (cssom, _) = CssParser::new(t).parse_stylesheet_with_errors();
+        cssom.lint()
+    }
+
+    #[test]
+    fn test_lint_zero_with_unit() {
+        assert_eq!(
+            vec![LintWarning::new(
+                "unit is redundant in `0px` for \"width\"".to_string(),
+                1
+            )],
+            lint("p { width: 0; } div { width: 0px; } a { width: 10px; }")
+        );
+    }
+
+    #[test]
+    fn test_lint_duplicate_declaration() {
+        assert_eq!(
+            vec![LintWarning::new(
+                "duplicate declaration of \"color\"".to_string(),
+                0
+            )],
+            lint("p { color: red; display: block; color: blue; } div { color: red; }")
+        );
+    }
+
+    #[test]
+    fn test_lint_inherit_on_non_inherited_property() {
+        assert_eq!(
+            vec![LintWarning::new(
+                "`inherit` is not supported for \"display\"".to_string(),
+                0
+            )],
+            lint("p { display: inherit; color: inherit; }")
+        );
+    }
+
+    #[test]
+    fn test_lint_vendor_prefix_without_fallback() {
+        assert_eq!(
+            vec![LintWarning::new(
+                "\"-webkit-transform\" has no standard \"transform\" fallback".to_string(),
+                1
+            )],
+            lint("p { -moz-float: left; float: left; } div { -webkit-transform: none; }")
+        );
+    }
+
+    #[test]
+    fn test_lint_clean_stylesheet() {
+        assert!(lint("p { color: red; width: 50%; } .note { display: none; }").is_empty());
+    }
+
+    fn parse_selector(selector: &str) -> Result<Selector, Vec<CssParseError>> {
+        CssParser::new(CssTokenizer::new(selector.to_string())).parse_selector()
+    }
+
+    #[test]
+    fn test_parse_selector() {
+        assert_eq!(
+            Ok(Selector::TypeSelector("p".to_string())),
+            parse_selector("p")
+        );
+        assert_eq!(
+            Ok(Selector::ClassSelector("note".to_string())),
+            parse_selector(" .note ")
+        );
+        assert_eq!(
+            Ok(Selector::IdSelector("main".to_string())),
+            parse_selector("#main")
+        );
+        assert_eq!(
+            Ok(Selector::AttributeSelector {
+                name: "data-id".to_string(),
+                value: None
+            }),
+            parse_selector("[data-id]")
+        );
+        assert_eq!(
+            Ok(Selector::AttributeSelector {
+                name: "type".to_string(),
+                value: Some("text".to_string())
+            }),
+            parse_selector("[type=\"text\"]")
+        );
+        for invalid in ["", ".", "[", "[type=]", "[type~=a]", "p p", "!"] {
+            assert!(
+                parse_selector(invalid).is_err(),
+                "{:?} should be invalid",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_attribute_selector_in_stylesheet() {
+        let style = "[hidden] { display: none; }".to_string();
+        let cssom = CssParser::new(CssTokenizer::new(style))
+            .parse_stylesheet()
+            .expect("stylesheet should be valid");
+
+        assert_eq!(
+            Selector::AttributeSelector {
+                name: "hidden".to_string(),
+                value: None
+            },
+            cssom.rules[0].selector
+        );
+        assert_eq!((0, 1, 0), cssom.rules[0].specificity());
+    }
 }
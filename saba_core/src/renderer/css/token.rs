@@ -1,11 +1,17 @@
+use alloc::rc::Rc;
 use alloc::string::String;
 use alloc::vec::Vec;
+use core::cell::Cell;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum CssToken {
     HashToken(String),
     Delim(char),
     Number(f64),
+    // `50%`のような割合
+    Percentage(f64),
+    // `10px`のような単位つきの数値
+    Dimension(f64, String),
     Colon,
     SemiColon,
     OpenParenthesis,
@@ -21,6 +27,12 @@ pub enum CssToken {
 pub struct CssTokenizer {
     pos: usize,
     input: Vec<char>,
+    line: u32,
+    // 現在の行の先頭の位置
+    line_start: usize,
+    // 最後に読み込んだトークンの先頭の(行, 列)。1から数える
+    // パーサーはPeekableを通してトークンを読むため、共有して参照できるようにする
+    location: Rc<Cell<(u32, u32)>>,
 }
 
 impl CssTokenizer {
@@ -28,18 +40,25 @@ impl CssTokenizer {
         Self {
             pos: 0,
             input: css.chars().collect(),
+            line: 1,
+            line_start: 0,
+            location: Rc::new(Cell::new((1, 1))),
         }
     }
 
+    pub fn location(&self) -> Rc<Cell<(u32, u32)>> {
+        self.location.clone()
+    }
+
     fn consume_string_token(&mut self) -> String {
         let mut s = String::new();
 
         loop {
+            // 閉じられていない文字列は入力の終わりまで
+            self.pos += 1;
             if self.pos >= self.input.len() {
                 return s;
             }
-
-            self.pos += 1;
             let c = self.input[self.pos];
             match c {
                 '"' | '\'' => break,
@@ -88,6 +107,10 @@ impl CssTokenizer {
 
         loop {
             self.pos += 1;
+            // セレクタだけの文字列などは、識別子で入力が終わることがある
+            if self.pos >= self.input.len() {
+                break;
+            }
             let c = self.input[self.pos];
             match c {
                 'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-' => {
@@ -111,6 +134,8 @@ impl Iterator for CssTokenizer {
             }
 
             let c = self.input[self.pos];
+            self.location
+                .set((self.line, (self.pos - self.line_start) as u32 + 1));
             let token = match c {
                 '(' => CssToken::OpenParenthesis,
                 ')' => CssToken::CloseParenthesis,
@@ -121,6 +146,10 @@ impl Iterator for CssTokenizer {
                 '{' => CssToken::OpenCurly,
                 '}' => CssToken::CloseCurly,
                 ' ' | '\n' => {
+                    if c == '\n' {
+                        self.line += 1;
+                        self.line_start = self.pos + 1;
+                    }
                     self.pos += 1;
                     continue;
                 }
@@ -129,9 +158,19 @@ impl Iterator for CssTokenizer {
                     CssToken::StringToken(value)
                 }
                 '0'..='9' => {
-                    let t = CssToken::Number(self.consume_numeric_token());
-                    self.pos -= 1;
-                    t
+                    let num = self.consume_numeric_token();
+                    if self.pos < self.input.len() && self.input[self.pos] == '%' {
+                        CssToken::Percentage(num)
+                    } else if self.pos < self.input.len()
+                        && self.input[self.pos].is_ascii_alphabetic()
+                    {
+                        let unit = self.consume_ident_token();
+                        self.pos -= 1;
+                        CssToken::Dimension(num, unit)
+                    } else {
+                        self.pos -= 1;
+                        CssToken::Number(num)
+                    }
                 }
                 '#' => {
                     let value = self.consume_ident_token();
@@ -161,9 +200,8 @@ impl Iterator for CssTokenizer {
                     self.pos -= 1;
                     t
                 }
-                _ => {
-                    unimplemented!("char {} is not supported yet", c);
-                }
+                // `[`や`=`など、ほかの文字は1文字のトークンにする
+                _ => CssToken::Delim(c),
             };
 
             self.pos += 1;
@@ -271,4 +309,50 @@ mod tests {
         }
         assert!(t.next().is_none());
     }
+
+    #[test]
+    fn test_percentage() {
+        let style = "div { width: 50%; height: 12.5; }".to_string();
+        let mut t = CssTokenizer::new(style);
+        let expected = [
+            CssToken::Ident("div".to_string()),
+            CssToken::OpenCurly,
+            CssToken::Ident("width".to_string()),
+            CssToken::Colon,
+            CssToken::Percentage(50.0),
+            CssToken::SemiColon,
+            CssToken::Ident("height".to_string()),
+            CssToken::Colon,
+            CssToken::Number(12.5),
+            CssToken::SemiColon,
+            CssToken::CloseCurly,
+        ];
+        for e in expected {
+            assert_eq!(Some(e.clone()), t.next());
+        }
+        assert!(t.next().is_none());
+    }
+
+    #[test]
+    fn test_dimension() {
+        let style = "p { margin: 0px; width: 1.5em; }".to_string();
+        let mut t = CssTokenizer::new(style);
+        let expected = [
+            CssToken::Ident("p".to_string()),
+            CssToken::OpenCurly,
+            CssToken::Ident("margin".to_string()),
+            CssToken::Colon,
+            CssToken::Dimension(0.0, "px".to_string()),
+            CssToken::SemiColon,
+            CssToken::Ident("width".to_string()),
+            CssToken::Colon,
+            CssToken::Dimension(1.5, "em".to_string()),
+            CssToken::SemiColon,
+            CssToken::CloseCurly,
+        ];
+        for e in expected {
+            assert_eq!(Some(e.clone()), t.next());
+        }
+        assert!(t.next().is_none());
+    }
 }
@@ -0,0 +1,261 @@
+use crate::renderer::dom::api::get_title;
+use crate::renderer::dom::form::{input_type, is_submit_control, is_text_input};
+use crate::renderer::dom::node::{Element, ElementKind, Node, NodeKind, TreeIter};
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+// 支援技術に伝える要素の役割
+// https://w3c.github.io/html-aam/#html-element-role-mappings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AriaRole {
+    Document,
+    // 見出しのレベル(1〜6)
+    Heading(u8),
+    Link,
+    Button,
+    TextInput,
+    Image,
+    Paragraph,
+    ListItem,
+}
+
+// アクセシビリティツリーのノード
+// 役割を持たない要素(divなど)はツリーに現れず、その子孫が親のノードの子になる
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessibilityNode {
+    role: AriaRole,
+    label: String,
+    children: Vec<AccessibilityNode>,
+}
+
+impl AccessibilityNode {
+    pub fn new(role: AriaRole, label: String, children: Vec<AccessibilityNode>) -> Self {
+        Self {
+            role,
+            label,
+            children,
+        }
+    }
+
+    pub fn role(&self) -> AriaRole {
+        self.role
+    }
+
+    pub fn label(&self) -> String {
+        self.label.clone()
+    }
+
+    pub fn children(&self) -> Vec<AccessibilityNode> {
+        self.children.clone()
+    }
+}
+
+// 文書からアクセシビリティツリーを作る。ルートはDocumentで、ラベルは文書のタイトル
+pub fn build_accessibility_tree(document: &Rc<RefCell<Node>>) -> AccessibilityNode {
+    let mut children = Vec::new();
+    collect_children(document, &mut children);
+    AccessibilityNode::new(
+        AriaRole::Document,
+        get_title(document.clone()).unwrap_or_default(),
+        children,
+    )
+}
+
+fn collect_children(node: &Rc<RefCell<Node>>, nodes: &mut Vec<AccessibilityNode>) {
+    let mut child = node.borrow().first_child();
+    while let Some(c) = child {
+        child = c.borrow().next_sibling();
+        let element = match c.borrow().get_element() {
+            Some(e) => e,
+            // テキストはノードにせず、要素のラベルに使う
+            // パーサーはテキストの後に続く要素をテキストの子にするので、子はたどる
+            None => {
+                collect_children(&c, nodes);
+                continue;
+            }
+        };
+        if is_hidden(&element) {
+            continue;
+        }
+
+        match role_of(&element) {
+            Some(role) => {
+                let mut children = Vec::new();
+                collect_children(&c, &mut children);
+                nodes.push(AccessibilityNode::new(
+                    role,
+                    label_of(&c, &element, role),
+                    children,
+                ));
+            }
+            None => collect_children(&c, nodes),
+        }
+    }
+}
+
+// 描画されない要素と、支援技術から隠された要素
+fn is_hidden(element: &Element) -> bool {
+    matches!(
+        element.kind(),
+        ElementKind::Head
+            | ElementKind::Title
+            | ElementKind::Meta
+            | ElementKind::Style
+            | ElementKind::Script
+            | ElementKind::Link
+            | ElementKind::Template
+    ) || element.has_attribute("hidden")
+        || element.get_attribute("aria-hidden").as_deref() == Some("true")
+}
+
+pub fn role_of(element: &Element) -> Option<AriaRole> {
+    match element.kind() {
+        ElementKind::H1 => Some(AriaRole::Heading(1)),
+        ElementKind::H2 => Some(AriaRole::Heading(2)),
+        // hrefのないaはリンクではない
+        ElementKind::A if element.has_attribute("href") => Some(AriaRole::Link),
+        ElementKind::Button => Some(AriaRole::Button),
+        ElementKind::Input if is_text_input(element) => Some(AriaRole::TextInput),
+        ElementKind::Input
+            if is_submit_control(element)
+                || matches!(input_type(element).as_str(), "button" | "reset") =>
+        {
+            Some(AriaRole::Button)
+        }
+        ElementKind::P => Some(AriaRole::Paragraph),
+        ElementKind::Svg => Some(AriaRole::Image),
+        // imgとliは個別のElementKindを持たない
+        ElementKind::Unknown(tag) if tag == "img" => Some(AriaRole::Image),
+        ElementKind::Unknown(tag) if tag == "li" => Some(AriaRole::ListItem),
+        _ => None,
+    }
+}
+
+// aria-label属性、画像のalt属性、テキストの順に、最初に見つかったものをラベルにする
+fn label_of(node: &Rc<RefCell<Node>>, element: &Element, role: AriaRole) -> String {
+    if let Some(label) = element.get_attribute("aria-label") {
+        if !label.trim().is_empty() {
+            return collapse_whitespace(&label);
+        }
+    }
+
+    match (role, element.kind()) {
+        (AriaRole::Image, _) => {
+            collapse_whitespace(&element.get_attribute("alt").unwrap_or_default())
+        }
+        // inputのボタンは、value属性の値がボタンに表示される
+        (AriaRole::Button, ElementKind::Input) => match element.get_attribute("value") {
+            Some(value) => collapse_whitespace(&value),
+            None if input_type(element) == "reset" => "Reset".to_string(),
+            None if input_type(element) == "submit" => "Submit".to_string(),
+            None => String::new(),
+        },
+        (_, ElementKind::Input) => String::new(),
+        _ => {
+            let text: String = TreeIter::new(node.clone())
+                .filter_map(|n| match n.borrow().kind() {
+                    NodeKind::Text(t) => Some(t.clone()),
+                    _ => None,
+                })
+                .collect();
+            collapse_whitespace(&text)
+        }
+    }
+}
+
+// 連続する空白を1つにまとめ、前後の空白を取り除く
+fn collapse_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::dom::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+
+    fn create_tree(html: &str) -> AccessibilityNode {
+        let t = HtmlTokenizer::new(html.to_string());
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+        build_accessibility_tree(&document)
+    }
+
+    fn leaf(role: AriaRole, label: &str) -> AccessibilityNode {
+        AccessibilityNode::new(role, label.to_string(), Vec::new())
+    }
+
+    #[test]
+    fn test_headings_and_paragraphs() {
+        let tree = create_tree(
+            "<html><head><title>Saba</title></head><body><h1>Hello</h1><div><p>first\n  line</p></div><h2>World</h2></body></html>",
+        );
+        assert_eq!(
+            AccessibilityNode::new(
+                AriaRole::Document,
+                "Saba".to_string(),
+                [
+                    leaf(AriaRole::Heading(1), "Hello"),
+                    // divはツリーに現れない
+                    leaf(AriaRole::Paragraph, "first line"),
+                    leaf(AriaRole::Heading(2), "World"),
+                ]
+                .to_vec()
+            ),
+            tree
+        );
+    }
+
+    #[test]
+    fn test_nested_roles_and_labels() {
+        let tree = create_tree(
+            r#"<html><head></head><body><p><a href="/a">the <b>docs</b></a></p><p><a>no link</a></p><li><img src="x.png" alt="logo"></li><button aria-label="Close">x</button></body></html>"#,
+        );
+        assert_eq!(
+            [
+                AccessibilityNode::new(
+                    AriaRole::Paragraph,
+                    "the docs".to_string(),
+                    [leaf(AriaRole::Link, "the docs")].to_vec(),
+                ),
+                // hrefのないaはリンクにならない
+                leaf(AriaRole::Paragraph, "no link"),
+                AccessibilityNode::new(
+                    AriaRole::ListItem,
+                    "".to_string(),
+                    [leaf(AriaRole::Image, "logo")].to_vec(),
+                ),
+                leaf(AriaRole::Button, "Close"),
+            ]
+            .to_vec(),
+            tree.children()
+        );
+    }
+
+    #[test]
+    fn test_form_controls() {
+        let tree = create_tree(
+            r#"<html><head></head><body><form><input name="q" aria-label="Search"><input type="hidden" name="h"><input type="submit"><input type="submit" value="Go"><button>Send</button></form></body></html>"#,
+        );
+        assert_eq!(
+            [
+                leaf(AriaRole::TextInput, "Search"),
+                leaf(AriaRole::Button, "Submit"),
+                leaf(AriaRole::Button, "Go"),
+                leaf(AriaRole::Button, "Send"),
+            ]
+            .to_vec(),
+            tree.children()
+        );
+    }
+
+    #[test]
+    fn test_hidden_elements_are_skipped() {
+        let tree = create_tree(
+            r#"<html><head><style>p { color: red; }</style></head><body><p hidden>a</p><div aria-hidden="true"><h1>b</h1></div><p aria-hidden="false">c</p><script>var x = 1;</script></body></html>"#,
+        );
+        assert_eq!([leaf(AriaRole::Paragraph, "c")].to_vec(), tree.children());
+    }
+}
@@ -1,5 +1,6 @@
-use crate::renderer::dom::node::{Element, ElementKind, Node, NodeKind};
-use alloc::rc::Rc;
+use crate::renderer::dom::node::{Element, ElementKind, Node, NodeKind, TreeIter};
+use alloc::collections::VecDeque;
+use alloc::rc::{Rc, Weak};
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use core::cell::RefCell;
@@ -8,25 +9,7 @@ pub fn get_target_element_node(
     node: Option<Rc<RefCell<Node>>>,
     element_kind: ElementKind,
 ) -> Option<Rc<RefCell<Node>>> {
-    match node {
-        Some(n) => {
-            if n.borrow().kind()
-                == NodeKind::Element(Element::new(&element_kind.to_string(), Vec::new()))
-            {
-                return Some(n.clone());
-            }
-            let result1 = get_target_element_node(n.borrow().first_child(), element_kind);
-            let result2 = get_target_element_node(n.borrow().next_sibling(), element_kind);
-            if result1.is_none() && result2.is_none() {
-                return None;
-            }
-            if result1.is_none() {
-                return result2;
-            }
-            result1
-        }
-        None => None,
-    }
+    TreeIter::new(node?).find(|n| n.borrow().element_kind().as_ref() == Some(&element_kind))
 }
 
 pub fn get_style_content(root: Rc<RefCell<Node>>) -> String {
@@ -45,28 +28,31 @@ pub fn get_style_content(root: Rc<RefCell<Node>>) -> String {
     content
 }
 
-pub fn get_element_by_id(
-    node: Option<Rc<RefCell<Node>>>,
-    id_name: &String,
-) -> Option<Rc<RefCell<Node>>> {
-    match node {
-        Some(n) => {
-            if let NodeKind::Element(e) = n.borrow().kind() {
-                for attr in &e.attributes() {
-                    if attr.name() == "id" && attr.value() == *id_name {
-                        return Some(n.clone());
-                    }
-                }
-            }
-            let result1 = get_element_by_id(n.borrow().first_child(), id_name);
-            let result2 = get_element_by_id(n.borrow().next_sibling(), id_name);
-            if result1.is_none() {
-                return result2;
+pub fn get_title(root: Rc<RefCell<Node>>) -> Option<String> {
+    let title_node = get_target_element_node(Some(root), ElementKind::Title)?;
+    Some(get_text_content(&title_node).trim().to_string())
+}
+
+// rootから幅優先探索で、id属性が一致する要素を探す
+pub fn find_element_by_id(root: &Rc<RefCell<Node>>, id: &str) -> Option<Rc<RefCell<Node>>> {
+    let mut queue = VecDeque::new();
+    queue.push_back(root.clone());
+
+    while let Some(node) = queue.pop_front() {
+        if let NodeKind::Element(e) = node.borrow().kind() {
+            if e.get_attribute("id").as_deref() == Some(id) {
+                return Some(node.clone());
             }
-            result1
         }
-        None => None,
+
+        let mut child = node.borrow().first_child();
+        while let Some(c) = child {
+            child = c.borrow().next_sibling();
+            queue.push_back(c);
+        }
     }
+
+    None
 }
 
 pub fn get_js_content(root: Rc<RefCell<Node>>) -> String {
@@ -84,3 +70,371 @@ pub fn get_js_content(root: Rc<RefCell<Node>>) -> String {
     };
     content
 }
+
+pub fn get_element_nodes(
+    node: Option<Rc<RefCell<Node>>>,
+    element_kind: ElementKind,
+) -> Vec<Rc<RefCell<Node>>> {
+    match node {
+        Some(n) => TreeIter::new(n)
+            .filter(|n| n.borrow().element_kind().as_ref() == Some(&element_kind))
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+// `<script type="module">`と`<script defer>`はDOMツリーの構築が終わるまで実行を遅らせる
+pub fn is_deferred_script(node: &Rc<RefCell<Node>>) -> bool {
+    match node.borrow().get_element() {
+        Some(e) => {
+            e.kind() == ElementKind::Script
+                && (e.get_attribute("type").as_deref() == Some("module")
+                    || e.get_attribute("defer").is_some())
+        }
+        None => false,
+    }
+}
+
+pub fn get_text_content(node: &Rc<RefCell<Node>>) -> String {
+    let text_node = match node.borrow().first_child() {
+        Some(node) => node,
+        None => return "".to_string(),
+    };
+    let content = match &text_node.borrow().kind() {
+        NodeKind::Text(s) => s.clone(),
+        _ => "".to_string(),
+    };
+    content
+}
+
+// nodeを親と前後の兄弟から切り離す
+pub fn detach(node: &Rc<RefCell<Node>>) {
+    let parent = node.borrow().parent().upgrade();
+    let previous = node.borrow().previous_sibling().upgrade();
+    let next = node.borrow().next_sibling();
+
+    match (&previous, &parent) {
+        (Some(p), _) => p.borrow_mut().set_next_sibling(next.clone()),
+        (None, Some(parent)) => parent.borrow_mut().set_first_child(next.clone()),
+        (None, None) => {}
+    }
+    let previous_weak = previous.as_ref().map(Rc::downgrade).unwrap_or_default();
+    match (&next, &parent) {
+        (Some(n), _) => n.borrow_mut().set_previous_sibling(previous_weak),
+        (None, Some(parent)) => parent.borrow_mut().set_last_child(previous_weak),
+        (None, None) => {}
+    }
+
+    let mut n = node.borrow_mut();
+    n.set_parent(Weak::new());
+    n.set_previous_sibling(Weak::new());
+    n.set_next_sibling(None);
+}
+
+// childを、今の場所から切り離してparentの最後の子にする
+// parent自身やその祖先は、ツリーが循環するので追加しない
+pub fn append_child(parent: &Rc<RefCell<Node>>, child: Rc<RefCell<Node>>) {
+    let mut ancestor = Some(parent.clone());
+    while let Some(a) = ancestor {
+        if Rc::ptr_eq(&a, &child) {
+            return;
+        }
+        ancestor = a.borrow().parent().upgrade();
+    }
+    detach(&child);
+
+    let first_child = parent.borrow().first_child();
+    match first_child {
+        Some(first) => {
+            let mut last_sibling = first;
+            loop {
+                let next = last_sibling.borrow().next_sibling();
+                match next {
+                    Some(n) => last_sibling = n,
+                    None => break,
+                }
+            }
+            last_sibling
+                .borrow_mut()
+                .set_next_sibling(Some(child.clone()));
+            child
+                .borrow_mut()
+                .set_previous_sibling(Rc::downgrade(&last_sibling));
+        }
+        None => parent.borrow_mut().set_first_child(Some(child.clone())),
+    }
+
+    parent.borrow_mut().set_last_child(Rc::downgrade(&child));
+    child.borrow_mut().set_parent(Rc::downgrade(parent));
+}
+
+pub fn clone_node(node: &Rc<RefCell<Node>>, deep: bool) -> Rc<RefCell<Node>> {
+    let cloned = Rc::new(RefCell::new(Node::new(node.borrow().kind())));
+
+    // templateの中身は元のtemplateと共有せず、複製する
+    let content = node.borrow().get_element().and_then(|e| e.content());
+    if let Some(content) = content {
+        let content = if deep {
+            content
+                .borrow()
+                .iter()
+                .map(|n| clone_node(n, deep))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        if let NodeKind::Element(ref mut e) = cloned.borrow_mut().kind {
+            e.set_content(content);
+        }
+    }
+
+    if deep {
+        let mut child = node.borrow().first_child();
+        while let Some(c) = child {
+            append_child(&cloned, clone_node(&c, deep));
+            child = c.borrow().next_sibling();
+        }
+    }
+
+    cloned
+}
+
+// SVG要素自身、またはSVG要素の子孫であるかどうか
+pub fn is_svg_element(node: &Rc<RefCell<Node>>) -> bool {
+    let mut current = Some(node.clone());
+    while let Some(n) = current {
+        if n.borrow().element_kind() == Some(ElementKind::Svg) {
+            return true;
+        }
+        current = n.borrow().parent().upgrade();
+    }
+    false
+}
+
+// DOMツリーの現在の状態をHTMLの文字列にする
+pub fn to_html(node: &Rc<RefCell<Node>>) -> String {
+    let mut html = String::new();
+    serialize_node(node, &mut html);
+    html
+}
+
+fn serialize_node(node: &Rc<RefCell<Node>>, html: &mut String) {
+    let kind = node.borrow().kind();
+    match kind {
+        NodeKind::Document => serialize_children(node, html),
+        NodeKind::Text(text) => {
+            // styleとscriptの中身はエスケープしない
+            let raw = node.borrow().parent().upgrade().is_some_and(|parent| {
+                matches!(
+                    parent.borrow().element_kind(),
+                    Some(ElementKind::Style) | Some(ElementKind::Script)
+                )
+            });
+            if raw {
+                html.push_str(&text);
+            } else {
+                html.push_str(&escape_html(&text, false));
+            }
+        }
+        NodeKind::Element(element) => {
+            html.push_str(&start_tag(&element));
+
+            // 終了タグを持たない要素
+            if matches!(element.kind(), ElementKind::Meta | ElementKind::Link) {
+                return;
+            }
+
+            match element.content() {
+                Some(content) => {
+                    for child in content.borrow().iter() {
+                        serialize_node(child, html);
+                    }
+                }
+                None => serialize_children(node, html),
+            }
+
+            html.push_str("</");
+            html.push_str(&element.kind().to_string());
+            html.push('>');
+        }
+    }
+}
+
+// 属性を含めた開始タグ
+pub fn start_tag(element: &Element) -> String {
+    let mut tag = String::from("<");
+    tag.push_str(&element.kind().to_string());
+    for attribute in element.attributes() {
+        tag.push(' ');
+        tag.push_str(&attribute.name());
+        tag.push_str("=\"");
+        tag.push_str(&escape_html(&attribute.value(), true));
+        tag.push('"');
+    }
+    tag.push('>');
+    tag
+}
+
+fn serialize_children(node: &Rc<RefCell<Node>>, html: &mut String) {
+    let mut child = node.borrow().first_child();
+    while let Some(c) = child {
+        serialize_node(&c, html);
+        child = c.borrow().next_sibling();
+    }
+}
+
+fn escape_html(s: &str, in_attribute: bool) -> String {
+    let mut escaped = String::new();
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' if !in_attribute => escaped.push_str("&lt;"),
+            '>' if !in_attribute => escaped.push_str("&gt;"),
+            '"' if in_attribute => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::dom::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+
+    fn create_document(html: &str) -> Rc<RefCell<Node>> {
+        let t = HtmlTokenizer::new(html.to_string());
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+        document
+    }
+
+    #[test]
+    fn test_find_element_by_id_nested() {
+        let document = create_document(
+            r#"<html><head></head><body><div id="outer"><div><p id="inner">text</p></div></div></body></html>"#,
+        );
+
+        let outer = find_element_by_id(&document, "outer").expect("outer should exist");
+        assert_eq!(Some(ElementKind::Div), outer.borrow().element_kind());
+        let inner = find_element_by_id(&document, "inner").expect("inner should exist");
+        assert_eq!(Some(ElementKind::P), inner.borrow().element_kind());
+        assert_eq!("text", get_text_content(&inner));
+
+        // 部分木の中だけを探す
+        assert!(find_element_by_id(&inner, "outer").is_none());
+        assert!(find_element_by_id(&document, "missing").is_none());
+    }
+
+    #[test]
+    fn test_find_element_by_id_prefers_shallower_element() {
+        // 幅優先探索なので、同じidでは浅い階層の要素が見つかる
+        let document = create_document(
+            r#"<html><head></head><body><div><p id="dup">deep</p></div><p id="dup">shallow</p></body></html>"#,
+        );
+
+        let found = find_element_by_id(&document, "dup").expect("dup should exist");
+        assert_eq!("shallow", get_text_content(&found));
+    }
+
+    #[test]
+    fn test_tree_iter_order() {
+        let t = HtmlTokenizer::new(
+            "<html><head><title>t</title></head><body><p>a<b>b</b></p><div>c</div></body></html>"
+                .to_string(),
+        );
+        let window = HtmlParser::new(t).construct_tree();
+
+        let names = window
+            .borrow()
+            .nodes()
+            .map(|n| match n.borrow().kind() {
+                NodeKind::Document => "#document".to_string(),
+                NodeKind::Element(e) => e.kind().to_string(),
+                NodeKind::Text(text) => text,
+            })
+            .collect::<Vec<String>>();
+        assert_eq!(
+            [
+                "#document",
+                "html",
+                "head",
+                "title",
+                "t",
+                "body",
+                "p",
+                "a",
+                "b",
+                "b",
+                "div",
+                "c"
+            ]
+            .to_vec(),
+            names
+        );
+
+        // 部分木だけをたどり、rootの兄弟には進まない
+        let p = get_element_nodes(Some(window.borrow().document()), ElementKind::P)[0].clone();
+        assert_eq!(4, TreeIter::new(p).count());
+    }
+
+    fn children_of(node: &Rc<RefCell<Node>>) -> Vec<String> {
+        let mut ids = Vec::new();
+        let mut child = node.borrow().first_child();
+        while let Some(c) = child {
+            let element = c
+                .borrow()
+                .get_element()
+                .expect("child should be an element");
+            ids.push(element.get_attribute("id").unwrap_or_default());
+            child = c.borrow().next_sibling();
+        }
+        ids
+    }
+
+    #[test]
+    fn test_append_same_node_twice() {
+        let document = create_document(
+            r#"<html><head></head><body><div id="a"><p id="x"></p><p id="y"></p></div><div id="b"></div></body></html>"#,
+        );
+        let a = find_element_by_id(&document, "a").expect("a should exist");
+        let b = find_element_by_id(&document, "b").expect("b should exist");
+        let x = find_element_by_id(&document, "x").expect("x should exist");
+
+        // 2回目の追加では、1回目に追加した場所から移動するだけで循環しない
+        append_child(&a, x.clone());
+        assert_eq!(["y", "x"].to_vec(), children_of(&a));
+        append_child(&a, x.clone());
+        assert_eq!(["y", "x"].to_vec(), children_of(&a));
+        assert!(x.borrow().next_sibling().is_none());
+
+        // 別の親に移すと、元の親からはなくなる
+        append_child(&b, x.clone());
+        append_child(&b, x.clone());
+        assert_eq!(["y"].to_vec(), children_of(&a));
+        assert_eq!(["x"].to_vec(), children_of(&b));
+        assert!(Rc::ptr_eq(
+            &b,
+            &x.borrow()
+                .parent()
+                .upgrade()
+                .expect("x should have a parent")
+        ));
+        let last = a
+            .borrow()
+            .last_child()
+            .upgrade()
+            .expect("a should have a child");
+        assert_eq!(
+            Some("y".to_string()),
+            last.borrow()
+                .get_element()
+                .and_then(|e| e.get_attribute("id"))
+        );
+
+        // 自分自身の子孫にはならない
+        append_child(&x, b.clone());
+        assert_eq!(["x"].to_vec(), children_of(&b));
+    }
+}
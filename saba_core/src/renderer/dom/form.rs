@@ -0,0 +1,419 @@
+use crate::http::url_encode_pairs;
+use crate::renderer::dom::api::get_text_content;
+use crate::renderer::dom::node::{Element, ElementKind, Node, NodeKind, TreeIter};
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormMethod {
+    Get,
+    Post,
+}
+
+// フォームを送信するときに必要な情報
+// actionは絶対URLに解決済みのもの
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormSubmission {
+    method: FormMethod,
+    action: String,
+    fields: Vec<(String, String)>,
+}
+
+impl FormSubmission {
+    pub fn new(method: FormMethod, action: String, fields: Vec<(String, String)>) -> Self {
+        Self {
+            method,
+            action,
+            fields,
+        }
+    }
+
+    pub fn method(&self) -> FormMethod {
+        self.method
+    }
+
+    pub fn action(&self) -> String {
+        self.action.clone()
+    }
+
+    pub fn fields(&self) -> Vec<(String, String)> {
+        self.fields.clone()
+    }
+
+    // application/x-www-form-urlencodedでエンコードしたフィールド
+    pub fn encoded_fields(&self) -> String {
+        url_encode_pairs(&self.fields)
+    }
+
+    // 送信したときに移動するURL
+    // GETではactionのクエリ文字列とフラグメントをフィールドで置き換える
+    pub fn url(&self) -> String {
+        match self.method {
+            FormMethod::Get => {
+                let end = self.action.find(['?', '#']).unwrap_or(self.action.len());
+                format!("{}?{}", &self.action[..end], self.encoded_fields())
+            }
+            FormMethod::Post => self.action.clone(),
+        }
+    }
+}
+
+// type属性の値。省略されたときはtext
+pub fn input_type(element: &Element) -> String {
+    element
+        .get_attribute("type")
+        .map(|t| t.to_ascii_lowercase())
+        .unwrap_or("text".to_string())
+}
+
+// クリックするとフォームを送信するボタンかどうか
+// buttonのtype属性のデフォルトはsubmit
+pub fn is_submit_control(element: &Element) -> bool {
+    match element.kind() {
+        ElementKind::Input => input_type(element) == "submit",
+        ElementKind::Button => match element.get_attribute("type") {
+            Some(t) => t.eq_ignore_ascii_case("submit"),
+            None => true,
+        },
+        _ => false,
+    }
+}
+
+// 文字を入力できるinputかどうか
+pub fn is_text_input(element: &Element) -> bool {
+    element.kind() == ElementKind::Input
+        && matches!(
+            input_type(element).as_str(),
+            "text" | "search" | "email" | "url" | "tel" | "password" | "number"
+        )
+}
+
+// フォームの既定のボタン。文書内の順で最初の送信ボタン
+pub fn default_button(form: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
+    TreeIter::new(form.clone()).find(|n| {
+        n.borrow()
+            .get_element()
+            .is_some_and(|e| is_submit_control(&e))
+    })
+}
+
+// nodeを含む、最も内側のform要素
+pub fn enclosing_form(node: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
+    let mut current = node.borrow().parent().upgrade();
+    while let Some(n) = current {
+        if n.borrow().element_kind() == Some(ElementKind::Form) {
+            return Some(n);
+        }
+        current = n.borrow().parent().upgrade();
+    }
+    None
+}
+
+pub fn form_method(form: &Element) -> FormMethod {
+    match form.get_attribute("method") {
+        Some(method) if method.eq_ignore_ascii_case("post") => FormMethod::Post,
+        _ => FormMethod::Get,
+    }
+}
+
+// selectの選択肢。valueが省略されたときは表示する文字列を送信する
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectOption {
+    label: String,
+    value: String,
+}
+
+impl SelectOption {
+    pub fn new(label: String, value: String) -> Self {
+        Self { label, value }
+    }
+
+    pub fn label(&self) -> String {
+        self.label.clone()
+    }
+
+    pub fn value(&self) -> String {
+        self.value.clone()
+    }
+}
+
+// selectの状態。選択肢と、選ばれている選択肢の位置
+// 描画とは独立して、DOMとの間で読み書きする
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectState {
+    options: Vec<SelectOption>,
+    selected_index: Option<usize>,
+}
+
+impl SelectState {
+    pub fn new(options: Vec<SelectOption>, selected_index: Option<usize>) -> Self {
+        let selected_index = selected_index.filter(|i| *i < options.len());
+        Self {
+            options,
+            selected_index,
+        }
+    }
+
+    // selectの中のoptionから状態を作る
+    // selected属性を持つ最後のoption、なければ最初のoptionが選ばれている
+    // https://html.spec.whatwg.org/multipage/form-elements.html#selectedness-setting-algorithm
+    pub fn from_node(select: &Rc<RefCell<Node>>) -> Self {
+        let mut options = Vec::new();
+        let mut selected_index = None;
+        for option in option_nodes(select) {
+            let element = match option.borrow().get_element() {
+                Some(e) => e,
+                None => continue,
+            };
+            let label = get_text_content(&option).trim().to_string();
+            let value = element.get_attribute("value").unwrap_or(label.clone());
+            if element.has_attribute("selected") {
+                selected_index = Some(options.len());
+            }
+            options.push(SelectOption::new(label, value));
+        }
+
+        if selected_index.is_none() && !options.is_empty() {
+            selected_index = Some(0);
+        }
+        Self::new(options, selected_index)
+    }
+
+    pub fn options(&self) -> Vec<SelectOption> {
+        self.options.clone()
+    }
+
+    pub fn selected_index(&self) -> Option<usize> {
+        self.selected_index
+    }
+
+    pub fn selected(&self) -> Option<SelectOption> {
+        self.options.get(self.selected_index?).cloned()
+    }
+
+    // 選択肢を選ぶ。範囲外のときは何もせずfalseを返す
+    pub fn select(&mut self, index: usize) -> bool {
+        if index >= self.options.len() {
+            return false;
+        }
+        self.selected_index = Some(index);
+        true
+    }
+
+    // 選ばれている選択肢だけがselected属性を持つように、DOMに書き戻す
+    pub fn apply_to(&self, select: &Rc<RefCell<Node>>) {
+        for (i, option) in option_nodes(select).iter().enumerate() {
+            if let NodeKind::Element(ref mut e) = option.borrow_mut().kind {
+                if Some(i) == self.selected_index {
+                    e.set_attribute("selected", "");
+                } else {
+                    e.remove_attribute("selected");
+                }
+            }
+        }
+    }
+}
+
+// selectの中のoption要素を文書内の順に集める
+fn option_nodes(select: &Rc<RefCell<Node>>) -> Vec<Rc<RefCell<Node>>> {
+    TreeIter::new(select.clone())
+        .filter(|n| n.borrow().element_kind() == Some(ElementKind::Option))
+        .collect()
+}
+
+// フォームの中のコントロールから、送信する名前と値の組を文書内の順に集める
+// 送信ボタンは、送信に使ったもの(submitter)だけを含める
+// https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#constructing-the-form-data-set
+pub fn collect_form_fields(
+    form: &Rc<RefCell<Node>>,
+    submitter: Option<&Rc<RefCell<Node>>>,
+) -> Vec<(String, String)> {
+    let mut fields = Vec::new();
+
+    for node in TreeIter::new(form.clone()) {
+        let element = match node.borrow().get_element() {
+            Some(e) => e,
+            None => continue,
+        };
+        let name = match element.get_attribute("name") {
+            Some(name) if !name.is_empty() => name,
+            _ => continue,
+        };
+        if element.has_attribute("disabled") {
+            continue;
+        }
+        let value = element.get_attribute("value");
+        let is_submitter = submitter.is_some_and(|s| Rc::ptr_eq(s, &node));
+
+        let value = match element.kind() {
+            ElementKind::Input => match input_type(&element).as_str() {
+                "submit" if is_submitter => value.unwrap_or_default(),
+                "submit" | "reset" | "button" | "image" => continue,
+                "checkbox" | "radio" if element.has_attribute("checked") => {
+                    value.unwrap_or("on".to_string())
+                }
+                "checkbox" | "radio" => continue,
+                _ => value.unwrap_or_default(),
+            },
+            ElementKind::Button if is_submitter => value.unwrap_or_default(),
+            ElementKind::Select => match SelectState::from_node(&node).selected() {
+                Some(option) => option.value(),
+                None => continue,
+            },
+            _ => continue,
+        };
+        fields.push((name, value));
+    }
+
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::dom::api::{find_element_by_id, get_element_nodes};
+    use crate::renderer::dom::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+
+    fn create_document(html: &str) -> Rc<RefCell<Node>> {
+        let t = HtmlTokenizer::new(html.to_string());
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+        document
+    }
+
+    fn field(name: &str, value: &str) -> (String, String) {
+        (name.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn test_collect_form_fields() {
+        let document = create_document(
+            r#"<html><head></head><body><form><input name="q" value="saba browser"><input type="hidden" name="lang" value="ja"><input type="checkbox" name="safe" checked><input type="checkbox" name="off"><input name="disabled" value="x" disabled><input value="no name"><input type="submit" id="go" name="action" value="go"><button id="more" name="action" value="more">more</button></form></body></html>"#,
+        );
+        let form = get_element_nodes(Some(document.clone()), ElementKind::Form)[0].clone();
+        let go = find_element_by_id(&document, "go").expect("go should exist");
+        let more = find_element_by_id(&document, "more").expect("more should exist");
+
+        let common = [
+            field("q", "saba browser"),
+            field("lang", "ja"),
+            field("safe", "on"),
+        ];
+        // 押されたボタンの名前と値だけを含める
+        assert_eq!(
+            [common.to_vec(), [field("action", "go")].to_vec()].concat(),
+            collect_form_fields(&form, Some(&go))
+        );
+        assert_eq!(
+            [common.to_vec(), [field("action", "more")].to_vec()].concat(),
+            collect_form_fields(&form, Some(&more))
+        );
+        assert_eq!(common.to_vec(), collect_form_fields(&form, None));
+    }
+
+    #[test]
+    fn test_select_state() {
+        let document = create_document(
+            r#"<html><head></head><body><select id="s"><option value="s">small<option selected>medium</option><option value="l">large</select></body></html>"#,
+        );
+        let select = find_element_by_id(&document, "s").expect("s should exist");
+
+        let mut state = SelectState::from_node(&select);
+        assert_eq!(
+            [
+                SelectOption::new("small".to_string(), "s".to_string()),
+                SelectOption::new("medium".to_string(), "medium".to_string()),
+                SelectOption::new("large".to_string(), "l".to_string()),
+            ]
+            .to_vec(),
+            state.options()
+        );
+        assert_eq!(Some(1), state.selected_index());
+
+        assert!(!state.select(3));
+        assert_eq!(Some(1), state.selected_index());
+        assert!(state.select(2));
+        assert_eq!(Some("l".to_string()), state.selected().map(|o| o.value()));
+
+        // DOMに書き戻すと、作り直しても同じ状態になる
+        state.apply_to(&select);
+        assert_eq!(state, SelectState::from_node(&select));
+    }
+
+    #[test]
+    fn test_select_state_without_selected_option() {
+        let document = create_document(
+            r#"<html><head></head><body><select id="a"><option>a</option><option>b</option></select><select id="b"></select></body></html>"#,
+        );
+        let a = find_element_by_id(&document, "a").expect("a should exist");
+        let b = find_element_by_id(&document, "b").expect("b should exist");
+
+        assert_eq!(Some(0), SelectState::from_node(&a).selected_index());
+        assert_eq!(None, SelectState::from_node(&b).selected());
+    }
+
+    #[test]
+    fn test_selection_changes_submitted_value() {
+        let document = create_document(
+            r#"<html><head></head><body><form><select name="size"><option value="s">small</option><option value="m">medium</option></select><select name="empty"></select></form></body></html>"#,
+        );
+        let form = get_element_nodes(Some(document.clone()), ElementKind::Form)[0].clone();
+        let select = get_element_nodes(Some(document.clone()), ElementKind::Select)[0].clone();
+        assert_eq!(
+            [field("size", "s")].to_vec(),
+            collect_form_fields(&form, None)
+        );
+
+        let mut state = SelectState::from_node(&select);
+        state.select(1);
+        state.apply_to(&select);
+        assert_eq!(
+            [field("size", "m")].to_vec(),
+            collect_form_fields(&form, None)
+        );
+    }
+
+    #[test]
+    fn test_is_submit_control() {
+        let submit = |tag: &str, attributes: &str| {
+            let document = create_document(&format!(
+                "<html><head></head><body><{} id=\"c\" {}></body></html>",
+                tag, attributes
+            ));
+            let node = find_element_by_id(&document, "c").expect("c should exist");
+            let element = node.borrow().get_element().expect("c should be an element");
+            is_submit_control(&element)
+        };
+
+        assert!(submit("input", "type=\"submit\""));
+        assert!(submit("input", "type=\"SUBMIT\""));
+        assert!(!submit("input", ""));
+        assert!(submit("button", ""));
+        assert!(submit("button", "type=\"submit\""));
+        assert!(!submit("button", "type=\"button\""));
+        assert!(!submit("p", ""));
+    }
+
+    #[test]
+    fn test_submission_url() {
+        let fields = [field("q", "a b"), field("n", "1")].to_vec();
+        let get = FormSubmission::new(
+            FormMethod::Get,
+            "http://example.com:80/search?old=1#top".to_string(),
+            fields.clone(),
+        );
+        assert_eq!("http://example.com:80/search?q=a+b&n=1", get.url());
+
+        let post = FormSubmission::new(
+            FormMethod::Post,
+            "http://example.com:80/search".to_string(),
+            fields,
+        );
+        assert_eq!("http://example.com:80/search", post.url());
+        assert_eq!("q=a+b&n=1", post.encoded_fields());
+    }
+}
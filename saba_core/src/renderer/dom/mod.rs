@@ -1,3 +1,5 @@
+pub mod accessibility;
 pub mod api;
+pub mod form;
 pub mod node;
 pub mod parser;
@@ -7,10 +7,16 @@ use alloc::vec::Vec;
 use core::cell::RefCell;
 use core::fmt::{Display, Formatter};
 use core::str::FromStr;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+// 次に作るノードの番号
+static NEXT_NODE_ID: AtomicUsize = AtomicUsize::new(0);
 
 #[derive(Debug, Clone)]
 pub struct Node {
     pub kind: NodeKind,
+    // ノードを作るたびに割り当てる番号。ノードが破棄されても、同じ番号は再利用しない
+    id: usize,
     window: Weak<RefCell<Window>>,
     parent: Weak<RefCell<Node>>,
     first_child: Option<Rc<RefCell<Node>>>,
@@ -29,6 +35,7 @@ impl Node {
     pub fn new(kind: NodeKind) -> Self {
         Self {
             kind,
+            id: NEXT_NODE_ID.fetch_add(1, Ordering::Relaxed),
             window: Weak::new(),
             parent: Weak::new(),
             first_child: None,
@@ -81,6 +88,10 @@ impl Node {
         self.kind.clone()
     }
 
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
     pub fn get_element(&self) -> Option<Element> {
         match self.kind {
             NodeKind::Document | NodeKind::Text(_) => None,
@@ -94,6 +105,19 @@ impl Node {
             NodeKind::Element(ref e) => Some(e.kind()),
         }
     }
+
+    // lang属性で指定された言語
+    // 指定されていなければ祖先から引き継ぎ、どこにもなければ空文字列になる
+    pub fn effective_lang(&self) -> String {
+        if let Some(lang) = self.get_element().and_then(|e| e.get_attribute("lang")) {
+            return lang;
+        }
+
+        match self.parent().upgrade() {
+            Some(parent) => parent.borrow().effective_lang(),
+            None => String::new(),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -116,6 +140,40 @@ impl PartialEq for NodeKind {
     }
 }
 
+// rootとその子孫を、深さ優先で文書内の順に返すイテレータ
+// rootの兄弟はたどらない
+#[derive(Debug, Clone)]
+pub struct TreeIter {
+    stack: Vec<Rc<RefCell<Node>>>,
+}
+
+impl TreeIter {
+    pub fn new(root: Rc<RefCell<Node>>) -> Self {
+        Self {
+            stack: [root].to_vec(),
+        }
+    }
+}
+
+impl Iterator for TreeIter {
+    type Item = Rc<RefCell<Node>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+
+        // 最初の子が先に取り出されるように、逆順に積む
+        let mut children = Vec::new();
+        let mut child = node.borrow().first_child();
+        while let Some(c) = child {
+            child = c.borrow().next_sibling();
+            children.push(c);
+        }
+        self.stack.extend(children.into_iter().rev());
+
+        Some(node)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Window {
     document: Rc<RefCell<Node>>,
@@ -138,30 +196,65 @@ impl Window {
     pub fn document(&self) -> Rc<RefCell<Node>> {
         self.document.clone()
     }
+
+    // 文書内のすべてのノードを、深さ優先で文書内の順に返す
+    pub fn nodes(&self) -> TreeIter {
+        TreeIter::new(self.document())
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+// DocumentFragmentの子ノードの並び。複数の持ち主で共有する
+pub type FragmentNodes = Rc<RefCell<Vec<Rc<RefCell<Node>>>>>;
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Element {
     kind: ElementKind,
     attributes: Vec<Attribute>,
+    // <template>要素の中身。メインのドキュメントツリーには含まれない
+    // JSのtemplate.contentと共有し、appendChildで移動したノードは中身からなくなる
+    content: Option<FragmentNodes>,
 }
 
 impl Element {
     pub fn new(element_name: &str, attributes: Vec<Attribute>) -> Self {
+        let kind =
+            ElementKind::from_str(element_name).expect("failed to convert string to ElementKind");
+        Self::with_kind(kind, attributes)
+    }
+
+    pub fn with_kind(kind: ElementKind, attributes: Vec<Attribute>) -> Self {
+        let content = if kind == ElementKind::Template {
+            Some(Rc::new(RefCell::new(Vec::new())))
+        } else {
+            None
+        };
+
         Self {
-            kind: ElementKind::from_str(element_name)
-                .expect("failed to convert string to ElementKind"),
+            kind,
             attributes,
+            content,
         }
     }
 
     pub fn kind(&self) -> ElementKind {
-        self.kind
+        self.kind.clone()
     }
 
     pub fn is_block_element(&self) -> bool {
         match self.kind {
-            ElementKind::Body | ElementKind::H1 | ElementKind::H2 | ElementKind::P => true,
+            ElementKind::Body
+            | ElementKind::H1
+            | ElementKind::H2
+            | ElementKind::P
+            | ElementKind::Div
+            | ElementKind::Nav
+            | ElementKind::Header
+            | ElementKind::Footer
+            | ElementKind::Article
+            | ElementKind::Section
+            | ElementKind::Details
+            | ElementKind::Summary
+            | ElementKind::Form => true,
             _ => false,
         }
     }
@@ -178,19 +271,70 @@ impl Element {
         }
         None
     }
+
+    // openやcheckedなどの真偽値の属性は、値ではなく属性があるかどうかで判定する
+    // `<details open>`の値は空文字列になるため、get_attributeでは判定できない
+    pub fn has_attribute(&self, name: &str) -> bool {
+        self.attributes.iter().any(|attr| attr.name() == name)
+    }
+
+    pub fn set_attribute(&mut self, name: &str, value: &str) {
+        match self.attributes.iter_mut().find(|attr| attr.name() == name) {
+            Some(attr) => attr.set_value(value),
+            None => self.attributes.push(Attribute::with_value(name, value)),
+        }
+    }
+
+    pub fn remove_attribute(&mut self, name: &str) {
+        self.attributes.retain(|attr| attr.name() != name);
+    }
+
+    pub fn set_content(&mut self, content: Vec<Rc<RefCell<Node>>>) {
+        self.content = Some(Rc::new(RefCell::new(content)));
+    }
+
+    pub fn content(&self) -> Option<FragmentNodes> {
+        self.content.clone()
+    }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ElementKind {
     Html,
     Head,
+    Title,
+    Meta,
     Style,
     Script,
     Body,
     P,
+    Div,
+    Nav,
+    Header,
+    Footer,
+    Article,
+    Section,
+    Details,
+    Summary,
     H1,
     H2,
     A,
+    B,
+    I,
+    Em,
+    Strong,
+    Link,
+    Template,
+    Svg,
+    Progress,
+    Meter,
+    Form,
+    Input,
+    Button,
+    Select,
+    Option,
+    // SVG内の要素など、個別にサポートしていない要素
+    Unknown(String),
 }
 
 impl FromStr for ElementKind {
@@ -200,13 +344,37 @@ impl FromStr for ElementKind {
         match s {
             "html" => Ok(ElementKind::Html),
             "head" => Ok(ElementKind::Head),
+            "title" => Ok(ElementKind::Title),
+            "meta" => Ok(ElementKind::Meta),
             "style" => Ok(ElementKind::Style),
             "script" => Ok(ElementKind::Script),
             "body" => Ok(ElementKind::Body),
             "p" => Ok(ElementKind::P),
+            "div" => Ok(ElementKind::Div),
+            "nav" => Ok(ElementKind::Nav),
+            "header" => Ok(ElementKind::Header),
+            "footer" => Ok(ElementKind::Footer),
+            "article" => Ok(ElementKind::Article),
+            "section" => Ok(ElementKind::Section),
+            "details" => Ok(ElementKind::Details),
+            "summary" => Ok(ElementKind::Summary),
             "h1" => Ok(ElementKind::H1),
             "h2" => Ok(ElementKind::H2),
             "a" => Ok(ElementKind::A),
+            "b" => Ok(ElementKind::B),
+            "i" => Ok(ElementKind::I),
+            "em" => Ok(ElementKind::Em),
+            "strong" => Ok(ElementKind::Strong),
+            "link" => Ok(ElementKind::Link),
+            "template" => Ok(ElementKind::Template),
+            "svg" => Ok(ElementKind::Svg),
+            "progress" => Ok(ElementKind::Progress),
+            "meter" => Ok(ElementKind::Meter),
+            "form" => Ok(ElementKind::Form),
+            "input" => Ok(ElementKind::Input),
+            "button" => Ok(ElementKind::Button),
+            "select" => Ok(ElementKind::Select),
+            "option" => Ok(ElementKind::Option),
             _ => Err(format!("unimplemented element name: {}", s)),
         }
     }
@@ -217,13 +385,38 @@ impl Display for ElementKind {
         let s = match self {
             ElementKind::Html => "html",
             ElementKind::Head => "head",
+            ElementKind::Title => "title",
+            ElementKind::Meta => "meta",
             ElementKind::Style => "style",
             ElementKind::Script => "script",
             ElementKind::Body => "body",
             ElementKind::P => "p",
+            ElementKind::Div => "div",
+            ElementKind::Nav => "nav",
+            ElementKind::Header => "header",
+            ElementKind::Footer => "footer",
+            ElementKind::Article => "article",
+            ElementKind::Section => "section",
+            ElementKind::Details => "details",
+            ElementKind::Summary => "summary",
             ElementKind::H1 => "h1",
             ElementKind::H2 => "h2",
             ElementKind::A => "a",
+            ElementKind::B => "b",
+            ElementKind::I => "i",
+            ElementKind::Em => "em",
+            ElementKind::Strong => "strong",
+            ElementKind::Link => "link",
+            ElementKind::Template => "template",
+            ElementKind::Svg => "svg",
+            ElementKind::Progress => "progress",
+            ElementKind::Meter => "meter",
+            ElementKind::Form => "form",
+            ElementKind::Input => "input",
+            ElementKind::Button => "button",
+            ElementKind::Select => "select",
+            ElementKind::Option => "option",
+            ElementKind::Unknown(tag) => tag,
         };
         write!(f, "{}", s)
     }
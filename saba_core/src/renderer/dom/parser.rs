@@ -3,12 +3,55 @@ use crate::renderer::dom::node::{Element, ElementKind, Node, NodeKind};
 use crate::renderer::html::attribute::Attribute;
 use crate::renderer::html::token::HtmlToken;
 use crate::renderer::html::token::HtmlTokenizer;
+use alloc::format;
 use alloc::rc::Rc;
-use alloc::string::String;
+use alloc::rc::Weak;
+use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use core::cell::RefCell;
+use core::fmt::{Display, Formatter};
 use core::str::FromStr;
 
+// 不正なHTMLを読み進めるために行った回復処理の記録。行と列は1から数える
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseWarning {
+    message: String,
+    line: u32,
+    column: u32,
+}
+
+impl ParseWarning {
+    pub fn new(message: String, (line, column): (u32, u32)) -> Self {
+        Self {
+            message,
+            line,
+            column,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    pub fn column(&self) -> u32 {
+        self.column
+    }
+}
+
+impl Display for ParseWarning {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        write!(
+            f,
+            "{} (line {}, column {})",
+            self.message, self.line, self.column
+        )
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct HtmlParser {
     window: Rc<RefCell<Window>>,
@@ -16,6 +59,7 @@ pub struct HtmlParser {
     original_insertion_mode: InsertionMode,
     stack_of_open_elements: Vec<Rc<RefCell<Node>>>,
     t: HtmlTokenizer,
+    warnings: Vec<ParseWarning>,
 }
 
 impl HtmlParser {
@@ -26,9 +70,22 @@ impl HtmlParser {
             original_insertion_mode: InsertionMode::Initial,
             stack_of_open_elements: Vec::new(),
             t,
+            warnings: Vec::new(),
         }
     }
 
+    // DOMツリーを構築し、その途中で行った回復処理の警告と一緒に返す
+    pub fn into_result(mut self) -> (Rc<RefCell<Window>>, Vec<ParseWarning>) {
+        let window = self.construct_tree();
+        (window, self.warnings)
+    }
+
+    // 直前に読んだトークンの位置で警告を記録する
+    fn warn(&mut self, message: String) {
+        self.warnings
+            .push(ParseWarning::new(message, self.t.location()));
+    }
+
     pub fn construct_tree(&mut self) -> Rc<RefCell<Window>> {
         let mut token = self.t.next();
 
@@ -68,6 +125,7 @@ impl HtmlParser {
                         }
                         _ => {}
                     }
+                    self.warn("missing <html> start tag".to_string());
                     self.insert_element("html", Vec::new());
                     self.mode = InsertionMode::BeforeHead;
                     continue;
@@ -97,6 +155,7 @@ impl HtmlParser {
                         }
                         _ => {}
                     }
+                    self.warn("missing <head> start tag".to_string());
                     self.insert_element("head", Vec::new());
                     self.mode = InsertionMode::InHead;
                     continue;
@@ -115,13 +174,22 @@ impl HtmlParser {
                             self_closing: _,
                             ref attributes,
                         }) => {
-                            if tag == "style" || tag == "script" {
+                            if tag == "style" || tag == "script" || tag == "title" {
                                 self.insert_element(tag, attributes.to_vec());
                                 self.original_insertion_mode = self.mode;
                                 self.mode = InsertionMode::Text;
                                 token = self.t.next();
                                 continue;
                             }
+                            // linkとmetaは空要素なので、すぐにスタックから取り除く
+                            if tag == "link" || tag == "meta" {
+                                self.insert_element(tag, attributes.to_vec());
+                                let element_kind = ElementKind::from_str(tag)
+                                    .expect("failed to convert string to ElementKind");
+                                self.pop_current_node(element_kind);
+                                token = self.t.next();
+                                continue;
+                            }
                             if tag == "body" {
                                 self.pop_until(ElementKind::Head);
                                 self.mode = InsertionMode::AfterHead;
@@ -132,49 +200,61 @@ impl HtmlParser {
                                 self.mode = InsertionMode::AfterHead;
                                 continue;
                             }
+                            self.warn(format!("unsupported <{}> in head is ignored", tag));
+                        }
+                        Some(HtmlToken::EndTag { ref tag }) => {
+                            if tag != "head" {
+                                self.warn(format!("unexpected end tag </{}> is ignored", tag));
+                            }
                         }
                         Some(HtmlToken::Eof) | None => {
                             return self.window.clone();
                         }
-                        _ => {}
                     }
                     // サポート外のタグは無視する
                     token = self.t.next();
                     continue;
                 }
-                InsertionMode::AfterHead => match token {
-                    Some(HtmlToken::Char(c)) => {
-                        if c == ' ' || c == '\n' {
-                            self.insert_char(c);
-                            token = self.t.next();
-                            continue;
+                InsertionMode::AfterHead => {
+                    match token {
+                        Some(HtmlToken::Char(c)) => {
+                            if c == ' ' || c == '\n' {
+                                self.insert_char(c);
+                                token = self.t.next();
+                                continue;
+                            }
                         }
-                    }
-                    Some(HtmlToken::StartTag {
-                        ref tag,
-                        self_closing: _,
-                        ref attributes,
-                    }) => {
-                        if tag == "body" {
-                            self.insert_element(tag, attributes.to_vec());
-                            token = self.t.next();
-                            self.mode = InsertionMode::InBody;
-                            continue;
+                        Some(HtmlToken::StartTag {
+                            ref tag,
+                            self_closing: _,
+                            ref attributes,
+                        }) => {
+                            if tag == "body" {
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                self.mode = InsertionMode::InBody;
+                                continue;
+                            }
                         }
+                        Some(HtmlToken::Eof) | None => {
+                            return self.window.clone();
+                        }
+                        _ => {}
                     }
-                    Some(HtmlToken::Eof) | None => {
-                        return self.window.clone();
-                    }
-                    _ => {}
-                },
+                    // bodyの開始タグがないので、body要素を補ってInBodyで処理し直す
+                    self.warn("missing <body> start tag".to_string());
+                    self.insert_element("body", Vec::new());
+                    self.mode = InsertionMode::InBody;
+                }
                 InsertionMode::InBody => {
                     match token {
                         Some(HtmlToken::StartTag {
                             ref tag,
-                            self_closing: _,
+                            self_closing,
                             ref attributes,
                         }) => match tag.as_str() {
-                            "p" => {
+                            "p" | "div" | "nav" | "header" | "footer" | "article" | "section"
+                            | "details" | "summary" | "form" => {
                                 self.insert_element(tag, attributes.to_vec());
                                 token = self.t.next();
                                 continue;
@@ -184,12 +264,54 @@ impl HtmlParser {
                                 token = self.t.next();
                                 continue;
                             }
-                            "a" => {
+                            "a" | "b" | "i" | "em" | "strong" | "button" => {
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+                            "option" => {
+                                // 閉じていないoptionは、次のoptionで閉じる
+                                self.pop_current_node(ElementKind::Option);
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+                            "input" => {
+                                // 終了タグを持たないので、すぐに閉じる
                                 self.insert_element(tag, attributes.to_vec());
+                                self.stack_of_open_elements.pop();
+                                token = self.t.next();
+                                continue;
+                            }
+                            "template" | "progress" | "meter" | "select" => {
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+                            "svg" => {
+                                self.insert_element(tag, attributes.to_vec());
+                                if self_closing {
+                                    self.pop_until(ElementKind::Svg);
+                                } else {
+                                    self.mode = InsertionMode::InSvg;
+                                }
                                 token = self.t.next();
                                 continue;
                             }
                             _ => {
+                                // 未知の要素やカスタム要素は、子要素を表示するためにUnknownとして挿入する
+                                if ElementKind::from_str(tag).is_err() {
+                                    // カスタム要素の名前はハイフンを含む
+                                    if !tag.contains('-') && !is_void_element(tag) {
+                                        self.warn(format!("unknown element <{}>", tag));
+                                    }
+                                    self.insert_element(tag, attributes.to_vec());
+                                    if self_closing || is_void_element(tag) {
+                                        self.stack_of_open_elements.pop();
+                                    }
+                                } else {
+                                    self.warn(format!("unexpected <{}> in body is ignored", tag));
+                                }
                                 token = self.t.next();
                             }
                         },
@@ -197,12 +319,16 @@ impl HtmlParser {
                             match tag.as_str() {
                                 "body" => {
                                     self.mode = InsertionMode::AfterBody;
-                                    token = self.t.next();
                                     if !self.contain_in_stack(ElementKind::Body) {
                                         // パース失敗、トークンを無視する
+                                        self.warn(
+                                            "unexpected end tag </body> is ignored".to_string(),
+                                        );
+                                        token = self.t.next();
                                         continue;
                                     }
-                                    self.pop_until(ElementKind::Body);
+                                    self.close_element(ElementKind::Body);
+                                    token = self.t.next();
                                     continue;
                                 }
                                 "html" => {
@@ -210,37 +336,56 @@ impl HtmlParser {
                                         self.mode = InsertionMode::AfterBody;
                                         assert!(self.pop_current_node(ElementKind::Html));
                                     } else {
+                                        self.warn(
+                                            "unexpected end tag </html> is ignored".to_string(),
+                                        );
                                         token = self.t.next();
                                     }
                                     continue;
                                 }
-                                "p" => {
+                                "p" | "div" | "nav" | "header" | "footer" | "article"
+                                | "section" | "details" | "summary" | "form" => {
                                     let element_kind = ElementKind::from_str(tag)
                                         .expect("failed to convert string to ElementKind");
+                                    self.close_element(element_kind);
                                     token = self.t.next();
-                                    self.pop_until(element_kind);
                                     continue;
                                 }
                                 "h1" | "h2" => {
                                     let element_kind = ElementKind::from_str(tag)
                                         .expect("failed to convert string to ElementKind");
+                                    self.close_element(element_kind);
                                     token = self.t.next();
-                                    self.pop_until(element_kind);
                                     continue;
                                 }
-                                "a" => {
+                                "a" | "b" | "i" | "em" | "strong" | "progress" | "meter"
+                                | "button" | "option" => {
                                     let element_kind = ElementKind::from_str(tag)
                                         .expect("failed to convert string to ElementKind");
+                                    self.close_element(element_kind);
+                                    token = self.t.next();
+                                    continue;
+                                }
+                                "select" => {
+                                    // 最後のoptionの終了タグは省略できる
+                                    self.pop_current_node(ElementKind::Option);
+                                    self.close_element(ElementKind::Select);
+                                    token = self.t.next();
+                                    continue;
+                                }
+                                "template" => {
+                                    self.close_element(ElementKind::Template);
                                     token = self.t.next();
-                                    self.pop_until(element_kind);
                                     continue;
                                 }
                                 _ => {
+                                    self.close_element(ElementKind::Unknown(tag.clone()));
                                     token = self.t.next();
                                 }
                             }
                         }
                         Some(HtmlToken::Eof) | None => {
+                            self.warn_unclosed_elements();
                             return self.window.clone();
                         }
                         Some(HtmlToken::Char(c)) => {
@@ -250,9 +395,46 @@ impl HtmlParser {
                         }
                     }
                 }
+                InsertionMode::InSvg => match token {
+                    Some(HtmlToken::StartTag {
+                        ref tag,
+                        self_closing,
+                        ref attributes,
+                    }) => {
+                        self.insert_element(tag, attributes.to_vec());
+                        if self_closing {
+                            self.stack_of_open_elements.pop();
+                        }
+                        token = self.t.next();
+                        continue;
+                    }
+                    Some(HtmlToken::EndTag { ref tag }) => {
+                        if tag == "svg" {
+                            self.pop_until(ElementKind::Svg);
+                            self.mode = InsertionMode::InBody;
+                            token = self.t.next();
+                            continue;
+                        }
+                        let element_kind = ElementKind::Unknown(tag.clone());
+                        if self.contain_in_stack(element_kind.clone()) {
+                            self.pop_until(element_kind);
+                        }
+                        token = self.t.next();
+                        continue;
+                    }
+                    Some(HtmlToken::Char(c)) => {
+                        self.insert_char(c);
+                        token = self.t.next();
+                        continue;
+                    }
+                    Some(HtmlToken::Eof) | None => {
+                        return self.window.clone();
+                    }
+                },
                 InsertionMode::Text => {
                     match token {
                         Some(HtmlToken::Eof) | None => {
+                            self.warn_unclosed_elements();
                             return self.window.clone();
                         }
                         Some(HtmlToken::EndTag { ref tag }) => {
@@ -268,6 +450,12 @@ impl HtmlParser {
                                 token = self.t.next();
                                 continue;
                             }
+                            if tag == "title" {
+                                self.pop_until(ElementKind::Title);
+                                self.mode = self.original_insertion_mode;
+                                token = self.t.next();
+                                continue;
+                            }
                         }
                         Some(HtmlToken::Char(c)) => {
                             self.insert_char(c);
@@ -296,6 +484,7 @@ impl HtmlParser {
                         }
                         _ => {}
                     }
+                    self.warn("unexpected content after </body>".to_string());
                     self.mode = InsertionMode::InBody;
                 }
                 InsertionMode::AfterAfterBody => {
@@ -318,6 +507,13 @@ impl HtmlParser {
     }
 
     fn create_element(&self, tag: &str, attributes: Vec<Attribute>) -> Node {
+        // SVG内の要素と、未知の要素はすべてUnknownとして扱う
+        if self.mode == InsertionMode::InSvg || ElementKind::from_str(tag).is_err() {
+            return Node::new(NodeKind::Element(Element::with_kind(
+                ElementKind::Unknown(tag.to_string()),
+                attributes,
+            )));
+        }
         Node::new(NodeKind::Element(Element::new(tag, attributes)))
     }
 
@@ -329,30 +525,12 @@ impl HtmlParser {
         };
         let node = Rc::new(RefCell::new(self.create_element(tag, attributes)));
 
-        if current.borrow().first_child().is_some() {
-            let mut last_sibling = current.borrow().first_child();
-            loop {
-                last_sibling = match last_sibling {
-                    Some(ref node) => {
-                        if node.borrow().next_sibling().is_some() {
-                            node.borrow().next_sibling()
-                        } else {
-                            break;
-                        }
-                    }
-                    None => unimplemented!("last_sibling should be Some"),
-                }
-            }
+        if let Some(last_sibling) = Self::last_child_of(&current) {
             last_sibling
-                .unwrap()
                 .borrow_mut()
                 .set_next_sibling(Some(node.clone()));
-            node.borrow_mut().set_previous_sibling(Rc::downgrade(
-                &current
-                    .borrow()
-                    .first_child()
-                    .expect("failed to get a first child"),
-            ))
+            node.borrow_mut()
+                .set_previous_sibling(Rc::downgrade(&last_sibling));
         } else {
             current.borrow_mut().set_first_child(Some(node.clone()));
         }
@@ -363,13 +541,24 @@ impl HtmlParser {
         self.stack_of_open_elements.push(node);
     }
 
+    fn last_child_of(node: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
+        let mut last_sibling = node.borrow().first_child()?;
+        loop {
+            let next = last_sibling.borrow().next_sibling();
+            match next {
+                Some(next) => last_sibling = next,
+                None => return Some(last_sibling),
+            }
+        }
+    }
+
     fn pop_current_node(&mut self, element_kind: ElementKind) -> bool {
         let current = match self.stack_of_open_elements.last() {
             Some(n) => n,
             None => return false,
         };
 
-        if current.borrow().element_kind() == Some(element_kind) {
+        if current.borrow().element_kind().as_ref() == Some(&element_kind) {
             self.stack_of_open_elements.pop();
             return true;
         }
@@ -379,7 +568,7 @@ impl HtmlParser {
 
     fn pop_until(&mut self, element_kind: ElementKind) {
         assert!(
-            self.contain_in_stack(element_kind),
+            self.contain_in_stack(element_kind.clone()),
             "stack doesn't have an element {:?}",
             element_kind,
         );
@@ -390,15 +579,85 @@ impl HtmlParser {
                 None => return,
             };
 
-            if current.borrow().element_kind() == Some(element_kind) {
+            if current.borrow().element_kind().as_ref() == Some(&element_kind) {
+                if element_kind == ElementKind::Template {
+                    self.move_children_to_template_content(&current);
+                }
                 return;
             }
         }
     }
 
+    // 終了タグに対応する要素までスタックから取り除く
+    // 対応する要素が開いていなければ終了タグを無視し、途中の要素は暗黙に閉じたものとして警告する
+    fn close_element(&mut self, element_kind: ElementKind) {
+        if !self.contain_in_stack(element_kind.clone()) {
+            self.warn(format!("unexpected end tag </{}> is ignored", element_kind));
+            return;
+        }
+
+        let mut implicitly_closed = Vec::new();
+        for node in self.stack_of_open_elements.iter().rev() {
+            match node.borrow().element_kind() {
+                Some(kind) if kind == element_kind => break,
+                Some(kind) => implicitly_closed.push(kind),
+                // テキストノードは要素ではないので警告しない
+                None => {}
+            }
+        }
+        for kind in implicitly_closed {
+            self.warn(format!(
+                "<{}> is implicitly closed by </{}>",
+                kind, element_kind
+            ));
+        }
+
+        self.pop_until(element_kind);
+    }
+
+    // 文書の終わりで閉じられていない要素を警告する。htmlとbodyの終了タグは省略できる
+    fn warn_unclosed_elements(&mut self) {
+        let unclosed: Vec<ElementKind> = self
+            .stack_of_open_elements
+            .iter()
+            .filter_map(|node| node.borrow().element_kind())
+            .filter(|kind| *kind != ElementKind::Html && *kind != ElementKind::Body)
+            .collect();
+        for kind in unclosed {
+            self.warn(format!("unclosed element <{}> at end of document", kind));
+        }
+    }
+
+    // <template>の子ノードはドキュメントツリーから切り離し、templateのcontentとして保持する
+    fn move_children_to_template_content(&mut self, template: &Rc<RefCell<Node>>) {
+        let mut content = Vec::new();
+
+        let mut child = template.borrow().first_child();
+        while let Some(c) = child {
+            child = c.borrow().next_sibling();
+
+            c.borrow_mut().set_parent(Weak::new());
+            c.borrow_mut().set_previous_sibling(Weak::new());
+            c.borrow_mut().set_next_sibling(None);
+            content.push(c);
+        }
+
+        template.borrow_mut().set_first_child(None);
+        template.borrow_mut().set_last_child(Weak::new());
+
+        if let NodeKind::Element(ref mut e) = template.borrow_mut().kind {
+            e.set_content(content);
+        }
+    }
+
     fn contain_in_stack(&mut self, element_kind: ElementKind) -> bool {
         for i in 0..self.stack_of_open_elements.len() {
-            if self.stack_of_open_elements[i].borrow().element_kind() == Some(element_kind) {
+            if self.stack_of_open_elements[i]
+                .borrow()
+                .element_kind()
+                .as_ref()
+                == Some(&element_kind)
+            {
                 return true;
             }
         }
@@ -428,19 +687,13 @@ impl HtmlParser {
 
         let node = Rc::new(RefCell::new(self.create_char(c)));
 
-        if current.borrow().first_child().is_some() {
-            current
-                .borrow()
-                .first_child()
-                .unwrap()
+        // 最初の子ではなく、最後の子の後ろに追加する
+        if let Some(last_sibling) = Self::last_child_of(&current) {
+            last_sibling
                 .borrow_mut()
                 .set_next_sibling(Some(node.clone()));
-            node.borrow_mut().set_previous_sibling(Rc::downgrade(
-                &current
-                    .borrow()
-                    .first_child()
-                    .expect("failed to get a first child"),
-            ));
+            node.borrow_mut()
+                .set_previous_sibling(Rc::downgrade(&last_sibling));
         } else {
             current.borrow_mut().set_first_child(Some(node.clone()));
         }
@@ -460,15 +713,25 @@ pub enum InsertionMode {
     InHead,
     AfterHead,
     InBody,
+    InSvg,
     Text,
     AfterBody,
     AfterAfterBody,
 }
 
+// 終了タグを持たない要素
+fn is_void_element(tag: &str) -> bool {
+    matches!(
+        tag,
+        "area" | "br" | "col" | "embed" | "hr" | "img" | "input" | "source" | "track" | "wbr"
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::alloc::string::ToString;
+    use crate::renderer::dom::api::get_element_nodes;
     use alloc::vec;
 
     #[test]
@@ -642,4 +905,420 @@ mod tests {
             text
         );
     }
+
+    #[test]
+    fn test_formatting_elements() {
+        let html =
+            "<html><head></head><body><p><strong>bold</strong><em>italic</em>text</p></body></html>"
+                .to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let p = get_element_nodes(Some(document), ElementKind::P)
+            .first()
+            .expect("failed to get p")
+            .clone();
+        let strong = p
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of p");
+        assert_eq!(Some(ElementKind::Strong), strong.borrow().element_kind());
+        let em = strong
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of strong");
+        assert_eq!(Some(ElementKind::Em), em.borrow().element_kind());
+        let text = em
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of em");
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Text("text".to_string())))),
+            text
+        );
+    }
+
+    #[test]
+    fn test_template() {
+        let html =
+            "<html><head></head><body><template><p>inert</p></template></body></html>".to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let body = document
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document")
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of head");
+
+        let template = body
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of body");
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Element(Element::new(
+                "template",
+                Vec::new()
+            ))))),
+            template
+        );
+        // templateの子ノードはドキュメントツリーに含まれない
+        assert!(template.borrow().first_child().is_none());
+
+        let content = template
+            .borrow()
+            .get_element()
+            .expect("template should be an element")
+            .content()
+            .expect("template should have content");
+        let content = content.borrow();
+        assert_eq!(1, content.len());
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Element(Element::new(
+                "p",
+                Vec::new()
+            ))))),
+            content[0]
+        );
+        assert!(content[0].borrow().parent().upgrade().is_none());
+
+        let text = content[0]
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of p");
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Text("inert".to_string())))),
+            text
+        );
+    }
+
+    #[test]
+    fn test_svg() {
+        let html = "<html><head></head><body><svg width=\"10\"><circle r=\"5\"/><g><rect/></g></svg><p>after</p></body></html>"
+            .to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let body = document
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document")
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of head");
+
+        let svg = body
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of body");
+        assert_eq!(Some(ElementKind::Svg), svg.borrow().element_kind());
+
+        let circle = svg
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of svg");
+        assert_eq!(
+            Some(ElementKind::Unknown("circle".to_string())),
+            circle.borrow().element_kind()
+        );
+        assert!(circle.borrow().first_child().is_none());
+
+        let g = circle
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of circle");
+        assert_eq!(
+            Some(ElementKind::Unknown("g".to_string())),
+            g.borrow().element_kind()
+        );
+        let rect = g
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of g");
+        assert_eq!(
+            Some(ElementKind::Unknown("rect".to_string())),
+            rect.borrow().element_kind()
+        );
+
+        // </svg>の後は通常のHTMLとしてパースされる
+        let p = svg
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of svg");
+        assert_eq!(Some(ElementKind::P), p.borrow().element_kind());
+    }
+
+    #[test]
+    fn test_unknown_element() {
+        let html =
+            "<html><head></head><body><my-widget><img>hello</my-widget><p>after</p></body></html>"
+                .to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let body = document
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document")
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of head");
+
+        let widget = body
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of body");
+        assert_eq!(
+            Some(ElementKind::Unknown("my-widget".to_string())),
+            widget.borrow().element_kind()
+        );
+
+        // imgは終了タグがないため、続くテキストはimgの子にならない
+        let img = widget
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of my-widget");
+        assert_eq!(
+            Some(ElementKind::Unknown("img".to_string())),
+            img.borrow().element_kind()
+        );
+        assert!(img.borrow().first_child().is_none());
+        let hello = img
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of img");
+        assert_eq!(
+            Node::new(NodeKind::Text("hello".to_string())),
+            *hello.borrow()
+        );
+
+        let p = widget
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of my-widget");
+        assert_eq!(Some(ElementKind::P), p.borrow().element_kind());
+    }
+
+    #[test]
+    fn test_link() {
+        let html =
+            r#"<html><head><link rel="stylesheet" href="style.css"><style></style></head></html>"#
+                .to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let head = document
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document");
+        let link = head
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of head");
+        assert_eq!(Some(ElementKind::Link), link.borrow().element_kind());
+        assert!(link.borrow().first_child().is_none());
+
+        // linkは空要素なので、後続の要素は兄弟になる
+        let style = link
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of link");
+        assert_eq!(Some(ElementKind::Style), style.borrow().element_kind());
+    }
+
+    #[test]
+    fn test_unquoted_attribute_values() {
+        let html =
+            "<html><body><div class=foo id=bar title=\"a b\"></div></body></html>".to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let div = get_element_nodes(Some(window.borrow().document()), ElementKind::Div);
+        let element = div[0]
+            .borrow()
+            .get_element()
+            .expect("failed to get a div element");
+
+        assert_eq!(Some("foo".to_string()), element.get_attribute("class"));
+        assert_eq!(Some("bar".to_string()), element.get_attribute("id"));
+        assert_eq!(Some("a b".to_string()), element.get_attribute("title"));
+    }
+
+    #[test]
+    fn test_title_and_meta_in_head() {
+        let html =
+            r#"<html><head><meta charset="utf-8"><title>title</title></head><body></body></html>"#
+                .to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let head = document
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document");
+        assert_eq!(Some(ElementKind::Head), head.borrow().element_kind());
+
+        let meta = head
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of head");
+        assert_eq!(Some(ElementKind::Meta), meta.borrow().element_kind());
+        assert!(meta.borrow().first_child().is_none());
+
+        let title = meta
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of meta");
+        assert_eq!(Some(ElementKind::Title), title.borrow().element_kind());
+        assert_eq!(
+            NodeKind::Text("title".to_string()),
+            title
+                .borrow()
+                .first_child()
+                .expect("failed to get a text of title")
+                .borrow()
+                .kind()
+        );
+    }
+
+    #[test]
+    fn test_raw_text_in_script_and_style() {
+        let html = "<html><head><style>a > b { color: red; }</style><script>if (1 < 2) { alert(\"<b>\"); }</script></head><body><p>text</p></body></html>".to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let text_of = |kind: ElementKind| {
+            let element = get_element_nodes(Some(document.clone()), kind)[0].clone();
+            let text = element
+                .borrow()
+                .first_child()
+                .expect("failed to get a text");
+            let kind = text.borrow().kind();
+            kind
+        };
+        assert_eq!(
+            NodeKind::Text("a > b { color: red; }".to_string()),
+            text_of(ElementKind::Style)
+        );
+        assert_eq!(
+            NodeKind::Text(r#"if (1 < 2) { alert("<b>"); }"#.to_string()),
+            text_of(ElementKind::Script)
+        );
+        // 生テキストの後も通常どおりパースされる
+        assert_eq!(
+            1,
+            get_element_nodes(Some(document.clone()), ElementKind::P).len()
+        );
+        assert!(get_element_nodes(Some(document), ElementKind::B).is_empty());
+    }
+
+    fn warning_messages(html: &str) -> Vec<String> {
+        let t = HtmlTokenizer::new(html.to_string());
+        let (_, warnings) = HtmlParser::new(t).into_result();
+        warnings.iter().map(|w| w.message()).collect()
+    }
+
+    #[test]
+    fn test_no_warnings_for_well_formed_html() {
+        assert!(warning_messages(
+            "<html><head><title>t</title></head><body><p>a<b>b</b></p></body></html>"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_warning_for_implicitly_closed_element() {
+        let html = "<html><head></head><body><p><b>x</p></body></html>".to_string();
+        let t = HtmlTokenizer::new(html);
+        let (_, warnings) = HtmlParser::new(t).into_result();
+        assert_eq!(
+            vec![ParseWarning::new(
+                "<b> is implicitly closed by </p>".to_string(),
+                (1, 33)
+            )],
+            warnings
+        );
+    }
+
+    #[test]
+    fn test_warning_for_stray_end_tag() {
+        assert_eq!(
+            vec!["unexpected end tag </div> is ignored".to_string()],
+            warning_messages("<html><head></head><body><p>x</p></div></body></html>")
+        );
+    }
+
+    #[test]
+    fn test_warnings_for_missing_body_and_unclosed_element() {
+        let html = "<html><head></head><p>hello".to_string();
+        let t = HtmlTokenizer::new(html);
+        let (window, warnings) = HtmlParser::new(t).into_result();
+        assert_eq!(
+            vec![
+                "missing <body> start tag".to_string(),
+                "unclosed element <p> at end of document".to_string()
+            ],
+            warnings
+                .iter()
+                .map(|w| w.message())
+                .collect::<Vec<String>>()
+        );
+        assert_eq!((1, 20), (warnings[0].line(), warnings[0].column()));
+
+        let p = get_element_nodes(Some(window.borrow().document()), ElementKind::P);
+        assert_eq!(1, p.len());
+    }
+
+    #[test]
+    fn test_warning_for_unknown_element() {
+        assert_eq!(
+            vec!["unknown element <foo>".to_string()],
+            warning_messages(
+                "<html><head></head><body><foo></foo><my-widget></my-widget></body></html>"
+            )
+        );
+    }
+
+    #[test]
+    fn test_effective_lang() {
+        let html = r#"<html lang="ja"><head></head><body><p>こんにちは<b lang="en">hello</b></p></body></html>"#.to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        // 指定のない要素とテキストは、html要素のlangを引き継ぐ
+        let p = get_element_nodes(Some(document.clone()), ElementKind::P)[0].clone();
+        assert_eq!("ja", p.borrow().effective_lang());
+        let text = p.borrow().first_child().expect("failed to get text of p");
+        assert_eq!("ja", text.borrow().effective_lang());
+
+        // 子孫で指定し直したときは、その値を使う
+        let b = get_element_nodes(Some(document.clone()), ElementKind::B)[0].clone();
+        assert_eq!("en", b.borrow().effective_lang());
+
+        assert_eq!("", document.borrow().effective_lang());
+    }
 }
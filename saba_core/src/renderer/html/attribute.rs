@@ -14,6 +14,14 @@ impl Attribute {
         }
     }
 
+    // JavaScriptのsetAttributeなど、トークナイザを通さずに属性を作る場合に使う
+    pub fn with_value(name: &str, value: &str) -> Self {
+        Self {
+            name: String::from(name),
+            value: String::from(value),
+        }
+    }
+
     pub fn add_char(&mut self, c: char, is_name: bool) {
         if is_name {
             self.name.push(c);
@@ -29,4 +37,8 @@ impl Attribute {
     pub fn value(&self) -> String {
         self.value.clone()
     }
+
+    pub fn set_value(&mut self, value: &str) {
+        self.value = String::from(value);
+    }
 }
@@ -10,6 +10,13 @@ pub struct HtmlTokenizer {
     latest_token: Option<HtmlToken>,
     input: Vec<char>,
     buf: String,
+    // 生テキストを読んでいる要素のタグ名
+    raw_text_tag: String,
+    // 現在の行番号と、その行の先頭の位置
+    line: u32,
+    line_start: usize,
+    // 最後に返したトークンの開始位置(行, 列)。行と列は1から数える
+    location: (u32, u32),
 }
 
 impl HtmlTokenizer {
@@ -21,9 +28,17 @@ impl HtmlTokenizer {
             latest_token: None,
             input: html.chars().collect(),
             buf: String::new(),
+            raw_text_tag: String::new(),
+            line: 1,
+            line_start: 0,
+            location: (1, 1),
         }
     }
 
+    pub fn location(&self) -> (u32, u32) {
+        self.location
+    }
+
     fn is_eof(&self) -> bool {
         self.pos >= self.input.len()
     }
@@ -31,9 +46,17 @@ impl HtmlTokenizer {
     fn consume_next_input(&mut self) -> char {
         let c = self.input[self.pos];
         self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.line_start = self.pos;
+        }
         c
     }
 
+    fn next_input_is(&self, c: char) -> bool {
+        self.input.get(self.pos) == Some(&c)
+    }
+
     fn reconsume_input(&mut self) -> char {
         self.reconsume = false;
         self.input[self.pos - 1]
@@ -74,6 +97,18 @@ impl HtmlTokenizer {
         self.latest_token = None;
         assert!(self.latest_token.is_none());
 
+        if let Some(HtmlToken::StartTag {
+            tag,
+            self_closing: false,
+            ..
+        }) = &t
+        {
+            if tag == "script" || tag == "style" {
+                self.state = State::RawText;
+                self.raw_text_tag = tag.clone();
+            }
+        }
+
         t
     }
 
@@ -159,10 +194,11 @@ pub enum State {
     AttributeValueUnquoted,
     AfterAttributeValueQuoted,
     SelfClosingStartTag,
-    ScriptData,
-    ScriptDataLessThanSign,
-    ScriptDataEndTagOpen,
-    ScriptDataEndTagName,
+    // scriptとstyleの中身。"</script>"のような対応する終了タグまでをすべて文字として扱う
+    RawText,
+    RawTextLessThanSign,
+    RawTextEndTagOpen,
+    RawTextEndTagName,
     TemporaryBuffer,
 }
 
@@ -174,6 +210,13 @@ impl Iterator for HtmlTokenizer {
             return None;
         }
 
+        let start = if self.reconsume {
+            self.pos - 1
+        } else {
+            self.pos
+        };
+        self.location = (self.line, start.saturating_sub(self.line_start) as u32 + 1);
+
         loop {
             let c = match self.reconsume {
                 true => self.reconsume_input(),
@@ -353,8 +396,14 @@ impl Iterator for HtmlTokenizer {
                     self.append_attribute(c, false);
                 }
                 State::AttributeValueUnquoted => {
-                    if c == ' ' {
-                        self.state = State::AfterAttributeValueQuoted;
+                    // 引用符のない値は空白か'>'、または"/>"の手前までとする
+                    if c.is_ascii_whitespace() {
+                        self.state = State::BeforeAttributeName;
+                        continue;
+                    }
+
+                    if c == '/' && self.next_input_is('>') {
+                        self.state = State::SelfClosingStartTag;
                         continue;
                     }
 
@@ -403,63 +452,72 @@ impl Iterator for HtmlTokenizer {
                         return Some(HtmlToken::Eof);
                     }
                 }
-                State::ScriptData => {
-                    if c == '<' {
-                        self.state = State::ScriptDataLessThanSign;
-                        continue;
-                    }
-
+                State::RawText => {
                     if self.is_eof() {
                         return Some(HtmlToken::Eof);
                     }
 
+                    if c == '<' {
+                        self.state = State::RawTextLessThanSign;
+                        continue;
+                    }
+
                     return Some(HtmlToken::Char(c));
                 }
-                State::ScriptDataLessThanSign => {
+                State::RawTextLessThanSign => {
                     if c == '/' {
                         self.buf = String::new();
-                        self.state = State::ScriptDataEndTagName;
+                        self.state = State::RawTextEndTagOpen;
                         continue;
                     }
 
                     self.reconsume = true;
-                    self.state = State::ScriptData;
-                    return Some(HtmlToken::Char(c));
+                    self.state = State::RawText;
+                    return Some(HtmlToken::Char('<'));
                 }
-                State::ScriptDataEndTagOpen => {
+                State::RawTextEndTagOpen => {
                     if c.is_ascii_alphabetic() {
                         self.reconsume = true;
-                        self.state = State::ScriptDataEndTagName;
+                        self.state = State::RawTextEndTagName;
                         self.create_tag(false);
                         continue;
                     }
 
                     self.reconsume = true;
-                    self.state = State::ScriptData;
-                    return Some(HtmlToken::Char('<'));
+                    self.state = State::TemporaryBuffer;
+                    self.buf = String::from("</");
                 }
-                State::ScriptDataEndTagName => {
-                    if c == '>' {
-                        self.state = State::Data;
-                        return self.take_latest_token();
-                    }
-
-                    if c.is_ascii_alphabetic() {
+                State::RawTextEndTagName => {
+                    if c.is_ascii_alphabetic() && !self.is_eof() {
                         self.buf.push(c);
                         self.append_tag_name(c.to_ascii_lowercase());
                         continue;
                     }
 
+                    // 開いている要素と同じ名前の終了タグだけが生テキストを終わらせる
+                    if self.buf.to_ascii_lowercase() == self.raw_text_tag {
+                        if c == '>' {
+                            self.state = State::Data;
+                            return self.take_latest_token();
+                        }
+
+                        if c.is_ascii_whitespace() {
+                            self.state = State::BeforeAttributeName;
+                            continue;
+                        }
+                    }
+
+                    // 終了タグではなかったので、読んだ文字をそのままテキストとして返す
+                    self.latest_token = None;
+                    self.reconsume = true;
                     self.state = State::TemporaryBuffer;
                     self.buf = String::from("</") + &self.buf;
-                    self.buf.push(c);
-                    continue;
                 }
                 State::TemporaryBuffer => {
                     self.reconsume = true;
 
                     if self.buf.chars().count() == 0 {
-                        self.state = State::ScriptData;
+                        self.state = State::RawText;
                         continue;
                     }
 
@@ -548,6 +606,61 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_unquoted_attributes() {
+        let cases = [
+            (
+                "<div class=foo id=bar>",
+                "div",
+                false,
+                vec![
+                    Attribute::with_value("class", "foo"),
+                    Attribute::with_value("id", "bar"),
+                ],
+            ),
+            (
+                "<input type=text value=hello>",
+                "input",
+                false,
+                vec![
+                    Attribute::with_value("type", "text"),
+                    Attribute::with_value("value", "hello"),
+                ],
+            ),
+            (
+                "<a href=\"/a b\" target=_blank title='t'>",
+                "a",
+                false,
+                vec![
+                    Attribute::with_value("href", "/a b"),
+                    Attribute::with_value("target", "_blank"),
+                    Attribute::with_value("title", "t"),
+                ],
+            ),
+            // "/>"の'/'は値に含めない
+            (
+                "<img src=a.png/>",
+                "img",
+                true,
+                vec![Attribute::with_value("src", "a.png")],
+            ),
+        ];
+
+        for (html, tag, self_closing, attributes) in cases {
+            let mut tokenizer = HtmlTokenizer::new(html.to_string());
+            assert_eq!(
+                Some(HtmlToken::StartTag {
+                    tag: tag.to_string(),
+                    self_closing,
+                    attributes,
+                }),
+                tokenizer.next(),
+                "{}",
+                html
+            );
+        }
+    }
+
     #[test]
     fn tests_self_closing_tag() {
         let html = "<img />".to_string();
@@ -588,4 +701,54 @@ mod tests {
             assert_eq!(Some(e), tokenizer.next());
         }
     }
+
+    // 最初の開始タグを読み飛ばし、終了タグまでの文字と終了タグを返す
+    fn raw_text_and_end_tag(html: &str) -> (String, Option<HtmlToken>) {
+        let mut tokenizer = HtmlTokenizer::new(html.to_string());
+        assert!(matches!(tokenizer.next(), Some(HtmlToken::StartTag { .. })));
+
+        let mut text = String::new();
+        loop {
+            match tokenizer.next() {
+                Some(HtmlToken::Char(c)) => text.push(c),
+                token => return (text, token),
+            }
+        }
+    }
+
+    #[test]
+    fn test_script_raw_text() {
+        let (text, end) =
+            raw_text_and_end_tag("<script>if (a < b && c > d) { x = \"<p></p>\"; }</SCRIPT><p>");
+        assert_eq!(r#"if (a < b && c > d) { x = "<p></p>"; }"#, text);
+        assert_eq!(
+            Some(HtmlToken::EndTag {
+                tag: "script".to_string()
+            }),
+            end
+        );
+    }
+
+    #[test]
+    fn test_style_raw_text() {
+        let (text, end) = raw_text_and_end_tag("<style>div > p { color: red; } </ style</style >a");
+        assert_eq!("div > p { color: red; } </ style", text);
+        assert_eq!(
+            Some(HtmlToken::EndTag {
+                tag: "style".to_string()
+            }),
+            end
+        );
+    }
+
+    #[test]
+    fn test_location() {
+        let html = "<p>a\n  <br></p>".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+        let expected = [(1, 1), (1, 4), (1, 5), (2, 1), (2, 2), (2, 3), (2, 7)];
+        for e in expected {
+            assert!(tokenizer.next().is_some());
+            assert_eq!(e, tokenizer.location());
+        }
+    }
 }
@@ -1,67 +1,181 @@
-use crate::renderer::js::token::{JsLexer, Token};
+use crate::renderer::js::runtime::JsError;
+use crate::renderer::js::token::{JsLexer, SourceLocation, Token};
+use alloc::collections::BTreeMap;
+use alloc::format;
 use alloc::rc::Rc;
-use alloc::string::String;
+use alloc::string::{String, ToString};
 use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
 use core::iter::Peekable;
 
+// どのノードも、ソースコード上で最初のトークンの位置を持つ
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Node {
-    ExpressionStatement(Option<Rc<Node>>),
+    ExpressionStatement(Option<Rc<Node>>, SourceLocation),
     AdditiveExpression {
         operator: char,
         left: Option<Rc<Node>>,
         right: Option<Rc<Node>>,
+        location: SourceLocation,
+    },
+    // `a < b`と`a > b`
+    RelationalExpression {
+        operator: char,
+        left: Option<Rc<Node>>,
+        right: Option<Rc<Node>>,
+        location: SourceLocation,
     },
     AssignmentExpression {
         operator: char,
         left: Option<Rc<Node>>,
         right: Option<Rc<Node>>,
+        location: SourceLocation,
     },
     MemberExpression {
         object: Option<Rc<Node>>,
         property: Option<Rc<Node>>,
+        // `a[b]`のときtrue。bを式として評価する。`a.b`のときは、bを名前のまま使う
+        computed: bool,
+        location: SourceLocation,
     },
-    NumericLiteral(u64),
+    NumericLiteral(u64, SourceLocation),
     VariableDeclaration {
+        // "var"、"let"、"const"のいずれか
+        kind: String,
         declarations: Vec<Option<Rc<Node>>>,
+        // 宣言の予約語の位置
+        location: SourceLocation,
     },
     VariableDeclarator {
         id: Option<Rc<Node>>,
         init: Option<Rc<Node>>,
+        location: SourceLocation,
     },
-    Identifier(String),
-    StringLiteral(String),
+    Identifier(String, SourceLocation),
+    StringLiteral(String, SourceLocation),
     BlockStatement {
         body: Vec<Option<Rc<Node>>>,
+        location: SourceLocation,
     },
     ReturnStatement {
         argument: Option<Rc<Node>>,
+        // `return`の予約語の位置
+        location: SourceLocation,
     },
     FunctionDeclaration {
         id: Option<Rc<Node>>,
         params: Vec<Option<Rc<Node>>>,
         body: Option<Rc<Node>>,
+        // `function`の予約語の位置
+        location: SourceLocation,
     },
     CallExpression {
         callee: Option<Rc<Node>>,
         arguments: Vec<Option<Rc<Node>>>,
+        // 実行時のエラーで報告する、呼び出し式の先頭の位置
+        location: SourceLocation,
+    },
+    IfStatement {
+        test: Option<Rc<Node>>,
+        consequent: Option<Rc<Node>>,
+        alternate: Option<Rc<Node>>,
+        // `if`の予約語の位置
+        location: SourceLocation,
+    },
+    // `test ? consequent : alternate`
+    ConditionalExpression {
+        test: Option<Rc<Node>>,
+        consequent: Option<Rc<Node>>,
+        alternate: Option<Rc<Node>>,
+        location: SourceLocation,
+    },
+    // `import { a, b } from "source"`
+    ImportDeclaration {
+        specifiers: Vec<String>,
+        source: String,
+        // `import`の予約語の位置
+        location: SourceLocation,
     },
 }
 
 impl Node {
-    pub fn new_expression_statement(expression: Option<Rc<Node>>) -> Option<Rc<Self>> {
-        Some(Rc::new(Node::ExpressionStatement(expression)))
+    // プロファイラの集計に使う、ノードの種類の名前
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            Node::ExpressionStatement(..) => "ExpressionStatement",
+            Node::AdditiveExpression { .. } => "AdditiveExpression",
+            Node::RelationalExpression { .. } => "RelationalExpression",
+            Node::AssignmentExpression { .. } => "AssignmentExpression",
+            Node::MemberExpression { .. } => "MemberExpression",
+            Node::NumericLiteral(..) => "NumericLiteral",
+            Node::VariableDeclaration { .. } => "VariableDeclaration",
+            Node::VariableDeclarator { .. } => "VariableDeclarator",
+            Node::Identifier(..) => "Identifier",
+            Node::StringLiteral(..) => "StringLiteral",
+            Node::BlockStatement { .. } => "BlockStatement",
+            Node::ReturnStatement { .. } => "ReturnStatement",
+            Node::FunctionDeclaration { .. } => "FunctionDeclaration",
+            Node::CallExpression { .. } => "CallExpression",
+            Node::IfStatement { .. } => "IfStatement",
+            Node::ConditionalExpression { .. } => "ConditionalExpression",
+            Node::ImportDeclaration { .. } => "ImportDeclaration",
+        }
+    }
+
+    pub fn location(&self) -> SourceLocation {
+        match self {
+            Node::ExpressionStatement(_, location)
+            | Node::NumericLiteral(_, location)
+            | Node::Identifier(_, location)
+            | Node::StringLiteral(_, location)
+            | Node::AdditiveExpression { location, .. }
+            | Node::RelationalExpression { location, .. }
+            | Node::AssignmentExpression { location, .. }
+            | Node::MemberExpression { location, .. }
+            | Node::VariableDeclaration { location, .. }
+            | Node::VariableDeclarator { location, .. }
+            | Node::BlockStatement { location, .. }
+            | Node::ReturnStatement { location, .. }
+            | Node::FunctionDeclaration { location, .. }
+            | Node::CallExpression { location, .. }
+            | Node::IfStatement { location, .. }
+            | Node::ConditionalExpression { location, .. }
+            | Node::ImportDeclaration { location, .. } => *location,
+        }
+    }
+
+    pub fn new_expression_statement(
+        expression: Option<Rc<Node>>,
+        location: SourceLocation,
+    ) -> Option<Rc<Self>> {
+        Some(Rc::new(Node::ExpressionStatement(expression, location)))
     }
 
     pub fn new_addirive_expression(
         operator: char,
         left: Option<Rc<Node>>,
         right: Option<Rc<Node>>,
+        location: SourceLocation,
     ) -> Option<Rc<Self>> {
         Some(Rc::new(Node::AdditiveExpression {
             operator,
             left,
             right,
+            location,
+        }))
+    }
+
+    pub fn new_relational_expression(
+        operator: char,
+        left: Option<Rc<Node>>,
+        right: Option<Rc<Node>>,
+        location: SourceLocation,
+    ) -> Option<Rc<Self>> {
+        Some(Rc::new(Node::RelationalExpression {
+            operator,
+            left,
+            right,
+            location,
         }))
     }
 
@@ -69,89 +183,213 @@ impl Node {
         operator: char,
         left: Option<Rc<Node>>,
         right: Option<Rc<Node>>,
+        location: SourceLocation,
     ) -> Option<Rc<Self>> {
         Some(Rc::new(Node::AssignmentExpression {
             operator,
             left,
             right,
+            location,
         }))
     }
 
     pub fn new_member_expression(
         object: Option<Rc<Self>>,
         property: Option<Rc<Self>>,
+        computed: bool,
+        location: SourceLocation,
     ) -> Option<Rc<Self>> {
-        Some(Rc::new(Node::MemberExpression { object, property }))
+        Some(Rc::new(Node::MemberExpression {
+            object,
+            property,
+            computed,
+            location,
+        }))
     }
 
-    pub fn new_numeric_literal(value: u64) -> Option<Rc<Self>> {
-        Some(Rc::new(Node::NumericLiteral(value)))
+    pub fn new_numeric_literal(value: u64, location: SourceLocation) -> Option<Rc<Self>> {
+        Some(Rc::new(Node::NumericLiteral(value, location)))
     }
 
     pub fn new_variable_declarator(
         id: Option<Rc<Node>>,
         init: Option<Rc<Node>>,
+        location: SourceLocation,
     ) -> Option<Rc<Self>> {
-        Some(Rc::new(Node::VariableDeclarator { id, init }))
+        Some(Rc::new(Node::VariableDeclarator { id, init, location }))
     }
 
-    pub fn new_variable_declaration(declarations: Vec<Option<Rc<Self>>>) -> Option<Rc<Self>> {
-        Some(Rc::new(Node::VariableDeclaration { declarations }))
+    pub fn new_variable_declaration(
+        kind: String,
+        declarations: Vec<Option<Rc<Self>>>,
+        location: SourceLocation,
+    ) -> Option<Rc<Self>> {
+        Some(Rc::new(Node::VariableDeclaration {
+            kind,
+            declarations,
+            location,
+        }))
     }
 
-    pub fn new_identifier(name: String) -> Option<Rc<Self>> {
-        Some(Rc::new(Node::Identifier(name)))
+    pub fn new_identifier(name: String, location: SourceLocation) -> Option<Rc<Self>> {
+        Some(Rc::new(Node::Identifier(name, location)))
     }
 
-    pub fn new_string_literal(value: String) -> Option<Rc<Self>> {
-        Some(Rc::new(Node::StringLiteral(value)))
+    pub fn new_string_literal(value: String, location: SourceLocation) -> Option<Rc<Self>> {
+        Some(Rc::new(Node::StringLiteral(value, location)))
     }
 
-    pub fn new_block_statement(body: Vec<Option<Rc<Node>>>) -> Option<Rc<Self>> {
-        Some(Rc::new(Node::BlockStatement { body }))
+    pub fn new_block_statement(
+        body: Vec<Option<Rc<Node>>>,
+        location: SourceLocation,
+    ) -> Option<Rc<Self>> {
+        Some(Rc::new(Node::BlockStatement { body, location }))
     }
 
-    pub fn new_return_statement(argument: Option<Rc<Node>>) -> Option<Rc<Self>> {
-        Some(Rc::new(Node::ReturnStatement { argument }))
+    pub fn new_return_statement(
+        argument: Option<Rc<Node>>,
+        location: SourceLocation,
+    ) -> Option<Rc<Self>> {
+        Some(Rc::new(Node::ReturnStatement { argument, location }))
     }
 
     pub fn new_function_declaration(
         id: Option<Rc<Node>>,
         params: Vec<Option<Rc<Node>>>,
         body: Option<Rc<Node>>,
+        location: SourceLocation,
     ) -> Option<Rc<Self>> {
-        Some(Rc::new(Node::FunctionDeclaration { id, params, body }))
+        Some(Rc::new(Node::FunctionDeclaration {
+            id,
+            params,
+            body,
+            location,
+        }))
     }
 
     pub fn new_call_expression(
         callee: Option<Rc<Node>>,
         arguments: Vec<Option<Rc<Node>>>,
+        location: SourceLocation,
     ) -> Option<Rc<Self>> {
-        Some(Rc::new(Node::CallExpression { callee, arguments }))
+        Some(Rc::new(Node::CallExpression {
+            callee,
+            arguments,
+            location,
+        }))
+    }
+
+    pub fn new_if_statement(
+        test: Option<Rc<Node>>,
+        consequent: Option<Rc<Node>>,
+        alternate: Option<Rc<Node>>,
+        location: SourceLocation,
+    ) -> Option<Rc<Self>> {
+        Some(Rc::new(Node::IfStatement {
+            test,
+            consequent,
+            alternate,
+            location,
+        }))
+    }
+
+    pub fn new_conditional_expression(
+        test: Option<Rc<Node>>,
+        consequent: Option<Rc<Node>>,
+        alternate: Option<Rc<Node>>,
+        location: SourceLocation,
+    ) -> Option<Rc<Self>> {
+        Some(Rc::new(Node::ConditionalExpression {
+            test,
+            consequent,
+            alternate,
+            location,
+        }))
     }
 }
 
 pub struct JsParser {
     t: Peekable<JsLexer>,
+    // 最後に読み込んだトークンの位置
+    location: Rc<Cell<SourceLocation>>,
+    // 最後に読み込んだトークンの前に改行があったか
+    newline_before: Rc<Cell<bool>>,
+    // `export`を付けて宣言された名前と、その宣言
+    exports: BTreeMap<String, Rc<Node>>,
+    // 最初に見つかった構文エラー。レキサーと共有する
+    error: Rc<RefCell<Option<JsError>>>,
 }
 
 impl JsParser {
     pub fn new(t: JsLexer) -> Self {
-        Self { t: t.peekable() }
+        let location = t.location();
+        let newline_before = t.newline_before();
+        let error = t.error();
+        Self {
+            t: t.peekable(),
+            location,
+            newline_before,
+            exports: BTreeMap::new(),
+            error,
+        }
+    }
+
+    // 構文エラーを記録してNoneを返す。記録した後はレキサーがトークンを返さないので、パースはそこで終わる
+    fn syntax_error(&mut self, message: String) -> Option<Rc<Node>> {
+        let mut error = self.error.borrow_mut();
+        if error.is_none() {
+            *error = Some(JsError::new(
+                format!("SyntaxError: {}", message),
+                self.location.get(),
+            ));
+        }
+        None
+    }
+
+    fn failed(&self) -> bool {
+        self.error.borrow().is_some()
+    }
+
+    // パースしたプログラムを返す。構文エラーがあれば、最初のものを返す
+    pub fn parse_program(&mut self) -> Result<Program, JsError> {
+        let program = self.parse_ast();
+        match self.error.borrow().clone() {
+            Some(error) => Err(error),
+            None => Ok(program),
+        }
+    }
+
+    // 次のトークンの先頭の位置
+    fn next_location(&mut self) -> SourceLocation {
+        self.t.peek();
+        self.location.get()
+    }
+
+    // 次のトークンの前に改行があればtrue
+    // 改行の後に`(`や`[`が続く場合は、前の文の続きではなく新しい文として扱う
+    fn newline_before_next(&mut self) -> bool {
+        self.t.peek();
+        self.newline_before.get()
     }
 
+    // 構文エラーがあれば、その前までの文を返す
     pub fn parse_ast(&mut self) -> Program {
         let mut program = Program::new();
 
         let mut body = Vec::new();
 
         loop {
-            let node = self.source_element();
+            let node = if self.failed() {
+                None
+            } else {
+                self.source_element()
+            };
 
             match node {
                 Some(n) => body.push(n),
                 None => {
                     program.set_body(body);
+                    program.set_exports(core::mem::take(&mut self.exports));
                     return program;
                 }
             }
@@ -167,8 +405,16 @@ impl JsParser {
         match t {
             Token::Keyword(keyword) => {
                 if keyword == "function" {
+                    let location = self.next_location();
                     assert!(self.t.next().is_some());
-                    self.function_declaration()
+                    self.function_declaration(location)
+                } else if keyword == "export" {
+                    assert!(self.t.next().is_some());
+                    self.export_declaration()
+                } else if keyword == "import" {
+                    let location = self.next_location();
+                    assert!(self.t.next().is_some());
+                    self.import_declaration(location)
                 } else {
                     self.statement()
                 }
@@ -177,6 +423,75 @@ impl JsParser {
         }
     }
 
+    // `export`の後に続く関数や変数の宣言を読み込み、宣言した名前をエクスポートとして記録する
+    fn export_declaration(&mut self) -> Option<Rc<Node>> {
+        let declaration = self.source_element()?;
+
+        match declaration.as_ref() {
+            Node::FunctionDeclaration { id, .. } => {
+                if let Some(Node::Identifier(name, _)) = id.as_deref() {
+                    self.exports.insert(name.to_string(), declaration.clone());
+                }
+            }
+            Node::VariableDeclaration { declarations, .. } => {
+                for declarator in declarations {
+                    if let Some(Node::VariableDeclarator { id, .. }) = declarator.as_deref() {
+                        if let Some(Node::Identifier(name, _)) = id.as_deref() {
+                            self.exports.insert(name.to_string(), declaration.clone());
+                        }
+                    }
+                }
+            }
+            _ => {
+                return self.syntax_error("export should be followed by a declaration".to_string())
+            }
+        }
+
+        Some(declaration)
+    }
+
+    // `import { a, b } from "source"`
+    fn import_declaration(&mut self, location: SourceLocation) -> Option<Rc<Node>> {
+        match self.t.next() {
+            Some(Token::Punctuator('{')) => {}
+            t => return self.syntax_error(format!("import should have `{{` but got {:?}", t)),
+        }
+
+        let mut specifiers = Vec::new();
+        loop {
+            match self.t.next() {
+                Some(Token::Identifier(name)) => specifiers.push(name),
+                Some(Token::Punctuator(',')) => {}
+                Some(Token::Punctuator('}')) => break,
+                t => {
+                    return self.syntax_error(format!("import should have a name but got {:?}", t))
+                }
+            }
+        }
+
+        match self.t.next() {
+            Some(Token::Identifier(from)) if from == "from" => {}
+            t => return self.syntax_error(format!("import should have `from` but got {:?}", t)),
+        }
+        let source = match self.t.next() {
+            Some(Token::StringLiteral(source)) => source,
+            t => {
+                return self
+                    .syntax_error(format!("import should have a module name but got {:?}", t))
+            }
+        };
+
+        if let Some(Token::Punctuator(';')) = self.t.peek() {
+            assert!(self.t.next().is_some());
+        }
+
+        Some(Rc::new(Node::ImportDeclaration {
+            specifiers,
+            source,
+            location,
+        }))
+    }
+
     fn statement(&mut self) -> Option<Rc<Node>> {
         let t = match self.t.peek() {
             Some(t) => t,
@@ -185,19 +500,41 @@ impl JsParser {
 
         let node = match t {
             Token::Keyword(keyword) => {
-                if keyword == "var" {
-                    // varの予約語を消費する
+                if keyword == "var" || keyword == "let" || keyword == "const" {
+                    let kind = keyword.clone();
+                    let location = self.next_location();
+                    // var、let、constの予約語を消費する
                     assert!(self.t.next().is_some());
 
-                    self.variable_declaration()
+                    self.variable_declaration(kind, location)
+                } else if keyword == "if" {
+                    let location = self.next_location();
+                    assert!(self.t.next().is_some());
+                    self.if_statement(location)
                 } else if keyword == "return" {
+                    let location = self.next_location();
                     assert!(self.t.next().is_some());
-                    Node::new_return_statement(self.assignment_expression())
+                    // `return;`のように値がない場合や、returnの直後で改行している場合は、次の文を読み込まない
+                    if self.newline_before_next() {
+                        return Node::new_return_statement(None, location);
+                    }
+                    match self.t.peek() {
+                        Some(Token::Punctuator(';')) | Some(Token::Punctuator('}')) | None => {
+                            Node::new_return_statement(None, location)
+                        }
+                        _ => Node::new_return_statement(self.assignment_expression(), location),
+                    }
                 } else {
-                    None
+                    let keyword = keyword.clone();
+                    self.t.next();
+                    self.syntax_error(format!("unexpected keyword `{}`", keyword))
                 }
             }
-            _ => Node::new_expression_statement(self.assignment_expression()),
+            Token::Punctuator('{') => self.block_statement(),
+            _ => {
+                let location = self.next_location();
+                Node::new_expression_statement(self.assignment_expression(), location)
+            }
         };
 
         if let Some(Token::Punctuator(c)) = self.t.peek() {
@@ -210,7 +547,8 @@ impl JsParser {
     }
 
     fn assignment_expression(&mut self) -> Option<Rc<Node>> {
-        let expr = self.additive_expression();
+        let location = self.next_location();
+        let expr = self.conditional_expression();
 
         let t = match self.t.peek() {
             Some(token) => token,
@@ -221,13 +559,63 @@ impl JsParser {
             Token::Punctuator('=') => {
                 // '='を消費する
                 assert!(self.t.next().is_some());
-                Node::new_assignment_expression('=', expr, self.assignment_expression())
+                Node::new_assignment_expression('=', expr, self.assignment_expression(), location)
             }
             _ => expr,
         }
     }
 
+    fn conditional_expression(&mut self) -> Option<Rc<Node>> {
+        let location = self.next_location();
+        let test = self.relational_expression();
+
+        match self.t.peek() {
+            Some(Token::Punctuator('?')) => {
+                // '?'を消費する
+                assert!(self.t.next().is_some());
+            }
+            _ => return test,
+        }
+
+        let consequent = self.assignment_expression();
+        match self.t.next() {
+            Some(Token::Punctuator(':')) => {}
+            t => {
+                return self.syntax_error(format!(
+                    "conditional expression should have `:` but got {:?}",
+                    t
+                ))
+            }
+        }
+        let alternate = self.assignment_expression();
+
+        Node::new_conditional_expression(test, consequent, alternate, location)
+    }
+
+    // `<`と`>`は左結合で、加算と減算より優先順位が低い
+    fn relational_expression(&mut self) -> Option<Rc<Node>> {
+        let location = self.next_location();
+        let mut expr = self.additive_expression();
+
+        loop {
+            match self.t.peek() {
+                Some(Token::Punctuator(c)) if *c == '<' || *c == '>' => {
+                    let operator = *c;
+                    assert!(self.t.next().is_some());
+                    expr = Node::new_relational_expression(
+                        operator,
+                        expr,
+                        self.additive_expression(),
+                        location,
+                    );
+                }
+                _ => return expr,
+            }
+        }
+    }
+
     fn additive_expression(&mut self) -> Option<Rc<Node>> {
+        let location = self.next_location();
         let left = self.left_hand_side_expression();
 
         let t = match self.t.peek() {
@@ -239,7 +627,7 @@ impl JsParser {
             Token::Punctuator(c) => match c {
                 '+' | '-' => {
                     assert!(self.t.next().is_some());
-                    Node::new_addirive_expression(c, left, self.assignment_expression())
+                    Node::new_addirive_expression(c, left, self.additive_expression(), location)
                 }
                 _ => left,
             },
@@ -248,22 +636,29 @@ impl JsParser {
     }
 
     fn left_hand_side_expression(&mut self) -> Option<Rc<Node>> {
-        let expr = self.member_expression();
+        let location = self.next_location();
+        let mut expr = self.member_expression(location);
 
-        let t = match self.t.peek() {
-            Some(token) => token,
-            None => return expr,
-        };
-
-        match t {
-            Token::Punctuator(c) => {
-                if c == &'(' {
+        loop {
+            let t = match self.t.peek() {
+                Some(token) => token,
+                None => return expr,
+            };
+
+            match t {
+                Token::Punctuator('(') | Token::Punctuator('[') if self.newline_before.get() => {
+                    return expr;
+                }
+                Token::Punctuator('(') => {
                     assert!(self.t.next().is_some());
-                    return Node::new_call_expression(expr, self.arguments());
+                    expr = Node::new_call_expression(expr, self.arguments(), location);
                 }
-                expr
+                // 関数呼び出しの後にプロパティアクセスが続く場合 (例: `foo().bar`)
+                Token::Punctuator('.') | Token::Punctuator('[') => {
+                    expr = self.member_expression_tail(expr, location);
+                }
+                _ => return expr,
             }
-            _ => expr,
         }
     }
 
@@ -272,158 +667,197 @@ impl JsParser {
 
         loop {
             match self.t.peek() {
-                Some(t) => match t {
-                    Token::Punctuator(c) => {
-                        if c == &')' {
-                            assert!(self.t.next().is_some());
-                            return arguments;
-                        }
-                        if c == &',' {
-                            assert!(self.t.next().is_some());
-                        }
-                    }
-                    _ => arguments.push(self.assignment_expression()),
-                },
-                None => return arguments,
+                Some(Token::Punctuator(')')) => {
+                    assert!(self.t.next().is_some());
+                    return arguments;
+                }
+                Some(Token::Punctuator(',')) => {
+                    assert!(self.t.next().is_some());
+                }
+                Some(_) => arguments.push(self.assignment_expression()),
+                None => {
+                    self.syntax_error("arguments should end with `)`".to_string());
+                    return arguments;
+                }
             }
         }
     }
 
-    fn member_expression(&mut self) -> Option<Rc<Node>> {
+    fn member_expression(&mut self, location: SourceLocation) -> Option<Rc<Node>> {
         let expr = self.primary_expression();
+        self.member_expression_tail(expr, location)
+    }
 
-        let t = match self.t.peek() {
-            Some(token) => token,
-            None => return expr,
-        };
+    // `.property`と`[expression]`が続く限り、左結合でMemberExpressionを組み立てる
+    // locationはobjectの先頭の位置
+    fn member_expression_tail(
+        &mut self,
+        object: Option<Rc<Node>>,
+        location: SourceLocation,
+    ) -> Option<Rc<Node>> {
+        let mut expr = object;
 
-        match t {
-            Token::Punctuator(c) => {
-                if c == &'.' {
+        loop {
+            let t = match self.t.peek() {
+                Some(token) => token,
+                None => return expr,
+            };
+
+            match t {
+                Token::Punctuator('.') => {
                     assert!(self.t.next().is_some());
-                    return Node::new_member_expression(expr, self.identifier());
+                    expr = Node::new_member_expression(expr, self.identifier(), false, location);
                 }
-
-                expr
+                Token::Punctuator('[') if !self.newline_before.get() => {
+                    assert!(self.t.next().is_some());
+                    let property = self.assignment_expression();
+                    match self.t.next() {
+                        Some(Token::Punctuator(']')) => {}
+                        t => {
+                            return self.syntax_error(format!(
+                                "member expression should have `]` but got {:?}",
+                                t
+                            ))
+                        }
+                    }
+                    expr = Node::new_member_expression(expr, property, true, location);
+                }
+                _ => return expr,
             }
-            _ => expr,
         }
     }
 
     fn primary_expression(&mut self) -> Option<Rc<Node>> {
+        let location = self.next_location();
         let t = match self.t.next() {
             Some(token) => token,
-            None => return None,
+            None => return self.syntax_error("unexpected end of input".to_string()),
         };
 
         match t {
-            Token::Identifier(value) => Node::new_identifier(value),
-            Token::StringLiteral(value) => Node::new_string_literal(value),
-            Token::Number(value) => Node::new_numeric_literal(value),
-            _ => None,
+            Token::Identifier(value) => Node::new_identifier(value, location),
+            Token::StringLiteral(value) => Node::new_string_literal(value, location),
+            Token::Number(value) => Node::new_numeric_literal(value, location),
+            t => self.syntax_error(format!("unexpected token {:?}", t)),
         }
     }
 
-    fn variable_declaration(&mut self) -> Option<Rc<Node>> {
+    fn variable_declaration(&mut self, kind: String, location: SourceLocation) -> Option<Rc<Node>> {
+        let declarator_location = self.next_location();
         let ident = self.identifier();
 
-        let declarator = Node::new_variable_declarator(ident, self.initializer());
+        let declarator =
+            Node::new_variable_declarator(ident, self.initializer(), declarator_location);
 
         let mut declarations = Vec::new();
         declarations.push(declarator);
 
-        Node::new_variable_declaration(declarations)
+        Node::new_variable_declaration(kind, declarations, location)
     }
 
-    fn identifier(&mut self) -> Option<Rc<Node>> {
-        let t = match self.t.next() {
-            Some(token) => token,
-            None => return None,
-        };
+    // `if`の予約語はすでに消費されている
+    fn if_statement(&mut self, location: SourceLocation) -> Option<Rc<Node>> {
+        match self.t.next() {
+            Some(Token::Punctuator('(')) => {}
+            t => return self.syntax_error(format!("if statement should have `(` but got {:?}", t)),
+        }
+        let test = self.assignment_expression();
+        match self.t.next() {
+            Some(Token::Punctuator(')')) => {}
+            t => return self.syntax_error(format!("if statement should have `)` but got {:?}", t)),
+        }
 
-        match t {
-            Token::Identifier(name) => Node::new_identifier(name),
+        let consequent = self.statement();
+        let alternate = match self.t.peek() {
+            Some(Token::Keyword(keyword)) if keyword == "else" => {
+                assert!(self.t.next().is_some());
+                self.statement()
+            }
             _ => None,
+        };
+
+        Node::new_if_statement(test, consequent, alternate, location)
+    }
+
+    fn identifier(&mut self) -> Option<Rc<Node>> {
+        let location = self.next_location();
+        match self.t.next() {
+            Some(Token::Identifier(name)) => Node::new_identifier(name, location),
+            t => self.syntax_error(format!("expected an identifier but got {:?}", t)),
         }
     }
 
+    // `=`がなければ、次のトークンは次の文のものなので消費しない
     fn initializer(&mut self) -> Option<Rc<Node>> {
-        let t = match self.t.next() {
-            Some(token) => token,
-            None => return None,
-        };
-
-        match t {
-            Token::Punctuator(c) => match c {
-                '=' => self.assignment_expression(),
-                _ => None,
-            },
+        match self.t.peek() {
+            Some(Token::Punctuator('=')) => {
+                assert!(self.t.next().is_some());
+                self.assignment_expression()
+            }
             _ => None,
         }
     }
 
-    fn function_declaration(&mut self) -> Option<Rc<Node>> {
+    // `function`の予約語はすでに消費されている
+    fn function_declaration(&mut self, location: SourceLocation) -> Option<Rc<Node>> {
         let id = self.identifier();
         let params = self.parameter_list();
-        Node::new_function_declaration(id, params, self.function_body())
+        Node::new_function_declaration(id, params, self.function_body(), location)
     }
 
     fn parameter_list(&mut self) -> Vec<Option<Rc<Node>>> {
         let mut params = Vec::new();
 
         match self.t.next() {
-            Some(t) => match t {
-                Token::Punctuator(c) => assert!(c == '('),
-                _ => unimplemented!("function should have `(` but got {:?}", t),
-            },
-            _ => unimplemented!("function should have `(` but got None"),
+            Some(Token::Punctuator('(')) => {}
+            t => {
+                self.syntax_error(format!("function should have `(` but got {:?}", t));
+                return params;
+            }
         }
 
         loop {
             match self.t.peek() {
-                Some(t) => match t {
-                    Token::Punctuator(c) => {
-                        if c == &')' {
-                            assert!(self.t.next().is_some());
-                            return params;
-                        }
-                        if c == &',' {
-                            assert!(self.t.next().is_some());
-                        }
-                    }
-                    _ => {
-                        params.push(self.identifier());
-                    }
-                },
-                None => return params,
+                Some(Token::Punctuator(')')) => {
+                    assert!(self.t.next().is_some());
+                    return params;
+                }
+                Some(Token::Punctuator(',')) => {
+                    assert!(self.t.next().is_some());
+                }
+                Some(_) => params.push(self.identifier()),
+                None => {
+                    self.syntax_error("parameters should end with `)`".to_string());
+                    return params;
+                }
             }
         }
     }
 
     fn function_body(&mut self) -> Option<Rc<Node>> {
+        self.block_statement()
+    }
+
+    fn block_statement(&mut self) -> Option<Rc<Node>> {
+        let location = self.next_location();
         match self.t.next() {
-            Some(t) => match t {
-                Token::Punctuator(c) => assert!(c == '{'),
-                _ => unimplemented!("function should have open curly but got {:?}", t),
-            },
-            None => unimplemented!("function should have open curly but got None"),
+            Some(Token::Punctuator('{')) => {}
+            t => return self.syntax_error(format!("block should have `{{` but got {:?}", t)),
         }
 
         let mut body = Vec::new();
         loop {
+            if self.failed() {
+                return None;
+            }
             match self.t.peek() {
-                Some(t) => match t {
-                    Token::Punctuator(c) => {
-                        if c == &'}' {
-                            assert!(self.t.next().is_some());
-                            return Node::new_block_statement(body);
-                        }
-                    }
-                    _ => {}
-                },
-                None => {}
+                Some(Token::Punctuator('}')) => {
+                    assert!(self.t.next().is_some());
+                    return Node::new_block_statement(body, location);
+                }
+                Some(_) => body.push(self.source_element()),
+                None => return self.syntax_error("block should end with `}`".to_string()),
             }
-            body.push(self.source_element());
         }
     }
 }
@@ -431,11 +865,16 @@ impl JsParser {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Program {
     body: Vec<Rc<Node>>,
+    // エクスポートされた名前と、その宣言
+    exports: BTreeMap<String, Rc<Node>>,
 }
 
 impl Program {
     pub fn new() -> Self {
-        Self { body: Vec::new() }
+        Self {
+            body: Vec::new(),
+            exports: BTreeMap::new(),
+        }
     }
 
     pub fn set_body(&mut self, body: Vec<Rc<Node>>) {
@@ -445,6 +884,31 @@ impl Program {
     pub fn body(&self) -> &Vec<Rc<Node>> {
         &self.body
     }
+
+    pub fn set_exports(&mut self, exports: BTreeMap<String, Rc<Node>>) {
+        self.exports = exports;
+    }
+
+    pub fn exports(&self) -> &BTreeMap<String, Rc<Node>> {
+        &self.exports
+    }
+
+    // トップレベルのimport宣言の一覧
+    pub fn imports(&self) -> Vec<Rc<Node>> {
+        self.body
+            .iter()
+            .filter(|node| matches!(node.as_ref(), Node::ImportDeclaration { .. }))
+            .cloned()
+            .collect()
+    }
+
+    // 読み込んだモジュールの文を、このプログラムの文より前に実行されるように追加する
+    // モジュールごとのスコープはまだないので、エクスポートしていない宣言も見えるようになる
+    pub fn import_module(&mut self, other: Program) {
+        let mut body = other.body;
+        body.append(&mut self.body);
+        self.body = body;
+    }
 }
 
 #[cfg(test)]
@@ -469,9 +933,87 @@ mod test {
         let mut parser = JsParser::new(lexer);
         let mut expected = Program::new();
         let mut body = Vec::new();
-        body.push(Rc::new(Node::ExpressionStatement(Some(Rc::new(
-            Node::NumericLiteral(42),
-        )))));
+        body.push(Rc::new(Node::ExpressionStatement(
+            Some(Rc::new(Node::NumericLiteral(42, SourceLocation::new(1, 1)))),
+            SourceLocation::new(1, 1),
+        )));
+        expected.set_body(body);
+        assert_eq!(expected, parser.parse_ast());
+    }
+
+    #[test]
+    fn test_if_statement_with_let() {
+        let input = "if (1) { let a = 1; } else b".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let mut expected = Program::new();
+        let mut body = Vec::new();
+        body.push(Rc::new(Node::IfStatement {
+            test: Some(Rc::new(Node::NumericLiteral(1, SourceLocation::new(1, 5)))),
+            consequent: Some(Rc::new(Node::BlockStatement {
+                body: [Some(Rc::new(Node::VariableDeclaration {
+                    kind: "let".to_string(),
+                    declarations: [Some(Rc::new(Node::VariableDeclarator {
+                        id: Some(Rc::new(Node::Identifier(
+                            "a".to_string(),
+                            SourceLocation::new(1, 14),
+                        ))),
+                        init: Some(Rc::new(Node::NumericLiteral(1, SourceLocation::new(1, 18)))),
+                        location: SourceLocation::new(1, 14),
+                    }))]
+                    .to_vec(),
+                    location: SourceLocation::new(1, 10),
+                }))]
+                .to_vec(),
+                location: SourceLocation::new(1, 8),
+            })),
+            alternate: Some(Rc::new(Node::ExpressionStatement(
+                Some(Rc::new(Node::Identifier(
+                    "b".to_string(),
+                    SourceLocation::new(1, 28),
+                ))),
+                SourceLocation::new(1, 28),
+            ))),
+            location: SourceLocation::new(1, 1),
+        }));
+        expected.set_body(body);
+        assert_eq!(expected, parser.parse_ast());
+    }
+
+    #[test]
+    fn test_return_without_argument() {
+        let input = "function foo() { return; } foo()".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let mut expected = Program::new();
+        let mut body = Vec::new();
+        body.push(Rc::new(Node::FunctionDeclaration {
+            id: Some(Rc::new(Node::Identifier(
+                "foo".to_string(),
+                SourceLocation::new(1, 10),
+            ))),
+            params: [].to_vec(),
+            body: Some(Rc::new(Node::BlockStatement {
+                body: [Some(Rc::new(Node::ReturnStatement {
+                    argument: None,
+                    location: SourceLocation::new(1, 18),
+                }))]
+                .to_vec(),
+                location: SourceLocation::new(1, 16),
+            })),
+            location: SourceLocation::new(1, 1),
+        }));
+        body.push(Rc::new(Node::ExpressionStatement(
+            Some(Rc::new(Node::CallExpression {
+                callee: Some(Rc::new(Node::Identifier(
+                    "foo".to_string(),
+                    SourceLocation::new(1, 28),
+                ))),
+                arguments: [].to_vec(),
+                location: SourceLocation::new(1, 28),
+            })),
+            SourceLocation::new(1, 28),
+        )));
         expected.set_body(body);
         assert_eq!(expected, parser.parse_ast());
     }
@@ -483,13 +1025,15 @@ mod test {
         let mut parser = JsParser::new(lexer);
         let mut expected = Program::new();
         let mut body = Vec::new();
-        body.push(Rc::new(Node::ExpressionStatement(Some(Rc::new(
-            Node::AdditiveExpression {
+        body.push(Rc::new(Node::ExpressionStatement(
+            Some(Rc::new(Node::AdditiveExpression {
                 operator: '+',
-                left: Some(Rc::new(Node::NumericLiteral(1))),
-                right: Some(Rc::new(Node::NumericLiteral(2))),
-            },
-        )))));
+                left: Some(Rc::new(Node::NumericLiteral(1, SourceLocation::new(1, 1)))),
+                right: Some(Rc::new(Node::NumericLiteral(2, SourceLocation::new(1, 5)))),
+                location: SourceLocation::new(1, 1),
+            })),
+            SourceLocation::new(1, 1),
+        )));
         expected.set_body(body);
         assert_eq!(expected, parser.parse_ast());
     }
@@ -502,16 +1046,219 @@ mod test {
         let mut expected = Program::new();
         let mut body = Vec::new();
         body.push(Rc::new(Node::VariableDeclaration {
+            kind: "var".to_string(),
             declarations: [Some(Rc::new(Node::VariableDeclarator {
-                id: Some(Rc::new(Node::Identifier("foo".to_string()))),
-                init: Some(Rc::new(Node::StringLiteral("bar".to_string()))),
+                id: Some(Rc::new(Node::Identifier(
+                    "foo".to_string(),
+                    SourceLocation::new(1, 5),
+                ))),
+                init: Some(Rc::new(Node::StringLiteral(
+                    "bar".to_string(),
+                    SourceLocation::new(1, 9),
+                ))),
+                location: SourceLocation::new(1, 5),
             }))]
             .to_vec(),
+            location: SourceLocation::new(1, 1),
         }));
         expected.set_body(body);
         assert_eq!(expected, parser.parse_ast());
     }
 
+    #[test]
+    fn test_conditional_expression() {
+        let input = "var a = 1 + 2 ? b : c ? 3 : 4;".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let mut expected = Program::new();
+        let body = [Rc::new(Node::VariableDeclaration {
+            kind: "var".to_string(),
+            declarations: [Some(Rc::new(Node::VariableDeclarator {
+                id: Some(Rc::new(Node::Identifier(
+                    "a".to_string(),
+                    SourceLocation::new(1, 5),
+                ))),
+                // `1 + 2 ? b : (c ? 3 : 4)`
+                init: Some(Rc::new(Node::ConditionalExpression {
+                    test: Some(Rc::new(Node::AdditiveExpression {
+                        operator: '+',
+                        left: Some(Rc::new(Node::NumericLiteral(1, SourceLocation::new(1, 9)))),
+                        right: Some(Rc::new(Node::NumericLiteral(2, SourceLocation::new(1, 13)))),
+                        location: SourceLocation::new(1, 9),
+                    })),
+                    consequent: Some(Rc::new(Node::Identifier(
+                        "b".to_string(),
+                        SourceLocation::new(1, 17),
+                    ))),
+                    alternate: Some(Rc::new(Node::ConditionalExpression {
+                        test: Some(Rc::new(Node::Identifier(
+                            "c".to_string(),
+                            SourceLocation::new(1, 21),
+                        ))),
+                        consequent: Some(Rc::new(Node::NumericLiteral(
+                            3,
+                            SourceLocation::new(1, 25),
+                        ))),
+                        alternate: Some(Rc::new(Node::NumericLiteral(
+                            4,
+                            SourceLocation::new(1, 29),
+                        ))),
+                        location: SourceLocation::new(1, 21),
+                    })),
+                    location: SourceLocation::new(1, 9),
+                })),
+                location: SourceLocation::new(1, 5),
+            }))]
+            .to_vec(),
+            location: SourceLocation::new(1, 1),
+        })]
+        .to_vec();
+        expected.set_body(body);
+        assert_eq!(expected, parser.parse_ast());
+    }
+
+    #[test]
+    fn test_if_without_braces() {
+        let call = |name: &str, location| {
+            Some(Rc::new(Node::ExpressionStatement(
+                Some(Rc::new(Node::CallExpression {
+                    callee: Some(Rc::new(Node::Identifier(name.to_string(), location))),
+                    arguments: Vec::new(),
+                    location,
+                })),
+                location,
+            )))
+        };
+        // 条件の名前は`if (`の後にある
+        let if_statement = |test: &str, consequent, location: SourceLocation| {
+            Rc::new(Node::IfStatement {
+                test: Some(Rc::new(Node::Identifier(
+                    test.to_string(),
+                    SourceLocation::new(location.line(), location.column() + 4),
+                ))),
+                consequent,
+                alternate: None,
+                location,
+            })
+        };
+        let expected_body = [
+            if_statement(
+                "a",
+                call("foo", SourceLocation::new(1, 8)),
+                SourceLocation::new(1, 1),
+            ),
+            if_statement(
+                "b",
+                call("bar", SourceLocation::new(2, 8)),
+                SourceLocation::new(2, 1),
+            ),
+            call("baz", SourceLocation::new(3, 1)).expect("should be a statement"),
+        ]
+        .to_vec();
+
+        // セミコロンの有無にかかわらず、if文は本体の1文だけで終わる
+        for input in [
+            "if (a) foo();\nif (b) bar();\nbaz();",
+            "if (a) foo()\nif (b) bar()\nbaz()",
+        ] {
+            let lexer = JsLexer::new(input.to_string());
+            let mut parser = JsParser::new(lexer);
+            let mut expected = Program::new();
+            expected.set_body(expected_body.clone());
+            assert_eq!(expected, parser.parse_ast(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_newline_terminates_statement() {
+        let assignment = |name: &str, value, line| {
+            let location = SourceLocation::new(line, 1);
+            Rc::new(Node::ExpressionStatement(
+                Some(Rc::new(Node::AssignmentExpression {
+                    operator: '=',
+                    left: Some(Rc::new(Node::Identifier(name.to_string(), location))),
+                    right: value,
+                    location,
+                })),
+                location,
+            ))
+        };
+
+        let input = "a = 1\nb = c\n[d]".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let program = parser.parse_ast();
+        assert_eq!(
+            [
+                assignment(
+                    "a",
+                    Some(Rc::new(Node::NumericLiteral(1, SourceLocation::new(1, 5)))),
+                    1
+                ),
+                assignment(
+                    "b",
+                    Some(Rc::new(Node::Identifier(
+                        "c".to_string(),
+                        SourceLocation::new(2, 5)
+                    ))),
+                    2
+                ),
+            ]
+            .to_vec(),
+            // 改行の後の`[`は、cのプロパティアクセスではなく新しい文になる
+            program.body()[..2].to_vec()
+        );
+    }
+
+    #[test]
+    fn test_var_without_initializer_before_newline() {
+        let input = "var a\nb = 2".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let program = parser.parse_ast();
+
+        assert_eq!(2, program.body().len());
+        assert_eq!(
+            Rc::new(Node::ExpressionStatement(
+                Some(Rc::new(Node::AssignmentExpression {
+                    operator: '=',
+                    left: Some(Rc::new(Node::Identifier(
+                        "b".to_string(),
+                        SourceLocation::new(2, 1)
+                    ))),
+                    right: Some(Rc::new(Node::NumericLiteral(2, SourceLocation::new(2, 5)))),
+                    location: SourceLocation::new(2, 1),
+                })),
+                SourceLocation::new(2, 1)
+            )),
+            program.body()[1]
+        );
+    }
+
+    #[test]
+    fn test_return_followed_by_newline() {
+        let input = "function f() {\n  return\n  g()\n}".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let program = parser.parse_ast();
+
+        let body = match program.body()[0].as_ref() {
+            Node::FunctionDeclaration { body: Some(b), .. } => match b.as_ref() {
+                Node::BlockStatement { body, .. } => body.clone(),
+                _ => panic!("function body should be a block"),
+            },
+            _ => panic!("should be a function declaration"),
+        };
+        assert_eq!(2, body.len());
+        assert_eq!(
+            Some(Rc::new(Node::ReturnStatement {
+                argument: None,
+                location: SourceLocation::new(2, 3),
+            })),
+            body[0]
+        );
+    }
+
     #[test]
     fn test_add_variable_and_num() {
         let input = "var foo=42; var result=foo+1;".to_string();
@@ -520,22 +1267,38 @@ mod test {
         let mut expected = Program::new();
         let mut body = Vec::new();
         body.push(Rc::new(Node::VariableDeclaration {
+            kind: "var".to_string(),
             declarations: [Some(Rc::new(Node::VariableDeclarator {
-                id: Some(Rc::new(Node::Identifier("foo".to_string()))),
-                init: Some(Rc::new(Node::NumericLiteral(42))),
+                id: Some(Rc::new(Node::Identifier(
+                    "foo".to_string(),
+                    SourceLocation::new(1, 5),
+                ))),
+                init: Some(Rc::new(Node::NumericLiteral(42, SourceLocation::new(1, 9)))),
+                location: SourceLocation::new(1, 5),
             }))]
             .to_vec(),
+            location: SourceLocation::new(1, 1),
         }));
         body.push(Rc::new(Node::VariableDeclaration {
+            kind: "var".to_string(),
             declarations: [Some(Rc::new(VariableDeclarator {
-                id: Some(Rc::new(Node::Identifier("result".to_string()))),
+                id: Some(Rc::new(Node::Identifier(
+                    "result".to_string(),
+                    SourceLocation::new(1, 17),
+                ))),
                 init: Some(Rc::new(Node::AdditiveExpression {
                     operator: '+',
-                    left: Some(Rc::new(Node::Identifier("foo".to_string()))),
-                    right: Some(Rc::new(Node::NumericLiteral(1))),
+                    left: Some(Rc::new(Node::Identifier(
+                        "foo".to_string(),
+                        SourceLocation::new(1, 24),
+                    ))),
+                    right: Some(Rc::new(Node::NumericLiteral(1, SourceLocation::new(1, 28)))),
+                    location: SourceLocation::new(1, 24),
                 })),
+                location: SourceLocation::new(1, 17),
             }))]
             .to_vec(),
+            location: SourceLocation::new(1, 13),
         }));
         expected.set_body(body);
         assert_eq!(expected, parser.parse_ast());
@@ -549,14 +1312,23 @@ mod test {
         let mut expected = Program::new();
         let mut body = Vec::new();
         body.push(Rc::new(Node::FunctionDeclaration {
-            id: Some(Rc::new(Node::Identifier("foo".to_string()))),
+            id: Some(Rc::new(Node::Identifier(
+                "foo".to_string(),
+                SourceLocation::new(1, 10),
+            ))),
             params: [].to_vec(),
             body: Some(Rc::new(Node::BlockStatement {
                 body: [Some(Rc::new(Node::ReturnStatement {
-                    argument: Some(Rc::new(Node::NumericLiteral(42))),
+                    argument: Some(Rc::new(Node::NumericLiteral(
+                        42,
+                        SourceLocation::new(1, 25),
+                    ))),
+                    location: SourceLocation::new(1, 18),
                 }))]
                 .to_vec(),
+                location: SourceLocation::new(1, 16),
             })),
+            location: SourceLocation::new(1, 1),
         }));
         expected.set_body(body);
         assert_eq!(expected, parser.parse_ast());
@@ -570,22 +1342,41 @@ mod test {
         let mut expected = Program::new();
         let mut body = Vec::new();
         body.push(Rc::new(Node::FunctionDeclaration {
-            id: Some(Rc::new(Node::Identifier("foo".to_string()))),
+            id: Some(Rc::new(Node::Identifier(
+                "foo".to_string(),
+                SourceLocation::new(1, 10),
+            ))),
             params: [
-                Some(Rc::new(Node::Identifier("a".to_string()))),
-                Some(Rc::new(Node::Identifier("b".to_string()))),
+                Some(Rc::new(Node::Identifier(
+                    "a".to_string(),
+                    SourceLocation::new(1, 14),
+                ))),
+                Some(Rc::new(Node::Identifier(
+                    "b".to_string(),
+                    SourceLocation::new(1, 17),
+                ))),
             ]
             .to_vec(),
             body: Some(Rc::new(Node::BlockStatement {
                 body: [Some(Rc::new(Node::ReturnStatement {
                     argument: Some(Rc::new(Node::AdditiveExpression {
                         operator: '+',
-                        left: Some(Rc::new(Node::Identifier("a".to_string()))),
-                        right: Some(Rc::new(Node::Identifier("b".to_string()))),
+                        left: Some(Rc::new(Node::Identifier(
+                            "a".to_string(),
+                            SourceLocation::new(1, 29),
+                        ))),
+                        right: Some(Rc::new(Node::Identifier(
+                            "b".to_string(),
+                            SourceLocation::new(1, 31),
+                        ))),
+                        location: SourceLocation::new(1, 29),
                     })),
+                    location: SourceLocation::new(1, 22),
                 }))]
                 .to_vec(),
+                location: SourceLocation::new(1, 20),
             })),
+            location: SourceLocation::new(1, 1),
         }));
         expected.set_body(body);
         assert_eq!(expected, parser.parse_ast());
@@ -599,30 +1390,249 @@ mod test {
         let mut expected = Program::new();
         let mut body = Vec::new();
         body.push(Rc::new(Node::FunctionDeclaration {
-            id: Some(Rc::new(Node::Identifier("foo".to_string()))),
+            id: Some(Rc::new(Node::Identifier(
+                "foo".to_string(),
+                SourceLocation::new(1, 10),
+            ))),
             params: [].to_vec(),
             body: Some(Rc::new(Node::BlockStatement {
                 body: [Some(Rc::new(Node::ReturnStatement {
-                    argument: Some(Rc::new(Node::NumericLiteral(42))),
+                    argument: Some(Rc::new(Node::NumericLiteral(
+                        42,
+                        SourceLocation::new(1, 25),
+                    ))),
+                    location: SourceLocation::new(1, 18),
                 }))]
                 .to_vec(),
+                location: SourceLocation::new(1, 16),
             })),
+            location: SourceLocation::new(1, 1),
         }));
         body.push(Rc::new(Node::VariableDeclaration {
+            kind: "var".to_string(),
             declarations: [Some(Rc::new(Node::VariableDeclarator {
-                id: Some(Rc::new(Node::Identifier("result".to_string()))),
+                id: Some(Rc::new(Node::Identifier(
+                    "result".to_string(),
+                    SourceLocation::new(1, 35),
+                ))),
                 init: Some(Rc::new(Node::AdditiveExpression {
                     operator: '+',
                     left: Some(Rc::new(Node::CallExpression {
-                        callee: Some(Rc::new(Node::Identifier("foo".to_string()))),
+                        callee: Some(Rc::new(Node::Identifier(
+                            "foo".to_string(),
+                            SourceLocation::new(1, 44),
+                        ))),
                         arguments: [].to_vec(),
+                        location: SourceLocation::new(1, 44),
                     })),
-                    right: Some(Rc::new(Node::NumericLiteral(1))),
+                    right: Some(Rc::new(Node::NumericLiteral(1, SourceLocation::new(1, 52)))),
+                    location: SourceLocation::new(1, 44),
                 })),
+                location: SourceLocation::new(1, 35),
             }))]
             .to_vec(),
+            location: SourceLocation::new(1, 31),
         }));
         expected.set_body(body);
         assert_eq!(expected, parser.parse_ast());
     }
+
+    #[test]
+    fn test_member_expression_chain() {
+        let input = "a.b.c".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let mut expected = Program::new();
+        let body = [Rc::new(Node::ExpressionStatement(
+            Some(Rc::new(Node::MemberExpression {
+                object: Some(Rc::new(Node::MemberExpression {
+                    object: Some(Rc::new(Node::Identifier(
+                        "a".to_string(),
+                        SourceLocation::new(1, 1),
+                    ))),
+                    property: Some(Rc::new(Node::Identifier(
+                        "b".to_string(),
+                        SourceLocation::new(1, 3),
+                    ))),
+                    computed: false,
+                    location: SourceLocation::new(1, 1),
+                })),
+                property: Some(Rc::new(Node::Identifier(
+                    "c".to_string(),
+                    SourceLocation::new(1, 5),
+                ))),
+                computed: false,
+                location: SourceLocation::new(1, 1),
+            })),
+            SourceLocation::new(1, 1),
+        ))]
+        .to_vec();
+        expected.set_body(body);
+        assert_eq!(expected, parser.parse_ast());
+    }
+
+    #[test]
+    fn test_call_expression_and_member() {
+        let input = "document.getElementById(\"x\").textContent".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let mut expected = Program::new();
+        let body = [Rc::new(Node::ExpressionStatement(
+            Some(Rc::new(Node::MemberExpression {
+                object: Some(Rc::new(Node::CallExpression {
+                    callee: Some(Rc::new(Node::MemberExpression {
+                        object: Some(Rc::new(Node::Identifier(
+                            "document".to_string(),
+                            SourceLocation::new(1, 1),
+                        ))),
+                        property: Some(Rc::new(Node::Identifier(
+                            "getElementById".to_string(),
+                            SourceLocation::new(1, 10),
+                        ))),
+                        computed: false,
+                        location: SourceLocation::new(1, 1),
+                    })),
+                    arguments: [Some(Rc::new(Node::StringLiteral(
+                        "x".to_string(),
+                        SourceLocation::new(1, 25),
+                    )))]
+                    .to_vec(),
+                    location: SourceLocation::new(1, 1),
+                })),
+                property: Some(Rc::new(Node::Identifier(
+                    "textContent".to_string(),
+                    SourceLocation::new(1, 30),
+                ))),
+                computed: false,
+                location: SourceLocation::new(1, 1),
+            })),
+            SourceLocation::new(1, 1),
+        ))]
+        .to_vec();
+        expected.set_body(body);
+        assert_eq!(expected, parser.parse_ast());
+    }
+
+    #[test]
+    fn test_computed_member_expression() {
+        let input = "a[0].b".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let mut expected = Program::new();
+        let body = [Rc::new(Node::ExpressionStatement(
+            Some(Rc::new(Node::MemberExpression {
+                object: Some(Rc::new(Node::MemberExpression {
+                    object: Some(Rc::new(Node::Identifier(
+                        "a".to_string(),
+                        SourceLocation::new(1, 1),
+                    ))),
+                    property: Some(Rc::new(Node::NumericLiteral(0, SourceLocation::new(1, 3)))),
+                    computed: true,
+                    location: SourceLocation::new(1, 1),
+                })),
+                property: Some(Rc::new(Node::Identifier(
+                    "b".to_string(),
+                    SourceLocation::new(1, 6),
+                ))),
+                computed: false,
+                location: SourceLocation::new(1, 1),
+            })),
+            SourceLocation::new(1, 1),
+        ))]
+        .to_vec();
+        expected.set_body(body);
+        assert_eq!(expected, parser.parse_ast());
+    }
+
+    #[test]
+    fn test_call_expression_location() {
+        let input = "var a = 1;\n  foo(a);\nbar.baz()".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let program = parser.parse_ast();
+
+        let locations = program
+            .body()
+            .iter()
+            .filter_map(|node| match node.as_ref() {
+                Node::ExpressionStatement(Some(expr), _) => match expr.as_ref() {
+                    Node::CallExpression { location, .. } => Some(*location),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            [SourceLocation::new(2, 3), SourceLocation::new(3, 1)].to_vec(),
+            locations
+        );
+    }
+
+    #[test]
+    fn test_export_declarations() {
+        let input =
+            "export function foo() { return 1; }\nexport const x = 5\nvar y = 2".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let program = parser.parse_ast();
+
+        assert_eq!(
+            ["foo".to_string(), "x".to_string()].to_vec(),
+            program.exports().keys().cloned().collect::<Vec<_>>()
+        );
+        // exportの付いた宣言も通常の文として実行される
+        assert_eq!(3, program.body().len());
+    }
+
+    #[test]
+    fn test_import_declaration() {
+        let input = "import { foo, x } from \"./module.js\"\nfoo()".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let program = parser.parse_ast();
+
+        assert_eq!(
+            [Rc::new(Node::ImportDeclaration {
+                specifiers: ["foo".to_string(), "x".to_string()].to_vec(),
+                source: "./module.js".to_string(),
+                location: SourceLocation::new(1, 1),
+            })]
+            .to_vec(),
+            program.imports()
+        );
+    }
+
+    fn parse_error(input: &str) -> Option<String> {
+        let mut parser = JsParser::new(JsLexer::new(input.to_string()));
+        parser.parse_program().err().map(|e| e.message())
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        assert_eq!(
+            Some("SyntaxError: if statement should have `)` but got None".to_string()),
+            parse_error("if (1")
+        );
+        assert_eq!(
+            Some("SyntaxError: block should end with `}`".to_string()),
+            parse_error("function f() { return 1")
+        );
+        assert_eq!(
+            Some("SyntaxError: block should end with `}`".to_string()),
+            parse_error("{")
+        );
+        assert_eq!(
+            Some("SyntaxError: arguments should end with `)`".to_string()),
+            parse_error("foo(1")
+        );
+        assert_eq!(
+            Some("SyntaxError: unexpected token Punctuator(')')".to_string()),
+            parse_error(")")
+        );
+        assert_eq!(
+            Some("SyntaxError: unexpected keyword `else`".to_string()),
+            parse_error("{ else }")
+        );
+        assert_eq!(None, parse_error("if (a) { b() } else { c() }"));
+    }
 }
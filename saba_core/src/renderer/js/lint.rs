@@ -0,0 +1,298 @@
+use crate::renderer::js::ast::{Node, Program};
+use crate::renderer::js::token::SourceLocation;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+
+// 宣言しなくても呼び出せる、ブラウザが提供する関数
+static GLOBAL_FUNCTIONS: [&str; 2] = ["alert", "fetch"];
+
+// 静的解析で見つかった、問題のありそうな書き方
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsLintWarning {
+    message: String,
+    location: SourceLocation,
+}
+
+impl JsLintWarning {
+    pub fn new(message: String, location: SourceLocation) -> Self {
+        Self { message, location }
+    }
+
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+
+    pub fn location(&self) -> SourceLocation {
+        self.location
+    }
+}
+
+impl Display for JsLintWarning {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        write!(f, "{} ({})", self.message, self.location)
+    }
+}
+
+// 実行せずにASTをたどり、よくある間違いを報告する
+// スコープは区別せず、名前だけで宣言と参照を対応づける
+pub fn lint(program: &Program) -> Vec<JsLintWarning> {
+    let mut linter = Linter {
+        warnings: Vec::new(),
+        functions: Vec::new(),
+        declared: Vec::new(),
+        used: Vec::new(),
+    };
+
+    // 関数の宣言は巻き上げられるので、先に名前を集めておく
+    for node in program.body() {
+        linter.collect_functions(&Some(node.clone()));
+    }
+    for node in program.body() {
+        linter.visit(&Some(node.clone()), false);
+    }
+
+    for (name, location) in &linter.declared {
+        if !linter.used.contains(name) {
+            linter.warnings.push(JsLintWarning::new(
+                format!("{} is declared but never used", name),
+                *location,
+            ));
+        }
+    }
+
+    let mut warnings = linter.warnings;
+    warnings.sort_by_key(|w| (w.location.line(), w.location.column()));
+    warnings
+}
+
+struct Linter {
+    warnings: Vec<JsLintWarning>,
+    functions: Vec<String>,
+    declared: Vec<(String, SourceLocation)>,
+    used: Vec<String>,
+}
+
+impl Linter {
+    fn collect_functions(&mut self, node: &Option<Rc<Node>>) {
+        let node = match node {
+            Some(n) => n,
+            None => return,
+        };
+
+        match node.as_ref() {
+            Node::FunctionDeclaration { id, body, .. } => {
+                if let Some(Node::Identifier(name, _)) = id.as_deref() {
+                    self.functions.push(name.to_string());
+                }
+                self.collect_functions(body);
+            }
+            Node::BlockStatement { body, .. } => {
+                for stmt in body {
+                    self.collect_functions(stmt);
+                }
+            }
+            Node::IfStatement {
+                consequent,
+                alternate,
+                ..
+            } => {
+                self.collect_functions(consequent);
+                self.collect_functions(alternate);
+            }
+            // importした関数も呼び出せる
+            Node::ImportDeclaration { specifiers, .. } => {
+                self.functions.extend(specifiers.iter().cloned());
+            }
+            _ => {}
+        }
+    }
+
+    // in_blockは、関数の本体ではないブロックの中にいるときにtrue
+    fn visit(&mut self, node: &Option<Rc<Node>>, in_block: bool) {
+        let node = match node {
+            Some(n) => n,
+            None => return,
+        };
+
+        match node.as_ref() {
+            Node::ExpressionStatement(expr, _) => self.visit(expr, in_block),
+            Node::AdditiveExpression { left, right, .. }
+            | Node::RelationalExpression { left, right, .. } => {
+                self.visit(left, in_block);
+                self.visit(right, in_block);
+            }
+            Node::AssignmentExpression { left, right, .. } => {
+                // 代入するだけでは、変数を使ったことにしない
+                if !matches!(left.as_deref(), Some(Node::Identifier(..))) {
+                    self.visit(left, in_block);
+                }
+                self.visit(right, in_block);
+            }
+            Node::MemberExpression {
+                object,
+                property,
+                computed,
+                ..
+            } => {
+                self.visit(object, in_block);
+                // `a.b`のbは変数ではない
+                if *computed {
+                    self.visit(property, in_block);
+                }
+            }
+            Node::VariableDeclaration {
+                kind,
+                declarations,
+                location,
+            } => {
+                if kind == "var" && in_block {
+                    self.warnings.push(JsLintWarning::new(
+                        "var in a block is visible outside of it; use let or const".to_string(),
+                        *location,
+                    ));
+                }
+                for declaration in declarations {
+                    if let Some(Node::VariableDeclarator { id, init, .. }) = declaration.as_deref()
+                    {
+                        if let Some(Node::Identifier(name, _)) = id.as_deref() {
+                            self.declared.push((name.to_string(), *location));
+                        }
+                        self.visit(init, in_block);
+                    }
+                }
+            }
+            Node::VariableDeclarator { init, .. } => self.visit(init, in_block),
+            Node::Identifier(name, _) => self.used.push(name.to_string()),
+            Node::NumericLiteral(..) | Node::StringLiteral(..) => {}
+            Node::BlockStatement { body, .. } => {
+                for stmt in body {
+                    self.visit(stmt, true);
+                }
+            }
+            Node::ReturnStatement { argument, .. } => self.visit(argument, in_block),
+            Node::FunctionDeclaration { body, .. } => {
+                // 関数の本体は関数のスコープなので、varを使ってもよい
+                if let Some(Node::BlockStatement { body, .. }) = body.as_deref() {
+                    for stmt in body {
+                        self.visit(stmt, false);
+                    }
+                }
+            }
+            Node::CallExpression {
+                callee,
+                arguments,
+                location,
+            } => {
+                match callee.as_deref() {
+                    Some(Node::Identifier(name, _)) => {
+                        if !self.functions.contains(name)
+                            && !GLOBAL_FUNCTIONS.contains(&name.as_str())
+                        {
+                            self.warnings.push(JsLintWarning::new(
+                                format!("call to undefined function {}", name),
+                                *location,
+                            ));
+                        }
+                    }
+                    _ => self.visit(callee, in_block),
+                }
+                for argument in arguments {
+                    self.visit(argument, in_block);
+                }
+            }
+            Node::IfStatement {
+                test,
+                consequent,
+                alternate,
+                location,
+            } => {
+                if let Some(Node::AssignmentExpression { operator: '=', .. }) = test.as_deref() {
+                    self.warnings.push(JsLintWarning::new(
+                        "assignment `=` in a condition; did you mean `==`?".to_string(),
+                        *location,
+                    ));
+                }
+                self.visit(test, in_block);
+                self.visit(consequent, in_block);
+                self.visit(alternate, in_block);
+            }
+            Node::ConditionalExpression {
+                test,
+                consequent,
+                alternate,
+                ..
+            } => {
+                self.visit(test, in_block);
+                self.visit(consequent, in_block);
+                self.visit(alternate, in_block);
+            }
+            Node::ImportDeclaration { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::js::ast::JsParser;
+    use crate::renderer::js::token::JsLexer;
+    use alloc::vec;
+
+    fn lint_source(input: &str) -> Vec<JsLintWarning> {
+        let lexer = JsLexer::new(input.to_string());
+        let mut parser = JsParser::new(lexer);
+        lint(&parser.parse_ast())
+    }
+
+    #[test]
+    fn test_var_in_block() {
+        assert_eq!(
+            vec![JsLintWarning::new(
+                "var in a block is visible outside of it; use let or const".to_string(),
+                SourceLocation::new(2, 3)
+            )],
+            lint_source("if (1) {\n  var a = 1;\n  let b = a;\n  alert(b);\n}")
+        );
+    }
+
+    #[test]
+    fn test_var_in_function_body() {
+        assert!(lint_source("function f() { var a = 1; return a; }\nf()").is_empty());
+    }
+
+    #[test]
+    fn test_assignment_in_condition() {
+        assert_eq!(
+            vec![JsLintWarning::new(
+                "assignment `=` in a condition; did you mean `==`?".to_string(),
+                SourceLocation::new(2, 1)
+            )],
+            lint_source("var a = 1;\nif (a = 2) { alert(a); }")
+        );
+    }
+
+    #[test]
+    fn test_unused_variable() {
+        assert_eq!(
+            vec![JsLintWarning::new(
+                "unused is declared but never used".to_string(),
+                SourceLocation::new(2, 1)
+            )],
+            lint_source("var used = 1;\nlet unused = 2;\nunused = 3;\nalert(used);")
+        );
+    }
+
+    #[test]
+    fn test_call_to_undefined_function() {
+        assert_eq!(
+            vec![JsLintWarning::new(
+                "call to undefined function missing".to_string(),
+                SourceLocation::new(3, 1)
+            )],
+            lint_source("known();\nfunction known() { return 1; }\nmissing();\nalert(1)")
+        );
+    }
+}
@@ -1,3 +1,5 @@
 pub mod ast;
+pub mod lint;
+pub mod optimize;
 pub mod runtime;
 pub mod token;
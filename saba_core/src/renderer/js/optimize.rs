@@ -0,0 +1,484 @@
+use crate::renderer::js::ast::{Node, Program};
+use crate::renderer::js::lint::JsLintWarning;
+use crate::renderer::js::token::SourceLocation;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+// 実行する前に、実行されることのない文をASTから取り除く
+// - ブロックの中でreturn文より後にある文
+// - 条件が定数のif文の、選ばれない方の分岐
+// 取り除いたものごとに警告を返す
+pub fn eliminate_dead_code(program: &Program) -> (Program, Vec<JsLintWarning>) {
+    let mut eliminator = DeadCodeEliminator {
+        warnings: Vec::new(),
+    };
+
+    let body = program
+        .body()
+        .iter()
+        .filter_map(|node| eliminator.statement(node))
+        .collect();
+    let mut optimized = Program::new();
+    optimized.set_body(body);
+
+    (optimized, eliminator.warnings)
+}
+
+struct DeadCodeEliminator {
+    warnings: Vec<JsLintWarning>,
+}
+
+impl DeadCodeEliminator {
+    // 文そのものが不要になったときはNoneを返す
+    fn statement(&mut self, node: &Rc<Node>) -> Option<Rc<Node>> {
+        match node.as_ref() {
+            Node::BlockStatement { body, location } => Some(Rc::new(Node::BlockStatement {
+                body: self.block_body(body),
+                location: *location,
+            })),
+            Node::FunctionDeclaration {
+                id,
+                params,
+                body,
+                location,
+            } => Some(Rc::new(Node::FunctionDeclaration {
+                id: id.clone(),
+                params: params.clone(),
+                body: self.optional_statement(body),
+                location: *location,
+            })),
+            Node::IfStatement {
+                test,
+                consequent,
+                alternate,
+                location,
+            } => match constant_condition(test) {
+                Some(true) => {
+                    self.warnings.push(JsLintWarning::new(
+                        "condition is always true".to_string(),
+                        *location,
+                    ));
+                    self.optional_statement(consequent)
+                }
+                Some(false) => {
+                    self.warnings.push(JsLintWarning::new(
+                        "condition is always false".to_string(),
+                        *location,
+                    ));
+                    self.optional_statement(alternate)
+                }
+                None => Some(Rc::new(Node::IfStatement {
+                    test: test.clone(),
+                    consequent: self.optional_statement(consequent),
+                    alternate: self.optional_statement(alternate),
+                    location: *location,
+                })),
+            },
+            _ => Some(node.clone()),
+        }
+    }
+
+    fn optional_statement(&mut self, node: &Option<Rc<Node>>) -> Option<Rc<Node>> {
+        match node {
+            Some(n) => self.statement(n),
+            None => None,
+        }
+    }
+
+    fn block_body(&mut self, body: &[Option<Rc<Node>>]) -> Vec<Option<Rc<Node>>> {
+        let mut optimized = Vec::new();
+        let mut statements = body.iter();
+
+        while let Some(stmt) = statements.next() {
+            let stmt = match self.optional_statement(stmt) {
+                Some(s) => s,
+                None => continue,
+            };
+            let is_return = matches!(stmt.as_ref(), Node::ReturnStatement { .. });
+            optimized.push(Some(stmt));
+
+            if is_return {
+                for dead in statements.flatten() {
+                    // 関数の宣言は巻き上げられるので、returnより後にあっても残す
+                    if let Node::FunctionDeclaration { .. } = dead.as_ref() {
+                        optimized.push(self.statement(dead));
+                        continue;
+                    }
+                    self.warnings.push(JsLintWarning::new(
+                        "unreachable code after return".to_string(),
+                        dead.location(),
+                    ));
+                }
+                break;
+            }
+        }
+
+        optimized
+    }
+}
+
+// 条件が定数のときはその真偽を返す
+// 数値とtrue、falseだけを定数とみなす
+fn constant_condition(test: &Option<Rc<Node>>) -> Option<bool> {
+    match test.as_deref() {
+        Some(Node::NumericLiteral(n, _)) => Some(*n != 0),
+        Some(Node::Identifier(name, _)) if name == "true" => Some(true),
+        Some(Node::Identifier(name, _)) if name == "false" => Some(false),
+        _ => None,
+    }
+}
+
+// 定数どうしの加算と減算を、実行する前に計算済みのリテラルに置き換える
+// 実行時と同じ結果になる場合だけ畳み込み、u64の範囲を超える計算はそのまま残す
+pub fn fold_constants(node: Rc<Node>) -> Rc<Node> {
+    let folded = match node.as_ref() {
+        Node::ExpressionStatement(expr, location) => {
+            Node::ExpressionStatement(fold_optional(expr), *location)
+        }
+        Node::AdditiveExpression {
+            operator,
+            left,
+            right,
+            location,
+        } => {
+            let left = fold_optional(left);
+            let right = fold_optional(right);
+            match fold_additive(*operator, left.as_deref(), right.as_deref(), *location) {
+                Some(literal) => literal,
+                None => Node::AdditiveExpression {
+                    operator: *operator,
+                    left,
+                    right,
+                    location: *location,
+                },
+            }
+        }
+        Node::RelationalExpression {
+            operator,
+            left,
+            right,
+            location,
+        } => Node::RelationalExpression {
+            operator: *operator,
+            left: fold_optional(left),
+            right: fold_optional(right),
+            location: *location,
+        },
+        Node::AssignmentExpression {
+            operator,
+            left,
+            right,
+            location,
+        } => Node::AssignmentExpression {
+            operator: *operator,
+            left: left.clone(),
+            right: fold_optional(right),
+            location: *location,
+        },
+        Node::VariableDeclaration {
+            kind,
+            declarations,
+            location,
+        } => Node::VariableDeclaration {
+            kind: kind.clone(),
+            declarations: declarations.iter().map(fold_optional).collect(),
+            location: *location,
+        },
+        Node::VariableDeclarator { id, init, location } => Node::VariableDeclarator {
+            id: id.clone(),
+            init: fold_optional(init),
+            location: *location,
+        },
+        Node::BlockStatement { body, location } => Node::BlockStatement {
+            body: body.iter().map(fold_optional).collect(),
+            location: *location,
+        },
+        Node::ReturnStatement { argument, location } => Node::ReturnStatement {
+            argument: fold_optional(argument),
+            location: *location,
+        },
+        Node::FunctionDeclaration {
+            id,
+            params,
+            body,
+            location,
+        } => Node::FunctionDeclaration {
+            id: id.clone(),
+            params: params.clone(),
+            body: fold_optional(body),
+            location: *location,
+        },
+        Node::CallExpression {
+            callee,
+            arguments,
+            location,
+        } => Node::CallExpression {
+            callee: callee.clone(),
+            arguments: arguments.iter().map(fold_optional).collect(),
+            location: *location,
+        },
+        Node::IfStatement {
+            test,
+            consequent,
+            alternate,
+            location,
+        } => Node::IfStatement {
+            test: fold_optional(test),
+            consequent: fold_optional(consequent),
+            alternate: fold_optional(alternate),
+            location: *location,
+        },
+        Node::ConditionalExpression {
+            test,
+            consequent,
+            alternate,
+            location,
+        } => Node::ConditionalExpression {
+            test: fold_optional(test),
+            consequent: fold_optional(consequent),
+            alternate: fold_optional(alternate),
+            location: *location,
+        },
+        Node::MemberExpression { .. }
+        | Node::ImportDeclaration { .. }
+        | Node::NumericLiteral(..)
+        | Node::Identifier(..)
+        | Node::StringLiteral(..) => return node,
+    };
+
+    Rc::new(folded)
+}
+
+fn fold_optional(node: &Option<Rc<Node>>) -> Option<Rc<Node>> {
+    node.clone().map(fold_constants)
+}
+
+// RuntimeValueの`+`と`-`と同じ規則で計算する
+// 畳み込んだリテラルは、元の式の位置を引き継ぐ
+fn fold_additive(
+    operator: char,
+    left: Option<&Node>,
+    right: Option<&Node>,
+    location: SourceLocation,
+) -> Option<Node> {
+    match (operator, left?, right?) {
+        ('+', Node::NumericLiteral(l, _), Node::NumericLiteral(r, _)) => {
+            l.checked_add(*r).map(|n| Node::NumericLiteral(n, location))
+        }
+        ('+', l, r) => Some(Node::StringLiteral(
+            literal_string(l)? + &literal_string(r)?,
+            location,
+        )),
+        ('-', Node::NumericLiteral(l, _), Node::NumericLiteral(r, _)) => {
+            l.checked_sub(*r).map(|n| Node::NumericLiteral(n, location))
+        }
+        _ => None,
+    }
+}
+
+fn literal_string(node: &Node) -> Option<String> {
+    match node {
+        Node::NumericLiteral(n, _) => Some(n.to_string()),
+        Node::StringLiteral(s, _) => Some(s.clone()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::dom::node::{Node as DomNode, NodeKind as DomNodeKind};
+    use crate::renderer::js::ast::JsParser;
+    use crate::renderer::js::runtime::{JsNumber, JsRuntime, RuntimeValue};
+    use crate::renderer::js::token::JsLexer;
+    use alloc::vec;
+    use core::cell::RefCell;
+
+    fn optimize_source(input: &str) -> (Program, Vec<JsLintWarning>) {
+        let lexer = JsLexer::new(input.to_string());
+        let mut parser = JsParser::new(lexer);
+        eliminate_dead_code(&parser.parse_ast())
+    }
+
+    fn function_body(program: &Program) -> Vec<Option<Rc<Node>>> {
+        match program.body()[0].as_ref() {
+            Node::FunctionDeclaration { body, .. } => match body.as_deref() {
+                Some(Node::BlockStatement { body, .. }) => body.clone(),
+                _ => panic!("function body should be a block"),
+            },
+            _ => panic!("first statement should be a function declaration"),
+        }
+    }
+
+    #[test]
+    fn test_statements_after_return_are_removed() {
+        let (program, warnings) =
+            optimize_source("function f() {\n  return 1;\n  alert(2);\n  alert(3);\n}");
+
+        let body = function_body(&program);
+        assert_eq!(1, body.len());
+        assert_eq!(
+            Some("ReturnStatement"),
+            body[0].as_deref().map(|n| n.kind_name())
+        );
+        assert_eq!(
+            vec![
+                JsLintWarning::new(
+                    "unreachable code after return".to_string(),
+                    SourceLocation::new(3, 3)
+                ),
+                JsLintWarning::new(
+                    "unreachable code after return".to_string(),
+                    SourceLocation::new(4, 3)
+                ),
+            ],
+            warnings
+        );
+    }
+
+    #[test]
+    fn test_function_declaration_after_return_is_kept() {
+        let (program, warnings) =
+            optimize_source("function f() { return 1; function g() { return 2; } }");
+
+        assert_eq!(2, function_body(&program).len());
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_constant_conditions_are_simplified() {
+        let (program, warnings) =
+            optimize_source("if (true) { alert(1); } else { alert(2); }\nif (0) { alert(3); }");
+
+        assert_eq!(1, program.body().len());
+        match program.body()[0].as_ref() {
+            Node::BlockStatement { body, .. } => assert_eq!(1, body.len()),
+            n => panic!("expected the consequent block but got {:?}", n),
+        }
+        assert_eq!(
+            vec![
+                JsLintWarning::new(
+                    "condition is always true".to_string(),
+                    SourceLocation::new(1, 1)
+                ),
+                JsLintWarning::new(
+                    "condition is always false".to_string(),
+                    SourceLocation::new(2, 1)
+                ),
+            ],
+            warnings
+        );
+    }
+
+    #[test]
+    fn test_non_constant_condition_is_kept() {
+        let (program, warnings) = optimize_source("var a = 1;\nif (a) { alert(a); }");
+
+        assert_eq!(
+            Some("IfStatement"),
+            program.body().get(1).map(|n| n.kind_name())
+        );
+        assert!(warnings.is_empty());
+    }
+
+    fn fold_source(input: &str) -> Program {
+        let lexer = JsLexer::new(input.to_string());
+        let mut parser = JsParser::new(lexer);
+        let mut program = Program::new();
+        program.set_body(
+            parser
+                .parse_ast()
+                .body()
+                .iter()
+                .map(|node| fold_constants(node.clone()))
+                .collect(),
+        );
+        program
+    }
+
+    fn execute(program: &Program) -> Option<RuntimeValue> {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        JsRuntime::new(dom).execute(program)
+    }
+
+    #[test]
+    fn test_fold_numbers() {
+        let program = fold_source("var a = 2 + 3;");
+        match program.body()[0].as_ref() {
+            Node::VariableDeclaration { declarations, .. } => match declarations[0].as_deref() {
+                Some(Node::VariableDeclarator { init, .. }) => {
+                    assert_eq!(
+                        Some(&Node::NumericLiteral(5, SourceLocation::new(1, 9))),
+                        init.as_deref()
+                    )
+                }
+                n => panic!("expected a declarator but got {:?}", n),
+            },
+            n => panic!("expected a variable declaration but got {:?}", n),
+        }
+    }
+
+    #[test]
+    fn test_fold_strings() {
+        let program = fold_source(r#""hello" + " " + "world""#);
+        assert_eq!(
+            &Node::ExpressionStatement(
+                Some(Rc::new(Node::StringLiteral(
+                    "hello world".to_string(),
+                    SourceLocation::new(1, 1)
+                ))),
+                SourceLocation::new(1, 1)
+            ),
+            program.body()[0].as_ref()
+        );
+    }
+
+    #[test]
+    fn test_expressions_with_variables_are_not_folded() {
+        let program = fold_source("var a = 1; a + 2");
+        assert_eq!(
+            Some("AdditiveExpression"),
+            match program.body()[1].as_ref() {
+                Node::ExpressionStatement(expr, _) => expr.as_deref().map(|n| n.kind_name()),
+                _ => None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_folded_result_is_identical() {
+        for input in [
+            "2 + 3",
+            "5 - 2 + 1",
+            r#""a" + 1 + 2"#,
+            r#"1 + 2 + "a""#,
+            r#""hello" + " " + "world""#,
+            "function f(x) { return x + (1 + 2); } var b = 4 - 1; f(b)",
+        ] {
+            let lexer = JsLexer::new(input.to_string());
+            let mut parser = JsParser::new(lexer);
+            assert_eq!(
+                execute(&parser.parse_ast()),
+                execute(&fold_source(input)),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_surviving_statements_execute() {
+        let (program, _) = optimize_source(
+            "function f(x) { alert(x); return x + 1; alert(\"dead\"); }\nif (false) { alert(\"never\"); } else { f(1) + 1 }",
+        );
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+
+        assert_eq!(
+            Some(RuntimeValue::Number(JsNumber::from(3))),
+            runtime.execute(&program)
+        );
+        assert_eq!(["1".to_string()].to_vec(), runtime.alert_messages());
+    }
+}
@@ -1,20 +1,56 @@
-use crate::renderer::dom::api::get_element_by_id;
+use crate::clock::{Clock, NoClock};
+use crate::constants::MAX_JS_CALL_DEPTH;
+use crate::http::{HttpRequest, HttpTransport};
+use crate::renderer::css::cssom::CssParser;
+use crate::renderer::css::token::CssTokenizer;
+use crate::renderer::dom::api::{
+    append_child, clone_node, find_element_by_id, get_element_nodes, is_svg_element, start_tag,
+    to_html,
+};
+use crate::renderer::dom::node::ElementKind;
+use crate::renderer::dom::node::FragmentNodes;
 use crate::renderer::dom::node::Node as DomNode;
 use crate::renderer::dom::node::NodeKind as DomNodeKind;
-use crate::renderer::js::ast::{Node, Program};
+use crate::renderer::js::ast::{JsParser, Node, Program};
+use crate::renderer::js::token::{JsLexer, SourceLocation};
+use crate::url::{is_same_origin, Url};
+use alloc::collections::{BTreeMap, BTreeSet};
 use alloc::format;
 use alloc::rc::Rc;
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use core::borrow::Borrow;
 use core::cell::RefCell;
+use core::cmp::Ordering;
 use core::fmt::{Display, Formatter};
 use core::ops::{Add, Sub};
 
+#[derive(Debug)]
 pub struct JsRuntime {
     dom_root: Rc<RefCell<DomNode>>,
     functions: Vec<Function>,
     env: Rc<RefCell<Environment>>,
+    alert_messages: Vec<String>,
+    console_messages: Vec<String>,
+    page_url: Option<String>,
+    transport: Option<Rc<dyn HttpTransport>>,
+    clock: Rc<dyn Clock>,
+    network_errors: Vec<String>,
+    profiling: bool,
+    profile: BTreeMap<&'static str, u64>,
+    call_depth: usize,
+    max_call_depth: usize,
+    script_errors: Vec<JsError>,
+    // ブレークポイントの位置(行, 列)
+    breakpoints: BTreeSet<(u32, u32)>,
+    pause_events: Vec<DebugPause>,
+    paused: bool,
+    // 一時停止したときに、まだ実行していないトップレベルの文
+    pending_statements: Vec<Rc<Node>>,
+    // ウォッチ式のソースと、一度だけパースしたAST、最後に評価した値
+    watches: Vec<String>,
+    watch_asts: Vec<Option<Rc<Node>>>,
+    watch_values: Vec<Option<RuntimeValue>>,
 }
 
 impl JsRuntime {
@@ -23,13 +59,258 @@ impl JsRuntime {
             dom_root,
             functions: Vec::new(),
             env: Rc::new(RefCell::new(Environment::new(None))),
+            alert_messages: Vec::new(),
+            console_messages: Vec::new(),
+            page_url: None,
+            transport: None,
+            clock: Rc::new(NoClock),
+            network_errors: Vec::new(),
+            profiling: false,
+            profile: BTreeMap::new(),
+            call_depth: 0,
+            max_call_depth: MAX_JS_CALL_DEPTH,
+            script_errors: Vec::new(),
+            breakpoints: BTreeSet::new(),
+            pause_events: Vec::new(),
+            paused: false,
+            pending_statements: Vec::new(),
+            watches: Vec::new(),
+            watch_asts: Vec::new(),
+            watch_values: Vec::new(),
         }
     }
 
-    pub fn execute(&mut self, program: &Program) {
+    // 関数呼び出しのネストの上限
+    // no_stdではスタックが溢れるとブラウザごと止まるので、上限を超えた呼び出しは実行しない
+    pub fn set_max_call_depth(&mut self, max_call_depth: usize) {
+        self.max_call_depth = max_call_depth;
+    }
+
+    pub fn script_errors(&self) -> Vec<JsError> {
+        self.script_errors.clone()
+    }
+
+    pub fn add_breakpoint(&mut self, line: u32, column: u32) {
+        self.breakpoints.insert((line, column));
+    }
+
+    pub fn remove_breakpoint(&mut self, line: u32, column: u32) {
+        self.breakpoints.remove(&(line, column));
+    }
+
+    pub fn breakpoints(&self) -> &BTreeSet<(u32, u32)> {
+        &self.breakpoints
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    // 前回の呼び出しから後に起きた一時停止のイベントを取り出す
+    pub fn take_pause_events(&mut self) -> Vec<DebugPause> {
+        core::mem::take(&mut self.pause_events)
+    }
+
+    // 一時停止を解除し、残りの文を次のブレークポイントまで実行する
+    pub fn continue_execution(&mut self) -> Option<RuntimeValue> {
+        self.paused = false;
+        self.run_pending_statements()
+    }
+
+    // トップレベルの文を実行するたびに評価し直す式を追加する
+    pub fn add_watch(&mut self, expr: String) {
+        let lexer = JsLexer::new(expr.clone());
+        let mut parser = JsParser::new(lexer);
+        // 構文エラーのある式は評価せず、値をundefinedのままにする
+        let ast = match parser.parse_program() {
+            Ok(program) => program.body().first().cloned(),
+            Err(_) => None,
+        };
+
+        self.watches.push(expr);
+        self.watch_asts.push(ast);
+        self.watch_values.push(None);
+    }
+
+    pub fn watches(&self) -> &[String] {
+        &self.watches
+    }
+
+    pub fn watch_values(&self) -> &[Option<RuntimeValue>] {
+        &self.watch_values
+    }
+
+    // 開発者コンソールに入力された式を、現在のスコープで評価する
+    // 宣言した変数はスコープに残るので、続けて入力した式から参照できる
+    // 値がundefinedのときはOk(None)を返す
+    // 構文エラーや評価中に起きたエラーはスクリプトのエラーには記録せず、最初のものを返す
+    pub fn eval_expression(&mut self, expr: &str) -> Result<Option<RuntimeValue>, JsError> {
+        let lexer = JsLexer::new(expr.to_string());
+        let mut parser = JsParser::new(lexer);
+        let program = parser.parse_program()?;
+
+        let errors_before = self.script_errors.len();
+        let breakpoints = core::mem::take(&mut self.breakpoints);
+        let mut result = None;
         for node in program.body() {
-            self.eval(&Some(node.clone()), self.env.clone());
+            result = self.eval(&Some(node.clone()), self.env.clone());
+        }
+        self.breakpoints = breakpoints;
+
+        match self
+            .script_errors
+            .split_off(errors_before)
+            .into_iter()
+            .next()
+        {
+            Some(error) => Err(error),
+            None => Ok(result),
+        }
+    }
+
+    // ウォッチ式を現在のスコープで評価し直す
+    // ウォッチ式の評価でブレークポイントに当たらないように、その間だけ取り除いておく
+    fn update_watches(&mut self) {
+        let breakpoints = core::mem::take(&mut self.breakpoints);
+        for i in 0..self.watch_asts.len() {
+            let ast = self.watch_asts[i].clone();
+            self.watch_values[i] = self.eval(&ast, self.env.clone());
+        }
+        self.breakpoints = breakpoints;
+    }
+
+    // 有効にすると、ノードの種類ごとに評価した回数を数える
+    // no_stdで時計がないため、時間の代わりにステップ数を使う
+    pub fn set_profiling(&mut self, profiling: bool) {
+        self.profiling = profiling;
+    }
+
+    pub fn profile_report(&self) -> &BTreeMap<&'static str, u64> {
+        &self.profile
+    }
+
+    pub fn alert_messages(&self) -> Vec<String> {
+        self.alert_messages.clone()
+    }
+
+    pub fn console_messages(&self) -> Vec<String> {
+        self.console_messages.clone()
+    }
+
+    // fetchでオリジンの判定と相対URLの解決に使う
+    pub fn set_page_url(&mut self, page_url: Option<String>) {
+        self.page_url = page_url;
+    }
+
+    pub fn set_transport(&mut self, transport: Option<Rc<dyn HttpTransport>>) {
+        self.transport = transport;
+    }
+
+    // Date.now()が返す時刻の取得元
+    pub fn set_clock(&mut self, clock: Rc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    pub fn network_errors(&self) -> Vec<String> {
+        self.network_errors.clone()
+    }
+
+    // Promiseに対応していないので、fetchはレスポンスのボディを同期的に返す
+    // 別オリジンへのリクエストは、Access-Control-Allow-Originで許可された場合だけボディを返す
+    fn fetch(&mut self, url: &str) -> Option<String> {
+        let page_url = match self.page_url.clone().map(|u| Url::new(u).parse()) {
+            Some(Ok(page_url)) => page_url,
+            _ => {
+                self.network_errors
+                    .push(format!("fetch to {} failed: page url is unknown", url));
+                return None;
+            }
+        };
+        let url = page_url.resolve(url);
+        let request_url = match Url::new(url.clone()).parse() {
+            Ok(request_url) => request_url,
+            Err(e) => {
+                self.network_errors.push(e);
+                return None;
+            }
+        };
+        let transport = match &self.transport {
+            Some(transport) => transport.clone(),
+            None => {
+                self.network_errors
+                    .push(format!("fetch to {} failed: no http transport", url));
+                return None;
+            }
+        };
+
+        let request = HttpRequest::builder(url.clone())
+            .header("Origin", &page_url.origin())
+            .build();
+        let response = match transport.request(request) {
+            Ok(response) => response,
+            Err(e) => {
+                self.network_errors
+                    .push(format!("fetch to {} failed: {:?}", url, e));
+                return None;
+            }
+        };
+
+        if !is_same_origin(&page_url, &request_url) {
+            let allowed = match response.header_value("Access-Control-Allow-Origin") {
+                Ok(value) => value == "*" || value == page_url.origin(),
+                Err(_) => false,
+            };
+            if !allowed {
+                self.network_errors
+                    .push(format!("fetch to {} was blocked by CORS policy", url));
+                return None;
+            }
+        }
+
+        Some(response.body())
+    }
+
+    // 現在の変数と関数を保存する
+    // DOMは保存しないため、restoreしてもDOMへの変更は元に戻らない
+    pub fn snapshot(&self) -> RuntimeSnapshot {
+        RuntimeSnapshot {
+            env: RefCell::borrow(&self.env).deep_clone(),
+            functions: self.functions.clone(),
+        }
+    }
+
+    // snapshotで保存したときの変数と関数に戻す
+    pub fn restore(&mut self, snapshot: RuntimeSnapshot) {
+        self.env = Rc::new(RefCell::new(snapshot.env));
+        self.functions = snapshot.functions;
+    }
+
+    // REPLと同じように、最後に評価した文の値を返す
+    // 一時停止中は文を実行せず、continue_executionで続きと一緒に実行する
+    pub fn execute(&mut self, program: &Program) -> Option<RuntimeValue> {
+        self.pending_statements
+            .extend(program.body().iter().cloned());
+        self.run_pending_statements()
+    }
+
+    // 評価器は再帰で実装されていて式の途中では止められないので、
+    // ブレークポイントに当たった文は最後まで実行し、次のトップレベルの文の前で止まる
+    fn run_pending_statements(&mut self) -> Option<RuntimeValue> {
+        let mut result = None;
+        while !self.paused && !self.pending_statements.is_empty() {
+            let node = self.pending_statements.remove(0);
+            result = self.eval(&Some(node), self.env.clone());
+            self.update_watches();
         }
+        result
+    }
+
+    fn pause(&mut self, node: Rc<Node>, env: &Rc<RefCell<Environment>>) {
+        self.paused = true;
+        self.pause_events.push(DebugPause {
+            node,
+            scope: RefCell::borrow(env).visible_variables(),
+        });
     }
 
     fn eval(
@@ -42,12 +323,27 @@ impl JsRuntime {
             None => return None,
         };
 
+        if self.profiling {
+            *self.profile.entry(node.kind_name()).or_insert(0) += 1;
+        }
+
+        // 同じ位置から始まるノードが入れ子になっている場合は、一番外側のノードで止まる
+        let location = node.location();
+        if !self.paused
+            && self
+                .breakpoints
+                .contains(&(location.line(), location.column()))
+        {
+            self.pause(node.clone(), &env);
+        }
+
         match node.borrow() {
-            Node::ExpressionStatement(expr) => return self.eval(&expr, env.clone()),
+            Node::ExpressionStatement(expr, _) => return self.eval(&expr, env.clone()),
             Node::AdditiveExpression {
                 operator,
                 left,
                 right,
+                ..
             } => {
                 let left_value = match self.eval(&left, env.clone()) {
                     Some(value) => value,
@@ -66,16 +362,40 @@ impl JsRuntime {
                     None
                 }
             }
+            Node::RelationalExpression {
+                operator,
+                left,
+                right,
+                ..
+            } => {
+                let left_value = match self.eval(left, env.clone()) {
+                    Some(value) => value,
+                    None => return None,
+                };
+                let right_value = match self.eval(right, env.clone()) {
+                    Some(value) => value,
+                    None => return None,
+                };
+
+                // 比較できない場合(NaNを含む場合)は、どちらの演算子でもfalseになる
+                let ordering = js_compare(&left_value, &right_value);
+                match operator {
+                    '<' => Some(RuntimeValue::Boolean(ordering == Some(Ordering::Less))),
+                    '>' => Some(RuntimeValue::Boolean(ordering == Some(Ordering::Greater))),
+                    _ => None,
+                }
+            }
             Node::AssignmentExpression {
                 operator,
                 left,
                 right,
+                ..
             } => {
                 if operator != &'=' {
                     return None;
                 }
                 if let Some(node) = left {
-                    if let Node::Identifier(id) = node.borrow() {
+                    if let Node::Identifier(id, _) = node.borrow() {
                         let new_value = self.eval(right, env.clone());
                         env.borrow_mut().update_variable(id.to_string(), new_value);
                         return None;
@@ -98,22 +418,77 @@ impl JsRuntime {
                                     DomNodeKind::Text(right_value.to_string()),
                                 )))));
                         }
+
+                        // 真偽値の属性は、値が真なら属性を追加し、偽なら属性を削除する
+                        if p == "open" {
+                            if let DomNodeKind::Element(ref mut e) = object.borrow_mut().kind {
+                                if is_truthy(&Some(right_value)) {
+                                    e.set_attribute(&p, "");
+                                } else {
+                                    e.remove_attribute(&p);
+                                }
+                            }
+                        }
                     }
                 }
                 None
             }
-            Node::MemberExpression { object, property } => {
+            Node::MemberExpression {
+                object,
+                property,
+                computed,
+                ..
+            } => {
                 let object_value = match self.eval(object, env.clone()) {
                     Some(value) => value,
                     None => return None,
                 };
-                let property_value = match self.eval(property, env.clone()) {
+                // `a[b]`はbを評価した値を、`a.b`はbという名前をプロパティ名にする
+                let property_value = if *computed {
+                    self.eval(property, env.clone())
+                } else {
+                    match property.as_deref() {
+                        Some(Node::Identifier(name, _)) => {
+                            Some(RuntimeValue::StringLiteral(name.to_string()))
+                        }
+                        _ => None,
+                    }
+                };
+                let property_value = match property_value {
                     Some(value) => value,
                     None => return Some(object_value),
                 };
 
+                // document.documentElementはhtml要素を返す
+                if object_value == RuntimeValue::StringLiteral("document".to_string())
+                    && property_value.to_string() == "documentElement"
+                {
+                    if let Some(html) =
+                        get_element_nodes(Some(self.dom_root.clone()), ElementKind::Html).first()
+                    {
+                        return Some(RuntimeValue::HtmlElement {
+                            object: html.clone(),
+                            property: None,
+                        });
+                    }
+                }
+
                 if let RuntimeValue::HtmlElement { object, property } = object_value {
                     assert!(property.is_none());
+
+                    // outerHTMLは現在のDOMツリーをシリアライズした文字列を返す
+                    if property_value.to_string() == "outerHTML" {
+                        return Some(RuntimeValue::StringLiteral(to_html(&object)));
+                    }
+
+                    // template.contentはDocumentFragmentとして返す
+                    if property_value.to_string() == "content" {
+                        let element = RefCell::borrow(&object).get_element();
+                        if let Some(content) = element.and_then(|e| e.content()) {
+                            return Some(RuntimeValue::DocumentFragment(content));
+                        }
+                    }
+
                     return Some(RuntimeValue::HtmlElement {
                         object,
                         property: Some(property_value.to_string()),
@@ -124,38 +499,86 @@ impl JsRuntime {
                     object_value + RuntimeValue::StringLiteral(".".to_string()) + property_value,
                 );
             }
-            Node::NumericLiteral(value) => Some(RuntimeValue::Number(*value)),
-            Node::VariableDeclaration { declarations } => {
+            Node::NumericLiteral(value, _) => Some(RuntimeValue::Number(JsNumber::from(*value))),
+            Node::VariableDeclaration {
+                kind, declarations, ..
+            } => {
+                // varは関数のスコープ、letとconstはブロックのスコープに追加する
+                let scope = if kind == "var" {
+                    Environment::function_scope(env.clone())
+                } else {
+                    env.clone()
+                };
                 for declaration in declarations {
-                    self.eval(&declaration, env.clone());
+                    if let Some(Node::VariableDeclarator { id, init, .. }) = declaration.as_deref()
+                    {
+                        if let Some(Node::Identifier(id, _)) = id.as_deref() {
+                            let init = self.eval(init, env.clone());
+                            scope.borrow_mut().add_variable(id.to_string(), init);
+                        }
+                    }
                 }
                 None
             }
-            Node::VariableDeclarator { id, init } => {
+            Node::VariableDeclarator { id, init, .. } => {
                 if let Some(node) = id {
-                    if let Node::Identifier(id) = node.borrow() {
+                    if let Node::Identifier(id, _) = node.borrow() {
                         let init = self.eval(&init, env.clone());
                         env.borrow_mut().add_variable(id.to_string(), init);
                     }
                 }
                 None
             }
-            Node::Identifier(name) => match env.borrow_mut().get_variable(name.to_string()) {
+            // true、false、nullは変数の名前には使えないので、そのまま値にする
+            Node::Identifier(name, _) if name == "true" => Some(RuntimeValue::Boolean(true)),
+            Node::Identifier(name, _) if name == "false" => Some(RuntimeValue::Boolean(false)),
+            Node::Identifier(name, _) if name == "null" => Some(RuntimeValue::Null),
+            Node::Identifier(name, _) => match env.borrow_mut().get_variable(name.to_string()) {
                 Some(v) => Some(v),
                 None => Some(RuntimeValue::StringLiteral(name.to_string())),
             },
-            Node::StringLiteral(value) => Some(RuntimeValue::StringLiteral(value.to_string())),
-            Node::BlockStatement { body } => {
+            Node::StringLiteral(value, _) => Some(RuntimeValue::StringLiteral(value.to_string())),
+            Node::BlockStatement { body, .. } => {
+                // ブロックを抜けると、ブロック内のletとconstは見えなくなる
+                let block_env = Rc::new(RefCell::new(Environment::new_block(env)));
                 let mut result: Option<RuntimeValue> = None;
                 for stmt in body {
-                    result = self.eval(&stmt, env.clone());
+                    result = self.eval(&stmt, block_env.clone());
                 }
                 result
             }
-            Node::ReturnStatement { argument } => {
+            Node::IfStatement {
+                test,
+                consequent,
+                alternate,
+                ..
+            } => {
+                if is_truthy(&self.eval(test, env.clone())) {
+                    self.eval(consequent, env.clone())
+                } else {
+                    self.eval(alternate, env.clone())
+                }
+            }
+            Node::ConditionalExpression {
+                test,
+                consequent,
+                alternate,
+                ..
+            } => {
+                if is_truthy(&self.eval(test, env.clone())) {
+                    self.eval(consequent, env.clone())
+                } else {
+                    self.eval(alternate, env.clone())
+                }
+            }
+            Node::ReturnStatement { argument, .. } => {
                 return self.eval(&argument, env.clone());
             }
-            Node::FunctionDeclaration { id, params, body } => {
+            // モジュールは実行する前にページが読み込んで連結しておくので、ここでは何もしない
+            Node::ImportDeclaration { .. } => None,
+            Node::FunctionDeclaration {
+                id, params, body, ..
+            } => {
                 if let Some(RuntimeValue::StringLiteral(id)) = self.eval(&id, env.clone()) {
                     let cloned_body = match body {
                         Some(b) => Some(b.clone()),
@@ -166,7 +589,11 @@ impl JsRuntime {
                 };
                 None
             }
-            Node::CallExpression { callee, arguments } => {
+            Node::CallExpression {
+                callee,
+                arguments,
+                location,
+            } => {
                 let new_env = Rc::new(RefCell::new(Environment::new(Some(env))));
 
                 let callee_value = match self.eval(callee, new_env.clone()) {
@@ -190,22 +617,53 @@ impl JsRuntime {
 
                     match f {
                         Some(f) => f,
-                        None => panic!("function {:?} doesn't exist", callee),
+                        None => {
+                            self.script_errors.push(JsError::new(
+                                format!("ReferenceError: {} is not defined", callee_value),
+                                *location,
+                            ));
+                            return None;
+                        }
                     }
                 };
 
-                assert!(arguments.len() == function.params.len());
+                if self.call_depth >= self.max_call_depth {
+                    self.script_errors.push(JsError::new(
+                        format!(
+                            "RangeError: maximum call stack size exceeded in {}",
+                            function.id
+                        ),
+                        *location,
+                    ));
+                    return None;
+                }
+
+                if arguments.len() != function.params.len() {
+                    self.script_errors.push(JsError::new(
+                        format!(
+                            "TypeError: {} takes {} arguments but {} were given",
+                            function.id,
+                            function.params.len(),
+                            arguments.len()
+                        ),
+                        *location,
+                    ));
+                    return None;
+                }
                 for (i, item) in arguments.iter().enumerate() {
-                    if let Some(RuntimeValue::StringLiteral(name)) =
-                        self.eval(&function.params[i], new_env.clone())
-                    {
-                        new_env
-                            .borrow_mut()
-                            .add_variable(name, self.eval(item, new_env.clone()));
+                    // 仮引数の名前は評価しない
+                    // 評価すると、再帰呼び出しで呼び出し元の同じ名前の変数の値になってしまう
+                    if let Some(Node::Identifier(name, _)) = function.params[i].as_deref() {
+                        // 引数の評価中に環境を参照するので、先に評価してから追加する
+                        let value = self.eval(item, new_env.clone());
+                        new_env.borrow_mut().add_variable(name.to_string(), value);
                     }
                 }
 
-                self.eval(&function.body.clone(), new_env.clone())
+                self.call_depth += 1;
+                let result = self.eval(&function.body.clone(), new_env.clone());
+                self.call_depth -= 1;
+                result
             }
         }
     }
@@ -221,10 +679,14 @@ impl JsRuntime {
                 Some(a) => a,
                 None => return (true, None),
             };
-            let target = match get_element_by_id(Some(self.dom_root.clone()), &arg.to_string()) {
+            let target = match find_element_by_id(&self.dom_root, &arg.to_string()) {
                 Some(n) => n,
                 None => return (true, None),
             };
+            // SVG要素はHTML要素としてのプロパティを持たないため、DOMノードとして返す
+            if is_svg_element(&target) {
+                return (true, Some(RuntimeValue::DomNode(target)));
+            }
             return (
                 true,
                 Some(RuntimeValue::HtmlElement {
@@ -234,107 +696,632 @@ impl JsRuntime {
             );
         }
 
-        (false, None)
-    }
-}
+        if func == &RuntimeValue::StringLiteral("alert".to_string()) {
+            // メッセージを記録するだけで、表示はUI側で行う
+            let message = match arguments.first() {
+                Some(arg) => match self.eval(arg, env.clone()) {
+                    Some(value) => value.to_string(),
+                    None => "".to_string(),
+                },
+                None => "".to_string(),
+            };
+            self.alert_messages.push(message);
+            return (true, None);
+        }
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum RuntimeValue {
-    Number(u64),
-    StringLiteral(String),
-    HtmlElement {
-        object: Rc<RefCell<DomNode>>,
-        property: Option<String>,
-    },
-}
+        if func == &RuntimeValue::StringLiteral("console.log".to_string()) {
+            // 引数を空白で区切って1行にする。表示はUI側で行う
+            let message = arguments
+                .iter()
+                .map(|arg| match self.eval(arg, env.clone()) {
+                    Some(value) => value.to_string(),
+                    None => "undefined".to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            self.console_messages.push(message);
+            return (true, None);
+        }
 
-impl Add<RuntimeValue> for RuntimeValue {
-    type Output = RuntimeValue;
+        if func == &RuntimeValue::StringLiteral("Date.now".to_string()) {
+            return (
+                true,
+                Some(RuntimeValue::Number(JsNumber::from(
+                    self.clock.now_millis(),
+                ))),
+            );
+        }
 
-    fn add(self, rhs: RuntimeValue) -> RuntimeValue {
-        if let (RuntimeValue::Number(left_num), RuntimeValue::Number(right_num)) = (&self, &rhs) {
-            return RuntimeValue::Number(left_num + right_num);
+        if func == &RuntimeValue::StringLiteral("fetch".to_string()) {
+            let url = match arguments.first() {
+                Some(arg) => match self.eval(arg, env.clone()) {
+                    Some(value) => value.to_string(),
+                    None => return (true, None),
+                },
+                None => return (true, None),
+            };
+            return (true, self.fetch(&url).map(RuntimeValue::StringLiteral));
         }
 
-        RuntimeValue::StringLiteral(self.to_string() + &rhs.to_string())
-    }
-}
+        if func == &RuntimeValue::StringLiteral("document.importNode".to_string()) {
+            let node = match self.eval(&arguments[0], env.clone()) {
+                Some(n) => n,
+                None => return (true, None),
+            };
+            let deep = match arguments.get(1) {
+                Some(arg) => is_truthy(&self.eval(arg, env.clone())),
+                None => false,
+            };
 
-impl Sub<RuntimeValue> for RuntimeValue {
-    type Output = RuntimeValue;
+            return match node {
+                RuntimeValue::DocumentFragment(nodes) => (
+                    true,
+                    Some(RuntimeValue::DocumentFragment(Rc::new(RefCell::new(
+                        RefCell::borrow(&nodes)
+                            .iter()
+                            .map(|n| clone_node(n, deep))
+                            .collect(),
+                    )))),
+                ),
+                RuntimeValue::HtmlElement {
+                    object,
+                    property: None,
+                } => (
+                    true,
+                    Some(RuntimeValue::HtmlElement {
+                        object: clone_node(&object, deep),
+                        property: None,
+                    }),
+                ),
+                _ => (true, None),
+            };
+        }
 
-    fn sub(self, rhs: RuntimeValue) -> RuntimeValue {
-        if let (RuntimeValue::Number(left_num), RuntimeValue::Number(right_num)) = (&self, &rhs) {
-            return RuntimeValue::Number(left_num - right_num);
+        if let RuntimeValue::HtmlElement {
+            object,
+            property: Some(p),
+        } = func
+        {
+            if p == "appendChild" {
+                let child = match self.eval(&arguments[0], env.clone()) {
+                    Some(c) => c,
+                    None => return (true, None),
+                };
+
+                match &child {
+                    // 子ノードを移動した後のフラグメントは空になる
+                    RuntimeValue::DocumentFragment(nodes) => {
+                        let nodes = core::mem::take(&mut *nodes.borrow_mut());
+                        for n in nodes {
+                            append_child(object, n);
+                        }
+                    }
+                    RuntimeValue::HtmlElement {
+                        object: child_node,
+                        property: None,
+                    } => append_child(object, child_node.clone()),
+                    _ => return (true, None),
+                }
+                return (true, Some(child));
+            }
+
+            if p == "setAttribute" {
+                let name = match self.eval(&arguments[0], env.clone()) {
+                    Some(n) => n.to_string(),
+                    None => return (true, None),
+                };
+                let value = match arguments.get(1) {
+                    Some(arg) => match self.eval(arg, env.clone()) {
+                        Some(v) => v.to_string(),
+                        None => "".to_string(),
+                    },
+                    None => "".to_string(),
+                };
+                if let DomNodeKind::Element(ref mut e) = object.borrow_mut().kind {
+                    e.set_attribute(&name, &value);
+                }
+                return (true, None);
+            }
+
+            // セレクタが不正なときは、どの要素にも一致しないものとしてfalseを返す
+            if p == "matches" {
+                let selector = match arguments
+                    .first()
+                    .and_then(|arg| self.eval(arg, env.clone()))
+                {
+                    Some(s) => s.to_string(),
+                    None => return (true, None),
+                };
+                let element = match RefCell::borrow(object).get_element() {
+                    Some(e) => e,
+                    None => return (true, None),
+                };
+                let matched = CssParser::new(CssTokenizer::new(selector))
+                    .parse_selector()
+                    .is_ok_and(|selector| selector.matches(&element));
+                return (true, Some(RuntimeValue::Boolean(matched)));
+            }
+
+            // 要素自身から祖先へたどり、セレクタに一致する最初の要素を返す
+            // 見つからないときやセレクタが不正なときはnullを返す
+            if p == "closest" {
+                let selector = match arguments
+                    .first()
+                    .and_then(|arg| self.eval(arg, env.clone()))
+                {
+                    Some(s) => s.to_string(),
+                    None => return (true, None),
+                };
+                let selector = match CssParser::new(CssTokenizer::new(selector)).parse_selector() {
+                    Ok(selector) => selector,
+                    Err(_) => return (true, Some(RuntimeValue::Null)),
+                };
+
+                let mut current = Some(object.clone());
+                while let Some(node) = current {
+                    // パーサーは要素をテキストの子にすることがあるので、テキストは飛ばしてたどる
+                    let element = RefCell::borrow(&node).get_element();
+                    if element.is_some_and(|e| selector.matches(&e)) {
+                        if is_svg_element(&node) {
+                            return (true, Some(RuntimeValue::DomNode(node)));
+                        }
+                        return (
+                            true,
+                            Some(RuntimeValue::HtmlElement {
+                                object: node,
+                                property: None,
+                            }),
+                        );
+                    }
+                    current = RefCell::borrow(&node).parent().upgrade();
+                }
+                return (true, Some(RuntimeValue::Null));
+            }
+
+            if p == "removeAttribute" || p == "hasAttribute" || p == "getAttribute" {
+                let name = match self.eval(&arguments[0], env.clone()) {
+                    Some(n) => n.to_string(),
+                    None => return (true, None),
+                };
+                let element = match RefCell::borrow(object).get_element() {
+                    Some(e) => e,
+                    None => return (true, None),
+                };
+                return match p.as_str() {
+                    "removeAttribute" => {
+                        if let DomNodeKind::Element(ref mut e) = object.borrow_mut().kind {
+                            e.remove_attribute(&name);
+                        }
+                        (true, None)
+                    }
+                    "hasAttribute" => (
+                        true,
+                        Some(RuntimeValue::Boolean(element.has_attribute(&name))),
+                    ),
+                    _ => (
+                        true,
+                        element
+                            .get_attribute(&name)
+                            .map(RuntimeValue::StringLiteral),
+                    ),
+                };
+            }
         }
 
-        // NaN: Not a number
-        RuntimeValue::Number(u64::MIN)
+        (false, None)
     }
 }
 
-impl Display for RuntimeValue {
-    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
-        let s = match self {
-            RuntimeValue::Number(value) => format!("{}", value),
-            RuntimeValue::StringLiteral(value) => value.to_string(),
-            RuntimeValue::HtmlElement {
-                object,
-                property: _,
-            } => {
-                format!("HtmlElement: {:#?}", object)
-            }
-        };
-        write!(f, "{}", s)
+// スコープは複製するので、複製したランタイムでスクリプトを実行しても元の変数は変わらない
+// DOMは共有する
+impl Clone for JsRuntime {
+    fn clone(&self) -> Self {
+        Self {
+            dom_root: self.dom_root.clone(),
+            functions: self.functions.clone(),
+            env: Rc::new(RefCell::new(RefCell::borrow(&self.env).deep_clone())),
+            alert_messages: self.alert_messages.clone(),
+            console_messages: self.console_messages.clone(),
+            page_url: self.page_url.clone(),
+            transport: self.transport.clone(),
+            clock: self.clock.clone(),
+            network_errors: self.network_errors.clone(),
+            profiling: self.profiling,
+            profile: self.profile.clone(),
+            call_depth: self.call_depth,
+            max_call_depth: self.max_call_depth,
+            script_errors: self.script_errors.clone(),
+            breakpoints: self.breakpoints.clone(),
+            pause_events: self.pause_events.clone(),
+            paused: self.paused,
+            pending_statements: self.pending_statements.clone(),
+            watches: self.watches.clone(),
+            watch_asts: self.watch_asts.clone(),
+            watch_values: self.watch_values.clone(),
+        }
     }
 }
 
-type VariableMap = Vec<(String, Option<RuntimeValue>)>;
+// JsRuntime::snapshotで保存した変数と関数
 #[derive(Debug, Clone)]
-pub struct Environment {
-    variables: VariableMap,
-    outer: Option<Rc<RefCell<Environment>>>,
+pub struct RuntimeSnapshot {
+    env: Environment,
+    functions: Vec<Function>,
 }
 
-impl Environment {
-    fn new(outer: Option<Rc<RefCell<Environment>>>) -> Self {
-        Self {
-            variables: VariableMap::new(),
-            outer,
-        }
-    }
+// ブレークポイントで一時停止したときの状態
+#[derive(Debug, Clone, PartialEq)]
+pub struct DebugPause {
+    // 評価しようとしていたノード
+    node: Rc<Node>,
+    // その時点で見える変数の値
+    scope: BTreeMap<String, RuntimeValue>,
+}
 
-    pub fn get_variable(&self, name: String) -> Option<RuntimeValue> {
-        for variable in &self.variables {
-            if variable.0 == name {
-                return variable.1.clone();
-            }
-        }
-        if let Some(env) = &self.outer {
-            env.borrow_mut().get_variable(name)
-        } else {
-            None
-        }
+impl DebugPause {
+    pub fn node(&self) -> Rc<Node> {
+        self.node.clone()
     }
 
-    fn add_variable(&mut self, name: String, value: Option<RuntimeValue>) {
-        self.variables.push((name, value));
+    pub fn scope(&self) -> &BTreeMap<String, RuntimeValue> {
+        &self.scope
     }
+}
 
-    fn update_variable(&mut self, name: String, value: Option<RuntimeValue>) {
-        for i in 0..self.variables.len() {
-            if self.variables[i].0 == name {
-                self.variables.remove(i);
-                self.variables.push((name, value));
-                return;
-            }
+impl Display for DebugPause {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        write!(
+            f,
+            "paused at {} ({})",
+            self.node.kind_name(),
+            self.node.location()
+        )?;
+        for (name, value) in &self.scope {
+            write!(f, " {}={}", name, value)?;
         }
+        Ok(())
     }
 }
 
+// スクリプトの実行中に起きたエラー
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Function {
-    id: String,
+pub struct JsError {
+    message: String,
+    // エラーが起きた式の、ソースコード上の位置
+    location: SourceLocation,
+}
+
+impl JsError {
+    pub fn new(message: String, location: SourceLocation) -> Self {
+        Self { message, location }
+    }
+
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+
+    pub fn location(&self) -> SourceLocation {
+        self.location
+    }
+}
+
+impl Display for JsError {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        write!(f, "{} ({})", self.message, self.location)
+    }
+}
+
+// JavaScriptの数値
+// f64は全順序を持たないため、NaNを他のどの値よりも大きいとして比較する
+// JavaScriptの比較演算子はNaNを比較できないものとして扱うので、js_compareを使う
+#[derive(Debug, Clone, Copy)]
+pub struct JsNumber(f64);
+
+impl JsNumber {
+    pub fn new(value: f64) -> Self {
+        Self(value)
+    }
+
+    pub fn nan() -> Self {
+        Self(f64::NAN)
+    }
+
+    pub fn value(&self) -> f64 {
+        self.0
+    }
+
+    pub fn is_nan(&self) -> bool {
+        self.0.is_nan()
+    }
+}
+
+impl From<u64> for JsNumber {
+    fn from(value: u64) -> Self {
+        Self(value as f64)
+    }
+}
+
+impl PartialEq for JsNumber {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for JsNumber {}
+
+impl PartialOrd for JsNumber {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for JsNumber {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.is_nan(), other.is_nan()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            // NaNでなければ比較できる。0と-0は等しい
+            (false, false) => self.0.partial_cmp(&other.0).unwrap_or(Ordering::Equal),
+        }
+    }
+}
+
+impl Add for JsNumber {
+    type Output = JsNumber;
+
+    fn add(self, rhs: JsNumber) -> JsNumber {
+        JsNumber(self.0 + rhs.0)
+    }
+}
+
+impl Sub for JsNumber {
+    type Output = JsNumber;
+
+    fn sub(self, rhs: JsNumber) -> JsNumber {
+        JsNumber(self.0 - rhs.0)
+    }
+}
+
+impl Display for JsNumber {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        if self.0 == f64::INFINITY {
+            write!(f, "Infinity")
+        } else if self.0 == f64::NEG_INFINITY {
+            write!(f, "-Infinity")
+        } else if self.0 == 0.0 {
+            // -0も0と表示する
+            write!(f, "0")
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeValue {
+    Number(JsNumber),
+    StringLiteral(String),
+    Boolean(bool),
+    Null,
+    HtmlElement {
+        object: Rc<RefCell<DomNode>>,
+        property: Option<String>,
+    },
+    // 子ノードの並び。template.contentと共有し、appendChildで移動すると空になる
+    DocumentFragment(FragmentNodes),
+    DomNode(Rc<RefCell<DomNode>>),
+}
+
+impl Add<RuntimeValue> for RuntimeValue {
+    type Output = RuntimeValue;
+
+    fn add(self, rhs: RuntimeValue) -> RuntimeValue {
+        if let (RuntimeValue::Number(left_num), RuntimeValue::Number(right_num)) = (&self, &rhs) {
+            return RuntimeValue::Number(*left_num + *right_num);
+        }
+
+        RuntimeValue::StringLiteral(self.to_string() + &rhs.to_string())
+    }
+}
+
+impl Sub<RuntimeValue> for RuntimeValue {
+    type Output = RuntimeValue;
+
+    fn sub(self, rhs: RuntimeValue) -> RuntimeValue {
+        if let (RuntimeValue::Number(left_num), RuntimeValue::Number(right_num)) = (&self, &rhs) {
+            return RuntimeValue::Number(*left_num - *right_num);
+        }
+
+        // NaN: Not a number
+        RuntimeValue::Number(JsNumber::nan())
+    }
+}
+
+impl RuntimeValue {
+    // 比較演算子のために数値に変換する
+    // 数値として読めない文字列やDOMのオブジェクトはNaNになる
+    fn to_number(&self) -> JsNumber {
+        match self {
+            RuntimeValue::Number(n) => *n,
+            RuntimeValue::Boolean(b) => JsNumber::from(u64::from(*b)),
+            RuntimeValue::Null => JsNumber::from(0),
+            RuntimeValue::StringLiteral(s) => {
+                let s = s.trim();
+                if s.is_empty() {
+                    return JsNumber::from(0);
+                }
+                s.parse::<f64>()
+                    .map(JsNumber::new)
+                    .unwrap_or(JsNumber::nan())
+            }
+            _ => JsNumber::nan(),
+        }
+    }
+}
+
+// JavaScriptの比較演算子と同じように、文字列どうしは辞書順で比べ、それ以外は数値に変換して比べる
+// どちらかがNaNになる場合は比較できないのでNoneを返す
+pub fn js_compare(left: &RuntimeValue, right: &RuntimeValue) -> Option<Ordering> {
+    if let (RuntimeValue::StringLiteral(left), RuntimeValue::StringLiteral(right)) = (left, right) {
+        return Some(left.cmp(right));
+    }
+
+    left.to_number()
+        .value()
+        .partial_cmp(&right.to_number().value())
+}
+
+// ブラウザの開発者ツールのコンソールと同じように、要素は開始タグで表す
+impl Display for RuntimeValue {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        match self {
+            RuntimeValue::Number(value) => write!(f, "{}", value),
+            RuntimeValue::StringLiteral(value) => write!(f, "{}", value),
+            RuntimeValue::Boolean(value) => write!(f, "{}", value),
+            RuntimeValue::Null => write!(f, "null"),
+            RuntimeValue::HtmlElement {
+                object,
+                property: None,
+            } => write!(f, "{}", describe_node(object)),
+            RuntimeValue::HtmlElement {
+                object,
+                property: Some(property),
+            } => write!(f, "{}.{}", describe_node(object), property),
+            RuntimeValue::DocumentFragment(_) => write!(f, "#document-fragment"),
+            RuntimeValue::DomNode(node) => write!(f, "{}", describe_node(node)),
+        }
+    }
+}
+
+fn describe_node(node: &Rc<RefCell<DomNode>>) -> String {
+    match RefCell::borrow(node).kind() {
+        DomNodeKind::Document => "#document".to_string(),
+        DomNodeKind::Element(element) => start_tag(&element),
+        DomNodeKind::Text(text) => text,
+    }
+}
+
+// undefined、null、0、NaN、空文字列、falseはfalseとして扱う
+fn is_truthy(value: &Option<RuntimeValue>) -> bool {
+    match value {
+        None => false,
+        Some(RuntimeValue::Number(n)) => !n.is_nan() && n.value() != 0.0,
+        Some(RuntimeValue::StringLiteral(s)) => !s.is_empty(),
+        Some(RuntimeValue::Boolean(b)) => *b,
+        Some(RuntimeValue::Null) => false,
+        Some(_) => true,
+    }
+}
+
+type VariableMap = Vec<(String, Option<RuntimeValue>)>;
+#[derive(Debug, Clone)]
+pub struct Environment {
+    variables: VariableMap,
+    outer: Option<Rc<RefCell<Environment>>>,
+    // ブロックのスコープの場合はtrue。varの宣言はブロックを越えて関数のスコープに追加する
+    is_block: bool,
+}
+
+impl Environment {
+    fn new(outer: Option<Rc<RefCell<Environment>>>) -> Self {
+        Self {
+            variables: VariableMap::new(),
+            outer,
+            is_block: false,
+        }
+    }
+
+    fn new_block(outer: Rc<RefCell<Environment>>) -> Self {
+        Self {
+            variables: VariableMap::new(),
+            outer: Some(outer),
+            is_block: true,
+        }
+    }
+
+    // 外側のスコープも含めて複製する
+    fn deep_clone(&self) -> Self {
+        Self {
+            variables: self.variables.clone(),
+            outer: self
+                .outer
+                .as_ref()
+                .map(|outer| Rc::new(RefCell::new(RefCell::borrow(outer).deep_clone()))),
+            is_block: self.is_block,
+        }
+    }
+
+    // envを含む、最も内側の関数(またはグローバル)のスコープ
+    fn function_scope(env: Rc<RefCell<Environment>>) -> Rc<RefCell<Environment>> {
+        let mut current = env;
+        loop {
+            let outer = {
+                let env = RefCell::borrow(&current);
+                match &env.outer {
+                    Some(outer) if env.is_block => outer.clone(),
+                    _ => break,
+                }
+            };
+            current = outer;
+        }
+        current
+    }
+
+    pub fn get_variable(&self, name: String) -> Option<RuntimeValue> {
+        for variable in &self.variables {
+            if variable.0 == name {
+                return variable.1.clone();
+            }
+        }
+        if let Some(env) = &self.outer {
+            env.borrow_mut().get_variable(name)
+        } else {
+            None
+        }
+    }
+
+    // 外側のスコープまで含めて、見えている変数の値。内側の変数が外側の同じ名前の変数を隠す
+    fn visible_variables(&self) -> BTreeMap<String, RuntimeValue> {
+        let mut variables = match &self.outer {
+            Some(outer) => RefCell::borrow(outer).visible_variables(),
+            None => BTreeMap::new(),
+        };
+        for (name, value) in &self.variables {
+            match value {
+                Some(value) => {
+                    variables.insert(name.clone(), value.clone());
+                }
+                None => {
+                    variables.remove(name);
+                }
+            }
+        }
+        variables
+    }
+
+    fn add_variable(&mut self, name: String, value: Option<RuntimeValue>) {
+        self.variables.push((name, value));
+    }
+
+    // 変数が宣言されている、最も内側のスコープの値を更新する
+    fn update_variable(&mut self, name: String, value: Option<RuntimeValue>) {
+        for i in 0..self.variables.len() {
+            if self.variables[i].0 == name {
+                self.variables.remove(i);
+                self.variables.push((name, value));
+                return;
+            }
+        }
+        if let Some(env) = &self.outer {
+            env.borrow_mut().update_variable(name, value);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    id: String,
     params: Vec<Option<Rc<Node>>>,
     body: Option<Rc<Node>>,
 }
@@ -348,9 +1335,15 @@ impl Function {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::http::HttpResponse;
+    use crate::renderer::dom::node::ElementKind;
+    use crate::renderer::dom::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
     use crate::renderer::js::ast::JsParser;
     use crate::renderer::js::token::JsLexer;
+    use crate::test_support::StubTransport;
     use alloc::string::ToString;
+    use core::cell::Cell;
 
     #[test]
     fn test_num() {
@@ -360,7 +1353,7 @@ mod tests {
         let ast = parser.parse_ast();
         let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
         let mut runtime = JsRuntime::new(dom);
-        let expected = [Some(RuntimeValue::Number(42))];
+        let expected = [Some(RuntimeValue::Number(JsNumber::from(42)))];
 
         let mut i = 0;
         for node in ast.body() {
@@ -378,7 +1371,7 @@ mod tests {
         let ast = parser.parse_ast();
         let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
         let mut runtime = JsRuntime::new(dom);
-        let expected = [Some(RuntimeValue::Number(3))];
+        let expected = [Some(RuntimeValue::Number(JsNumber::from(3)))];
 
         let mut i = 0;
         for node in ast.body() {
@@ -396,7 +1389,7 @@ mod tests {
         let ast = parser.parse_ast();
         let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
         let mut runtime = JsRuntime::new(dom);
-        let expected = [Some(RuntimeValue::Number(1))];
+        let expected = [Some(RuntimeValue::Number(JsNumber::from(1)))];
 
         let mut i = 0;
         for node in ast.body() {
@@ -432,7 +1425,7 @@ mod tests {
         let ast = parser.parse_ast();
         let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
         let mut runtime = JsRuntime::new(dom);
-        let expected = [None, Some(RuntimeValue::Number(43))];
+        let expected = [None, Some(RuntimeValue::Number(JsNumber::from(43)))];
 
         let mut i = 0;
         for node in ast.body() {
@@ -450,7 +1443,7 @@ mod tests {
         let ast = parser.parse_ast();
         let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
         let mut runtime = JsRuntime::new(dom);
-        let expected = [None, None, Some(RuntimeValue::Number(1))];
+        let expected = [None, None, Some(RuntimeValue::Number(JsNumber::from(1)))];
 
         let mut i = 0;
         for node in ast.body() {
@@ -468,7 +1461,7 @@ mod tests {
         let ast = parser.parse_ast();
         let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
         let mut runtime = JsRuntime::new(dom);
-        let expected = [None, Some(RuntimeValue::Number(43))];
+        let expected = [None, Some(RuntimeValue::Number(JsNumber::from(43)))];
 
         let mut i = 0;
         for node in ast.body() {
@@ -486,7 +1479,7 @@ mod tests {
         let ast = parser.parse_ast();
         let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
         let mut runtime = JsRuntime::new(dom);
-        let expected = [None, Some(RuntimeValue::Number(6))];
+        let expected = [None, Some(RuntimeValue::Number(JsNumber::from(6)))];
 
         let mut i = 0;
         for node in ast.body() {
@@ -504,7 +1497,7 @@ mod tests {
         let ast = parser.parse_ast();
         let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
         let mut runtime = JsRuntime::new(dom);
-        let expected = [None, None, Some(RuntimeValue::Number(43))];
+        let expected = [None, None, Some(RuntimeValue::Number(JsNumber::from(43)))];
 
         let mut i = 0;
         for node in ast.body() {
@@ -513,4 +1506,1187 @@ mod tests {
             i += 1;
         }
     }
+
+    fn number(value: f64) -> RuntimeValue {
+        RuntimeValue::Number(JsNumber::new(value))
+    }
+
+    fn string(value: &str) -> RuntimeValue {
+        RuntimeValue::StringLiteral(value.to_string())
+    }
+
+    #[test]
+    fn test_js_number_order() {
+        assert!(JsNumber::from(1) < JsNumber::from(2));
+        assert!(JsNumber::new(-1.5) < JsNumber::from(0));
+        assert_eq!(JsNumber::new(0.0), JsNumber::new(-0.0));
+        // 並べ替えのための全順序では、NaNは他のどの値よりも大きく、NaNどうしは等しい
+        assert!(JsNumber::nan() > JsNumber::new(f64::INFINITY));
+        assert_eq!(JsNumber::nan(), JsNumber::nan());
+        let mut numbers = [JsNumber::nan(), JsNumber::from(3), JsNumber::new(-2.0)].to_vec();
+        numbers.sort();
+        assert_eq!(
+            [JsNumber::new(-2.0), JsNumber::from(3), JsNumber::nan()].to_vec(),
+            numbers
+        );
+    }
+
+    #[test]
+    fn test_display_js_number() {
+        assert_eq!("42", JsNumber::from(42).to_string());
+        assert_eq!("-1.5", JsNumber::new(-1.5).to_string());
+        assert_eq!("0", JsNumber::new(-0.0).to_string());
+        assert_eq!("NaN", JsNumber::nan().to_string());
+        assert_eq!("Infinity", JsNumber::new(f64::INFINITY).to_string());
+    }
+
+    #[test]
+    fn test_compare_numbers() {
+        assert_eq!(Some(Ordering::Less), js_compare(&number(1.0), &number(2.0)));
+        assert_eq!(
+            Some(Ordering::Greater),
+            js_compare(&number(2.5), &number(2.0))
+        );
+        assert_eq!(
+            Some(Ordering::Equal),
+            js_compare(&number(0.0), &number(-0.0))
+        );
+        // NaNはどの値とも比較できない
+        assert_eq!(
+            None,
+            js_compare(&RuntimeValue::Number(JsNumber::nan()), &number(1.0))
+        );
+        assert_eq!(
+            None,
+            js_compare(
+                &RuntimeValue::Number(JsNumber::nan()),
+                &RuntimeValue::Number(JsNumber::nan())
+            )
+        );
+    }
+
+    #[test]
+    fn test_compare_strings() {
+        // 文字列どうしは数値に変換せず、辞書順で比べる
+        assert_eq!(
+            Some(Ordering::Less),
+            js_compare(&string("apple"), &string("banana"))
+        );
+        assert_eq!(
+            Some(Ordering::Less),
+            js_compare(&string("10"), &string("9"))
+        );
+        assert_eq!(
+            Some(Ordering::Equal),
+            js_compare(&string("a"), &string("a"))
+        );
+    }
+
+    #[test]
+    fn test_compare_number_and_string() {
+        // 片方が数値なら、文字列を数値に変換して比べる
+        assert_eq!(
+            Some(Ordering::Less),
+            js_compare(&number(9.0), &string("10"))
+        );
+        assert_eq!(
+            Some(Ordering::Greater),
+            js_compare(&string("10"), &number(9.0))
+        );
+        assert_eq!(
+            Some(Ordering::Equal),
+            js_compare(&number(1.0), &string(" 1 "))
+        );
+        // 空文字列は0になる
+        assert_eq!(Some(Ordering::Equal), js_compare(&string(""), &number(0.0)));
+        // 数値として読めない文字列はNaNになり、比較できない
+        assert_eq!(None, js_compare(&string("abc"), &number(100.0)));
+        // 真偽値は1と0になる
+        assert_eq!(
+            Some(Ordering::Greater),
+            js_compare(&RuntimeValue::Boolean(true), &number(0.5))
+        );
+    }
+
+    #[test]
+    fn test_compare_dom_node() {
+        let html = "<html><head></head><body><p>hello</p></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let node = RuntimeValue::DomNode(RefCell::borrow(&window).document());
+        assert_eq!(None, js_compare(&node, &number(1.0)));
+        assert_eq!(None, js_compare(&node, &string("abc")));
+    }
+
+    #[test]
+    fn test_relational_expression() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+        run(
+            &mut runtime,
+            r#"var a = 1;
+alert(a < 2);
+alert(a > 2);
+alert("10" < "9");
+alert(9 < "10");
+alert("abc" < 1);
+alert("abc" > 1);
+alert(a + 1 > 1 ? "yes" : "no");"#,
+        );
+
+        assert_eq!(
+            ["true", "false", "true", "true", "false", "false", "yes"].to_vec(),
+            runtime.alert_messages()
+        );
+    }
+
+    #[test]
+    fn test_subtract_below_zero() {
+        assert_eq!(number(-1.0), number(1.0) - number(2.0));
+    }
+
+    #[test]
+    fn test_subtract_non_number_is_nan() {
+        let result = RuntimeValue::StringLiteral("a".to_string()) - number(1.0);
+        assert_eq!("NaN", result.to_string());
+        assert_eq!(RuntimeValue::Number(JsNumber::nan()), result);
+    }
+
+    #[test]
+    fn test_display_runtime_value() {
+        let html = r#"<html><head></head><body><p id="target" class="a">text</p></body></html>"#
+            .to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = RefCell::borrow(&window).document();
+        let p = find_element_by_id(&dom, "target").expect("p should exist");
+        let text = RefCell::borrow(&p)
+            .first_child()
+            .expect("text should exist");
+
+        assert_eq!("42", RuntimeValue::Number(JsNumber::from(42)).to_string());
+        assert_eq!(
+            "hello",
+            RuntimeValue::StringLiteral("hello".to_string()).to_string()
+        );
+        assert_eq!(
+            r#"<p id="target" class="a">"#,
+            RuntimeValue::HtmlElement {
+                object: p.clone(),
+                property: None,
+            }
+            .to_string()
+        );
+        assert_eq!(
+            r#"<p id="target" class="a">.textContent"#,
+            RuntimeValue::HtmlElement {
+                object: p.clone(),
+                property: Some("textContent".to_string()),
+            }
+            .to_string()
+        );
+        assert_eq!(
+            "#document-fragment",
+            RuntimeValue::DocumentFragment(Rc::new(RefCell::new([p.clone()].to_vec()))).to_string()
+        );
+        assert_eq!(
+            "<p id=\"target\" class=\"a\">",
+            RuntimeValue::DomNode(p).to_string()
+        );
+        assert_eq!("text", RuntimeValue::DomNode(text).to_string());
+        assert_eq!("#document", RuntimeValue::DomNode(dom).to_string());
+    }
+
+    #[test]
+    fn test_console_log() {
+        let html = r#"<html><head></head><body><p id="target">text</p></body></html>"#.to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = RefCell::borrow(&window).document();
+
+        let input = r#"console.log("sum", 1 + 2)
+console.log(document.getElementById("target"))
+console.log()"#
+            .to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let mut runtime = JsRuntime::new(dom);
+        runtime.execute(&ast);
+
+        assert_eq!(
+            [
+                "sum 3".to_string(),
+                r#"<p id="target">"#.to_string(),
+                "".to_string()
+            ]
+            .to_vec(),
+            runtime.console_messages()
+        );
+        assert!(runtime.alert_messages().is_empty());
+    }
+
+    fn run(runtime: &mut JsRuntime, input: &str) {
+        let lexer = JsLexer::new(input.to_string());
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        runtime.execute(&ast);
+    }
+
+    #[test]
+    fn test_element_matches() {
+        let html = r#"<html><head></head><body><input id="target" class="active" type="text" disabled></body></html>"#
+            .to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = RefCell::borrow(&window).document();
+        let mut runtime = JsRuntime::new(dom);
+        let selectors = [
+            // 型セレクタ
+            "input",
+            "p",
+            // クラスセレクタ
+            ".active",
+            ".inactive",
+            // IDセレクタ
+            "#target",
+            "#other",
+            // 属性セレクタ
+            "[disabled]",
+            "[type=text]",
+            "[type='text']",
+            "[type=submit]",
+            "[checked]",
+            // パースできないセレクタ
+            "",
+            ".",
+            "[type=",
+            "input input",
+        ];
+        let mut script = "var e = document.getElementById(\"target\");".to_string();
+        for selector in selectors {
+            script.push_str(&format!("alert(e.matches(\"{}\"));", selector));
+        }
+        run(&mut runtime, &script);
+
+        assert_eq!(
+            [
+                "true", "false", "true", "false", "true", "false", "true", "true", "true", "false",
+                "false", "false", "false", "false", "false",
+            ]
+            .to_vec(),
+            runtime.alert_messages()
+        );
+        assert_eq!(
+            Ok(Some(RuntimeValue::Boolean(true))),
+            runtime.eval_expression("e.matches(\"input\")")
+        );
+    }
+
+    #[test]
+    fn test_boolean_values() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+        run(
+            &mut runtime,
+            r#"var a = true; var b = false;
+alert(a ? 1 : 2);
+alert(b ? 1 : 2);
+alert("false" ? 1 : 2);
+alert(b);"#,
+        );
+
+        // 文字列の"false"は空でないので真になる
+        assert_eq!(["1", "2", "1", "false"].to_vec(), runtime.alert_messages());
+        assert_eq!(
+            Ok(Some(RuntimeValue::Boolean(false))),
+            runtime.eval_expression("b")
+        );
+    }
+
+    #[test]
+    fn test_details_open_reflects_truthiness() {
+        let html = r#"<html><head></head><body><details id="d"><summary>s</summary></details></body></html>"#
+            .to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = RefCell::borrow(&window).document();
+        let mut runtime = JsRuntime::new(dom);
+        run(&mut runtime, "var d = document.getElementById(\"d\");");
+
+        let open_after = |runtime: &mut JsRuntime, script: &str| {
+            run(runtime, script);
+            runtime.eval_expression("d.hasAttribute(\"open\")")
+        };
+        assert_eq!(
+            Ok(Some(RuntimeValue::Boolean(true))),
+            open_after(&mut runtime, "d.open = true;")
+        );
+        assert_eq!(
+            Ok(Some(RuntimeValue::Boolean(false))),
+            open_after(&mut runtime, "d.open = false;")
+        );
+        // 文字列の"false"は真なので、属性が追加される
+        assert_eq!(
+            Ok(Some(RuntimeValue::Boolean(true))),
+            open_after(&mut runtime, "d.open = \"false\";")
+        );
+        assert_eq!(
+            Ok(Some(RuntimeValue::Boolean(false))),
+            open_after(&mut runtime, "d.open = 0;")
+        );
+    }
+
+    #[test]
+    fn test_element_closest() {
+        let html = r#"<html><head></head><body><div id="outer" class="container"><div id="middle" class="container"><p id="inner" class="item"><b id="leaf">text</b></p></div></div></body></html>"#
+            .to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = RefCell::borrow(&window).document();
+        let mut runtime = JsRuntime::new(dom);
+        run(
+            &mut runtime,
+            r##"var leaf = document.getElementById("leaf");
+            alert(leaf.closest(".container").getAttribute("id"));
+            alert(leaf.closest("#outer").getAttribute("id"));
+            alert(leaf.closest("p").getAttribute("id"));
+            alert(leaf.closest("b").getAttribute("id"));
+            alert(leaf.closest(".missing"));
+            alert(leaf.closest("[type="));"##,
+        );
+
+        assert_eq!(
+            // 最も近い祖先と、要素自身も対象になる
+            ["middle", "outer", "inner", "leaf", "null", "null"].to_vec(),
+            runtime.alert_messages()
+        );
+        assert_eq!(
+            Ok(Some(RuntimeValue::Null)),
+            runtime.eval_expression("leaf.closest(\".missing\")")
+        );
+        assert_eq!(
+            Ok(Some(RuntimeValue::Null)),
+            runtime.eval_expression("null")
+        );
+    }
+
+    #[test]
+    fn test_snapshot_and_restore() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+        run(&mut runtime, "var a = 1; function f() { return 10; }");
+
+        let snapshot = runtime.snapshot();
+        run(
+            &mut runtime,
+            "a = 2; var b = 3; function f() { return 20; } function g() { return 30; }",
+        );
+        assert_eq!(
+            Ok(Some(RuntimeValue::Number(JsNumber::from(2)))),
+            runtime.eval_expression("a")
+        );
+
+        runtime.restore(snapshot);
+        assert_eq!(
+            Ok(Some(RuntimeValue::Number(JsNumber::from(1)))),
+            runtime.eval_expression("a")
+        );
+        assert_eq!(
+            Ok(Some(RuntimeValue::Number(JsNumber::from(10)))),
+            runtime.eval_expression("f()")
+        );
+        // 保存した後に宣言した変数と関数はなくなる
+        assert_eq!(
+            Ok(Some(RuntimeValue::StringLiteral("b".to_string()))),
+            runtime.eval_expression("b")
+        );
+        assert_eq!(
+            "ReferenceError: g is not defined",
+            runtime.eval_expression("g()").unwrap_err().message()
+        );
+    }
+
+    #[test]
+    fn test_run_script_in_cloned_runtime() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+        run(&mut runtime, "var a = 1");
+
+        let mut sandbox = runtime.clone();
+        run(&mut sandbox, "a = 5; var c = 6");
+        assert_eq!(
+            Ok(Some(RuntimeValue::Number(JsNumber::from(5)))),
+            sandbox.eval_expression("a")
+        );
+
+        // 複製したランタイムでの変更は元のランタイムに影響しない
+        assert_eq!(
+            Ok(Some(RuntimeValue::Number(JsNumber::from(1)))),
+            runtime.eval_expression("a")
+        );
+        assert_eq!(
+            Ok(Some(RuntimeValue::StringLiteral("c".to_string()))),
+            runtime.eval_expression("c")
+        );
+    }
+
+    #[test]
+    fn test_import_template_content() {
+        let html = r#"<html><head></head><body><template id="tpl"><p>cloned</p></template><p id="target"></p></body></html>"#.to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = RefCell::borrow(&window).document();
+
+        let input = r#"var tpl = document.getElementById("tpl");
+var fragment = document.importNode(tpl.content, true);
+var target = document.getElementById("target");
+target.appendChild(fragment);"#
+            .to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let mut runtime = JsRuntime::new(dom.clone());
+        runtime.execute(&ast);
+
+        let target = find_element_by_id(&dom, "target").expect("target should exist");
+        let p = RefCell::borrow(&target)
+            .first_child()
+            .expect("cloned node should be inserted");
+        assert_eq!(Some(ElementKind::P), RefCell::borrow(&p).element_kind());
+        let text = RefCell::borrow(&p)
+            .first_child()
+            .expect("text should be cloned");
+        assert_eq!(
+            DomNodeKind::Text("cloned".to_string()),
+            RefCell::borrow(&text).kind()
+        );
+
+        // 元のtemplateの中身はそのまま残る
+        let tpl = find_element_by_id(&dom, "tpl").expect("tpl should exist");
+        let content = RefCell::borrow(&tpl)
+            .get_element()
+            .and_then(|e| e.content())
+            .expect("template should have content");
+        assert_eq!(1, RefCell::borrow(&content).len());
+        assert!(!Rc::ptr_eq(&RefCell::borrow(&content)[0], &p));
+    }
+
+    #[test]
+    fn test_append_child_twice() {
+        let html = r#"<html><head></head><body><template id="tpl"><p id="pa">a</p><p>b</p></template><div id="target"></div></body></html>"#.to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = RefCell::borrow(&window).document();
+
+        let input = r#"var tpl = document.getElementById("tpl");
+var target = document.getElementById("target");
+var fragment = tpl.content;
+target.appendChild(fragment);
+target.appendChild(fragment);
+var p = document.getElementById("pa");
+target.appendChild(p);
+target.appendChild(p);"#
+            .to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let mut runtime = JsRuntime::new(dom.clone());
+        runtime.execute(&ast);
+
+        // フラグメントの子は1回目で移動し、2回目には何も追加されない
+        let target = find_element_by_id(&dom, "target").expect("target should exist");
+        let mut texts = Vec::new();
+        let mut child = RefCell::borrow(&target).first_child();
+        while let Some(c) = child {
+            let text = RefCell::borrow(&c)
+                .first_child()
+                .expect("text should exist");
+            texts.push(RefCell::borrow(&text).kind());
+            child = RefCell::borrow(&c).next_sibling();
+        }
+        assert_eq!(
+            [
+                DomNodeKind::Text("b".to_string()),
+                DomNodeKind::Text("a".to_string())
+            ]
+            .to_vec(),
+            texts
+        );
+
+        let tpl = find_element_by_id(&dom, "tpl").expect("tpl should exist");
+        let content = RefCell::borrow(&tpl)
+            .get_element()
+            .and_then(|e| e.content())
+            .expect("template should have content");
+        assert!(RefCell::borrow(&content).is_empty());
+    }
+
+    #[test]
+    fn test_computed_member_with_variable() {
+        let html = r#"<html><head></head><body><p id="target">text</p></body></html>"#.to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = RefCell::borrow(&window).document();
+        let mut runtime = JsRuntime::new(dom);
+        run(
+            &mut runtime,
+            r#"var p = document.getElementById("target"); var key = "outerHTML"; var outerHTML = "id";"#,
+        );
+
+        // `p[key]`はkeyの値をプロパティ名にする
+        assert_eq!(
+            Ok(Some(RuntimeValue::StringLiteral(
+                r#"<p id="target">text</p>"#.to_string()
+            ))),
+            runtime.eval_expression("p[key]")
+        );
+        // `p.outerHTML`は、同じ名前の変数があっても名前のまま使う
+        assert_eq!(
+            runtime.eval_expression("p[key]"),
+            runtime.eval_expression("p.outerHTML")
+        );
+    }
+
+    #[test]
+    fn test_assign_to_chained_member() {
+        let html =
+            r#"<html><head></head><body><p id="target">original</p></body></html>"#.to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = RefCell::borrow(&window).document();
+
+        let input = r#"document.getElementById("target").textContent = "changed";"#.to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let mut runtime = JsRuntime::new(dom.clone());
+        runtime.execute(&ast);
+
+        let target = find_element_by_id(&dom, "target").expect("target should exist");
+        let text = RefCell::borrow(&target)
+            .first_child()
+            .expect("text should exist");
+        assert_eq!(
+            DomNodeKind::Text("changed".to_string()),
+            RefCell::borrow(&text).kind()
+        );
+    }
+
+    #[test]
+    fn test_get_svg_element() {
+        let html = r#"<html><head></head><body><svg><circle id="c" r="5"/></svg></body></html>"#
+            .to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = RefCell::borrow(&window).document();
+
+        let input = r#"document.getElementById("c")"#.to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let mut runtime = JsRuntime::new(dom.clone());
+
+        let circle = find_element_by_id(&dom, "c").expect("circle should exist");
+        for node in ast.body() {
+            let result = runtime.eval(&Some(node.clone()), runtime.env.clone());
+            assert_eq!(Some(RuntimeValue::DomNode(circle.clone())), result);
+        }
+    }
+
+    #[test]
+    fn test_execute_returns_last_value() {
+        let input =
+            r#"var a = 1; function add(x) { return x + 2; } alert(a); add(a) + 3"#.to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+
+        assert_eq!(
+            Some(RuntimeValue::Number(JsNumber::from(6))),
+            runtime.execute(&ast)
+        );
+        // 途中の文の副作用も残る
+        assert_eq!(["1".to_string()].to_vec(), runtime.alert_messages());
+    }
+
+    #[test]
+    fn test_return_without_argument() {
+        let input = r#"function foo() { return; } foo()"#.to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+
+        // 値のない`return`はundefinedになる
+        assert_eq!(None, runtime.execute(&ast));
+    }
+
+    #[test]
+    fn test_let_is_block_scoped_but_var_is_not() {
+        let input = r#"if (1) { let a = 1; const c = 3; var b = 2; alert(a + c); }"#.to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+        runtime.execute(&ast);
+
+        // ブロックの中ではletとconstが見える
+        assert_eq!(["4".to_string()].to_vec(), runtime.alert_messages());
+        let env = RefCell::borrow(&runtime.env);
+        assert_eq!(None, env.get_variable("a".to_string()));
+        assert_eq!(None, env.get_variable("c".to_string()));
+        assert_eq!(
+            Some(RuntimeValue::Number(JsNumber::from(2))),
+            env.get_variable("b".to_string())
+        );
+    }
+
+    #[test]
+    fn test_var_in_block_hoists_to_function_scope() {
+        let input = r#"function f() { if (1) { var x = 4; let y = 5; } x = x + 1; return x; } var r = f();"#
+            .to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+        runtime.execute(&ast);
+
+        let env = RefCell::borrow(&runtime.env);
+        assert_eq!(
+            Some(RuntimeValue::Number(JsNumber::from(5))),
+            env.get_variable("r".to_string())
+        );
+        // 関数の中のvarは、関数の外からは見えない
+        assert_eq!(None, env.get_variable("x".to_string()));
+    }
+
+    #[test]
+    fn test_let_shadows_outer_variable() {
+        let input = r#"let a = 1; if (1) { let a = 2; alert(a); } else { alert(0); } alert(a);"#
+            .to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+        runtime.execute(&ast);
+
+        assert_eq!(
+            ["2".to_string(), "1".to_string()].to_vec(),
+            runtime.alert_messages()
+        );
+    }
+
+    #[test]
+    fn test_unbounded_recursion_stops_at_depth_limit() {
+        let input = r#"function count(n) { alert(n); return count(n + 1); } count(1)"#.to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+        runtime.set_max_call_depth(5);
+
+        assert_eq!(None, runtime.execute(&ast));
+        assert_eq!(
+            ["1", "2", "3", "4", "5"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>(),
+            runtime.alert_messages()
+        );
+        assert_eq!(
+            [JsError::new(
+                "RangeError: maximum call stack size exceeded in count".to_string(),
+                SourceLocation::new(1, 38),
+            )]
+            .to_vec(),
+            runtime.script_errors()
+        );
+    }
+
+    #[test]
+    fn test_default_call_depth_limit() {
+        let input = r#"function f() { return f(); } f()"#.to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+
+        assert_eq!(None, runtime.execute(&ast));
+        assert_eq!(1, runtime.script_errors().len());
+    }
+
+    #[test]
+    fn test_script_error_location() {
+        let input = "function f() {\n  return f();\n}\nf()".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+        runtime.set_max_call_depth(3);
+
+        assert_eq!(None, runtime.execute(&ast));
+        let errors = runtime.script_errors();
+        assert_eq!(1, errors.len());
+        // 上限を超えたのは、関数の中の再帰呼び出し
+        assert_eq!(SourceLocation::new(2, 10), errors[0].location());
+        assert_eq!(
+            "RangeError: maximum call stack size exceeded in f (line 2, column 10)",
+            errors[0].to_string()
+        );
+    }
+
+    #[test]
+    fn test_execute_empty_program() {
+        let lexer = JsLexer::new("".to_string());
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+
+        assert_eq!(None, runtime.execute(&ast));
+    }
+
+    #[test]
+    fn test_profile_report() {
+        let input = "var a = 1 + 2; a + 3 + 4".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+        runtime.set_profiling(true);
+        runtime.execute(&ast);
+
+        let report = runtime.profile_report();
+        assert_eq!(Some(&4), report.get("NumericLiteral"));
+        assert_eq!(Some(&3), report.get("AdditiveExpression"));
+        assert_eq!(Some(&1), report.get("Identifier"));
+        assert_eq!(None, report.get("CallExpression"));
+    }
+
+    #[test]
+    fn test_profiling_is_disabled_by_default() {
+        let lexer = JsLexer::new("1 + 2".to_string());
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+        runtime.execute(&ast);
+
+        assert!(runtime.profile_report().is_empty());
+    }
+
+    #[test]
+    fn test_alert() {
+        let input = r#"alert("hello"); var a=1; alert(a + 2);"#.to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+        runtime.execute(&ast);
+
+        assert_eq!(
+            ["hello".to_string(), "3".to_string()].to_vec(),
+            runtime.alert_messages()
+        );
+    }
+
+    #[test]
+    fn test_conditional_expression() {
+        let input = r#"var a = 0; var b = "x";
+alert(a ? "yes" : "no");
+alert(b ? a ? 1 : 2 : 3);
+alert(a ? 1 : b ? 2 : 3);
+a = 1 ? 10 : 20;
+alert(a + 1);"#
+            .to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+        runtime.execute(&ast);
+
+        assert_eq!(
+            [
+                "no".to_string(),
+                "2".to_string(),
+                "2".to_string(),
+                "11".to_string()
+            ]
+            .to_vec(),
+            runtime.alert_messages()
+        );
+    }
+
+    // 呼ばれるたびに10ミリ秒進む時計
+    #[derive(Debug)]
+    struct StubClock {
+        now: Cell<u64>,
+    }
+
+    impl Clock for StubClock {
+        fn now_millis(&self) -> u64 {
+            let now = self.now.get();
+            self.now.set(now + 10);
+            now
+        }
+    }
+
+    #[test]
+    fn test_date_now() {
+        let input =
+            "var start = Date.now(); var end = Date.now(); alert(start); alert(end - start);"
+                .to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+        runtime.set_clock(Rc::new(StubClock {
+            now: Cell::new(1000),
+        }));
+        runtime.execute(&ast);
+
+        assert_eq!(
+            ["1000".to_string(), "10".to_string()].to_vec(),
+            runtime.alert_messages()
+        );
+    }
+
+    #[test]
+    fn test_date_now_without_clock() {
+        let input = "alert(Date.now())".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+        runtime.execute(&ast);
+
+        assert_eq!(["0".to_string()].to_vec(), runtime.alert_messages());
+    }
+
+    fn execute_with_transport(input: &str) -> (JsRuntime, Rc<StubTransport>) {
+        let lexer = JsLexer::new(input.to_string());
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let response = |name: &str, value: &str, body: &str| {
+            HttpResponse::builder()
+                .header(name, value)
+                .body(body.to_string())
+                .build()
+        };
+        let transport = Rc::new(StubTransport::new(
+            [
+                (
+                    "http://example.com:80/data",
+                    response("Content-Type", "text/plain", "same"),
+                ),
+                (
+                    "http://other.com/data",
+                    response("Content-Type", "text/plain", "other"),
+                ),
+                (
+                    "http://other.com/public",
+                    response("Access-Control-Allow-Origin", "*", "public"),
+                ),
+                (
+                    "http://other.com/private",
+                    response(
+                        "Access-Control-Allow-Origin",
+                        "http://example.com",
+                        "private",
+                    ),
+                ),
+            ]
+            .to_vec(),
+        ));
+        let mut runtime = JsRuntime::new(dom);
+        runtime.set_page_url(Some("http://example.com/index.html".to_string()));
+        runtime.set_transport(Some(transport.clone()));
+        runtime.execute(&ast);
+        (runtime, transport)
+    }
+
+    #[test]
+    fn test_fetch_same_origin() {
+        let (runtime, transport) = execute_with_transport(r#"alert(fetch("/data"));"#);
+
+        assert_eq!(["same".to_string()].to_vec(), runtime.alert_messages());
+        assert!(runtime.network_errors().is_empty());
+        assert_eq!(
+            Some("http://example.com".to_string()),
+            transport.requests()[0].header_value("Origin")
+        );
+    }
+
+    #[test]
+    fn test_fetch_cross_origin_is_blocked() {
+        let (runtime, transport) =
+            execute_with_transport(r#"alert(fetch("http://other.com/data"));"#);
+
+        assert_eq!(["".to_string()].to_vec(), runtime.alert_messages());
+        assert_eq!(1, runtime.network_errors().len());
+        assert_eq!(
+            Some("http://example.com".to_string()),
+            transport.requests()[0].header_value("Origin")
+        );
+    }
+
+    #[test]
+    fn test_fetch_cross_origin_allowed_by_cors() {
+        let (runtime, _) = execute_with_transport(
+            r#"alert(fetch("http://other.com/public")); alert(fetch("http://other.com/private"));"#,
+        );
+
+        assert_eq!(
+            ["public".to_string(), "private".to_string()].to_vec(),
+            runtime.alert_messages()
+        );
+        assert!(runtime.network_errors().is_empty());
+    }
+
+    #[test]
+    fn test_add_and_remove_breakpoint() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+        runtime.add_breakpoint(3, 1);
+        runtime.add_breakpoint(5, 2);
+        runtime.add_breakpoint(3, 1);
+        assert_eq!(
+            [(3, 1), (5, 2)].to_vec(),
+            runtime.breakpoints().iter().cloned().collect::<Vec<_>>()
+        );
+
+        runtime.remove_breakpoint(3, 1);
+        assert_eq!(
+            [(5, 2)].to_vec(),
+            runtime.breakpoints().iter().cloned().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_breakpoint_pauses_and_continues() {
+        let input = "function f(x) { return x; }\nvar a = 1;\nf(a);\nvar b = 2;".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+        runtime.add_breakpoint(3, 1);
+
+        runtime.execute(&ast);
+        assert!(runtime.is_paused());
+        let events = runtime.take_pause_events();
+        assert_eq!(1, events.len());
+        assert_eq!("ExpressionStatement", events[0].node().kind_name());
+        assert_eq!(
+            Some(&RuntimeValue::Number(JsNumber::from(1))),
+            events[0].scope().get("a")
+        );
+        assert_eq!(None, events[0].scope().get("b"));
+        // 止まった後の文はまだ実行されていない
+        assert_eq!(
+            None,
+            RefCell::borrow(&runtime.env).get_variable("b".to_string())
+        );
+
+        runtime.continue_execution();
+        assert!(!runtime.is_paused());
+        assert!(runtime.take_pause_events().is_empty());
+        assert_eq!(
+            Some(RuntimeValue::Number(JsNumber::from(2))),
+            RefCell::borrow(&runtime.env).get_variable("b".to_string())
+        );
+    }
+
+    #[test]
+    fn test_breakpoint_on_assignment_and_nested_statement() {
+        let input = "var a = 1;\na = a + 1;\nfunction f() {\n  a = 10;\n}\nf();".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+        runtime.add_breakpoint(2, 1);
+        runtime.add_breakpoint(4, 3);
+
+        runtime.execute(&ast);
+        let events = runtime.take_pause_events();
+        assert_eq!(1, events.len());
+        assert_eq!("ExpressionStatement", events[0].node().kind_name());
+        assert_eq!(
+            Some(&RuntimeValue::Number(JsNumber::from(1))),
+            events[0].scope().get("a")
+        );
+
+        // 関数の本体の中の代入でも止まる
+        runtime.continue_execution();
+        let events = runtime.take_pause_events();
+        assert_eq!(1, events.len());
+        assert_eq!(SourceLocation::new(4, 3), events[0].node().location());
+        assert_eq!(
+            Some(&RuntimeValue::Number(JsNumber::from(2))),
+            events[0].scope().get("a")
+        );
+    }
+
+    #[test]
+    fn test_breakpoint_without_match_does_not_pause() {
+        let input = "var a = 1;\nalert(a);".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+        runtime.add_breakpoint(2, 3);
+
+        runtime.execute(&ast);
+        assert!(!runtime.is_paused());
+        assert!(runtime.take_pause_events().is_empty());
+        assert_eq!(["1".to_string()].to_vec(), runtime.alert_messages());
+    }
+
+    #[test]
+    fn test_eval_expression() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+
+        assert_eq!(
+            Ok(Some(RuntimeValue::Number(JsNumber::from(3)))),
+            runtime.eval_expression("1 + 2")
+        );
+        assert_eq!(
+            Ok(Some(RuntimeValue::StringLiteral("ab".to_string()))),
+            runtime.eval_expression(r#""a" + "b""#)
+        );
+    }
+
+    #[test]
+    fn test_eval_expression_keeps_scope() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+
+        // 宣言の値はundefined
+        assert_eq!(Ok(None), runtime.eval_expression("var a = 40"));
+        assert_eq!(
+            Ok(None),
+            runtime.eval_expression("function add(x) { return x + 2; }")
+        );
+        assert_eq!(
+            Ok(Some(RuntimeValue::Number(JsNumber::from(42)))),
+            runtime.eval_expression("add(a)")
+        );
+    }
+
+    #[test]
+    fn test_eval_expression_sees_script_variables() {
+        let input = "var count = 1; count = count + 1;".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+        runtime.execute(&ast);
+
+        assert_eq!(
+            Ok(Some(RuntimeValue::Number(JsNumber::from(2)))),
+            runtime.eval_expression("count")
+        );
+    }
+
+    #[test]
+    fn test_eval_expression_error() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+
+        assert_eq!(
+            Err(JsError::new(
+                "ReferenceError: missing is not defined".to_string(),
+                SourceLocation::new(1, 1)
+            )),
+            runtime.eval_expression("missing()")
+        );
+        // コンソールでのエラーはスクリプトのエラーとしては残らない
+        assert!(runtime.script_errors().is_empty());
+    }
+
+    #[test]
+    fn test_watch_tracks_counter() {
+        let input = "var count = 0;\ncount = count + 1;\ncount = count + 1;".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+        runtime.add_watch("count".to_string());
+        runtime.add_watch("count + 10".to_string());
+        assert_eq!(
+            ["count".to_string(), "count + 10".to_string()].to_vec(),
+            runtime.watches().to_vec()
+        );
+        assert_eq!([None, None].to_vec(), runtime.watch_values().to_vec());
+
+        runtime.execute(&ast);
+        assert_eq!(
+            [
+                Some(RuntimeValue::Number(JsNumber::from(2))),
+                Some(RuntimeValue::Number(JsNumber::from(12)))
+            ]
+            .to_vec(),
+            runtime.watch_values().to_vec()
+        );
+    }
+
+    #[test]
+    fn test_watch_is_updated_after_each_statement() {
+        let input = "var count = 1;\nalert(count);\ncount = count + 1;".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+        runtime.add_watch("count".to_string());
+        runtime.add_breakpoint(2, 1);
+
+        // 一時停止したときには、止まった文までの値が見える
+        runtime.execute(&ast);
+        assert!(runtime.is_paused());
+        assert_eq!(
+            [Some(RuntimeValue::Number(JsNumber::from(1)))].to_vec(),
+            runtime.watch_values().to_vec()
+        );
+
+        runtime.continue_execution();
+        assert_eq!(
+            [Some(RuntimeValue::Number(JsNumber::from(2)))].to_vec(),
+            runtime.watch_values().to_vec()
+        );
+    }
+
+    #[test]
+    fn test_eval_expression_malformed_input() {
+        let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
+        let mut runtime = JsRuntime::new(dom);
+
+        // 入力の誤りはパニックせずにエラーになる
+        for input in ["2 * 3", "if (1", "{", "function f( {", "foo(", "\"abc"] {
+            assert!(runtime.eval_expression(input).is_err(), "{}", input);
+        }
+
+        assert_eq!(
+            Ok(None),
+            runtime.eval_expression("function add(a, b) { return a + b; }")
+        );
+        assert_eq!(
+            Err(JsError::new(
+                "TypeError: add takes 2 arguments but 1 were given".to_string(),
+                SourceLocation::new(1, 1)
+            )),
+            runtime.eval_expression("add(1)")
+        );
+        assert!(runtime.script_errors().is_empty());
+    }
 }
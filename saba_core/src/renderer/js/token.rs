@@ -1,7 +1,14 @@
+use crate::renderer::js::runtime::JsError;
+use alloc::format;
+use alloc::rc::Rc;
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
+use core::fmt::{Display, Formatter};
 
-static RESERVED_WORDS: [&str; 3] = ["var", "function", "return"];
+static RESERVED_WORDS: [&str; 9] = [
+    "var", "let", "const", "function", "return", "if", "else", "import", "export",
+];
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Token {
@@ -12,9 +19,51 @@ pub enum Token {
     StringLiteral(String),
 }
 
+// ソースコード上の位置。行と列は1から数える
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceLocation {
+    line: u32,
+    column: u32,
+}
+
+impl SourceLocation {
+    pub fn new(line: u32, column: u32) -> Self {
+        Self { line, column }
+    }
+
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    pub fn column(&self) -> u32 {
+        self.column
+    }
+}
+
+impl Display for SourceLocation {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
 pub struct JsLexer {
     pos: usize,
     input: Vec<char>,
+    line: u32,
+    // 現在の行の先頭の位置
+    line_start: usize,
+    // 最後に読み込んだトークンの先頭の位置
+    // パーサーはPeekableを通してトークンを読むため、共有して参照できるようにする
+    location: Rc<Cell<SourceLocation>>,
+    // 最後に読み込んだトークンの前に改行があればtrue。locationと同じように共有する
+    newline_before: Rc<Cell<bool>>,
+    // 最初に見つかった構文エラー。パーサーと共有し、記録された後はトークンを返さない
+    error: Rc<RefCell<Option<JsError>>>,
+    // 読み込んだトークンの先頭のバイト位置と、行と列の対応
+    source_map: Vec<(usize, u32, u32)>,
+    // バイト位置を差分で数えるため、最後に数えた文字の位置とバイト位置を覚えておく
+    counted_pos: usize,
+    counted_bytes: usize,
 }
 
 impl JsLexer {
@@ -22,7 +71,54 @@ impl JsLexer {
         Self {
             pos: 0,
             input: js.chars().collect(),
+            line: 1,
+            line_start: 0,
+            location: Rc::new(Cell::new(SourceLocation::new(1, 1))),
+            newline_before: Rc::new(Cell::new(false)),
+            error: Rc::new(RefCell::new(None)),
+            source_map: Vec::new(),
+            counted_pos: 0,
+            counted_bytes: 0,
+        }
+    }
+
+    // トークンの先頭のバイト位置と、行と列の対応を、バイト位置の順に返す
+    pub fn source_map(&self) -> Vec<(usize, u32, u32)> {
+        self.source_map.clone()
+    }
+
+    // 現在の位置を、入力の先頭からのバイト数に変換する
+    fn byte_offset(&mut self) -> usize {
+        for c in &self.input[self.counted_pos..self.pos] {
+            self.counted_bytes += c.len_utf8();
         }
+        self.counted_pos = self.pos;
+        self.counted_bytes
+    }
+
+    pub fn location(&self) -> Rc<Cell<SourceLocation>> {
+        self.location.clone()
+    }
+
+    pub fn newline_before(&self) -> Rc<Cell<bool>> {
+        self.newline_before.clone()
+    }
+
+    pub fn error(&self) -> Rc<RefCell<Option<JsError>>> {
+        self.error.clone()
+    }
+
+    // 最初の構文エラーだけを記録する
+    fn set_error(&self, message: String, location: SourceLocation) {
+        let mut error = self.error.borrow_mut();
+        if error.is_none() {
+            *error = Some(JsError::new(message, location));
+        }
+    }
+
+    fn new_line(&mut self) {
+        self.line += 1;
+        self.line_start = self.pos + 1;
     }
 
     fn consume_number(&mut self) -> u64 {
@@ -37,7 +133,10 @@ impl JsLexer {
 
             match c {
                 '0'..='9' => {
-                    num = num * 10 + (c.to_digit(10).unwrap() as u64);
+                    // 大きすぎる数はu64の最大値にする
+                    num = num
+                        .saturating_mul(10)
+                        .saturating_add(c.to_digit(10).unwrap() as u64);
                     self.pos += 1;
                 }
                 _ => break,
@@ -47,6 +146,10 @@ impl JsLexer {
     }
 
     fn contains(&self, keyword: &str) -> bool {
+        if self.pos + keyword.len() > self.input.len() {
+            return false;
+        }
+
         for i in 0..keyword.len() {
             if keyword
                 .chars()
@@ -58,7 +161,11 @@ impl JsLexer {
             }
         }
 
-        true
+        // `letter`や`iframe`のような識別子の一部は予約語として扱わない
+        match self.input.get(self.pos + keyword.len()) {
+            Some(c) => !(c.is_ascii_alphanumeric() || *c == '_' || *c == '$'),
+            None => true,
+        }
     }
 
     fn check_reserved_word(&self) -> Option<String> {
@@ -87,18 +194,52 @@ impl JsLexer {
         }
     }
 
-    fn consume_string(&mut self) -> String {
+    // `//`から行末までを読み飛ばす。改行は空白として読むため残しておく
+    fn skip_line_comment(&mut self) {
+        while self.pos < self.input.len() && self.input[self.pos] != '\n' {
+            self.pos += 1;
+        }
+    }
+
+    // `/*`から`*/`までを読み飛ばす。閉じていなければ入力の最後まで読み飛ばす
+    // コメントの中に改行があればtrueを返す
+    fn skip_block_comment(&mut self) -> bool {
+        let mut has_newline = false;
+        self.pos += 2;
+
+        while self.pos < self.input.len() {
+            if self.input[self.pos] == '*' && self.input.get(self.pos + 1) == Some(&'/') {
+                self.pos += 2;
+                return has_newline;
+            }
+
+            if self.input[self.pos] == '\n' {
+                self.new_line();
+                has_newline = true;
+            }
+            self.pos += 1;
+        }
+
+        has_newline
+    }
+
+    // 閉じていない文字列はNoneを返す
+    fn consume_string(&mut self) -> Option<String> {
         let mut result = String::new();
         self.pos += 1;
 
         loop {
             if self.pos >= self.input.len() {
-                return result;
+                return None;
             }
 
             if self.input[self.pos] == '"' {
                 self.pos += 1;
-                return result;
+                return Some(result);
+            }
+
+            if self.input[self.pos] == '\n' {
+                self.new_line();
             }
 
             result.push(self.input[self.pos]);
@@ -111,18 +252,41 @@ impl Iterator for JsLexer {
     type Item = Token;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.pos >= self.input.len() {
+        if self.pos >= self.input.len() || self.error.borrow().is_some() {
             return None;
         }
 
-        // ホワイトスペース or 改行が続く限り、次の位置に進める
-        while self.input[self.pos] == ' ' || self.input[self.pos] == '\n' {
-            self.pos += 1;
+        // ホワイトスペース、改行、コメントが続く限り、次の位置に進める
+        let mut newline_before = false;
+        loop {
             if self.pos >= self.input.len() {
                 return None;
             }
+
+            match (self.input[self.pos], self.input.get(self.pos + 1)) {
+                (' ' | '\t' | '\r', _) => self.pos += 1,
+                ('\n', _) => {
+                    self.new_line();
+                    newline_before = true;
+                    self.pos += 1;
+                }
+                ('/', Some('/')) => self.skip_line_comment(),
+                ('/', Some('*')) => {
+                    if self.skip_block_comment() {
+                        newline_before = true;
+                    }
+                }
+                _ => break,
+            }
         }
 
+        let location = SourceLocation::new(self.line, (self.pos - self.line_start) as u32 + 1);
+        self.location.set(location);
+        self.newline_before.set(newline_before);
+        let offset = self.byte_offset();
+        self.source_map
+            .push((offset, location.line(), location.column()));
+
         // 予約語が現れたら、Keywordトークンを返す
         if let Some(keyword) = self.check_reserved_word() {
             self.pos += keyword.len();
@@ -133,15 +297,28 @@ impl Iterator for JsLexer {
         let c = self.input[self.pos];
 
         let token = match c {
-            '+' | '-' | ';' | '=' | '(' | ')' | '{' | '}' | ',' | '.' => {
+            '+' | '-' | '/' | ';' | '=' | '(' | ')' | '{' | '}' | '[' | ']' | ',' | '.' | '?'
+            | ':' | '<' | '>' => {
                 let t = Token::Punctuator(c);
                 self.pos += 1;
                 t
             }
             '0'..='9' => Token::Number(self.consume_number()),
             'a'..='z' | 'A'..='Z' | '_' | '$' => Token::Identifier(self.consume_identifier()),
-            '"' => Token::StringLiteral(self.consume_string()),
-            _ => unimplemented!("char {:?} is not supported yet", c),
+            '"' => match self.consume_string() {
+                Some(s) => Token::StringLiteral(s),
+                None => {
+                    self.set_error("SyntaxError: unterminated string".to_string(), location);
+                    return None;
+                }
+            },
+            _ => {
+                self.set_error(
+                    format!("SyntaxError: unexpected character {:?}", c),
+                    location,
+                );
+                return None;
+            }
         };
 
         Some(token)
@@ -270,4 +447,192 @@ mod test {
         }
         assert!(lexer.peek().is_none());
     }
+
+    #[test]
+    fn test_module_keywords() {
+        let input = r#"import { foo } from "./m.js"; export const x"#.to_string();
+        let lexer = JsLexer::new(input);
+        let expected = [
+            Token::Keyword("import".to_string()),
+            Token::Punctuator('{'),
+            Token::Identifier("foo".to_string()),
+            Token::Punctuator('}'),
+            Token::Identifier("from".to_string()),
+            Token::StringLiteral("./m.js".to_string()),
+            Token::Punctuator(';'),
+            Token::Keyword("export".to_string()),
+            Token::Keyword("const".to_string()),
+            Token::Identifier("x".to_string()),
+        ]
+        .to_vec();
+        assert_eq!(expected, lexer.collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_keywords_need_word_boundary() {
+        let input = "let letter = iframe; const constant".to_string();
+        let lexer = JsLexer::new(input);
+        let expected = [
+            Token::Keyword("let".to_string()),
+            Token::Identifier("letter".to_string()),
+            Token::Punctuator('='),
+            Token::Identifier("iframe".to_string()),
+            Token::Punctuator(';'),
+            Token::Keyword("const".to_string()),
+            Token::Identifier("constant".to_string()),
+        ]
+        .to_vec();
+        assert_eq!(expected, lexer.collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_location() {
+        let input = "var a = 1;\n  foo(\"x\ny\", a);".to_string();
+        let mut lexer = JsLexer::new(input);
+        let location = lexer.location();
+        let mut locations = Vec::new();
+        while lexer.next().is_some() {
+            let l = location.get();
+            locations.push((l.line(), l.column()));
+        }
+        assert_eq!(
+            [
+                (1, 1),
+                (1, 5),
+                (1, 7),
+                (1, 9),
+                (1, 10),
+                (2, 3),
+                (2, 6),
+                (2, 7),
+                // 文字列中の改行の後は次の行になる
+                (3, 3),
+                (3, 5),
+                (3, 6),
+                (3, 7),
+            ]
+            .to_vec(),
+            locations
+        );
+    }
+
+    #[test]
+    fn test_source_map() {
+        let input = "var s = \"é\";\nfunction f() {\n  return s;\n}".to_string();
+        let mut lexer = JsLexer::new(input.clone());
+        let tokens = lexer.by_ref().collect::<Vec<_>>();
+        let source_map = lexer.source_map();
+        assert_eq!(tokens.len(), source_map.len());
+
+        // `é`は2バイトなので、それ以降のバイト位置は文字数より1つ大きい
+        let semicolon = input.find(';').expect("; should exist");
+        assert_eq!((semicolon, 1, 12), source_map[4]);
+        let function = input.find("function").expect("function should exist");
+        assert_eq!((function, 2, 1), source_map[5]);
+        let ret = input.find("return").expect("return should exist");
+        assert_eq!(Token::Keyword("return".to_string()), tokens[10]);
+        assert_eq!((ret, 3, 3), source_map[10]);
+        let close = input.rfind('}').expect("} should exist");
+        assert_eq!((close, 4, 1), source_map[13]);
+
+        // バイト位置の順に並んでいる
+        assert!(source_map.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
+    fn tokens(input: &str) -> Vec<Token> {
+        JsLexer::new(input.to_string()).collect::<Vec<_>>()
+    }
+
+    #[test]
+    fn test_line_comment() {
+        let expected = [
+            Token::Identifier("a".to_string()),
+            Token::Punctuator('='),
+            Token::Number(1),
+            Token::Punctuator(';'),
+        ]
+        .to_vec();
+        assert_eq!(expected, tokens("// start\na = 1; // end"));
+        assert_eq!(expected, tokens("a = 1;// end\n"));
+        // トークンに隣接したコメント
+        assert_eq!(expected, tokens("a// comment\n= 1;"));
+        assert!(tokens("// only a comment").is_empty());
+    }
+
+    #[test]
+    fn test_block_comment() {
+        let expected = [
+            Token::Identifier("a".to_string()),
+            Token::Punctuator('='),
+            Token::Number(1),
+        ]
+        .to_vec();
+        assert_eq!(expected, tokens("/* start */a = 1"));
+        assert_eq!(expected, tokens("a = 1 /* end */"));
+        assert_eq!(expected, tokens("a/**/=/* multi\nline */1"));
+        // `*/`の前の`*`や`/`もコメントの一部
+        assert_eq!(expected, tokens("a = /** // **/1"));
+        // 閉じていないコメントは最後まで読み飛ばす
+        assert_eq!(expected, tokens("a = 1 /* unterminated"));
+        assert!(tokens("/**/").is_empty());
+    }
+
+    #[test]
+    fn test_comment_location() {
+        let input = "/* a\nb */ x // c\ny".to_string();
+        let mut lexer = JsLexer::new(input);
+        let location = lexer.location();
+        let newline_before = lexer.newline_before();
+
+        assert_eq!(Some(Token::Identifier("x".to_string())), lexer.next());
+        assert_eq!(SourceLocation::new(2, 6), location.get());
+        // 改行を含むコメントは改行として扱う
+        assert!(newline_before.get());
+
+        assert_eq!(Some(Token::Identifier("y".to_string())), lexer.next());
+        assert_eq!(SourceLocation::new(3, 1), location.get());
+        assert!(newline_before.get());
+    }
+
+    #[test]
+    fn test_division() {
+        assert_eq!(
+            [
+                Token::Identifier("a".to_string()),
+                Token::Punctuator('/'),
+                Token::Number(2),
+                Token::Punctuator('/'),
+                Token::Identifier("b".to_string()),
+            ]
+            .to_vec(),
+            tokens("a / 2/b")
+        );
+    }
+
+    #[test]
+    fn test_unexpected_character() {
+        let mut lexer = JsLexer::new("2 * 3".to_string());
+        let error = lexer.error();
+        assert_eq!(Some(Token::Number(2)), lexer.next());
+        // エラーを記録した後はトークンを返さない
+        assert_eq!(None, lexer.next());
+        assert_eq!(None, lexer.next());
+        assert_eq!(
+            Some(JsError::new(
+                "SyntaxError: unexpected character '*'".to_string(),
+                SourceLocation::new(1, 3)
+            )),
+            error.borrow().clone()
+        );
+    }
+
+    #[test]
+    fn test_unterminated_string() {
+        let mut lexer = JsLexer::new("\"abc".to_string());
+        assert_eq!(None, lexer.next());
+        assert_eq!(
+            Some("SyntaxError: unterminated string".to_string()),
+            lexer.error().borrow().as_ref().map(|e| e.message())
+        );
+    }
 }
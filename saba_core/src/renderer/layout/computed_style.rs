@@ -1,8 +1,10 @@
 use crate::error::Error;
-use crate::renderer::dom::node::{ElementKind, Node, NodeKind};
-use alloc::format;
+use crate::renderer::dom::node::{ElementKind, Node, NodeKind, TreeIter};
+use crate::renderer::layout::grid::TrackSize;
+use alloc::collections::BTreeMap;
 use alloc::rc::Rc;
 use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 use core::cell::RefCell;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -11,9 +13,22 @@ pub struct ComputedStyle {
     color: Option<Color>,
     display: Option<DisplayType>,
     font_size: Option<FontSize>,
+    font_weight: Option<FontWeight>,
+    font_style: Option<FontStyle>,
+    word_wrap: Option<WordWrap>,
+    white_space: Option<WhiteSpace>,
+    overflow: Option<Overflow>,
+    visibility: Option<Visibility>,
+    float: Option<Float>,
     text_decoration: Option<TextDecoration>,
     height: Option<f64>,
-    width: Option<f64>,
+    width: Option<Width>,
+    content: Option<String>,
+    grid_template_columns: Option<Vec<TrackSize>>,
+    grid_template_rows: Option<Vec<TrackSize>>,
+    column_gap: Option<f64>,
+    row_gap: Option<f64>,
+    flex_wrap: Option<FlexWrap>,
 }
 
 impl ComputedStyle {
@@ -23,12 +38,111 @@ impl ComputedStyle {
             color: None,
             display: None,
             font_size: None,
+            font_weight: None,
+            font_style: None,
+            word_wrap: None,
+            white_space: None,
+            overflow: None,
+            visibility: None,
+            float: None,
             text_decoration: None,
             height: None,
             width: None,
+            content: None,
+            grid_template_columns: None,
+            grid_template_rows: None,
+            column_gap: None,
+            row_gap: None,
+            flex_wrap: None,
         }
     }
 
+    // ユーザーエージェントのデフォルトスタイル。作者のスタイルを適用する前の土台として使う
+    // 要素ごとに決まっているプロパティだけを設定し、それ以外は親から継承するかdefaultingで決める
+    pub fn default_for(kind: &ElementKind) -> Self {
+        let mut style = Self::new();
+
+        match kind {
+            ElementKind::Html
+            | ElementKind::Body
+            | ElementKind::P
+            | ElementKind::Div
+            | ElementKind::Nav
+            | ElementKind::Header
+            | ElementKind::Footer
+            | ElementKind::Article
+            | ElementKind::Section
+            | ElementKind::Details
+            | ElementKind::Summary
+            | ElementKind::Form
+            | ElementKind::Option => {
+                style.display = Some(DisplayType::Block);
+            }
+            ElementKind::H1 => {
+                style.display = Some(DisplayType::Block);
+                style.font_size = Some(FontSize::XXLarge);
+                style.font_weight = Some(FontWeight::Bold);
+            }
+            ElementKind::H2 => {
+                style.display = Some(DisplayType::Block);
+                style.font_size = Some(FontSize::XLarge);
+                style.font_weight = Some(FontWeight::Bold);
+            }
+            ElementKind::A => {
+                style.display = Some(DisplayType::Inline);
+                style.color =
+                    Some(Color::from_name("blue").expect("blue should be a supported color"));
+                style.text_decoration = Some(TextDecoration::Underline);
+            }
+            ElementKind::B | ElementKind::Strong => {
+                style.display = Some(DisplayType::Inline);
+                style.font_weight = Some(FontWeight::Bold);
+            }
+            ElementKind::I | ElementKind::Em => {
+                style.display = Some(DisplayType::Inline);
+                style.font_style = Some(FontStyle::Italic);
+            }
+            ElementKind::Head
+            | ElementKind::Title
+            | ElementKind::Meta
+            | ElementKind::Style
+            | ElementKind::Script
+            | ElementKind::Link
+            | ElementKind::Template => {
+                style.display = Some(DisplayType::DisplayNone);
+            }
+            ElementKind::Svg => {
+                // SVGの中身は描画しないため、グレーのプレースホルダーとして表示する
+                style.display = Some(DisplayType::Block);
+                style.background_color =
+                    Some(Color::from_name("gray").expect("gray should be a supported color"));
+            }
+            ElementKind::Progress | ElementKind::Meter => {
+                // 中身は描画せず、背景をトラックとして、値の割合だけ塗りつぶす
+                style.display = Some(DisplayType::Block);
+                style.background_color = Some(
+                    Color::from_name("lightgray").expect("lightgray should be a supported color"),
+                );
+            }
+            ElementKind::Input | ElementKind::Select => {
+                // 入力欄は値を、selectは選ばれている選択肢を表示する箱として描画する
+                style.display = Some(DisplayType::Block);
+                style.background_color = Some(
+                    Color::from_name("lightgray").expect("lightgray should be a supported color"),
+                );
+            }
+            ElementKind::Button => {
+                style.display = Some(DisplayType::Inline);
+            }
+            ElementKind::Unknown(_) => {
+                // 未知の要素やカスタム要素は、インライン要素として子要素を表示する
+                style.display = Some(DisplayType::Inline);
+            }
+        }
+
+        style
+    }
+
     pub fn set_background_color(&mut self, color: Color) {
         self.background_color = Some(color);
     }
@@ -58,11 +172,73 @@ impl ComputedStyle {
             .expect("failed to access CSS property: display")
     }
 
+    pub fn set_font_size(&mut self, font_size: FontSize) {
+        self.font_size = Some(font_size);
+    }
+
     pub fn font_size(&self) -> FontSize {
         self.font_size
             .expect("failed to access CSS property: font_size")
     }
 
+    pub fn font_weight(&self) -> FontWeight {
+        self.font_weight
+            .expect("failed to access CSS property: font_weight")
+    }
+
+    pub fn set_font_style(&mut self, font_style: FontStyle) {
+        self.font_style = Some(font_style);
+    }
+
+    pub fn font_style(&self) -> FontStyle {
+        self.font_style
+            .expect("failed to access CSS property: font_style")
+    }
+
+    pub fn set_word_wrap(&mut self, word_wrap: WordWrap) {
+        self.word_wrap = Some(word_wrap);
+    }
+
+    pub fn word_wrap(&self) -> WordWrap {
+        self.word_wrap
+            .expect("failed to access CSS property: word_wrap")
+    }
+
+    pub fn set_white_space(&mut self, white_space: WhiteSpace) {
+        self.white_space = Some(white_space);
+    }
+
+    pub fn white_space(&self) -> WhiteSpace {
+        self.white_space
+            .expect("failed to access CSS property: white_space")
+    }
+
+    pub fn set_overflow(&mut self, overflow: Overflow) {
+        self.overflow = Some(overflow);
+    }
+
+    pub fn overflow(&self) -> Overflow {
+        self.overflow
+            .expect("failed to access CSS property: overflow")
+    }
+
+    pub fn set_visibility(&mut self, visibility: Visibility) {
+        self.visibility = Some(visibility);
+    }
+
+    pub fn visibility(&self) -> Visibility {
+        self.visibility
+            .expect("failed to access CSS property: visibility")
+    }
+
+    pub fn set_float(&mut self, float: Float) {
+        self.float = Some(float);
+    }
+
+    pub fn float(&self) -> Float {
+        self.float.expect("failed to access CSS property: float")
+    }
+
     pub fn text_decoration(&self) -> TextDecoration {
         self.text_decoration
             .expect("failed to access CSS property: text_decoration")
@@ -76,14 +252,70 @@ impl ComputedStyle {
         self.height.expect("failed to access CSS property: height")
     }
 
-    pub fn set_width(&mut self, width: f64) {
+    pub fn set_width(&mut self, width: Width) {
         self.width = Some(width);
     }
 
-    pub fn width(&self) -> f64 {
+    pub fn width(&self) -> Width {
         self.width.expect("failed to access CSS property: width")
     }
 
+    pub fn set_content(&mut self, content: String) {
+        self.content = Some(content);
+    }
+
+    // `::before`/`::after`疑似要素で挿入されるテキスト。継承されないため、デフォルト値はない
+    pub fn content(&self) -> Option<String> {
+        self.content.clone()
+    }
+
+    pub fn set_grid_template_columns(&mut self, tracks: Vec<TrackSize>) {
+        self.grid_template_columns = Some(tracks);
+    }
+
+    pub fn grid_template_columns(&self) -> Vec<TrackSize> {
+        self.grid_template_columns
+            .clone()
+            .expect("failed to access CSS property: grid-template-columns")
+    }
+
+    pub fn set_grid_template_rows(&mut self, tracks: Vec<TrackSize>) {
+        self.grid_template_rows = Some(tracks);
+    }
+
+    pub fn grid_template_rows(&self) -> Vec<TrackSize> {
+        self.grid_template_rows
+            .clone()
+            .expect("failed to access CSS property: grid-template-rows")
+    }
+
+    pub fn set_column_gap(&mut self, gap: f64) {
+        self.column_gap = Some(gap);
+    }
+
+    pub fn column_gap(&self) -> f64 {
+        self.column_gap
+            .expect("failed to access CSS property: column-gap")
+    }
+
+    pub fn set_row_gap(&mut self, gap: f64) {
+        self.row_gap = Some(gap);
+    }
+
+    pub fn row_gap(&self) -> f64 {
+        self.row_gap
+            .expect("failed to access CSS property: row-gap")
+    }
+
+    pub fn set_flex_wrap(&mut self, flex_wrap: FlexWrap) {
+        self.flex_wrap = Some(flex_wrap);
+    }
+
+    pub fn flex_wrap(&self) -> FlexWrap {
+        self.flex_wrap
+            .expect("failed to access CSS property: flex-wrap")
+    }
+
     pub fn defaulting(&mut self, node: &Rc<RefCell<Node>>, parent_style: Option<ComputedStyle>) {
         if let Some(parent_style) = parent_style {
             if self.background_color.is_none() && parent_style.background_color() != Color::white()
@@ -96,6 +328,21 @@ impl ComputedStyle {
             if self.font_size.is_none() && parent_style.font_size() != FontSize::Medium {
                 self.font_size = Some(parent_style.font_size());
             }
+            if self.font_weight.is_none() && parent_style.font_weight() != FontWeight::Normal {
+                self.font_weight = Some(parent_style.font_weight());
+            }
+            if self.font_style.is_none() && parent_style.font_style() != FontStyle::Normal {
+                self.font_style = Some(parent_style.font_style());
+            }
+            if self.word_wrap.is_none() && parent_style.word_wrap() != WordWrap::Normal {
+                self.word_wrap = Some(parent_style.word_wrap());
+            }
+            if self.white_space.is_none() && parent_style.white_space() != WhiteSpace::Normal {
+                self.white_space = Some(parent_style.white_space());
+            }
+            if self.visibility.is_none() && parent_style.visibility() != Visibility::Visible {
+                self.visibility = Some(parent_style.visibility());
+            }
             if self.text_decoration.is_none()
                 && parent_style.text_decoration() != TextDecoration::None
             {
@@ -113,18 +360,125 @@ impl ComputedStyle {
             self.display = Some(DisplayType::default(node));
         }
         if self.font_size.is_none() {
-            self.font_size = Some(FontSize::default(node));
+            self.font_size = Some(FontSize::Medium);
+        }
+        if self.font_weight.is_none() {
+            self.font_weight = Some(FontWeight::Normal);
+        }
+        if self.font_style.is_none() {
+            self.font_style = Some(FontStyle::Normal);
+        }
+        if self.word_wrap.is_none() {
+            self.word_wrap = Some(WordWrap::Normal);
+        }
+        if self.white_space.is_none() {
+            self.white_space = Some(WhiteSpace::Normal);
+        }
+        // overflowは継承されない
+        if self.overflow.is_none() {
+            self.overflow = Some(Overflow::Visible);
+        }
+        if self.visibility.is_none() {
+            self.visibility = Some(Visibility::Visible);
+        }
+        // floatは継承されない
+        if self.float.is_none() {
+            self.float = Some(Float::None);
         }
         if self.text_decoration.is_none() {
-            self.text_decoration = Some(TextDecoration::default(node));
+            self.text_decoration = Some(TextDecoration::None);
         }
         if self.height.is_none() {
             self.height = Some(0.0);
         }
+        // widthは継承されない
         if self.width.is_none() {
-            self.width = Some(0.0);
+            self.width = Some(Width::Auto);
+        }
+        // グリッドのトラックは継承されず、指定がなければ明示的なトラックはない
+        if self.grid_template_columns.is_none() {
+            self.grid_template_columns = Some(Vec::new());
+        }
+        if self.grid_template_rows.is_none() {
+            self.grid_template_rows = Some(Vec::new());
+        }
+        // gapは継承されない
+        if self.column_gap.is_none() {
+            self.column_gap = Some(0.0);
+        }
+        if self.row_gap.is_none() {
+            self.row_gap = Some(0.0);
+        }
+        // flex-wrapは継承されない
+        if self.flex_wrap.is_none() {
+            self.flex_wrap = Some(FlexWrap::NoWrap);
+        }
+    }
+}
+
+// ノードごとに解決済みのスタイルを、継承元になった親のスタイルと一緒に保持する
+// 親のスタイルが変わったノードのスタイルは使わずに解決し直す
+// スタイルシートが変わったときはclearで、DOMツリーが変わったときはinvalidateで破棄する必要がある
+#[derive(Debug, Clone, PartialEq)]
+pub struct StyleCache {
+    // ノードの番号から(親のスタイル, 解決済みのスタイル)
+    styles: BTreeMap<usize, (Option<ComputedStyle>, ComputedStyle)>,
+    resolve_count: usize,
+}
+
+impl Default for StyleCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StyleCache {
+    pub fn new() -> Self {
+        Self {
+            styles: BTreeMap::new(),
+            resolve_count: 0,
+        }
+    }
+
+    pub fn get(
+        &self,
+        node: &Rc<RefCell<Node>>,
+        parent_style: &Option<ComputedStyle>,
+    ) -> Option<ComputedStyle> {
+        match self.styles.get(&node.borrow().id()) {
+            Some((cached_parent_style, style)) if cached_parent_style == parent_style => {
+                Some(style.clone())
+            }
+            _ => None,
+        }
+    }
+
+    pub fn insert(
+        &mut self,
+        node: &Rc<RefCell<Node>>,
+        parent_style: Option<ComputedStyle>,
+        style: ComputedStyle,
+    ) {
+        self.resolve_count += 1;
+        self.styles
+            .insert(node.borrow().id(), (parent_style, style));
+    }
+
+    // nodeとその子孫のスタイルを破棄する。属性の変更などでセレクタに一致するかが変わったときに使う
+    pub fn invalidate(&mut self, node: &Rc<RefCell<Node>>) {
+        for n in TreeIter::new(node.clone()) {
+            self.styles.remove(&n.borrow().id());
         }
     }
+
+    pub fn clear(&mut self) {
+        self.styles.clear();
+    }
+
+    // スタイルを解決(カスケードとデフォルティング)した回数
+    pub fn resolve_count(&self) -> usize {
+        self.resolve_count
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -155,10 +509,10 @@ impl Color {
             "orange" => "#ffa500".to_string(),
             "lightgray" => "#d3d3d3".to_string(),
             _ => {
-                return Err(Error::UnexpectedInput(format!(
-                    "color name {:?} is not supported yet",
-                    name
-                )));
+                return Err(Error::UnexpectedInput {
+                    input: name.to_string(),
+                    message: "color name is not supported yet".to_string(),
+                });
             }
         };
 
@@ -170,10 +524,10 @@ impl Color {
 
     pub fn from_code(code: &str) -> Result<Self, Error> {
         if code.chars().nth(0) != Some('#') || code.len() != 7 {
-            return Err(Error::UnexpectedInput(format!(
-                "invalid color code {}",
-                code
-            )));
+            return Err(Error::UnexpectedInput {
+                input: code.to_string(),
+                message: "invalid color code".to_string(),
+            });
         }
 
         let name = match code {
@@ -196,10 +550,10 @@ impl Color {
             "#ffa500" => "orange".to_string(),
             "#d3d3d3" => "lightgray".to_string(),
             _ => {
-                return Err(Error::UnexpectedInput(format!(
-                    "color code {:?} is not supported yet",
-                    code
-                )));
+                return Err(Error::UnexpectedInput {
+                    input: code.to_string(),
+                    message: "color code is not supported yet".to_string(),
+                });
             }
         };
 
@@ -235,15 +589,156 @@ pub enum FontSize {
     XXLarge,
 }
 
-impl FontSize {
-    fn default(node: &Rc<RefCell<Node>>) -> Self {
-        match &node.borrow().kind() {
-            NodeKind::Element(element) => match element.kind() {
-                ElementKind::H1 => FontSize::XXLarge,
-                ElementKind::H2 => FontSize::XLarge,
-                _ => FontSize::Medium,
-            },
-            _ => FontSize::Medium,
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FontWeight {
+    Normal,
+    Bold,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FontStyle {
+    Normal,
+    Italic,
+}
+
+impl FontStyle {
+    // obliqueはitalicとして扱う
+    pub fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "normal" => Ok(Self::Normal),
+            "italic" | "oblique" => Ok(Self::Italic),
+            _ => Err(Error::UnexpectedInput {
+                input: s.to_string(),
+                message: "font-style is not supported yet".to_string(),
+            }),
+        }
+    }
+}
+
+// word-wrapとoverflow-wrapのどちらでも指定できる
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum WordWrap {
+    Normal,
+    // 1つの単語が行に収まらないとき、単語の途中で改行する
+    BreakWord,
+}
+
+impl WordWrap {
+    // anywhereはbreak-wordとして扱う
+    pub fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "normal" => Ok(Self::Normal),
+            "break-word" | "anywhere" => Ok(Self::BreakWord),
+            _ => Err(Error::UnexpectedInput {
+                input: s.to_string(),
+                message: "word-wrap is not supported yet".to_string(),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum WhiteSpace {
+    Normal,
+    // 包含ブロックの端でも改行せず、1行に並べる
+    NoWrap,
+}
+
+impl WhiteSpace {
+    pub fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "normal" => Ok(Self::Normal),
+            "nowrap" => Ok(Self::NoWrap),
+            _ => Err(Error::UnexpectedInput {
+                input: s.to_string(),
+                message: "white-space is not supported yet".to_string(),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Overflow {
+    Visible,
+    // はみ出したテキストを包含ブロックの右端で切り取る
+    Hidden,
+}
+
+impl Overflow {
+    // scrollとautoはスクロールできないので、hiddenとして扱う
+    pub fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "visible" => Ok(Self::Visible),
+            "hidden" | "clip" | "scroll" | "auto" => Ok(Self::Hidden),
+            _ => Err(Error::UnexpectedInput {
+                input: s.to_string(),
+                message: "overflow is not supported yet".to_string(),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Visibility {
+    Visible,
+    // レイアウト上の場所は確保するが、描画しない
+    Hidden,
+}
+
+impl Visibility {
+    // 表の行や列がないので、collapseはhiddenとして扱う
+    pub fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "visible" => Ok(Self::Visible),
+            "hidden" | "collapse" => Ok(Self::Hidden),
+            _ => Err(Error::UnexpectedInput {
+                input: s.to_string(),
+                message: "visibility is not supported yet".to_string(),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Width {
+    // 包含ブロックの横幅いっぱいに広がる
+    Auto,
+    Px(f64),
+    // 包含ブロックの横幅に対する割合
+    Percentage(f64),
+}
+
+impl Width {
+    // 包含ブロックの横幅から、実際の横幅を決める。autoのときはNoneを返す
+    pub fn resolve(&self, containing_width: i64) -> Option<i64> {
+        match self {
+            Self::Auto => None,
+            Self::Px(px) => Some(*px as i64),
+            Self::Percentage(percentage) => {
+                Some((containing_width as f64 * percentage / 100.0) as i64)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Float {
+    None,
+    // 包含ブロックの左端または右端に寄せ、同じ向きのfloatとは横に並べる
+    Left,
+    Right,
+}
+
+impl Float {
+    pub fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "none" => Ok(Self::None),
+            "left" => Ok(Self::Left),
+            "right" => Ok(Self::Right),
+            _ => Err(Error::UnexpectedInput {
+                input: s.to_string(),
+                message: "float is not supported yet".to_string(),
+            }),
         }
     }
 }
@@ -252,21 +747,19 @@ impl FontSize {
 pub enum DisplayType {
     Block,
     Inline,
+    // 外側はブロックとして並び、子をグリッドのセルに置く
+    Grid,
+    // 外側はブロックとして並び、子を横に並べる
+    Flex,
     DisplayNone,
 }
 
 impl DisplayType {
+    // 要素のデフォルトはdefault_forで決まるため、ここではそれ以外のノードを扱う
     fn default(node: &Rc<RefCell<Node>>) -> Self {
         match &node.borrow().kind() {
             NodeKind::Document => DisplayType::Block,
-            NodeKind::Element(e) => {
-                if e.is_block_element() {
-                    DisplayType::Block
-                } else {
-                    DisplayType::Inline
-                }
-            }
-            NodeKind::Text(_) => DisplayType::Inline,
+            NodeKind::Element(_) | NodeKind::Text(_) => DisplayType::Inline,
         }
     }
 
@@ -274,11 +767,33 @@ impl DisplayType {
         match s {
             "block" => Ok(Self::Block),
             "inline" => Ok(Self::Inline),
+            "grid" => Ok(Self::Grid),
+            "flex" => Ok(Self::Flex),
             "none" => Ok(Self::DisplayNone),
-            _ => Err(Error::UnexpectedInput(format!(
-                "display {:?} is not supported yet",
-                s
-            ))),
+            _ => Err(Error::UnexpectedInput {
+                input: s.to_string(),
+                message: "display is not supported yet".to_string(),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FlexWrap {
+    NoWrap,
+    // コンテナの横幅に収まらない子を次の行に置く
+    Wrap,
+}
+
+impl FlexWrap {
+    pub fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "nowrap" => Ok(Self::NoWrap),
+            "wrap" => Ok(Self::Wrap),
+            _ => Err(Error::UnexpectedInput {
+                input: s.to_string(),
+                message: "flex-wrap is not supported yet".to_string(),
+            }),
         }
     }
 }
@@ -289,14 +804,126 @@ pub enum TextDecoration {
     Underline,
 }
 
-impl TextDecoration {
-    fn default(node: &Rc<RefCell<Node>>) -> Self {
-        match &node.borrow().kind() {
-            NodeKind::Element(element) => match element.kind() {
-                ElementKind::A => TextDecoration::Underline,
-                _ => TextDecoration::None,
-            },
-            _ => TextDecoration::None,
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::dom::node::Element;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_default_for_h1() {
+        let style = ComputedStyle::default_for(&ElementKind::H1);
+        assert_eq!(Some(DisplayType::Block), style.display);
+        assert_eq!(Some(FontSize::XXLarge), style.font_size);
+        assert_eq!(Some(FontWeight::Bold), style.font_weight);
+        assert_eq!(None, style.color);
+    }
+
+    #[test]
+    fn test_default_for_a() {
+        let style = ComputedStyle::default_for(&ElementKind::A);
+        assert_eq!(Some(DisplayType::Inline), style.display);
+        assert_eq!(Color::from_name("blue").ok(), style.color);
+        assert_eq!(Some(TextDecoration::Underline), style.text_decoration);
+        assert_eq!(None, style.font_size);
+    }
+
+    #[test]
+    fn test_default_for_p() {
+        let style = ComputedStyle::default_for(&ElementKind::P);
+        assert_eq!(Some(DisplayType::Block), style.display);
+        assert_eq!(None, style.font_size);
+        assert_eq!(None, style.font_weight);
+        assert_eq!(None, style.color);
+    }
+
+    #[test]
+    fn test_default_for_div() {
+        let style = ComputedStyle::default_for(&ElementKind::Div);
+        assert_eq!(Some(DisplayType::Block), style.display);
+        assert_eq!(None, style.font_size);
+        assert_eq!(None, style.text_decoration);
+    }
+
+    #[test]
+    fn test_default_for_strong_and_em() {
+        let strong = ComputedStyle::default_for(&ElementKind::Strong);
+        assert_eq!(Some(DisplayType::Inline), strong.display);
+        assert_eq!(Some(FontWeight::Bold), strong.font_weight);
+        assert_eq!(None, strong.font_style);
+
+        let em = ComputedStyle::default_for(&ElementKind::Em);
+        assert_eq!(Some(DisplayType::Inline), em.display);
+        assert_eq!(Some(FontStyle::Italic), em.font_style);
+        assert_eq!(None, em.font_weight);
+    }
+
+    #[test]
+    fn test_defaulting_inherits_font_style() {
+        let node = Rc::new(RefCell::new(Node::new(NodeKind::Text("text".to_string()))));
+        let mut parent = ComputedStyle::default_for(&ElementKind::I);
+        parent.defaulting(&node, None);
+
+        let mut style = ComputedStyle::new();
+        style.defaulting(&node, Some(parent));
+        assert_eq!(FontStyle::Italic, style.font_style());
+        assert_eq!(FontWeight::Normal, style.font_weight());
+    }
+
+    #[test]
+    fn test_font_style_from_str() {
+        assert_eq!(Ok(FontStyle::Italic), FontStyle::from_str("italic"));
+        assert_eq!(Ok(FontStyle::Italic), FontStyle::from_str("oblique"));
+        assert_eq!(Ok(FontStyle::Normal), FontStyle::from_str("normal"));
+        assert!(FontStyle::from_str("bold").is_err());
+    }
+
+    #[test]
+    fn test_word_wrap_from_str() {
+        assert_eq!(Ok(WordWrap::BreakWord), WordWrap::from_str("break-word"));
+        assert_eq!(Ok(WordWrap::BreakWord), WordWrap::from_str("anywhere"));
+        assert_eq!(Ok(WordWrap::Normal), WordWrap::from_str("normal"));
+        assert!(WordWrap::from_str("break-all").is_err());
+    }
+
+    #[test]
+    fn test_white_space_and_overflow_from_str() {
+        assert_eq!(Ok(WhiteSpace::NoWrap), WhiteSpace::from_str("nowrap"));
+        assert_eq!(Ok(WhiteSpace::Normal), WhiteSpace::from_str("normal"));
+        assert!(WhiteSpace::from_str("pre").is_err());
+        assert_eq!(Ok(Overflow::Hidden), Overflow::from_str("hidden"));
+        assert_eq!(Ok(Overflow::Visible), Overflow::from_str("visible"));
+        assert!(Overflow::from_str("overlay").is_err());
+        assert_eq!(Ok(Visibility::Hidden), Visibility::from_str("hidden"));
+        assert_eq!(Ok(Visibility::Visible), Visibility::from_str("visible"));
+        assert!(Visibility::from_str("none").is_err());
+    }
+
+    #[test]
+    fn test_float_from_str() {
+        assert_eq!(Ok(Float::Left), Float::from_str("left"));
+        assert_eq!(Ok(Float::Right), Float::from_str("right"));
+        assert_eq!(Ok(Float::None), Float::from_str("none"));
+        assert!(Float::from_str("inline-start").is_err());
+    }
+
+    #[test]
+    fn test_flex_wrap_from_str() {
+        assert_eq!(Ok(FlexWrap::Wrap), FlexWrap::from_str("wrap"));
+        assert_eq!(Ok(FlexWrap::NoWrap), FlexWrap::from_str("nowrap"));
+        assert!(FlexWrap::from_str("wrap-reverse").is_err());
+    }
+
+    #[test]
+    fn test_defaulting_keeps_ua_defaults() {
+        let node = Rc::new(RefCell::new(Node::new(NodeKind::Element(Element::new(
+            "h1",
+            Vec::new(),
+        )))));
+        let mut style = ComputedStyle::default_for(&ElementKind::H1);
+        style.defaulting(&node, None);
+        assert_eq!(FontSize::XXLarge, style.font_size());
+        assert_eq!(FontWeight::Bold, style.font_weight());
+        assert_eq!(Color::black(), style.color());
     }
 }
@@ -0,0 +1,102 @@
+use alloc::vec::Vec;
+use core::cmp::max;
+
+// フレックスコンテナの子の、コンテナの左上からの位置と、コンテナの高さ
+// 子は内容の大きさのまま横に並べる。伸縮や揃え位置には対応していない
+// https://www.w3.org/TR/css-flexbox-1/#layout-algorithm
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlexLayout {
+    item_offsets: Vec<(i64, i64)>,
+    height: i64,
+}
+
+impl FlexLayout {
+    // item_sizesは、子ごとの(横幅, 高さ)
+    // wrapのときは、コンテナの横幅を超える子から次の行に折り返す
+    // 間隔は、同じ行の子どうしの間と行どうしの間にだけ入る
+    pub fn new(
+        item_sizes: &[(i64, i64)],
+        available_width: i64,
+        wrap: bool,
+        column_gap: i64,
+        row_gap: i64,
+    ) -> Self {
+        let mut item_offsets = Vec::new();
+        let mut x = 0;
+        let mut y = 0;
+        let mut line_height = 0;
+        for (width, height) in item_sizes {
+            // 行の先頭の子は、コンテナより広くても折り返さない
+            if !item_offsets.is_empty() {
+                if wrap && x + column_gap + width > available_width {
+                    y += line_height + row_gap;
+                    x = 0;
+                    line_height = 0;
+                } else {
+                    x += column_gap;
+                }
+            }
+
+            item_offsets.push((x, y));
+            x += width;
+            line_height = max(line_height, *height);
+        }
+
+        Self {
+            item_offsets,
+            height: y + line_height,
+        }
+    }
+
+    pub fn item_offset(&self, index: usize) -> (i64, i64) {
+        self.item_offsets.get(index).copied().unwrap_or((0, 0))
+    }
+
+    pub fn height(&self) -> i64 {
+        self.height
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_items_in_a_row() {
+        let flex = FlexLayout::new(&[(10, 20), (30, 40), (50, 10)], 100, false, 0, 0);
+        assert_eq!((0, 0), flex.item_offset(0));
+        assert_eq!((10, 0), flex.item_offset(1));
+        assert_eq!((40, 0), flex.item_offset(2));
+        assert_eq!(40, flex.height());
+    }
+
+    #[test]
+    fn test_column_gap() {
+        let flex = FlexLayout::new(&[(10, 20), (30, 20), (50, 20)], 100, false, 16, 8);
+        assert_eq!((0, 0), flex.item_offset(0));
+        assert_eq!((26, 0), flex.item_offset(1));
+        assert_eq!((72, 0), flex.item_offset(2));
+        // 折り返さなければ、行の間隔は入らない
+        assert_eq!(20, flex.height());
+    }
+
+    #[test]
+    fn test_wrap_with_row_gap() {
+        // 3つ目は間隔を含めると100pxを超えるため、次の行に置く
+        let flex = FlexLayout::new(&[(40, 20), (40, 30), (20, 10), (30, 10)], 100, true, 10, 8);
+        assert_eq!((0, 0), flex.item_offset(0));
+        assert_eq!((50, 0), flex.item_offset(1));
+        assert_eq!((0, 38), flex.item_offset(2));
+        assert_eq!((30, 38), flex.item_offset(3));
+        assert_eq!(30 + 8 + 10, flex.height());
+    }
+
+    #[test]
+    fn test_wide_item_is_not_wrapped_alone() {
+        // コンテナより広い子も、行の先頭であれば折り返さない
+        let flex = FlexLayout::new(&[(150, 20), (10, 20)], 100, true, 0, 4);
+        assert_eq!((0, 0), flex.item_offset(0));
+        assert_eq!((0, 24), flex.item_offset(1));
+        assert_eq!(44, flex.height());
+    }
+}
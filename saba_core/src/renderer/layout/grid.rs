@@ -0,0 +1,224 @@
+use crate::renderer::css::cssom::ComponentValue;
+use alloc::vec::Vec;
+use core::cmp::max;
+
+// グリッドの列または行(トラック)の大きさ
+// https://www.w3.org/TR/css-grid-1/#track-sizing
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TrackSize {
+    Px(f64),
+    // 固定の大きさのトラックを除いた残りを、frの比で分け合う
+    Fr(f64),
+    // 内容の大きさ
+    Auto,
+}
+
+impl TrackSize {
+    fn from_component_value(value: &ComponentValue) -> Option<Self> {
+        match value {
+            ComponentValue::Dimension(v, unit) if unit == "px" && *v >= 0.0 => {
+                Some(TrackSize::Px(*v))
+            }
+            ComponentValue::Dimension(v, unit) if unit == "fr" && *v >= 0.0 => {
+                Some(TrackSize::Fr(*v))
+            }
+            ComponentValue::Number(v) if *v == 0.0 => Some(TrackSize::Px(0.0)),
+            ComponentValue::Ident(v) if v == "auto" => Some(TrackSize::Auto),
+            _ => None,
+        }
+    }
+}
+
+// `1fr 2fr`や`auto 100px`のようなトラックの並び。解釈できない値が含まれていればNone
+pub fn parse_track_list(values: &[ComponentValue]) -> Option<Vec<TrackSize>> {
+    values.iter().map(TrackSize::from_component_value).collect()
+}
+
+// トラックの大きさを決める。content_sizesは、トラックごとの内容の大きさ
+// availableが決まっていない(高さのように内容で決まる)ときは、frもautoと同じく内容の大きさにする
+pub fn resolve_tracks(
+    tracks: &[TrackSize],
+    available: Option<i64>,
+    content_sizes: &[i64],
+) -> Vec<i64> {
+    let content = |i: usize| content_sizes.get(i).copied().unwrap_or(0);
+    let fr_total: f64 = tracks
+        .iter()
+        .map(|t| match t {
+            TrackSize::Fr(fr) => *fr,
+            _ => 0.0,
+        })
+        .sum();
+
+    let mut sizes: Vec<i64> = tracks
+        .iter()
+        .enumerate()
+        .map(|(i, t)| match t {
+            TrackSize::Px(px) => *px as i64,
+            TrackSize::Auto => content(i),
+            TrackSize::Fr(_) => 0,
+        })
+        .collect();
+
+    let remaining = match available {
+        Some(available) if fr_total > 0.0 => Some(max(available - sizes.iter().sum::<i64>(), 0)),
+        _ => None,
+    };
+    for (i, t) in tracks.iter().enumerate() {
+        if let TrackSize::Fr(fr) = t {
+            sizes[i] = match remaining {
+                Some(remaining) => (remaining as f64 * fr / fr_total) as i64,
+                None => content(i),
+            };
+        }
+    }
+
+    sizes
+}
+
+// gap、column-gap、row-gapの値。`normal`は0にする
+pub fn parse_gap(value: &ComponentValue) -> Option<f64> {
+    match value {
+        ComponentValue::Dimension(v, unit) if unit == "px" && *v >= 0.0 => Some(*v),
+        ComponentValue::Number(v) if *v == 0.0 => Some(0.0),
+        ComponentValue::Ident(v) if v == "normal" => Some(0.0),
+        _ => None,
+    }
+}
+
+// index番目の子を置くセルの(行, 列)。子は行優先の順に置く
+pub fn cell_of(index: usize, column_count: usize) -> (usize, usize) {
+    (index / column_count, index % column_count)
+}
+
+// グリッドコンテナの列の幅と行の高さ、それらの間隔
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GridLayout {
+    column_widths: Vec<i64>,
+    row_heights: Vec<i64>,
+    column_gap: i64,
+    row_gap: i64,
+}
+
+impl GridLayout {
+    pub fn new(
+        column_widths: Vec<i64>,
+        row_heights: Vec<i64>,
+        column_gap: i64,
+        row_gap: i64,
+    ) -> Self {
+        Self {
+            column_widths,
+            row_heights,
+            column_gap,
+            row_gap,
+        }
+    }
+
+    pub fn column_widths(&self) -> Vec<i64> {
+        self.column_widths.clone()
+    }
+
+    pub fn row_heights(&self) -> Vec<i64> {
+        self.row_heights.clone()
+    }
+
+    // index番目の子を置くセルの、コンテナの左上からの位置
+    // 間隔は、前にある列や行との間にだけ入る
+    pub fn cell_offset(&self, index: usize) -> (i64, i64) {
+        let (row, column) = cell_of(index, max(self.column_widths.len(), 1));
+        (
+            self.column_widths[..column].iter().sum::<i64>() + self.column_gap * column as i64,
+            self.row_heights[..row].iter().sum::<i64>() + self.row_gap * row as i64,
+        )
+    }
+
+    // コンテナの高さは、行の高さと行の間の間隔の合計
+    pub fn height(&self) -> i64 {
+        let gaps = self.row_heights.len().saturating_sub(1) as i64;
+        self.row_heights.iter().sum::<i64>() + self.row_gap * gaps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::css::token::{CssToken, CssTokenizer};
+    use alloc::string::ToString;
+
+    fn parse(s: &str) -> Option<Vec<TrackSize>> {
+        let values: Vec<CssToken> = CssTokenizer::new(s.to_string()).collect();
+        parse_track_list(&values)
+    }
+
+    #[test]
+    fn test_parse_track_list() {
+        assert_eq!(
+            Some([TrackSize::Fr(1.0), TrackSize::Fr(2.0)].to_vec()),
+            parse("1fr 2fr")
+        );
+        assert_eq!(
+            Some([TrackSize::Auto, TrackSize::Px(100.0)].to_vec()),
+            parse("auto 100px")
+        );
+        assert_eq!(None, parse("1fr 50%"));
+        assert_eq!(None, parse("repeat"));
+    }
+
+    #[test]
+    fn test_resolve_columns() {
+        let tracks = [TrackSize::Px(100.0), TrackSize::Fr(1.0), TrackSize::Fr(3.0)];
+        // 固定の100pxを除いた残りの400pxを、1:3に分ける
+        assert_eq!(
+            [100, 100, 300].to_vec(),
+            resolve_tracks(&tracks, Some(500), &[])
+        );
+        // 残りがなければ、frのトラックは0になる
+        assert_eq!([100, 0, 0].to_vec(), resolve_tracks(&tracks, Some(80), &[]));
+
+        let tracks = [TrackSize::Auto, TrackSize::Fr(1.0)];
+        assert_eq!(
+            [40, 460].to_vec(),
+            resolve_tracks(&tracks, Some(500), &[40, 10])
+        );
+    }
+
+    #[test]
+    fn test_resolve_rows_without_available_size() {
+        let tracks = [TrackSize::Auto, TrackSize::Px(100.0), TrackSize::Fr(1.0)];
+        assert_eq!(
+            [20, 100, 30].to_vec(),
+            resolve_tracks(&tracks, None, &[20, 50, 30])
+        );
+    }
+
+    #[test]
+    fn test_cell_offset() {
+        let grid = GridLayout::new([100, 200].to_vec(), [20, 30, 40].to_vec(), 0, 0);
+        assert_eq!((0, 0), grid.cell_offset(0));
+        assert_eq!((100, 0), grid.cell_offset(1));
+        assert_eq!((0, 20), grid.cell_offset(2));
+        assert_eq!((100, 50), grid.cell_offset(5));
+        assert_eq!(90, grid.height());
+    }
+
+    #[test]
+    fn test_cell_offset_with_gaps() {
+        let grid = GridLayout::new([100, 200].to_vec(), [20, 30, 40].to_vec(), 16, 8);
+        assert_eq!((0, 0), grid.cell_offset(0));
+        assert_eq!((116, 0), grid.cell_offset(1));
+        assert_eq!((0, 28), grid.cell_offset(2));
+        assert_eq!((116, 66), grid.cell_offset(5));
+        // 最後の行の後には間隔を入れない
+        assert_eq!(90 + 8 * 2, grid.height());
+    }
+
+    #[test]
+    fn test_parse_gap() {
+        let values: Vec<CssToken> = CssTokenizer::new("16px 0 normal 1em".to_string()).collect();
+        assert_eq!(
+            [Some(16.0), Some(0.0), Some(0.0), None].to_vec(),
+            values.iter().map(parse_gap).collect::<Vec<_>>()
+        );
+    }
+}
@@ -1,15 +1,29 @@
-use crate::constants::{
-    CHAR_HEIGHT_WITH_PADDING, CHAR_WIDTH, CONTENT_AREA_WIDTH, WINDOW_PADDING, WINDOW_WIDTH,
-};
+use crate::constants::CHAR_HEIGHT;
 use crate::display_item::DisplayItem;
-use crate::renderer::css::cssom::{ComponentValue, Declaration, Selector, StyleSheet};
-use crate::renderer::dom::node::{Node, NodeKind};
-use crate::renderer::layout::computed_style::{Color, ComputedStyle, DisplayType, FontSize};
+use crate::renderer::css::cssom::{
+    ComponentValue, Declaration, PseudoElement, QualifiedRule, Selector, StyleSheet,
+};
+use crate::renderer::dom::form::{input_type, is_text_input, SelectState};
+use crate::renderer::dom::node::{ElementKind, Node, NodeKind};
+use crate::renderer::layout::computed_style::{
+    Color, ComputedStyle, DisplayType, FlexWrap, Float, FontSize, FontStyle, Overflow, StyleCache,
+    Visibility, WhiteSpace, Width, WordWrap,
+};
+use crate::renderer::layout::flex::FlexLayout;
+use crate::renderer::layout::grid::{parse_gap, parse_track_list, GridLayout, TrackSize};
+use crate::renderer::layout::text::{fitting_chars, font_scale, line_height, text_width};
 use alloc::rc::{Rc, Weak};
 use alloc::string::{String, ToString};
 use alloc::vec;
 use alloc::vec::Vec;
 use core::cell::RefCell;
+use core::cmp::{max, min};
+
+// progressとmeterの大きさ
+static GAUGE_WIDTH: i64 = 160;
+static GAUGE_HEIGHT: i64 = CHAR_HEIGHT;
+// inputの横幅
+static INPUT_WIDTH: i64 = 160;
 
 #[derive(Debug, Clone)]
 pub struct LayoutObject {
@@ -21,6 +35,12 @@ pub struct LayoutObject {
     style: ComputedStyle,
     point: LayoutPoint,
     size: LayoutSize,
+    // 文字の大きさとpxで指定された横幅に掛ける倍率(%)
+    zoom: i64,
+    // display: gridのとき、子のサイズを決めた後の列の幅と行の高さ
+    grid: Option<GridLayout>,
+    // display: flexのとき、子のサイズを決めた後の子の位置
+    flex: Option<FlexLayout>,
 }
 
 impl LayoutObject {
@@ -30,22 +50,52 @@ impl LayoutObject {
             None => Weak::new(),
         };
 
+        // 要素であれば、ユーザーエージェントのデフォルトスタイルを土台にする
+        let style = match node.borrow().element_kind() {
+            Some(kind) => ComputedStyle::default_for(&kind),
+            None => ComputedStyle::new(),
+        };
+
         Self {
             kind: LayoutObjectKind::Block,
             node: node.clone(),
             first_child: None,
             next_sibling: None,
             parent,
-            style: ComputedStyle::new(),
+            style,
             point: LayoutPoint::new(0, 0),
             size: LayoutSize::new(0, 0),
+            zoom: 100,
+            grid: None,
+            flex: None,
         }
     }
 
+    pub fn set_zoom(&mut self, zoom: i64) {
+        self.zoom = zoom;
+    }
+
+    fn zoomed(&self, length: i64) -> i64 {
+        length * self.zoom / 100
+    }
+
+    // このテキストの文字の大きさで、文字列を1行に描画したときの横幅
+    fn text_width(&self, s: &str) -> i64 {
+        self.zoomed(text_width(s, self.style.font_size()))
+    }
+
+    fn line_height(&self) -> i64 {
+        self.zoomed(line_height(self.style.font_size()))
+    }
+
     pub fn kind(&self) -> LayoutObjectKind {
         self.kind
     }
 
+    pub fn node(&self) -> Rc<RefCell<Node>> {
+        self.node.clone()
+    }
+
     pub fn node_kind(&self) -> NodeKind {
         self.node.borrow().kind().clone()
     }
@@ -74,41 +124,117 @@ impl LayoutObject {
         self.style.clone()
     }
 
+    pub fn set_style(&mut self, style: ComputedStyle) {
+        self.style = style;
+    }
+
     pub fn point(&self) -> LayoutPoint {
         self.point
     }
 
+    pub fn set_point(&mut self, point: LayoutPoint) {
+        self.point = point;
+    }
+
+    // display: gridのとき、pxのトラックに倍率を掛けた(列, 行)のトラック
+    // 列の指定がなければ、内容の大きさの1列にする
+    pub fn grid_template(&self) -> Option<(Vec<TrackSize>, Vec<TrackSize>)> {
+        if self.style.display() != DisplayType::Grid {
+            return None;
+        }
+
+        let zoom = |tracks: Vec<TrackSize>| -> Vec<TrackSize> {
+            tracks
+                .into_iter()
+                .map(|t| match t {
+                    TrackSize::Px(px) => TrackSize::Px(self.zoomed(px as i64) as f64),
+                    t => t,
+                })
+                .collect()
+        };
+        let mut columns = zoom(self.style.grid_template_columns());
+        if columns.is_empty() {
+            columns.push(TrackSize::Auto);
+        }
+        Some((columns, zoom(self.style.grid_template_rows())))
+    }
+
+    // 倍率を掛けた(列, 行)の間隔。グリッドとフレックスで使う
+    pub fn gaps(&self) -> (i64, i64) {
+        (
+            self.zoomed(self.style.column_gap() as i64),
+            self.zoomed(self.style.row_gap() as i64),
+        )
+    }
+
+    pub fn grid(&self) -> Option<GridLayout> {
+        self.grid.clone()
+    }
+
+    pub fn set_grid(&mut self, grid: Option<GridLayout>) {
+        self.grid = grid;
+    }
+
+    // display: flexのときだけ、flex-wrapの値を返す
+    pub fn flex_wrap(&self) -> Option<FlexWrap> {
+        if self.style.display() != DisplayType::Flex {
+            return None;
+        }
+        Some(self.style.flex_wrap())
+    }
+
+    pub fn flex(&self) -> Option<FlexLayout> {
+        self.flex.clone()
+    }
+
+    pub fn set_flex(&mut self, flex: Option<FlexLayout>) {
+        self.flex = flex;
+    }
+
+    pub fn is_float(&self) -> bool {
+        self.style.float() != Float::None
+    }
+
+    // widthを指定していないfloatのブロックは、内容の横幅まで縮める
+    // 子ノードのサイズが決まった後に呼び出す
+    pub fn shrink_to_fit(&mut self) {
+        if self.is_float() {
+            self.shrink_to_content();
+        }
+    }
+
+    // widthを指定していないブロックの横幅を、内容の横幅まで縮める
+    // floatのブロックとフレックスアイテムに使う
+    pub fn shrink_to_content(&mut self) {
+        if self.kind != LayoutObjectKind::Block || self.style.width() != Width::Auto {
+            return;
+        }
+
+        // 横に並ぶインライン要素は横幅を足し合わせ、最も長い行の横幅を内容の横幅とする
+        let mut content_width = 0;
+        let mut line_width = 0;
+        let mut child = self.first_child();
+        while let Some(c) = child {
+            if c.borrow().kind() == LayoutObjectKind::Block {
+                line_width = 0;
+                content_width = max(content_width, c.borrow().size.width());
+            } else {
+                line_width += c.borrow().size.width();
+                content_width = max(content_width, line_width);
+            }
+            child = c.borrow().next_sibling();
+        }
+
+        self.size.set_width(min(self.size.width(), content_width));
+    }
+
     pub fn size(&self) -> LayoutSize {
         self.size
     }
 
     pub fn is_node_selected(&self, selector: &Selector) -> bool {
         match &self.node_kind() {
-            NodeKind::Element(e) => match selector {
-                Selector::TypeSelector(type_name) => {
-                    if e.kind().to_string() == *type_name {
-                        return true;
-                    }
-                    false
-                }
-                Selector::ClassSelector(class_name) => {
-                    for attr in &e.attributes() {
-                        if attr.name() == "class" && attr.value() == *class_name {
-                            return true;
-                        }
-                    }
-                    false
-                }
-                Selector::IdSelector(id_name) => {
-                    for attr in &e.attributes() {
-                        if attr.name() == "id" && attr.value() == *id_name {
-                            return true;
-                        }
-                    }
-                    false
-                }
-                Selector::UnknownSelector => false,
-            },
+            NodeKind::Element(e) => selector.matches(e),
             _ => false,
         }
     }
@@ -160,6 +286,108 @@ impl LayoutObject {
                         self.style.set_display(display_type);
                     }
                 }
+                "font-style" => {
+                    if let ComponentValue::Ident(value) = &declaration.value {
+                        if let Ok(font_style) = FontStyle::from_str(value) {
+                            self.style.set_font_style(font_style);
+                        }
+                    }
+                }
+                "white-space" => {
+                    if let ComponentValue::Ident(value) = &declaration.value {
+                        if let Ok(white_space) = WhiteSpace::from_str(value) {
+                            self.style.set_white_space(white_space);
+                        }
+                    }
+                }
+                "overflow" => {
+                    if let ComponentValue::Ident(value) = &declaration.value {
+                        if let Ok(overflow) = Overflow::from_str(value) {
+                            self.style.set_overflow(overflow);
+                        }
+                    }
+                }
+                "visibility" => {
+                    if let ComponentValue::Ident(value) = &declaration.value {
+                        if let Ok(visibility) = Visibility::from_str(value) {
+                            self.style.set_visibility(visibility);
+                        }
+                    }
+                }
+                "float" => {
+                    if let ComponentValue::Ident(value) = &declaration.value {
+                        if let Ok(float) = Float::from_str(value) {
+                            self.style.set_float(float);
+                        }
+                    }
+                }
+                "word-wrap" | "overflow-wrap" => {
+                    if let ComponentValue::Ident(value) = &declaration.value {
+                        if let Ok(word_wrap) = WordWrap::from_str(value) {
+                            self.style.set_word_wrap(word_wrap);
+                        }
+                    }
+                }
+                "width" => match declaration.value {
+                    ComponentValue::Percentage(value) => {
+                        self.style.set_width(Width::Percentage(value))
+                    }
+                    ComponentValue::Number(value) => self.style.set_width(Width::Px(value)),
+                    ComponentValue::Dimension(value, ref unit) if unit == "px" => {
+                        self.style.set_width(Width::Px(value))
+                    }
+                    ComponentValue::Ident(ref value) if value == "auto" => {
+                        self.style.set_width(Width::Auto)
+                    }
+                    _ => {}
+                },
+                "grid-template-columns" | "grid-template-rows" => {
+                    if let Some(tracks) = parse_track_list(&declaration.values()) {
+                        if declaration.property == "grid-template-columns" {
+                            self.style.set_grid_template_columns(tracks);
+                        } else {
+                            self.style.set_grid_template_rows(tracks);
+                        }
+                    }
+                }
+                // `gap: 行 列`。値が1つなら行と列の両方に使う
+                "gap" => {
+                    let gaps: Option<Vec<f64>> =
+                        declaration.values().iter().map(parse_gap).collect();
+                    match gaps.as_deref() {
+                        Some([gap]) => {
+                            self.style.set_row_gap(*gap);
+                            self.style.set_column_gap(*gap);
+                        }
+                        Some([row, column]) => {
+                            self.style.set_row_gap(*row);
+                            self.style.set_column_gap(*column);
+                        }
+                        _ => {}
+                    }
+                }
+                "column-gap" => {
+                    if let Some(gap) = parse_gap(&declaration.value) {
+                        self.style.set_column_gap(gap);
+                    }
+                }
+                "row-gap" => {
+                    if let Some(gap) = parse_gap(&declaration.value) {
+                        self.style.set_row_gap(gap);
+                    }
+                }
+                "flex-wrap" => {
+                    if let ComponentValue::Ident(value) = &declaration.value {
+                        if let Ok(flex_wrap) = FlexWrap::from_str(value) {
+                            self.style.set_flex_wrap(flex_wrap);
+                        }
+                    }
+                }
+                "content" => {
+                    if let ComponentValue::StringToken(value) = declaration.value {
+                        self.style.set_content(value);
+                    }
+                }
                 _ => {}
             }
         }
@@ -179,7 +407,11 @@ impl LayoutObject {
             NodeKind::Element(_) => {
                 let display = self.style.display();
                 match display {
-                    DisplayType::Block => self.kind = LayoutObjectKind::Block,
+                    DisplayType::Block | DisplayType::Grid | DisplayType::Flex => {
+                        self.kind = LayoutObjectKind::Block
+                    }
+                    // floatを指定したインライン要素は、ブロック要素として扱う
+                    DisplayType::Inline if self.is_float() => self.kind = LayoutObjectKind::Block,
                     DisplayType::Inline => self.kind = LayoutObjectKind::Inline,
                     DisplayType::DisplayNone => {
                         panic!("should not create a layout object for a display:none")
@@ -195,11 +427,55 @@ impl LayoutObject {
 
         match self.kind() {
             LayoutObjectKind::Block => {
-                size.set_width(parent_size.width());
+                if let Some(svg_size) = self.svg_placeholder_size(parent_size) {
+                    self.size = svg_size;
+                    return;
+                }
+                if self.gauge_fill().is_some() {
+                    self.size =
+                        LayoutSize::new(min(GAUGE_WIDTH, parent_size.width()), GAUGE_HEIGHT);
+                    return;
+                }
+                if matches!(
+                    self.node.borrow().element_kind(),
+                    Some(ElementKind::Input) | Some(ElementKind::Select)
+                ) {
+                    self.size = match self.control_text() {
+                        Some(_) => LayoutSize::new(
+                            min(INPUT_WIDTH, parent_size.width()),
+                            self.line_height(),
+                        ),
+                        // type=hiddenのinputは場所を取らない
+                        None => LayoutSize::new(0, 0),
+                    };
+                    return;
+                }
+
+                // widthが指定されていなければ、包含ブロックの横幅いっぱいに広がる
+                let width = match self.style.width() {
+                    Width::Px(_) => self.style.width().resolve(0).map(|w| self.zoomed(w)),
+                    width => width.resolve(parent_size.width()),
+                }
+                .unwrap_or(parent_size.width());
+                size.set_width(width);
+
+                if let Some(grid) = &self.grid {
+                    size.set_height(grid.height());
+                    self.size = size;
+                    return;
+                }
+                if let Some(flex) = &self.flex {
+                    size.set_height(flex.height());
+                    self.size = size;
+                    return;
+                }
 
                 // すべての子ノードの高さを足し合わせた結果が高さになる
                 // ただし、インライン要素が横に並んでいる場合は注意が必要
+                // floatの子ノードは横に並べ、行ごとに最も高いものの高さを足す
                 let mut height = 0;
+                let mut float_row_width = 0;
+                let mut float_row_height = 0;
                 let mut child = self.first_child();
                 let mut previous_child_kind = LayoutObjectKind::Block;
                 while child.is_some() {
@@ -208,15 +484,38 @@ impl LayoutObject {
                         None => panic!("first child should exist"),
                     };
 
+                    let child_size = c.borrow().size;
+                    if c.borrow().is_float() {
+                        // 行に収まらなければ、次の行に送る
+                        if float_row_width > 0 && float_row_width + child_size.width() > width {
+                            height += float_row_height;
+                            float_row_width = 0;
+                            float_row_height = 0;
+                        }
+                        float_row_width += child_size.width();
+                        float_row_height = max(float_row_height, child_size.height());
+                        child = c.borrow().next_sibling();
+                        continue;
+                    }
+
+                    // floatの後に続く内容は、floatの行の下から始める
+                    if float_row_width > 0 {
+                        height += float_row_height;
+                        float_row_width = 0;
+                        float_row_height = 0;
+                        previous_child_kind = LayoutObjectKind::Block;
+                    }
+
                     if previous_child_kind == LayoutObjectKind::Block
                         || c.borrow().kind() == LayoutObjectKind::Block
                     {
-                        height += c.borrow().size.height();
+                        height += child_size.height();
                     }
 
                     previous_child_kind = c.borrow().kind();
                     child = c.borrow().next_sibling();
                 }
+                height += float_row_height;
                 size.set_height(height);
             }
             LayoutObjectKind::Inline => {
@@ -239,32 +538,193 @@ impl LayoutObject {
             }
             LayoutObjectKind::Text => {
                 if let NodeKind::Text(t) = self.node_kind() {
-                    let ratio = match self.style.font_size() {
-                        FontSize::Medium => 1,
-                        FontSize::XLarge => 2,
-                        FontSize::XXLarge => 3,
-                    };
-                    let width = CHAR_WIDTH * ratio * t.len() as i64;
-                    if width > CONTENT_AREA_WIDTH {
+                    // 改行の位置を決めるため、包含ブロックの横幅で行に分ける
+                    let lines = self.text_lines(&t, parent_size.width());
+                    // word-wrap: normalやwhite-space: nowrapのとき、テキストは包含ブロックからはみ出す
+                    let longest = lines
+                        .iter()
+                        .map(|line| self.text_width(line))
+                        .max()
+                        .unwrap_or(0);
+                    if lines.len() > 1 {
                         // テキストが複数行のとき
-                        size.set_width(CONTENT_AREA_WIDTH);
-                        let line_num = if width.wrapping_rem(CONTENT_AREA_WIDTH) == 0 {
-                            width.wrapping_div(CONTENT_AREA_WIDTH)
-                        } else {
-                            width.wrapping_div(CONTENT_AREA_WIDTH) + 1
-                        };
-                        size.set_height(CHAR_HEIGHT_WITH_PADDING * ratio * line_num);
+                        size.set_width(max(parent_size.width(), longest));
                     } else {
                         // テキストが1行に収まるとき
-                        size.set_width(width);
-                        size.set_height(CHAR_HEIGHT_WITH_PADDING * ratio);
+                        // 前後の空白は1文字分の幅として、隣のインライン要素との間隔に残す
+                        let edges = if t.trim().is_empty() {
+                            min(1, t.len())
+                        } else {
+                            [
+                                t.starts_with(char::is_whitespace),
+                                t.ends_with(char::is_whitespace),
+                            ]
+                            .iter()
+                            .filter(|edge| **edge)
+                            .count()
+                        };
+                        size.set_width(longest + self.text_width(" ") * edges as i64);
                     }
+                    size.set_height(self.line_height() * lines.len() as i64);
                 }
             }
         }
         self.size = size;
     }
 
+    fn text_lines(&self, t: &str, max_width: i64) -> Vec<String> {
+        match self.style.white_space() {
+            WhiteSpace::NoWrap => vec![plain_text(t)],
+            WhiteSpace::Normal => split_text(
+                plain_text(t),
+                |s| self.text_width(s),
+                max_width,
+                self.style.word_wrap(),
+            ),
+        }
+    }
+
+    // 包含ブロックがoverflow: hiddenのとき、1行に描画できる横幅
+    fn clip_width(&self) -> Option<i64> {
+        let mut parent = self.parent().upgrade();
+        while let Some(p) = parent {
+            if p.borrow().kind() == LayoutObjectKind::Block {
+                if p.borrow().style().overflow() != Overflow::Hidden {
+                    return None;
+                }
+                let right = p.borrow().point().x() + p.borrow().size().width();
+                return Some(max(0, right - self.point().x()));
+            }
+            parent = p.borrow().parent().upgrade();
+        }
+        None
+    }
+
+    // SVGは中身を描画せず、width/height属性(デフォルトは300x150)の大きさの四角として扱う
+    fn svg_placeholder_size(&self, parent_size: LayoutSize) -> Option<LayoutSize> {
+        let element = match self.node.borrow().get_element() {
+            Some(e) if e.kind() == ElementKind::Svg => e,
+            _ => return None,
+        };
+
+        let width = element
+            .get_attribute("width")
+            .and_then(|w| w.parse::<i64>().ok())
+            .unwrap_or(300);
+        let height = element
+            .get_attribute("height")
+            .and_then(|h| h.parse::<i64>().ok())
+            .unwrap_or(150);
+
+        Some(LayoutSize::new(
+            core::cmp::min(width, parent_size.width()),
+            height,
+        ))
+    }
+
+    // inputとselectの箱の中に表示する文字列。type=hiddenのinputのときはNone
+    fn control_text(&self) -> Option<String> {
+        let element = self.node.borrow().get_element()?;
+        if element.kind() == ElementKind::Select {
+            return Some(
+                SelectState::from_node(&self.node)
+                    .selected()
+                    .map(|o| o.label())
+                    .unwrap_or_default(),
+            );
+        }
+        match input_type(&element).as_str() {
+            "hidden" => None,
+            "submit" => Some(
+                element
+                    .get_attribute("value")
+                    .unwrap_or("Submit".to_string()),
+            ),
+            _ => Some(element.get_attribute("value").unwrap_or_default()),
+        }
+    }
+
+    // 値が空の入力欄に、値の代わりに表示するplaceholder属性の文字列
+    fn placeholder(&self) -> Option<String> {
+        let element = self.node.borrow().get_element()?;
+        if !is_text_input(&element)
+            || element
+                .get_attribute("value")
+                .is_some_and(|v| !v.is_empty())
+        {
+            return None;
+        }
+        element
+            .get_attribute("placeholder")
+            .filter(|p| !p.is_empty())
+    }
+
+    // progressとmeterで、トラックのうち塗りつぶす割合(0.0〜1.0)と色
+    fn gauge_fill(&self) -> Option<(f64, Color)> {
+        let element = self.node.borrow().get_element()?;
+        let number = |name: &str| {
+            element
+                .get_attribute(name)
+                .and_then(|v| v.trim().parse::<f64>().ok())
+        };
+        let clamp = |v: f64, low: f64, high: f64| v.max(low).min(high);
+        let color = |name: &str| Color::from_name(name).expect("color should be supported");
+
+        match element.kind() {
+            ElementKind::Progress => {
+                let max = match number("max") {
+                    Some(max) if max > 0.0 => max,
+                    _ => 1.0,
+                };
+                // valueがない(不確定な)場合は塗りつぶさない
+                let fraction = match number("value") {
+                    Some(value) => clamp(value, 0.0, max) / max,
+                    None => 0.0,
+                };
+                Some((fraction, color("blue")))
+            }
+            ElementKind::Meter => {
+                let min = number("min").unwrap_or(0.0);
+                let max = number("max").map_or(min.max(1.0), |max| max.max(min));
+                let value = clamp(number("value").unwrap_or(0.0), min, max);
+                let low = clamp(number("low").unwrap_or(min), min, max);
+                let high = clamp(number("high").unwrap_or(max), low, max);
+                let optimum = clamp(number("optimum").unwrap_or((min + max) / 2.0), min, max);
+
+                // optimumと同じ範囲なら緑、隣の範囲なら黄、反対側の範囲なら赤
+                let color_name = if optimum < low {
+                    if value < low {
+                        "green"
+                    } else if value <= high {
+                        "yellow"
+                    } else {
+                        "red"
+                    }
+                } else if optimum > high {
+                    if value > high {
+                        "green"
+                    } else if value >= low {
+                        "yellow"
+                    } else {
+                        "red"
+                    }
+                } else if value >= low && value <= high {
+                    "green"
+                } else {
+                    "yellow"
+                };
+
+                let fraction = if max > min {
+                    (value - min) / (max - min)
+                } else {
+                    0.0
+                };
+                Some((fraction, color(color_name)))
+            }
+            _ => None,
+        }
+    }
+
     pub fn compute_position(
         &mut self,
         parent_point: LayoutPoint,
@@ -284,7 +744,9 @@ impl LayoutObject {
                 }
                 point.set_x(parent_point.x());
             }
-            (LayoutObjectKind::Inline, _) | (_, LayoutObjectKind::Inline) => {
+            (LayoutObjectKind::Inline, _)
+            | (_, LayoutObjectKind::Inline)
+            | (LayoutObjectKind::Text, LayoutObjectKind::Text) => {
                 if let (Some(size), Some(pos)) = (previous_sibling_size, previous_sibling_point) {
                     point.set_x(pos.x() + size.width());
                     point.set_y(pos.y());
@@ -293,28 +755,73 @@ impl LayoutObject {
                     point.set_y(parent_point.y());
                 }
             }
-            _ => {
-                point.set_x(parent_point.x());
-                point.set_y(parent_point.y());
-            }
         }
 
         self.point = point;
     }
 
+    fn paint_control(&self) -> Vec<DisplayItem> {
+        let mut text = match self.control_text() {
+            Some(text) => text,
+            None => return vec![],
+        };
+        // placeholderは、値と区別できるように灰色で表示する
+        let mut text_style = self.style();
+        if let Some(placeholder) = self.placeholder() {
+            text = placeholder;
+            text_style.set_color(Color::from_name("gray").expect("gray should be supported"));
+        }
+
+        let mut items = vec![DisplayItem::Rect {
+            style: self.style(),
+            layout_point: self.point(),
+            layout_size: self.size(),
+        }];
+        // 箱からはみ出す部分は描画しない
+        let chars = fitting_chars(&text, &|s: &str| self.text_width(s), self.size().width());
+        if chars > 0 {
+            items.push(DisplayItem::Text {
+                text: text.chars().take(chars).collect(),
+                style: text_style,
+                layout_point: self.point(),
+            });
+        }
+        items
+    }
+
     pub fn paint(&mut self) -> Vec<DisplayItem> {
         if self.style.display() == DisplayType::DisplayNone {
             return vec![];
         }
+        // visibility: hiddenは場所を確保したまま描画しない
+        if self.style.visibility() == Visibility::Hidden {
+            return vec![];
+        }
 
         match self.kind {
             LayoutObjectKind::Block => {
-                if let NodeKind::Element(_e) = self.node_kind() {
-                    return vec![DisplayItem::Rect {
+                if let NodeKind::Element(e) = self.node_kind() {
+                    if matches!(e.kind(), ElementKind::Input | ElementKind::Select) {
+                        return self.paint_control();
+                    }
+                    let mut items = vec![DisplayItem::Rect {
                         style: self.style(),
                         layout_point: self.point(),
                         layout_size: self.size(),
                     }];
+                    if let Some((fraction, color)) = self.gauge_fill() {
+                        let mut style = self.style();
+                        style.set_background_color(color);
+                        items.push(DisplayItem::Rect {
+                            style,
+                            layout_point: self.point(),
+                            layout_size: LayoutSize::new(
+                                (self.size().width() as f64 * fraction) as i64,
+                                self.size().height(),
+                            ),
+                        });
+                    }
+                    return items;
                 }
             }
             LayoutObjectKind::Inline => {
@@ -324,26 +831,32 @@ impl LayoutObject {
                 if let NodeKind::Text(t) = self.node_kind() {
                     let mut v = vec![];
 
-                    let ratio = match self.style.font_size() {
-                        FontSize::Medium => 1,
-                        FontSize::XLarge => 2,
-                        FontSize::XXLarge => 3,
-                    };
-                    let plain_text = t
-                        .replace("\n", " ")
-                        .split(' ')
-                        .filter(|s| !s.is_empty())
-                        .collect::<Vec<_>>()
-                        .join(" ");
-                    let lines = split_text(plain_text, CHAR_WIDTH * ratio);
+                    let line_height = self.line_height();
+                    // 拡大したときは、倍率に最も近い大きさのフォントで描画する
+                    let mut style = self.style();
+                    style.set_font_size(font_size_for_scale(
+                        self.zoomed(font_scale(self.style.font_size()) * 100),
+                    ));
+                    // compute_sizeで決めた横幅で、同じように行に分ける
+                    let lines = self.text_lines(&t, self.size().width());
+                    let clip_width = self.clip_width();
                     let mut i = 0;
                     for line in lines {
+                        // overflow: hiddenの包含ブロックからはみ出した部分は描画しない
+                        let line = match clip_width {
+                            Some(clip_width) => {
+                                let chars =
+                                    fitting_chars(&line, &|s: &str| self.text_width(s), clip_width);
+                                line.chars().take(chars).collect()
+                            }
+                            None => line,
+                        };
                         let item = DisplayItem::Text {
                             text: line,
-                            style: self.style(),
+                            style: style.clone(),
                             layout_point: LayoutPoint::new(
                                 self.point().x(),
-                                self.point().y() + CHAR_HEIGHT_WITH_PADDING * i,
+                                self.point().y() + line_height * i,
                             ),
                         };
                         v.push(item);
@@ -359,6 +872,17 @@ impl LayoutObject {
     }
 }
 
+// 標準の文字の大きさを100としたときの大きさに、最も近いフォントの大きさ
+fn font_size_for_scale(scale: i64) -> FontSize {
+    if scale < 150 {
+        FontSize::Medium
+    } else if scale < 250 {
+        FontSize::XLarge
+    } else {
+        FontSize::XXLarge
+    }
+}
+
 impl PartialEq for LayoutObject {
     fn eq(&self, other: &LayoutObject) -> bool {
         self.kind() == other.kind()
@@ -432,25 +956,32 @@ pub fn create_layout_object(
     node: &Option<Rc<RefCell<Node>>>,
     parent_obj: &Option<Rc<RefCell<LayoutObject>>>,
     cssom: &StyleSheet,
+    style_cache: &mut StyleCache,
 ) -> Option<Rc<RefCell<LayoutObject>>> {
     if let Some(n) = node {
+        if is_hidden_in_closed_details(n) {
+            return None;
+        }
+
         let layout_object = Rc::new(RefCell::new(LayoutObject::new(n.clone(), parent_obj)));
 
-        for rule in &cssom.rules {
-            if layout_object.borrow().is_node_selected(&rule.selector) {
+        let parent_style = parent_obj.as_ref().map(|parent| parent.borrow().style());
+        match style_cache.get(n, &parent_style) {
+            Some(style) => layout_object.borrow_mut().set_style(style),
+            None => {
+                let declarations = cascaded_declarations(cssom, |rule| {
+                    rule.pseudo_element.is_none()
+                        && layout_object.borrow().is_node_selected(&rule.selector)
+                });
+                layout_object.borrow_mut().cascading_style(declarations);
                 layout_object
                     .borrow_mut()
-                    .cascading_style(rule.declarations.clone());
+                    .defaulting_style(n, parent_style.clone());
+
+                style_cache.insert(n, parent_style, layout_object.borrow().style());
             }
         }
 
-        let parent_style = if let Some(parent) = parent_obj {
-            Some(parent.borrow().style())
-        } else {
-            None
-        };
-        layout_object.borrow_mut().defaulting_style(n, parent_style);
-
         if layout_object.borrow_mut().style().display() == DisplayType::DisplayNone {
             return None;
         }
@@ -461,26 +992,133 @@ pub fn create_layout_object(
     None
 }
 
-fn find_index_for_line_break(line: String, max_index: usize) -> usize {
-    for i in (0..max_index).rev() {
-        if line.chars().collect::<Vec<char>>()[i] == ' ' {
-            return i;
-        }
+// open属性のないdetails要素の中では、summary要素以外の子ノードを表示しない
+fn is_hidden_in_closed_details(node: &Rc<RefCell<Node>>) -> bool {
+    if node.borrow().element_kind() == Some(ElementKind::Summary) {
+        return false;
     }
-    max_index
+    match node.borrow().parent().upgrade() {
+        Some(parent) => match parent.borrow().get_element() {
+            Some(e) => e.kind() == ElementKind::Details && !e.has_attribute("open"),
+            None => false,
+        },
+        None => false,
+    }
+}
+
+// `::before`/`::after`の内容を表すテキストのレイアウトオブジェクトを作る
+// DOMツリーには挿入せず、どこにもつながっていないテキストノードを使う
+pub fn create_pseudo_element_layout_object(
+    parent_obj: &Rc<RefCell<LayoutObject>>,
+    pseudo_element: PseudoElement,
+    cssom: &StyleSheet,
+) -> Option<Rc<RefCell<LayoutObject>>> {
+    if !matches!(parent_obj.borrow().node_kind(), NodeKind::Element(_)) {
+        return None;
+    }
+
+    let node = Rc::new(RefCell::new(Node::new(NodeKind::Text(String::new()))));
+    let layout_object = Rc::new(RefCell::new(LayoutObject::new(
+        node.clone(),
+        &Some(parent_obj.clone()),
+    )));
+
+    let declarations = cascaded_declarations(cssom, |rule| {
+        rule.pseudo_element == Some(pseudo_element)
+            && parent_obj.borrow().is_node_selected(&rule.selector)
+    });
+    layout_object.borrow_mut().cascading_style(declarations);
+
+    // contentプロパティがない疑似要素は生成されない
+    let content = layout_object.borrow().style().content()?;
+    node.borrow_mut().kind = NodeKind::Text(content);
+
+    let parent_style = parent_obj.borrow().style();
+    layout_object
+        .borrow_mut()
+        .defaulting_style(&node, Some(parent_style));
+
+    if layout_object.borrow().style().display() == DisplayType::DisplayNone {
+        return None;
+    }
+
+    layout_object.borrow_mut().update_kind();
+    Some(layout_object)
 }
 
-fn split_text(line: String, char_width: i64) -> Vec<String> {
+// 条件に合うルールの宣言を、優先度の低い順に並べる
+// ブラウザの既定のルールを作者のルールより先に置き、その中では詳細度の低い順に並べる
+// 詳細度が同じ場合はソース上の順番に並べ、後に書かれた宣言が後から適用されるようにする
+fn cascaded_declarations<F>(cssom: &StyleSheet, matches: F) -> Vec<Declaration>
+where
+    F: Fn(&QualifiedRule) -> bool,
+{
+    let mut declarations = cssom
+        .rules
+        .iter()
+        .filter(|rule| matches(rule))
+        .flat_map(|rule| {
+            let priority = (rule.origin, rule.specificity());
+            rule.declarations
+                .iter()
+                .map(move |declaration| (priority, declaration.clone()))
+        })
+        .collect::<Vec<_>>();
+
+    declarations.sort_by(|(a_priority, a), (b_priority, b)| {
+        a_priority
+            .cmp(b_priority)
+            .then(a.source_index.cmp(&b.source_index))
+    });
+
+    declarations
+        .into_iter()
+        .map(|(_, declaration)| declaration)
+        .collect()
+}
+
+// 連続する空白や改行を1つの空白にまとめる
+fn plain_text(t: &str) -> String {
+    t.replace("\n", " ")
+        .split(' ')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// 単語の区切りで、max_widthに収まるように行に分ける
+// 1つの単語がmax_widthを超える場合、word-wrap: break-wordなら単語の途中で改行する
+fn split_text<F: Fn(&str) -> i64>(
+    line: String,
+    measure: F,
+    max_width: i64,
+    word_wrap: WordWrap,
+) -> Vec<String> {
     let mut result: Vec<String> = vec![];
-    if line.len() as i64 * char_width > (WINDOW_WIDTH + WINDOW_PADDING) {
-        let s = line.split_at(find_index_for_line_break(
-            line.clone(),
-            ((WINDOW_WIDTH + WINDOW_PADDING) / char_width) as usize,
-        ));
-        result.push(s.0.to_string());
-        result.extend(split_text(s.1.trim().to_string(), char_width))
-    } else {
-        result.push(line);
+    let mut current = String::new();
+
+    for word in line.split(' ') {
+        if current.is_empty() {
+            current.push_str(word);
+        } else if measure(&current) + measure(" ") + measure(word) <= max_width {
+            current.push(' ');
+            current.push_str(word);
+        } else {
+            result.push(current);
+            current = word.to_string();
+        }
+
+        if word_wrap == WordWrap::BreakWord {
+            // 1文字も収まらないときでも、1行に1文字は置く
+            while current.chars().count() > 1 && measure(&current) > max_width {
+                let chars = max(1, fitting_chars(&current, &measure, max_width));
+                let rest = current.chars().skip(chars).collect::<String>();
+                result.push(current.chars().take(chars).collect::<String>());
+                current = rest;
+            }
+        }
     }
+
+    result.push(current);
     result
 }
@@ -1,76 +1,348 @@
 use crate::constants::CONTENT_AREA_WIDTH;
 use crate::display_item::DisplayItem;
-use crate::renderer::css::cssom::StyleSheet;
+use crate::renderer::css::cssom::{PseudoElement, StyleSheet};
 use crate::renderer::dom::api::get_target_element_node;
 use crate::renderer::dom::node::ElementKind;
 use crate::renderer::dom::node::Node;
+use crate::renderer::layout::computed_style::{FlexWrap, Float, StyleCache};
+use crate::renderer::layout::flex::FlexLayout;
+use crate::renderer::layout::grid::{cell_of, resolve_tracks, GridLayout, TrackSize};
 use crate::renderer::layout::layout_object::{
-    create_layout_object, LayoutObject, LayoutObjectKind, LayoutPoint, LayoutSize,
+    create_layout_object, create_pseudo_element_layout_object, LayoutObject, LayoutObjectKind,
+    LayoutPoint, LayoutSize,
 };
 use alloc::rc::Rc;
+use alloc::vec;
 use alloc::vec::Vec;
 use core::cell::RefCell;
+use core::cmp::max;
+
+// LayoutView::diffで比べる、ノードの位置と大きさ
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutProperty {
+    X,
+    Y,
+    Width,
+    Height,
+}
+
+// 2つのレイアウトツリーの、同じ位置にあるノードの値の違い
+// ノードは、ルートから何番目の子をたどったかの並びで表す。ルートは空の並び
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutDiff {
+    path: Vec<usize>,
+    property: LayoutProperty,
+    old_value: i64,
+    new_value: i64,
+}
+
+impl LayoutDiff {
+    pub fn path(&self) -> Vec<usize> {
+        self.path.clone()
+    }
+
+    pub fn property(&self) -> LayoutProperty {
+        self.property
+    }
+
+    pub fn old_value(&self) -> i64 {
+        self.old_value
+    }
+
+    pub fn new_value(&self) -> i64 {
+        self.new_value
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct LayoutView {
     root: Option<Rc<RefCell<LayoutObject>>>,
+    style_cache: StyleCache,
+    // 文字の大きさの倍率(%)
+    zoom: i64,
 }
 
 impl LayoutView {
     pub fn new(root: Rc<RefCell<Node>>, cssom: &StyleSheet) -> Self {
-        let body_root = get_target_element_node(Some(root), ElementKind::Body);
-
         let mut tree = Self {
-            root: build_layout_tree(&body_root, &None, cssom),
+            root: None,
+            style_cache: StyleCache::new(),
+            zoom: 100,
         };
 
-        tree.update_layout();
+        tree.relayout(root, cssom);
 
         tree
     }
 
+    // レイアウトツリーを作り直す。スタイルはキャッシュ済みのものを再利用する
+    pub fn relayout(&mut self, root: Rc<RefCell<Node>>, cssom: &StyleSheet) {
+        let body_root = get_target_element_node(Some(root), ElementKind::Body);
+
+        self.root = build_layout_tree(&body_root, &None, cssom, &mut self.style_cache);
+        Self::apply_zoom(&self.root, self.zoom);
+
+        self.update_layout();
+    }
+
+    // 倍率を変えて、レイアウトツリーを作り直さずに大きさと位置を計算し直す
+    pub fn set_zoom(&mut self, zoom: i64) {
+        self.zoom = zoom;
+        Self::apply_zoom(&self.root, zoom);
+        self.update_layout();
+    }
+
+    pub fn zoom(&self) -> i64 {
+        self.zoom
+    }
+
+    fn apply_zoom(node: &Option<Rc<RefCell<LayoutObject>>>, zoom: i64) {
+        if let Some(n) = node {
+            n.borrow_mut().set_zoom(zoom);
+            Self::apply_zoom(&n.borrow().first_child(), zoom);
+            Self::apply_zoom(&n.borrow().next_sibling(), zoom);
+        }
+    }
+
+    // スタイルシートが変わったときに呼び出す
+    pub fn invalidate_style_cache(&mut self) {
+        self.style_cache.clear();
+    }
+
+    // nodeやその子孫が変わったときに呼び出す
+    pub fn invalidate_node_style(&mut self, node: &Rc<RefCell<Node>>) {
+        self.style_cache.invalidate(node);
+    }
+
+    pub fn style_resolve_count(&self) -> usize {
+        self.style_cache.resolve_count()
+    }
+
     pub fn root(&self) -> Option<Rc<RefCell<LayoutObject>>> {
         self.root.clone()
     }
 
+    // レイアウトツリーのノードの数
+    pub fn node_count(&self) -> usize {
+        Self::count_nodes(&self.root)
+    }
+
+    // ルートを1としたときの、最も深いノードの深さ
+    pub fn max_depth(&self) -> usize {
+        Self::depth(&self.root)
+    }
+
+    fn count_nodes(node: &Option<Rc<RefCell<LayoutObject>>>) -> usize {
+        match node {
+            Some(n) => {
+                1 + Self::count_nodes(&n.borrow().first_child())
+                    + Self::count_nodes(&n.borrow().next_sibling())
+            }
+            None => 0,
+        }
+    }
+
+    fn depth(node: &Option<Rc<RefCell<LayoutObject>>>) -> usize {
+        match node {
+            Some(n) => {
+                let child_depth = 1 + Self::depth(&n.borrow().first_child());
+                let sibling_depth = Self::depth(&n.borrow().next_sibling());
+                child_depth.max(sibling_depth)
+            }
+            None => 0,
+        }
+    }
+
+    // 2つのレイアウトツリーを同じ位置のノードどうしで比べ、位置と大きさの違いを返す
+    // 片方のツリーにしかない位置のノードは比べない
+    pub fn diff(old: &LayoutView, new: &LayoutView) -> Vec<LayoutDiff> {
+        let mut diffs = Vec::new();
+        if let (Some(old_root), Some(new_root)) = (old.root(), new.root()) {
+            Self::diff_node(&old_root, &new_root, &mut Vec::new(), &mut diffs);
+        }
+        diffs
+    }
+
+    fn diff_node(
+        old: &Rc<RefCell<LayoutObject>>,
+        new: &Rc<RefCell<LayoutObject>>,
+        path: &mut Vec<usize>,
+        diffs: &mut Vec<LayoutDiff>,
+    ) {
+        let (old_point, old_size) = (old.borrow().point(), old.borrow().size());
+        let (new_point, new_size) = (new.borrow().point(), new.borrow().size());
+        for (property, old_value, new_value) in [
+            (LayoutProperty::X, old_point.x(), new_point.x()),
+            (LayoutProperty::Y, old_point.y(), new_point.y()),
+            (LayoutProperty::Width, old_size.width(), new_size.width()),
+            (LayoutProperty::Height, old_size.height(), new_size.height()),
+        ] {
+            if old_value != new_value {
+                diffs.push(LayoutDiff {
+                    path: path.clone(),
+                    property,
+                    old_value,
+                    new_value,
+                });
+            }
+        }
+
+        // 子ノードを先頭から順に組にして比べる
+        let mut old_child = old.borrow().first_child();
+        let mut new_child = new.borrow().first_child();
+        let mut index = 0;
+        while let (Some(o), Some(n)) = (old_child, new_child) {
+            path.push(index);
+            Self::diff_node(&o, &n, path, diffs);
+            path.pop();
+
+            old_child = o.borrow().next_sibling();
+            new_child = n.borrow().next_sibling();
+            index += 1;
+        }
+    }
+
     fn update_layout(&mut self) {
         Self::calculate_node_size(&self.root, LayoutSize::new(CONTENT_AREA_WIDTH, 0));
 
         Self::calculate_node_position(
             &self.root,
             LayoutPoint::new(0, 0),
+            CONTENT_AREA_WIDTH,
             LayoutObjectKind::Block,
             None,
             None,
+            None,
         )
     }
 
     fn calculate_node_size(node: &Option<Rc<RefCell<LayoutObject>>>, parent_size: LayoutSize) {
         if let Some(n) = node {
-            // ノードがブロック要素の場合、子ノードのレイアウトを計算する前に横幅を決める
-            if n.borrow().kind() == LayoutObjectKind::Block {
-                n.borrow_mut().compute_size(parent_size);
-            }
-
-            let first_child = n.borrow().first_child();
-            Self::calculate_node_size(&first_child, n.borrow().size());
+            Self::calculate_single_node_size(n, parent_size);
 
             let next_sibling = n.borrow().next_sibling();
             Self::calculate_node_size(&next_sibling, parent_size);
+        }
+    }
 
-            // 子ノードのサイズが決まった後にサイズを計算する
-            // ブロック用のとき、高さは子ノードの高さに依存する
-            // インライン要素のとき、高さも横幅も子ノードに依存する
+    // 兄弟ノードは含めず、nとその子孫のサイズを計算する
+    fn calculate_single_node_size(n: &Rc<RefCell<LayoutObject>>, parent_size: LayoutSize) {
+        // ノードがブロック要素の場合、子ノードのレイアウトを計算する前に横幅を決める
+        if n.borrow().kind() == LayoutObjectKind::Block {
             n.borrow_mut().compute_size(parent_size);
         }
+
+        let grid_template = n.borrow().grid_template();
+        let flex_wrap = n.borrow().flex_wrap();
+        if let Some((columns, rows)) = grid_template {
+            Self::calculate_grid_size(n, &columns, &rows);
+        } else if let Some(flex_wrap) = flex_wrap {
+            Self::calculate_flex_size(n, flex_wrap == FlexWrap::Wrap);
+        } else {
+            // インライン要素の子ノードは、包含ブロックの横幅で折り返す
+            let first_child = n.borrow().first_child();
+            let child_parent_size = if n.borrow().kind() == LayoutObjectKind::Block {
+                n.borrow().size()
+            } else {
+                parent_size
+            };
+            Self::calculate_node_size(&first_child, child_parent_size);
+        }
+
+        // 子ノードのサイズが決まった後にサイズを計算する
+        // ブロック用のとき、高さは子ノードの高さに依存する
+        // インライン要素のとき、高さも横幅も子ノードに依存する
+        n.borrow_mut().compute_size(parent_size);
+        n.borrow_mut().shrink_to_fit();
+    }
+
+    // グリッドコンテナの子のサイズと、列の幅と行の高さを決める
+    // 子は行優先の順にセルに置き、テンプレートの行が足りなければautoの行を足す
+    fn calculate_grid_size(
+        n: &Rc<RefCell<LayoutObject>>,
+        columns: &[TrackSize],
+        rows: &[TrackSize],
+    ) {
+        let container_size = n.borrow().size();
+        let (column_gap, row_gap) = n.borrow().gaps();
+        let mut children = Vec::new();
+        let mut child = n.borrow().first_child();
+        while let Some(c) = child {
+            child = c.borrow().next_sibling();
+            children.push(c);
+        }
+
+        // autoの列の幅を決めるため、まずコンテナの横幅で子の内容の大きさを測る
+        let mut content_widths = vec![0; columns.len()];
+        for (i, c) in children.iter().enumerate() {
+            Self::calculate_single_node_size(c, container_size);
+            let (_, column) = cell_of(i, columns.len());
+            content_widths[column] = max(content_widths[column], c.borrow().size().width());
+        }
+        // frの列は、列の間の間隔を除いた残りを分け合う
+        let column_gaps = column_gap * (columns.len() as i64 - 1);
+        let column_widths = resolve_tracks(
+            columns,
+            Some(container_size.width() - column_gaps),
+            &content_widths,
+        );
+
+        let mut rows = rows.to_vec();
+        let row_count = children.len().div_ceil(columns.len());
+        if rows.len() < row_count {
+            rows.resize(row_count, TrackSize::Auto);
+        }
+        let mut content_heights = vec![0; rows.len()];
+        for (i, c) in children.iter().enumerate() {
+            let (row, column) = cell_of(i, columns.len());
+            Self::calculate_single_node_size(
+                c,
+                LayoutSize::new(column_widths[column], container_size.height()),
+            );
+            content_heights[row] = max(content_heights[row], c.borrow().size().height());
+        }
+        let row_heights = resolve_tracks(&rows, None, &content_heights);
+
+        n.borrow_mut().set_grid(Some(GridLayout::new(
+            column_widths,
+            row_heights,
+            column_gap,
+            row_gap,
+        )));
+    }
+
+    // フレックスコンテナの子のサイズを決め、子を横に並べた位置を決める
+    // 子の横幅は、コンテナの横幅で測った内容の横幅にする
+    fn calculate_flex_size(n: &Rc<RefCell<LayoutObject>>, wrap: bool) {
+        let container_size = n.borrow().size();
+        let (column_gap, row_gap) = n.borrow().gaps();
+        let mut item_sizes = Vec::new();
+        let mut child = n.borrow().first_child();
+        while let Some(c) = child {
+            Self::calculate_single_node_size(&c, container_size);
+            c.borrow_mut().shrink_to_content();
+            let size = c.borrow().size();
+            item_sizes.push((size.width(), size.height()));
+            child = c.borrow().next_sibling();
+        }
+
+        n.borrow_mut().set_flex(Some(FlexLayout::new(
+            &item_sizes,
+            container_size.width(),
+            wrap,
+            column_gap,
+            row_gap,
+        )));
     }
 
     fn calculate_node_position(
         node: &Option<Rc<RefCell<LayoutObject>>>,
         parent_point: LayoutPoint,
+        parent_width: i64,
         previous_sibling_kind: LayoutObjectKind,
         previous_sibling_point: Option<LayoutPoint>,
         previous_sibling_size: Option<LayoutSize>,
+        float_row: Option<FloatRow>,
     ) {
         if let Some(n) = node {
             n.borrow_mut().compute_position(
@@ -80,26 +352,87 @@ impl LayoutView {
                 previous_sibling_size,
             );
 
-            let first_child = n.borrow().first_child();
-            Self::calculate_node_position(
-                &first_child,
-                n.borrow().point(),
-                LayoutObjectKind::Block,
-                None,
-                None,
-            );
+            let float_row = if n.borrow().is_float() {
+                let row = float_row.unwrap_or_else(|| {
+                    FloatRow::new(parent_point.x(), parent_width, n.borrow().point().y())
+                });
+                let (point, row) = row.place(n.borrow().style().float(), n.borrow().size());
+                n.borrow_mut().set_point(point);
+                Some(row)
+            } else {
+                // floatの後に続く内容は、floatの行の下から始める
+                if let Some(row) = float_row {
+                    n.borrow_mut()
+                        .set_point(LayoutPoint::new(parent_point.x(), row.bottom()));
+                }
+                None
+            };
+
+            let grid = n.borrow().grid();
+            let flex = n.borrow().flex();
+            match (grid, flex) {
+                (Some(grid), _) => {
+                    Self::calculate_item_positions(n, |index| grid.cell_offset(index))
+                }
+                (None, Some(flex)) => {
+                    Self::calculate_item_positions(n, |index| flex.item_offset(index))
+                }
+                (None, None) => {
+                    let first_child = n.borrow().first_child();
+                    Self::calculate_node_position(
+                        &first_child,
+                        n.borrow().point(),
+                        n.borrow().size().width(),
+                        LayoutObjectKind::Block,
+                        None,
+                        None,
+                        None,
+                    );
+                }
+            }
 
             let next_sibling = n.borrow().next_sibling();
             Self::calculate_node_position(
                 &next_sibling,
                 parent_point,
+                parent_width,
                 n.borrow().kind(),
                 Some(n.borrow().point()),
                 Some(n.borrow().size()),
+                float_row,
             );
         }
     }
 
+    // グリッドやフレックスのコンテナの子を、offsetが返すコンテナの左上からの位置に置く
+    fn calculate_item_positions(
+        n: &Rc<RefCell<LayoutObject>>,
+        offset: impl Fn(usize) -> (i64, i64),
+    ) {
+        let point = n.borrow().point();
+        let mut child = n.borrow().first_child();
+        let mut index = 0;
+        while let Some(c) = child {
+            let (x, y) = offset(index);
+            c.borrow_mut()
+                .set_point(LayoutPoint::new(point.x() + x, point.y() + y));
+
+            let first_child = c.borrow().first_child();
+            Self::calculate_node_position(
+                &first_child,
+                c.borrow().point(),
+                c.borrow().size().width(),
+                LayoutObjectKind::Block,
+                None,
+                None,
+                None,
+            );
+
+            child = c.borrow().next_sibling();
+            index += 1;
+        }
+    }
+
     fn paint_node(node: &Option<Rc<RefCell<LayoutObject>>>, display_items: &mut Vec<DisplayItem>) {
         match node {
             Some(n) => {
@@ -159,27 +492,92 @@ impl LayoutView {
     }
 }
 
+// floatのブロックを横に並べている行
+// 左寄せのものは左端から、右寄せのものは右端から詰めていく
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FloatRow {
+    // 包含ブロックの左端と横幅
+    x: i64,
+    width: i64,
+    // 行の中で、まだ使われていない範囲
+    left: i64,
+    right: i64,
+    top: i64,
+    bottom: i64,
+}
+
+impl FloatRow {
+    fn new(x: i64, width: i64, top: i64) -> Self {
+        Self {
+            x,
+            width,
+            left: x,
+            right: x + width,
+            top,
+            bottom: top,
+        }
+    }
+
+    fn bottom(&self) -> i64 {
+        self.bottom
+    }
+
+    // floatのブロックを置く位置と、置いた後の行を返す
+    // 行に収まらなければ、次の行の先頭に置く
+    fn place(&self, float: Float, size: LayoutSize) -> (LayoutPoint, Self) {
+        let mut row = *self;
+        let used = row.width - (row.right - row.left);
+        if used > 0 && used + size.width() > row.width {
+            row = Self::new(row.x, row.width, row.bottom);
+        }
+
+        let x = if float == Float::Right {
+            row.right -= size.width();
+            row.right
+        } else {
+            row.left += size.width();
+            row.left - size.width()
+        };
+        row.bottom = max(row.bottom, row.top + size.height());
+
+        (LayoutPoint::new(x, row.top), row)
+    }
+}
+
 fn build_layout_tree(
     node: &Option<Rc<RefCell<Node>>>,
     parent_obj: &Option<Rc<RefCell<LayoutObject>>>,
     cssom: &StyleSheet,
+    style_cache: &mut StyleCache,
 ) -> Option<Rc<RefCell<LayoutObject>>> {
     let mut target_node = node.clone();
-    let mut layout_object = create_layout_object(node, parent_obj, cssom);
+    let mut layout_object = create_layout_object(node, parent_obj, cssom, style_cache);
     while layout_object.is_none() {
         if let Some(n) = target_node {
             target_node = n.borrow().next_sibling().clone();
-            layout_object = create_layout_object(&target_node, parent_obj, cssom);
+            layout_object = create_layout_object(&target_node, parent_obj, cssom, style_cache);
         } else {
             return layout_object;
         }
     }
 
     if let Some(n) = target_node {
-        let original_first_child = n.borrow().first_child();
+        // SVG、progress、meter、selectの子要素はレイアウトしない
+        let original_first_child = if matches!(
+            n.borrow().element_kind(),
+            Some(ElementKind::Svg)
+                | Some(ElementKind::Progress)
+                | Some(ElementKind::Meter)
+                | Some(ElementKind::Select)
+        ) {
+            None
+        } else {
+            n.borrow().first_child()
+        };
         let original_next_sibling = n.borrow().next_sibling();
-        let mut first_child = build_layout_tree(&original_first_child, &layout_object, cssom);
-        let mut next_sibling = build_layout_tree(&original_next_sibling, &None, cssom);
+        let mut first_child =
+            build_layout_tree(&original_first_child, &layout_object, cssom, style_cache);
+        let mut next_sibling = build_layout_tree(&original_next_sibling, &None, cssom, style_cache);
 
         if first_child.is_none() && original_first_child.is_some() {
             let mut original_dom_node = original_first_child
@@ -188,7 +586,8 @@ fn build_layout_tree(
                 .next_sibling();
 
             loop {
-                first_child = build_layout_tree(&original_dom_node, &layout_object, cssom);
+                first_child =
+                    build_layout_tree(&original_dom_node, &layout_object, cssom, style_cache);
 
                 if first_child.is_none() && original_dom_node.is_some() {
                     original_dom_node = original_dom_node
@@ -209,7 +608,7 @@ fn build_layout_tree(
                 .next_sibling();
 
             loop {
-                next_sibling = build_layout_tree(&original_dom_node, &None, cssom);
+                next_sibling = build_layout_tree(&original_dom_node, &None, cssom, style_cache);
 
                 if next_sibling.is_none() && original_dom_node.is_some() {
                     original_dom_node = original_dom_node
@@ -225,6 +624,7 @@ fn build_layout_tree(
             Some(ref obj) => obj,
             None => panic!("render object should exist here"),
         };
+        let first_child = insert_pseudo_elements(obj, first_child, cssom);
         obj.borrow_mut().set_first_child(first_child);
         obj.borrow_mut().set_next_sibling(next_sibling);
     }
@@ -232,15 +632,51 @@ fn build_layout_tree(
     layout_object
 }
 
+// `::before`を子ノードの先頭に、`::after`を子ノードの末尾に追加する
+fn insert_pseudo_elements(
+    obj: &Rc<RefCell<LayoutObject>>,
+    first_child: Option<Rc<RefCell<LayoutObject>>>,
+    cssom: &StyleSheet,
+) -> Option<Rc<RefCell<LayoutObject>>> {
+    let mut first_child = first_child;
+
+    if let Some(after) = create_pseudo_element_layout_object(obj, PseudoElement::After, cssom) {
+        match first_child {
+            Some(ref first) => {
+                let mut last_child = first.clone();
+                loop {
+                    let next = last_child.borrow().next_sibling();
+                    match next {
+                        Some(n) => last_child = n,
+                        None => break,
+                    }
+                }
+                last_child.borrow_mut().set_next_sibling(Some(after));
+            }
+            None => first_child = Some(after),
+        }
+    }
+
+    if let Some(before) = create_pseudo_element_layout_object(obj, PseudoElement::Before, cssom) {
+        before.borrow_mut().set_next_sibling(first_child);
+        first_child = Some(before);
+    }
+
+    first_child
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::constants::{CHAR_HEIGHT_WITH_PADDING, CHAR_WIDTH, CONTENT_AREA_WIDTH};
     use crate::renderer::css::cssom::CssParser;
     use crate::renderer::css::token::CssTokenizer;
-    use crate::renderer::dom::api::get_style_content;
+    use crate::renderer::dom::api::{get_element_nodes, get_style_content};
     use crate::renderer::dom::node::{Element, NodeKind};
     use crate::renderer::dom::parser::HtmlParser;
     use crate::renderer::html::token::HtmlTokenizer;
+    use crate::renderer::layout::computed_style::{Color, FontSize, FontStyle};
+    use alloc::format;
     use alloc::string::{String, ToString};
     use alloc::vec::Vec;
 
@@ -250,7 +686,9 @@ mod tests {
         let dom = window.borrow().document();
         let style = get_style_content(dom.clone());
         let css_tokenizer = CssTokenizer::new(style);
-        let cssom = CssParser::new(css_tokenizer).parse_stylesheet();
+        let cssom = CssParser::new(css_tokenizer)
+            .parse_stylesheet()
+            .expect("stylesheet should be valid");
         LayoutView::new(dom, &cssom)
     }
 
@@ -325,6 +763,60 @@ mod tests {
         assert_eq!(None, layout_view.root());
     }
 
+    fn text_colors(layout_view: &LayoutView) -> Vec<(String, Color)> {
+        layout_view
+            .paint()
+            .iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text { text, style, .. } => Some((text.clone(), style.color())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_later_rule_wins_on_equal_specificity() {
+        let html = r#"<html><head><style>
+            p { color: red; }
+            p { color: blue; }
+        </style></head><body><p>text</p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        assert_eq!(
+            [(
+                "text".to_string(),
+                Color::from_name("blue").expect("blue should be supported")
+            )]
+            .to_vec(),
+            text_colors(&layout_view)
+        );
+    }
+
+    #[test]
+    fn test_higher_specificity_wins_over_source_order() {
+        let html = r#"<html><head><style>
+            #target { color: red; }
+            .note { color: green; }
+            p { color: blue; }
+        </style></head><body><p id="target" class="note">id</p><p class="note">class</p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        assert_eq!(
+            [
+                (
+                    "id".to_string(),
+                    Color::from_name("red").expect("red should be supported")
+                ),
+                (
+                    "class".to_string(),
+                    Color::from_name("green").expect("green should be supported")
+                ),
+            ]
+            .to_vec(),
+            text_colors(&layout_view)
+        );
+    }
+
     #[test]
     fn test_hidden_class() {
         let html = r#"<html>
@@ -374,4 +866,905 @@ mod tests {
             .next_sibling()
             .is_none());
     }
+
+    #[test]
+    fn test_svg_placeholder() {
+        let html =
+            r#"<html><head></head><body><svg width="100" height="50"><rect/></svg></body></html>"#
+                .to_string();
+        let layout_view = create_layout_view(html);
+
+        let svg = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("svg should exist");
+        assert_eq!(LayoutObjectKind::Block, svg.borrow().kind());
+        assert_eq!(LayoutSize::new(100, 50), svg.borrow().size());
+        assert!(svg.borrow().first_child().is_none());
+        assert_eq!(
+            Color::from_name("gray").expect("gray should be supported"),
+            svg.borrow().style().background_color()
+        );
+    }
+
+    #[test]
+    fn test_pseudo_elements() {
+        let html = r#"<html><head><style>
+            p::before { content: "-> "; color: red; }
+            p::after { content: "!"; }
+        </style></head><body><p>hello</p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        let display_items = layout_view.paint();
+        let texts = display_items
+            .iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text {
+                    text,
+                    style,
+                    layout_point,
+                } => Some((text.clone(), style.color(), layout_point.x())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            [
+                (
+                    "->".to_string(),
+                    Color::from_name("red").expect("red should be supported"),
+                    0
+                ),
+                ("hello".to_string(), Color::black(), CHAR_WIDTH * 3),
+                ("!".to_string(), Color::black(), CHAR_WIDTH * 8),
+            ]
+            .to_vec(),
+            texts
+        );
+    }
+
+    #[test]
+    fn test_metrics_empty() {
+        let layout_view = create_layout_view("".to_string());
+        assert_eq!(0, layout_view.node_count());
+        assert_eq!(0, layout_view.max_depth());
+    }
+
+    #[test]
+    fn test_metrics() {
+        // body > (p > text, p > (a > text), div)
+        let html = "<html><head></head><body><p>a</p><p><a>link</a></p><div></div></body></html>"
+            .to_string();
+        let layout_view = create_layout_view(html);
+        assert_eq!(7, layout_view.node_count());
+        assert_eq!(4, layout_view.max_depth());
+    }
+
+    #[test]
+    fn test_metrics_skip_display_none() {
+        let html = r#"<html><head><style>.hidden { display: none; }</style></head>
+<body><p class="hidden"><a>hidden</a></p><p>text</p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        assert_eq!(3, layout_view.node_count());
+        assert_eq!(3, layout_view.max_depth());
+    }
+
+    #[test]
+    fn test_italic_text() {
+        let html = r#"<html><head><style>
+            .note { font-style: italic; }
+            em { font-style: normal; }
+        </style></head><body><p class="note">note</p><p><i>i</i><em>em</em>text</p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        let texts = layout_view
+            .paint()
+            .iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text { text, style, .. } => Some((text.clone(), style.font_style())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            [
+                ("note".to_string(), FontStyle::Italic),
+                ("i".to_string(), FontStyle::Italic),
+                ("em".to_string(), FontStyle::Normal),
+                ("text".to_string(), FontStyle::Normal),
+            ]
+            .to_vec(),
+            texts
+        );
+    }
+
+    fn text_lines(layout_view: &LayoutView) -> Vec<(String, i64)> {
+        layout_view
+            .paint()
+            .iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text {
+                    text, layout_point, ..
+                } => Some((text.clone(), layout_point.y())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_break_word() {
+        // コンテンツ領域より長い、空白を含まない単語
+        let chars_per_line = (CONTENT_AREA_WIDTH / CHAR_WIDTH) as usize;
+        let word = "a".repeat(chars_per_line * 2 + 3);
+        let html = format!(
+            "<html><head><style>p {{ word-wrap: break-word; }}</style></head><body><p>{}</p></body></html>",
+            word
+        );
+        let layout_view = create_layout_view(html);
+
+        let lines = text_lines(&layout_view);
+        let top = lines[0].1;
+        assert_eq!(
+            [
+                ("a".repeat(chars_per_line), top),
+                ("a".repeat(chars_per_line), top + CHAR_HEIGHT_WITH_PADDING),
+                ("aaa".to_string(), top + CHAR_HEIGHT_WITH_PADDING * 2),
+            ]
+            .to_vec(),
+            lines
+        );
+    }
+
+    #[test]
+    fn test_overflow_wrap_moves_long_word_to_next_line() {
+        let chars_per_line = (CONTENT_AREA_WIDTH / CHAR_WIDTH) as usize;
+        let word = "b".repeat(chars_per_line + 1);
+        let html = format!(
+            "<html><head><style>body {{ overflow-wrap: break-word; }}</style></head><body><p>short {}</p></body></html>",
+            word
+        );
+        let layout_view = create_layout_view(html);
+
+        // 単語の途中で改行する前に、まず単語の区切りで改行する
+        let texts = text_lines(&layout_view)
+            .into_iter()
+            .map(|(text, _)| text)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            [
+                "short".to_string(),
+                "b".repeat(chars_per_line),
+                "b".to_string()
+            ]
+            .to_vec(),
+            texts
+        );
+    }
+
+    #[test]
+    fn test_long_word_overflows_without_break_word() {
+        let chars_per_line = (CONTENT_AREA_WIDTH / CHAR_WIDTH) as usize;
+        let word = "c".repeat(chars_per_line * 2);
+        let html = format!("<html><head></head><body><p>{}</p></body></html>", word);
+        let layout_view = create_layout_view(html);
+
+        let texts = text_lines(&layout_view)
+            .into_iter()
+            .map(|(text, _)| text)
+            .collect::<Vec<_>>();
+        assert_eq!([word].to_vec(), texts);
+    }
+
+    #[test]
+    fn test_nowrap_keeps_text_on_one_line() {
+        let chars_per_line = (CONTENT_AREA_WIDTH / CHAR_WIDTH) as usize;
+        // 1行に収まらない、空白を含むテキスト
+        let text = "word ".repeat(chars_per_line / 2).trim_end().to_string();
+        let html = format!(
+            "<html><head><style>p {{ white-space: nowrap; }}</style></head><body><p>{}</p><div>{}</div></body></html>",
+            text, text
+        );
+        let layout_view = create_layout_view(html);
+
+        let lines = text_lines(&layout_view);
+        // pの中のテキストははみ出したまま1行に並び、divは折り返される
+        assert_eq!(text, lines[0].0);
+        assert_eq!(CHAR_HEIGHT_WITH_PADDING, lines[1].1 - lines[0].1);
+        assert!(lines.len() > 2);
+    }
+
+    #[test]
+    fn test_overflow_hidden_clips_nowrap_text() {
+        let chars_per_line = (CONTENT_AREA_WIDTH / CHAR_WIDTH) as usize;
+        let text = "word ".repeat(chars_per_line / 2).trim_end().to_string();
+        let html = format!(
+            "<html><head><style>p {{ white-space: nowrap; overflow: hidden; }}</style></head><body><p>{}</p></body></html>",
+            text
+        );
+        let layout_view = create_layout_view(html);
+
+        let lines = text_lines(&layout_view);
+        assert_eq!(1, lines.len());
+        assert_eq!(
+            text.chars().take(chars_per_line).collect::<String>(),
+            lines[0].0
+        );
+    }
+
+    #[test]
+    fn test_custom_element_renders_children_inline() {
+        let html = "<html><head></head><body><custom>hello</custom></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        let custom = layout_view
+            .root()
+            .and_then(|body| body.borrow().first_child())
+            .expect("custom element should exist");
+        assert_eq!(LayoutObjectKind::Inline, custom.borrow().kind());
+
+        assert_eq!(
+            [("hello".to_string(), 0)].to_vec(),
+            text_lines(&layout_view)
+        );
+    }
+
+    #[test]
+    fn test_percentage_width() {
+        let html = "<html><head><style>.half { width: 50%; }</style></head><body><div class=\"half\"><div class=\"half\">text</div></div><p>full</p></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        let outer = layout_view
+            .root()
+            .and_then(|body| body.borrow().first_child())
+            .expect("outer div should exist");
+        assert_eq!(CONTENT_AREA_WIDTH / 2, outer.borrow().size().width());
+
+        // 入れ子の場合は、親の解決済みの横幅に対する割合になる
+        let inner = outer
+            .borrow()
+            .first_child()
+            .expect("inner div should exist");
+        assert_eq!(CONTENT_AREA_WIDTH / 4, inner.borrow().size().width());
+
+        // widthを指定しないブロックは、包含ブロックの横幅いっぱいに広がる
+        let p = outer.borrow().next_sibling().expect("p should exist");
+        assert_eq!(CONTENT_AREA_WIDTH, p.borrow().size().width());
+    }
+
+    // テキストの表示項目の(テキスト, y座標, フォントの大きさ)
+    fn text_fonts(layout_view: &LayoutView) -> Vec<(String, i64, FontSize)> {
+        layout_view
+            .paint()
+            .iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text {
+                    text,
+                    style,
+                    layout_point,
+                } => Some((text.clone(), layout_point.y(), style.font_size())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_zoom_scales_text() {
+        let html = "<html><head></head><body><p>first</p><p>second</p><h1>big</h1></body></html>"
+            .to_string();
+        let mut layout_view = create_layout_view(html);
+        assert_eq!(100, layout_view.zoom());
+        assert_eq!(
+            [
+                ("first".to_string(), 0, FontSize::Medium),
+                (
+                    "second".to_string(),
+                    CHAR_HEIGHT_WITH_PADDING,
+                    FontSize::Medium
+                ),
+                (
+                    "big".to_string(),
+                    CHAR_HEIGHT_WITH_PADDING * 2,
+                    FontSize::XXLarge
+                )
+            ]
+            .to_vec(),
+            text_fonts(&layout_view)
+        );
+        let first = layout_view
+            .root()
+            .and_then(|body| body.borrow().first_child())
+            .and_then(|p| p.borrow().first_child())
+            .expect("text should exist");
+        assert_eq!(
+            LayoutSize::new(CHAR_WIDTH * 5, CHAR_HEIGHT_WITH_PADDING),
+            first.borrow().size()
+        );
+
+        // 倍率を2倍にすると、行の高さと位置も2倍になる
+        layout_view.set_zoom(200);
+        assert_eq!(
+            [
+                ("first".to_string(), 0, FontSize::XLarge),
+                (
+                    "second".to_string(),
+                    CHAR_HEIGHT_WITH_PADDING * 2,
+                    FontSize::XLarge
+                ),
+                (
+                    "big".to_string(),
+                    CHAR_HEIGHT_WITH_PADDING * 4,
+                    FontSize::XXLarge
+                )
+            ]
+            .to_vec(),
+            text_fonts(&layout_view)
+        );
+        assert_eq!(
+            LayoutSize::new(CHAR_WIDTH * 10, CHAR_HEIGHT_WITH_PADDING * 2),
+            first.borrow().size()
+        );
+    }
+
+    #[test]
+    fn test_zoom_scales_px_width() {
+        let html = "<html><head><style>.box { width: 100px; } .half { width: 50%; }</style></head><body><div class=\"box\">a</div><div class=\"half\">b</div></body></html>".to_string();
+        let mut layout_view = create_layout_view(html);
+        layout_view.set_zoom(150);
+
+        // 割合で指定した横幅は、包含ブロックに対する割合のまま
+        assert_eq!(
+            [
+                (0, 0, 150),
+                (0, CHAR_HEIGHT_WITH_PADDING * 3 / 2, CONTENT_AREA_WIDTH / 2)
+            ]
+            .to_vec(),
+            child_boxes(&layout_view)
+        );
+    }
+
+    // bodyの子ノードの(x, y, 横幅)を返す
+    fn child_boxes(layout_view: &LayoutView) -> Vec<(i64, i64, i64)> {
+        let mut boxes = Vec::new();
+        let mut child = layout_view
+            .root()
+            .and_then(|body| body.borrow().first_child());
+        while let Some(c) = child {
+            boxes.push((
+                c.borrow().point().x(),
+                c.borrow().point().y(),
+                c.borrow().size().width(),
+            ));
+            child = c.borrow().next_sibling();
+        }
+        boxes
+    }
+
+    #[test]
+    fn test_left_floats_side_by_side() {
+        let html = "<html><head><style>.l { float: left; }</style></head><body><div class=\"l\">one</div><div class=\"l\">three</div><p>after</p></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        // widthを指定しないfloatは内容の横幅まで縮み、横に並ぶ
+        // floatの後のブロックは、floatの行の下から始まる
+        assert_eq!(
+            [
+                (0, 0, CHAR_WIDTH * 3),
+                (CHAR_WIDTH * 3, 0, CHAR_WIDTH * 5),
+                (0, CHAR_HEIGHT_WITH_PADDING, CONTENT_AREA_WIDTH),
+            ]
+            .to_vec(),
+            child_boxes(&layout_view)
+        );
+        let body = layout_view.root().expect("body should exist");
+        assert_eq!(CHAR_HEIGHT_WITH_PADDING * 2, body.borrow().size().height());
+    }
+
+    #[test]
+    fn test_floats_wrap_when_row_is_full() {
+        let html = "<html><head><style>.l { float: left; width: 40%; } .r { float: right; width: 40%; }</style></head><body><div class=\"l\">a</div><div class=\"r\">b</div><div class=\"l\">c</div></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        let width = CONTENT_AREA_WIDTH * 2 / 5;
+        assert_eq!(
+            [
+                (0, 0, width),
+                (CONTENT_AREA_WIDTH - width, 0, width),
+                // 3つ目は行に収まらないので、次の行に送られる
+                (0, CHAR_HEIGHT_WITH_PADDING, width),
+            ]
+            .to_vec(),
+            child_boxes(&layout_view)
+        );
+        let body = layout_view.root().expect("body should exist");
+        assert_eq!(CHAR_HEIGHT_WITH_PADDING * 2, body.borrow().size().height());
+    }
+
+    #[test]
+    fn test_section_stacks_paragraphs() {
+        let html = "<html><head></head><body><header><p>title</p></header><section><p>first</p><p>second</p></section></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        let section = layout_view
+            .root()
+            .and_then(|body| body.borrow().first_child())
+            .and_then(|header| header.borrow().next_sibling())
+            .expect("section should exist");
+        assert_eq!(LayoutObjectKind::Block, section.borrow().kind());
+        assert_eq!(
+            NodeKind::Element(Element::new("section", Vec::new())),
+            section.borrow().node_kind()
+        );
+
+        assert_eq!(
+            [
+                ("title".to_string(), 0),
+                ("first".to_string(), CHAR_HEIGHT_WITH_PADDING),
+                ("second".to_string(), CHAR_HEIGHT_WITH_PADDING * 2),
+            ]
+            .to_vec(),
+            text_lines(&layout_view)
+        );
+    }
+
+    // トラックと塗りつぶし部分の四角の(横幅, 背景色)を返す
+    fn gauge_rects(element: &str) -> Vec<(i64, Color)> {
+        let html = format!("<html><head></head><body>{}</body></html>", element);
+        let layout_view = create_layout_view(html);
+        layout_view
+            .paint()
+            .iter()
+            .filter_map(|item| match item {
+                DisplayItem::Rect {
+                    style, layout_size, ..
+                } if style.background_color() != Color::white() => {
+                    Some((layout_size.width(), style.background_color()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_progress() {
+        let lightgray = Color::from_name("lightgray").expect("lightgray should be supported");
+        let blue = Color::from_name("blue").expect("blue should be supported");
+
+        assert_eq!(
+            [(160, lightgray.clone()), (80, blue.clone())].to_vec(),
+            gauge_rects(r#"<progress value="50" max="100">50%</progress>"#)
+        );
+        assert_eq!(
+            [(160, lightgray.clone()), (40, blue.clone())].to_vec(),
+            gauge_rects(r#"<progress value="1" max="4"></progress>"#)
+        );
+        // maxがないときは1.0で、値はmaxを超えない
+        assert_eq!(
+            [(160, lightgray.clone()), (160, blue.clone())].to_vec(),
+            gauge_rects(r#"<progress value="30"></progress>"#)
+        );
+        // valueがないときは塗りつぶさない
+        assert_eq!(
+            [(160, lightgray), (0, blue)].to_vec(),
+            gauge_rects(r#"<progress max="100"></progress>"#)
+        );
+    }
+
+    #[test]
+    fn test_meter() {
+        let lightgray = Color::from_name("lightgray").expect("lightgray should be supported");
+        let color = |name: &str| Color::from_name(name).expect("color should be supported");
+
+        assert_eq!(
+            [(160, lightgray.clone()), (96, color("green"))].to_vec(),
+            gauge_rects(r#"<meter value="0.6" min="0" max="1" low="0.3" high="0.7"></meter>"#)
+        );
+        assert_eq!(
+            [(160, lightgray.clone()), (144, color("yellow"))].to_vec(),
+            gauge_rects(r#"<meter value="0.9" min="0" max="1" low="0.3" high="0.7"></meter>"#)
+        );
+        assert_eq!(
+            [(160, lightgray.clone()), (144, color("red"))].to_vec(),
+            gauge_rects(r#"<meter value="0.9" low="0.3" high="0.7" optimum="0"></meter>"#)
+        );
+        assert_eq!(
+            [(160, lightgray), (40, color("green"))].to_vec(),
+            gauge_rects(r#"<meter value="15" min="10" max="30"></meter>"#)
+        );
+    }
+
+    #[test]
+    fn test_pseudo_element_without_content() {
+        let html = r#"<html><head><style>
+            p::before { color: red; }
+        </style></head><body><p>hello</p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        let p = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("p should exist");
+        let text = p.borrow().first_child().expect("text should exist");
+        assert_eq!(
+            NodeKind::Text("hello".to_string()),
+            text.borrow().node_kind()
+        );
+        assert!(text.borrow().next_sibling().is_none());
+    }
+
+    #[test]
+    fn test_style_resolved_once_per_node() {
+        let html = r#"<html><head><style>
+            .hidden { display: none; }
+        </style></head><body><p class="hidden">a</p><p class="hidden">b</p><p>c</p></body></html>"#
+            .to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let dom = window.borrow().document();
+        let style = get_style_content(dom.clone());
+        let cssom = CssParser::new(CssTokenizer::new(style))
+            .parse_stylesheet()
+            .expect("stylesheet should be valid");
+
+        // body, 3つのp, テキスト"c"
+        let mut layout_view = LayoutView::new(dom.clone(), &cssom);
+        assert_eq!(5, layout_view.style_resolve_count());
+
+        layout_view.relayout(dom.clone(), &cssom);
+        assert_eq!(5, layout_view.style_resolve_count());
+
+        layout_view.invalidate_style_cache();
+        layout_view.relayout(dom, &cssom);
+        assert_eq!(10, layout_view.style_resolve_count());
+    }
+
+    // 表示項目のテキストと、そのy座標
+    fn text_positions(layout_view: &LayoutView) -> Vec<(String, i64)> {
+        layout_view
+            .paint()
+            .iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text {
+                    text, layout_point, ..
+                } => Some((text.clone(), layout_point.y())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_visibility_hidden_reserves_space() {
+        let html = |style: &str| {
+            format!("<html><head><style>.target {{ {} }}</style></head><body><p class=\"target\">hidden</p><p>after</p></body></html>", style)
+        };
+
+        let visible = create_layout_view(html("color: red;"));
+        let hidden = create_layout_view(html("visibility: hidden;"));
+        let none = create_layout_view(html("display: none;"));
+
+        // 描画はされないが、場所は確保されるので、後の兄弟の位置は変わらない
+        assert_eq!(
+            [("after".to_string(), CHAR_HEIGHT_WITH_PADDING)].to_vec(),
+            text_positions(&hidden)
+        );
+        assert_eq!(child_boxes(&visible), child_boxes(&hidden));
+        let target = hidden
+            .root()
+            .and_then(|body| body.borrow().first_child())
+            .expect("hidden p should exist");
+        assert_eq!(CHAR_HEIGHT_WITH_PADDING, target.borrow().size().height());
+        // 隠した要素の背景とテキストの分だけ、表示項目が少なくなる
+        assert_eq!(visible.paint().len() - 2, hidden.paint().len());
+
+        // display: noneでは場所も確保されない
+        assert_eq!([("after".to_string(), 0)].to_vec(), text_positions(&none));
+    }
+
+    #[test]
+    fn test_diff_reports_moved_node() {
+        let html = "<html><head></head><body><p>first</p><p>second</p></body></html>".to_string();
+        let old = create_layout_view(html.clone());
+        let new = create_layout_view(html);
+        assert!(LayoutView::diff(&old, &new).is_empty());
+
+        // 2つ目のpのテキストだけを下に動かす
+        let text = new
+            .root()
+            .and_then(|body| body.borrow().first_child())
+            .and_then(|p| p.borrow().next_sibling())
+            .and_then(|p| p.borrow().first_child())
+            .expect("text of the second p should exist");
+        let point = text.borrow().point();
+        text.borrow_mut()
+            .set_point(LayoutPoint::new(point.x(), point.y() + 5));
+
+        assert_eq!(
+            [LayoutDiff {
+                path: [1, 0].to_vec(),
+                property: LayoutProperty::Y,
+                old_value: point.y(),
+                new_value: point.y() + 5,
+            }]
+            .to_vec(),
+            LayoutView::diff(&old, &new)
+        );
+    }
+
+    #[test]
+    fn test_diff_after_dom_mutation() {
+        let html = "<html><head></head><body><p>short</p><p>next</p></body></html>".to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let dom = window.borrow().document();
+        let cssom = StyleSheet::new();
+        let old = LayoutView::new(dom.clone(), &cssom);
+
+        // 1つ目のpのテキストを、2行に折り返す長さにする
+        let text = get_element_nodes(Some(dom.clone()), ElementKind::P)[0]
+            .borrow()
+            .first_child()
+            .expect("text should exist");
+        let long_text = "word ".repeat((CONTENT_AREA_WIDTH / CHAR_WIDTH) as usize / 5 + 1);
+        text.borrow_mut().kind = NodeKind::Text(long_text.trim_end().to_string());
+        let new = LayoutView::new(dom, &cssom);
+
+        // 1行増えた分だけ、後ろのpとそのテキストが下に動く
+        let diffs = LayoutView::diff(&old, &new);
+        let moved = diffs
+            .iter()
+            .filter(|d| d.property() == LayoutProperty::Y)
+            .map(|d| (d.path(), d.new_value() - d.old_value()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            [
+                ([1].to_vec(), CHAR_HEIGHT_WITH_PADDING),
+                ([1, 0].to_vec(), CHAR_HEIGHT_WITH_PADDING)
+            ]
+            .to_vec(),
+            moved
+        );
+        // 大きさが変わるのは、書き換えたテキストとその祖先だけ
+        let resized = diffs
+            .iter()
+            .filter(|d| d.property() != LayoutProperty::Y)
+            .map(|d| (d.path(), d.property()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            [
+                (Vec::new(), LayoutProperty::Height),
+                ([0].to_vec(), LayoutProperty::Height),
+                ([0, 0].to_vec(), LayoutProperty::Width),
+                ([0, 0].to_vec(), LayoutProperty::Height),
+            ]
+            .to_vec(),
+            resized
+        );
+    }
+
+    // bodyの最初の子(グリッドやフレックスのコンテナ)の、子の(x, y, 横幅, 高さ)
+    fn item_boxes(layout_view: &LayoutView) -> Vec<(i64, i64, i64, i64)> {
+        let container = layout_view
+            .root()
+            .and_then(|body| body.borrow().first_child())
+            .expect("container should exist");
+        let mut boxes = Vec::new();
+        let mut child = container.borrow().first_child();
+        while let Some(c) = child {
+            boxes.push((
+                c.borrow().point().x(),
+                c.borrow().point().y(),
+                c.borrow().size().width(),
+                c.borrow().size().height(),
+            ));
+            child = c.borrow().next_sibling();
+        }
+        boxes
+    }
+
+    #[test]
+    fn test_grid_fr_columns() {
+        let html = "<html><head><style>.g { display: grid; grid-template-columns: 1fr 1fr; }</style></head><body><div class=\"g\"><div>a</div><div>b</div><div>c</div></div><p>after</p></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        // 子は行優先で、横幅を2等分した列に並ぶ
+        let half = CONTENT_AREA_WIDTH / 2;
+        assert_eq!(
+            [
+                (0, 0, half, CHAR_HEIGHT_WITH_PADDING),
+                (half, 0, half, CHAR_HEIGHT_WITH_PADDING),
+                (0, CHAR_HEIGHT_WITH_PADDING, half, CHAR_HEIGHT_WITH_PADDING),
+            ]
+            .to_vec(),
+            item_boxes(&layout_view)
+        );
+        // コンテナの高さは2行分になり、後ろのpはその下から始まる
+        assert_eq!(
+            [
+                (0, 0, CONTENT_AREA_WIDTH),
+                (0, CHAR_HEIGHT_WITH_PADDING * 2, CONTENT_AREA_WIDTH),
+            ]
+            .to_vec(),
+            child_boxes(&layout_view)
+        );
+    }
+
+    #[test]
+    fn test_grid_px_and_fr_columns_with_rows() {
+        let html = "<html><head><style>.g { display: grid; grid-template-columns: 100px 1fr; grid-template-rows: auto 100px; }</style></head><body><div class=\"g\"><div>a</div><div>b</div><div>c</div><div>d</div></div></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        let rest = CONTENT_AREA_WIDTH - 100;
+        assert_eq!(
+            [
+                (0, 0, 100, CHAR_HEIGHT_WITH_PADDING),
+                (100, 0, rest, CHAR_HEIGHT_WITH_PADDING),
+                (0, CHAR_HEIGHT_WITH_PADDING, 100, CHAR_HEIGHT_WITH_PADDING),
+                (
+                    100,
+                    CHAR_HEIGHT_WITH_PADDING,
+                    rest,
+                    CHAR_HEIGHT_WITH_PADDING
+                ),
+            ]
+            .to_vec(),
+            item_boxes(&layout_view)
+        );
+        // 2行目は内容によらず100pxになる
+        let grid = layout_view
+            .root()
+            .and_then(|body| body.borrow().first_child())
+            .expect("grid container should exist");
+        assert_eq!(
+            CHAR_HEIGHT_WITH_PADDING + 100,
+            grid.borrow().size().height()
+        );
+    }
+
+    #[test]
+    fn test_grid_auto_column() {
+        let html = "<html><head><style>.g { display: grid; grid-template-columns: auto 1fr; }</style></head><body><div class=\"g\"><span>abc</span><div>b</div><span>a</span><div>d</div></div></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        // autoの列は、その列で最も広い内容の横幅になる
+        let auto = CHAR_WIDTH * 3;
+        let cells = item_boxes(&layout_view);
+        assert_eq!(
+            [
+                (0, 0),
+                (auto, 0),
+                (0, CHAR_HEIGHT_WITH_PADDING),
+                (auto, CHAR_HEIGHT_WITH_PADDING),
+            ]
+            .to_vec(),
+            cells.iter().map(|c| (c.0, c.1)).collect::<Vec<_>>()
+        );
+        assert_eq!(CONTENT_AREA_WIDTH - auto, cells[1].2);
+    }
+
+    #[test]
+    fn test_grid_gap() {
+        let html = "<html><head><style>.g { display: grid; grid-template-columns: 1fr 1fr; gap: 16px; }</style></head><body><div class=\"g\"><div>a</div><div>b</div><div>c</div></div><p>after</p></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        // 列の間と行の間にだけ16pxの間隔が入り、frの列は残りを分け合う
+        let width = (CONTENT_AREA_WIDTH - 16) / 2;
+        let cells = item_boxes(&layout_view);
+        assert_eq!(
+            [
+                (0, 0, width, CHAR_HEIGHT_WITH_PADDING),
+                (width + 16, 0, width, CHAR_HEIGHT_WITH_PADDING),
+                (
+                    0,
+                    CHAR_HEIGHT_WITH_PADDING + 16,
+                    width,
+                    CHAR_HEIGHT_WITH_PADDING
+                ),
+            ]
+            .to_vec(),
+            cells
+        );
+        assert_eq!(16, cells[1].0 - (cells[0].0 + cells[0].2));
+        assert_eq!(16, cells[2].1 - (cells[0].1 + cells[0].3));
+        // 最後の行の後には間隔が入らない
+        assert_eq!(
+            CHAR_HEIGHT_WITH_PADDING * 2 + 16,
+            child_boxes(&layout_view)[1].1
+        );
+    }
+
+    #[test]
+    fn test_grid_row_and_column_gap() {
+        let html = "<html><head><style>.g { display: grid; grid-template-columns: 100px 100px 100px; gap: 4px 8px; }</style></head><body><div class=\"g\"><div>a</div><div>b</div><div>c</div><div>d</div></div></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        // `gap: 行 列`
+        let cells = item_boxes(&layout_view);
+        assert_eq!(
+            [
+                (0, 0),
+                (108, 0),
+                (216, 0),
+                (0, CHAR_HEIGHT_WITH_PADDING + 4)
+            ]
+            .to_vec(),
+            cells.iter().map(|c| (c.0, c.1)).collect::<Vec<_>>()
+        );
+
+        let html = "<html><head><style>.g { display: grid; grid-template-columns: 100px 100px; gap: 4px; row-gap: 0; column-gap: 30px; }</style></head><body><div class=\"g\"><div>a</div><div>b</div><div>c</div></div></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        // 後から指定したrow-gapとcolumn-gapが、gapの値を上書きする
+        let cells = item_boxes(&layout_view);
+        assert_eq!(
+            [(0, 0), (130, 0), (0, CHAR_HEIGHT_WITH_PADDING)].to_vec(),
+            cells.iter().map(|c| (c.0, c.1)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_flex_items_in_a_row() {
+        let html = "<html><head><style>.f { display: flex; }</style></head><body><div class=\"f\"><div>ab</div><div>c</div><span>de</span></div><p>after</p></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        // 子は内容の横幅のまま、間隔なしで横に並ぶ
+        assert_eq!(
+            [
+                (0, 0, CHAR_WIDTH * 2, CHAR_HEIGHT_WITH_PADDING),
+                (CHAR_WIDTH * 2, 0, CHAR_WIDTH, CHAR_HEIGHT_WITH_PADDING),
+                (CHAR_WIDTH * 3, 0, CHAR_WIDTH * 2, CHAR_HEIGHT_WITH_PADDING),
+            ]
+            .to_vec(),
+            item_boxes(&layout_view)
+        );
+        // コンテナの高さは1行分になる
+        assert_eq!(
+            [
+                (0, 0, CONTENT_AREA_WIDTH),
+                (0, CHAR_HEIGHT_WITH_PADDING, CONTENT_AREA_WIDTH),
+            ]
+            .to_vec(),
+            child_boxes(&layout_view)
+        );
+    }
+
+    #[test]
+    fn test_flex_gap() {
+        for gap in [0, 8, 16] {
+            let html = format!("<html><head><style>.f {{ display: flex; gap: {}px; }}</style></head><body><div class=\"f\"><div>a</div><div>bc</div><div>d</div></div><p>after</p></body></html>", gap);
+            let layout_view = create_layout_view(html);
+
+            // 隣り合う子の間にだけ、column-gapの間隔が入る
+            let items = item_boxes(&layout_view);
+            assert_eq!(0, items[0].0);
+            assert_eq!(gap, items[1].0 - (items[0].0 + items[0].2));
+            assert_eq!(gap, items[2].0 - (items[1].0 + items[1].2));
+            // 折り返さなければ、row-gapは入らない
+            assert_eq!(CHAR_HEIGHT_WITH_PADDING, child_boxes(&layout_view)[1].1);
+        }
+    }
+
+    #[test]
+    fn test_flex_wrap_with_row_and_column_gap() {
+        let html = "<html><head><style>.f { display: flex; flex-wrap: wrap; width: 100px; gap: 4px 10px; } .f div { width: 40px; }</style></head><body><div class=\"f\"><div>a</div><div>b</div><div>c</div></div><p>after</p></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        // 3つ目は間隔を含めると100pxを超えるため、次の行の先頭に置く
+        assert_eq!(
+            [(0, 0), (50, 0), (0, CHAR_HEIGHT_WITH_PADDING + 4)].to_vec(),
+            item_boxes(&layout_view)
+                .iter()
+                .map(|b| (b.0, b.1))
+                .collect::<Vec<_>>()
+        );
+        // 最後の行の後には間隔が入らない
+        assert_eq!(
+            CHAR_HEIGHT_WITH_PADDING * 2 + 4,
+            child_boxes(&layout_view)[1].1
+        );
+    }
 }
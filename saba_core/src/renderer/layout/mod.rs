@@ -1,3 +1,6 @@
 pub mod computed_style;
+pub mod flex;
+pub mod grid;
 pub mod layout_object;
 pub mod layout_view;
+pub mod text;
@@ -0,0 +1,73 @@
+use crate::constants::{CHAR_HEIGHT_WITH_PADDING, CHAR_WIDTH};
+use crate::renderer::layout::computed_style::FontSize;
+
+// 標準の文字の大きさを1としたときの倍率
+pub fn font_scale(font_size: FontSize) -> i64 {
+    match font_size {
+        FontSize::Medium => 1,
+        FontSize::XLarge => 2,
+        FontSize::XXLarge => 3,
+    }
+}
+
+// 1文字を描画した後に進む横幅
+// noliのフォントは文字ごとの幅を持たない等幅のビットマップフォントなので、文字の大きさだけで決まる
+pub fn glyph_advance(_c: char, font_size: FontSize) -> i64 {
+    CHAR_WIDTH * font_scale(font_size)
+}
+
+// 文字列を1行に描画したときの横幅
+pub fn text_width(s: &str, font_size: FontSize) -> i64 {
+    s.chars().map(|c| glyph_advance(c, font_size)).sum()
+}
+
+pub fn line_height(font_size: FontSize) -> i64 {
+    CHAR_HEIGHT_WITH_PADDING * font_scale(font_size)
+}
+
+// 先頭から何文字までなら、横幅がmax_widthに収まるか
+pub fn fitting_chars<F: Fn(&str) -> i64>(s: &str, measure: &F, max_width: i64) -> usize {
+    let mut width = 0;
+    let mut count = 0;
+    for c in s.chars() {
+        width += measure(c.encode_utf8(&mut [0; 4]));
+        if width > max_width {
+            break;
+        }
+        count += 1;
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_width() {
+        assert_eq!(0, text_width("", FontSize::Medium));
+        assert_eq!(CHAR_WIDTH, text_width("a", FontSize::Medium));
+        assert_eq!(CHAR_WIDTH * 5, text_width("hello", FontSize::Medium));
+        // 長い文字列ほど、大きな文字ほど幅が広い
+        assert!(text_width("hello", FontSize::Medium) < text_width("hello!", FontSize::Medium));
+        assert!(text_width("hello", FontSize::Medium) < text_width("hello", FontSize::XLarge));
+        assert!(text_width("hello", FontSize::XLarge) < text_width("hello", FontSize::XXLarge));
+        // バイト数ではなく文字数で数える
+        assert_eq!(CHAR_WIDTH * 2, text_width("あい", FontSize::Medium));
+    }
+
+    #[test]
+    fn test_line_height() {
+        assert_eq!(CHAR_HEIGHT_WITH_PADDING, line_height(FontSize::Medium));
+        assert_eq!(CHAR_HEIGHT_WITH_PADDING * 3, line_height(FontSize::XXLarge));
+    }
+
+    #[test]
+    fn test_fitting_chars() {
+        let measure = |s: &str| text_width(s, FontSize::Medium);
+        assert_eq!(3, fitting_chars("hello", &measure, CHAR_WIDTH * 3));
+        assert_eq!(3, fitting_chars("hello", &measure, CHAR_WIDTH * 4 - 1));
+        assert_eq!(5, fitting_chars("hello", &measure, CHAR_WIDTH * 10));
+        assert_eq!(0, fitting_chars("hello", &measure, CHAR_WIDTH - 1));
+    }
+}
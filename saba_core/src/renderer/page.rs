@@ -1,22 +1,104 @@
-use crate::browser::Browser;
-use crate::display_item::DisplayItem;
-use crate::http::HttpResponse;
-use crate::renderer::css::cssom::{CssParser, StyleSheet};
+use crate::browser::{referrer, Browser};
+use crate::clock::{Clock, NoClock};
+use crate::constants::{MAX_REDIRECTS, MAX_STYLESHEET_CACHE_ENTRIES};
+use crate::csp::{CspPolicy, XFrameOptions};
+use crate::display_item::{dirty_rect, DisplayItem};
+use crate::http::{sniff_mime_type, HttpRequest, HttpResponse, HttpTransport, ResourceStat};
+use crate::renderer::css::cssom::{CascadeOrigin, CssParser, LintWarning, StyleSheet};
 use crate::renderer::css::token::CssTokenizer;
-use crate::renderer::dom::api::{get_js_content, get_style_content};
-use crate::renderer::dom::node::{ElementKind, NodeKind, Window};
-use crate::renderer::dom::parser::HtmlParser;
+use crate::renderer::dom::accessibility::{build_accessibility_tree, AccessibilityNode};
+use crate::renderer::dom::api::{
+    append_child, find_element_by_id, get_element_nodes, get_style_content, get_text_content,
+    get_title, is_deferred_script, to_html,
+};
+use crate::renderer::dom::form::{
+    collect_form_fields, default_button, enclosing_form, form_method, is_submit_control,
+    is_text_input, FormSubmission, SelectState,
+};
+use crate::renderer::dom::node::{ElementKind, Node, NodeKind, Window};
+use crate::renderer::dom::parser::{HtmlParser, ParseWarning};
 use crate::renderer::html::token::HtmlTokenizer;
-use crate::renderer::js::ast::JsParser;
-use crate::renderer::js::runtime::JsRuntime;
-use crate::renderer::js::token::JsLexer;
+use crate::renderer::js::ast::{JsParser, Node as JsNode, Program};
+use crate::renderer::js::lint::{lint, JsLintWarning};
+use crate::renderer::js::optimize::{eliminate_dead_code, fold_constants};
+use crate::renderer::js::runtime::{DebugPause, JsError, JsRuntime, RuntimeValue};
+use crate::renderer::js::token::{JsLexer, SourceLocation};
+use crate::renderer::layout::computed_style::Color;
+use crate::renderer::layout::layout_object::{LayoutObject, LayoutPoint, LayoutSize};
 use crate::renderer::layout::layout_view::LayoutView;
+use crate::sri::matches_integrity;
+use crate::url::Url;
+use alloc::collections::BTreeMap;
+use alloc::format;
 use alloc::rc::Rc;
 use alloc::rc::Weak;
-use alloc::string::String;
+use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use core::cell::RefCell;
 
+// 描画の遅さを調べるための、レイアウトツリーと表示項目の大きさ
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayoutMetrics {
+    node_count: usize,
+    max_depth: usize,
+    paint_item_count: usize,
+}
+
+impl LayoutMetrics {
+    pub fn node_count(&self) -> usize {
+        self.node_count
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    pub fn paint_item_count(&self) -> usize {
+        self.paint_item_count
+    }
+}
+
+// ページをクリックしたときに起きること
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClickAction {
+    // リンクのhref属性の値。URLはまだ解決していない
+    Link(String),
+    Submit(FormSubmission),
+}
+
+// 指定した位置にある要素の情報。開発者ツールのような機能で使う
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElementDescription {
+    tag: String,
+    id: Option<String>,
+    classes: Vec<String>,
+    // コンテンツ領域の中での位置と大きさ
+    point: LayoutPoint,
+    size: LayoutSize,
+}
+
+impl ElementDescription {
+    pub fn tag(&self) -> String {
+        self.tag.clone()
+    }
+
+    pub fn id(&self) -> Option<String> {
+        self.id.clone()
+    }
+
+    pub fn classes(&self) -> Vec<String> {
+        self.classes.clone()
+    }
+
+    pub fn point(&self) -> LayoutPoint {
+        self.point
+    }
+
+    pub fn size(&self) -> LayoutSize {
+        self.size
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Page {
     browser: Weak<RefCell<Browser>>,
@@ -24,6 +106,52 @@ pub struct Page {
     style: Option<StyleSheet>,
     layout_view: Option<LayoutView>,
     display_items: Vec<DisplayItem>,
+    // 表示項目を作り直した回数
+    paint_count: usize,
+    dirty_rect: Option<(LayoutPoint, LayoutSize)>,
+    alert_messages: Vec<String>,
+    // スクリプトがconsole.logで出力したメッセージ
+    console_messages: Vec<String>,
+    deferred_scripts: Vec<String>,
+    url: Option<String>,
+    transport: Option<Rc<dyn HttpTransport>>,
+    // スクリプトのDate.now()に使う
+    clock: Rc<dyn Clock>,
+    // ブラウザの既定のスタイルシート。作者のスタイルより優先度が低い
+    default_stylesheet: String,
+    prefetch_cache: BTreeMap<String, HttpResponse>,
+    csp_policy: Option<CspPolicy>,
+    csp_violations: Vec<String>,
+    x_frame_options: Option<XFrameOptions>,
+    network_errors: Vec<String>,
+    script_errors: Vec<String>,
+    parse_warnings: Vec<ParseWarning>,
+    // 表示中の文書のメディアタイプ
+    mime_type: String,
+    resource_stats: Vec<ResourceStat>,
+    js_profiling: bool,
+    // falseのときはスクリプトを実行せず、HTMLとCSSだけで描画する
+    js_enabled: bool,
+    js_breakpoints: Vec<(u32, u32)>,
+    js_pause_events: Vec<DebugPause>,
+    js_watches: Vec<String>,
+    js_watch_values: Vec<Option<RuntimeValue>>,
+    // スクリプトを実行し終えた後のランタイム。開発者コンソールから同じスコープで式を評価する
+    js_runtime: Option<Rc<RefCell<JsRuntime>>>,
+    // CSSのテキストのハッシュをキーにした、CSSのテキストとパース済みのスタイルシート
+    stylesheet_cache: BTreeMap<u64, (String, StyleSheet)>,
+    stylesheet_parse_count: usize,
+    css_lint_warnings: Vec<LintWarning>,
+    js_lint_warnings: Vec<JsLintWarning>,
+    js_profile: BTreeMap<&'static str, u64>,
+    // コンテンツ領域の上端から、どれだけ下にスクロールしているか
+    scroll_offset: i64,
+    // 文字の大きさの倍率(%)
+    zoom: i64,
+    // キー入力を受け取るinput要素
+    focused_input: Option<Rc<RefCell<Node>>>,
+    // 選択肢の一覧を開いているselectのレイアウトオブジェクト
+    open_select: Option<Rc<RefCell<LayoutObject>>>,
 }
 
 impl Page {
@@ -34,30 +162,222 @@ impl Page {
             style: None,
             layout_view: None,
             display_items: Vec::new(),
+            paint_count: 0,
+            dirty_rect: None,
+            alert_messages: Vec::new(),
+            console_messages: Vec::new(),
+            deferred_scripts: Vec::new(),
+            url: None,
+            transport: None,
+            clock: Rc::new(NoClock),
+            default_stylesheet: String::new(),
+            prefetch_cache: BTreeMap::new(),
+            csp_policy: None,
+            csp_violations: Vec::new(),
+            x_frame_options: None,
+            network_errors: Vec::new(),
+            script_errors: Vec::new(),
+            parse_warnings: Vec::new(),
+            mime_type: String::new(),
+            resource_stats: Vec::new(),
+            js_profiling: false,
+            js_enabled: true,
+            js_breakpoints: Vec::new(),
+            js_pause_events: Vec::new(),
+            js_watches: Vec::new(),
+            js_watch_values: Vec::new(),
+            js_runtime: None,
+            stylesheet_cache: BTreeMap::new(),
+            stylesheet_parse_count: 0,
+            css_lint_warnings: Vec::new(),
+            js_lint_warnings: Vec::new(),
+            js_profile: BTreeMap::new(),
+            scroll_offset: 0,
+            zoom: 100,
+            focused_input: None,
+            open_select: None,
         }
     }
 
+    pub fn set_scroll_offset(&mut self, scroll_offset: i64) {
+        self.scroll_offset = scroll_offset;
+    }
+
+    pub fn scroll_offset(&self) -> i64 {
+        self.scroll_offset
+    }
+
     pub fn set_browser(&mut self, browser: Weak<RefCell<Browser>>) {
         self.browser = browser;
     }
 
+    pub fn browser(&self) -> Weak<RefCell<Browser>> {
+        self.browser.clone()
+    }
+
+    pub fn set_url(&mut self, url: String) {
+        self.url = Some(url);
+    }
+
+    pub fn url(&self) -> Option<String> {
+        self.url.clone()
+    }
+
+    // サブリソース(外部CSSやJavaScript)を取得するための通信層を設定する
+    pub fn set_transport(&mut self, transport: Rc<dyn HttpTransport>) {
+        self.transport = Some(transport);
+    }
+
+    pub fn set_clock(&mut self, clock: Rc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    pub fn set_default_stylesheet(&mut self, css: String) {
+        self.default_stylesheet = css;
+    }
+
+    pub fn zoom(&self) -> i64 {
+        self.zoom
+    }
+
+    // 表示中の文書があれば、レイアウトを計算し直して描画する
+    pub fn set_zoom(&mut self, zoom: i64) {
+        if self.zoom == zoom {
+            return;
+        }
+        self.zoom = zoom;
+
+        if let Some(layout_view) = &mut self.layout_view {
+            layout_view.set_zoom(zoom);
+            self.paint_tree();
+        }
+    }
+
+    // 既定のスタイルシートを差し替えて、表示中の文書を描画し直す
+    // DOMと作者のスタイルはそのまま使い、スクリプトは実行し直さない
+    pub fn restyle_with_default_stylesheet(&mut self, css: String) {
+        self.default_stylesheet = css;
+
+        let mut author = match self.style.take() {
+            Some(style) => style,
+            None => return,
+        };
+        author
+            .rules
+            .retain(|rule| rule.origin == CascadeOrigin::Author);
+        self.style = Some(self.with_default_stylesheet(author));
+
+        if let Some(layout_view) = &mut self.layout_view {
+            layout_view.invalidate_style_cache();
+        }
+        self.set_layout_view();
+        self.paint_tree();
+    }
+
     pub fn receive_response(&mut self, response: HttpResponse) {
-        self.create_frame(response.body());
+        self.csp_policy = response
+            .header_value("Content-Security-Policy")
+            .ok()
+            .map(|header| CspPolicy::parse(&header));
+        self.csp_violations = Vec::new();
+        self.focused_input = None;
+        // 新しい文書では、前の文書のレイアウトとスタイルは使えない
+        self.layout_view = None;
+        self.network_errors = Vec::new();
+        self.script_errors = Vec::new();
+        self.resource_stats = Vec::new();
+        if let Some(url) = self.url.clone() {
+            self.resource_stats.push(ResourceStat::new(url, &response));
+        }
+        // フレームにはまだ対応していないので、値を保存しておくだけ
+        self.x_frame_options = response
+            .header_value("X-Frame-Options")
+            .ok()
+            .and_then(|value| XFrameOptions::parse(&value));
+
+        // Content-Typeがなければボディから推測する。HTML以外はテキストとしてそのまま表示する
+        self.mime_type = response
+            .content_type()
+            .unwrap_or_else(|| sniff_mime_type(&response.body()).to_string());
+        if self.mime_type == "text/html" {
+            self.create_frame(response.body());
+        } else {
+            self.create_text_frame(response.body());
+        }
 
-        self.execute_js();
+        // JavaScriptを実行しない場合も、前の文書の実行結果を残さない
+        self.alert_messages = Vec::new();
+        self.console_messages = Vec::new();
+        self.js_lint_warnings = Vec::new();
+        self.js_pause_events = Vec::new();
+        self.js_profile = BTreeMap::new();
+        self.js_watch_values = self.js_watches.iter().map(|_| None).collect();
+        if self.js_enabled {
+            self.execute_js();
+        } else {
+            self.js_runtime = None;
+        }
 
         self.set_layout_view();
         self.paint_tree();
     }
 
+    // 空の文書のbodyに、textを1つのテキストノードとして入れる
+    fn create_text_frame(&mut self, text: String) {
+        self.create_frame("<html><head></head><body></body></html>".to_string());
+
+        let dom = match &self.frame {
+            Some(frame) => frame.borrow().document(),
+            None => return,
+        };
+        if let Some(body) = get_element_nodes(Some(dom), ElementKind::Body).first() {
+            append_child(body, Rc::new(RefCell::new(Node::new(NodeKind::Text(text)))));
+        }
+    }
+
     fn create_frame(&mut self, html: String) {
         let html_tokenizer = HtmlTokenizer::new(html);
-        let frame = HtmlParser::new(html_tokenizer).construct_tree();
+        let (frame, parse_warnings) = HtmlParser::new(html_tokenizer).into_result();
+        self.parse_warnings = parse_warnings;
         let dom = frame.borrow().document();
 
-        let style = get_style_content(dom);
-        let css_tokenizer = CssTokenizer::new(style);
-        let cssom = CssParser::new(css_tokenizer).parse_stylesheet();
+        self.prefetch_cache = BTreeMap::new();
+        self.prefetch_resources(dom.clone());
+
+        let mut style = String::new();
+        let allows_inline_style = match &self.csp_policy {
+            Some(csp) => csp.allows_inline_style(),
+            None => true,
+        };
+        if allows_inline_style {
+            style.push_str(&get_style_content(dom.clone()));
+        } else {
+            self.report_csp_violation("inline style".to_string());
+        }
+        style.push_str(&self.external_style_content(dom.clone()));
+        let cssom = self.parse_stylesheet(style);
+        // lintは開発中にだけ実行する
+        self.css_lint_warnings = if cfg!(debug_assertions) {
+            cssom.lint()
+        } else {
+            Vec::new()
+        };
+        let cssom = self.with_default_stylesheet(cssom);
+
+        // JavaScriptが無効なときは実行しないので、スクリプトを取得しない
+        self.deferred_scripts = Vec::new();
+        let scripts = if self.js_enabled {
+            get_element_nodes(Some(dom.clone()), ElementKind::Script)
+        } else {
+            Vec::new()
+        };
+        for script in scripts {
+            if is_deferred_script(&script) {
+                if let Some(js) = self.script_content(&script) {
+                    self.deferred_scripts.push(js);
+                }
+            }
+        }
 
         self.frame = Some(frame);
         self.style = Some(cssom);
@@ -69,16 +389,346 @@ impl Page {
             None => return,
         };
 
-        let js = get_js_content(dom.clone());
+        let mut runtime = JsRuntime::new(dom.clone());
+        runtime.set_page_url(self.url.clone());
+        runtime.set_transport(self.transport.clone());
+        runtime.set_clock(self.clock.clone());
+        runtime.set_profiling(self.js_profiling);
+        for (line, column) in &self.js_breakpoints {
+            runtime.add_breakpoint(*line, *column);
+        }
+        for watch in &self.js_watches {
+            runtime.add_watch(watch.clone());
+        }
+
+        // 同じモジュールは、いくつのスクリプトからimportされても一度だけ実行する
+        let mut loaded_modules = Vec::new();
+        // 通常のスクリプトを文書内の順番で実行してから、遅延されたスクリプトを実行する
+        for script in get_element_nodes(Some(dom), ElementKind::Script) {
+            if !is_deferred_script(&script) {
+                if let Some(js) = self.script_content(&script) {
+                    let warnings = self.execute_script(&mut runtime, js, &mut loaded_modules);
+                    self.js_lint_warnings.extend(warnings);
+                }
+            }
+        }
+        for js in self.deferred_scripts.clone() {
+            let warnings = self.execute_script(&mut runtime, js, &mut loaded_modules);
+            self.js_lint_warnings.extend(warnings);
+        }
+
+        self.alert_messages = runtime.alert_messages();
+        self.console_messages = runtime.console_messages();
+        self.network_errors.extend(runtime.network_errors());
+        self.script_errors.extend(
+            runtime
+                .script_errors()
+                .iter()
+                .map(|error| error.to_string()),
+        );
+        self.js_profile = runtime.profile_report().clone();
+        self.js_pause_events = runtime.take_pause_events();
+        self.js_watch_values = runtime.watch_values().to_vec();
+        self.js_runtime = Some(Rc::new(RefCell::new(runtime)));
+    }
+
+    // 実行する前にlintをかけ、定数を畳み込んで到達しない文を取り除き、見つかった問題を返す
+    fn execute_script(
+        &mut self,
+        runtime: &mut JsRuntime,
+        js: String,
+        loaded_modules: &mut Vec<String>,
+    ) -> Vec<JsLintWarning> {
         let lexer = JsLexer::new(js);
         let mut parser = JsParser::new(lexer);
-        let ast = parser.parse_ast();
+        // 構文エラーのあるスクリプトは実行しない
+        let ast = match parser.parse_program() {
+            Ok(ast) => ast,
+            Err(error) => {
+                self.script_errors.push(error.to_string());
+                return Vec::new();
+            }
+        };
+        let mut warnings = lint(&ast);
+        let ast = self.link_modules(ast, loaded_modules);
+
+        let mut folded = Program::new();
+        folded.set_body(
+            ast.body()
+                .iter()
+                .map(|node| fold_constants(node.clone()))
+                .collect(),
+        );
+        let (ast, dead_code_warnings) = eliminate_dead_code(&folded);
+        warnings.extend(dead_code_warnings);
 
-        let mut runtime = JsRuntime::new(dom);
         runtime.execute(&ast);
+        // UIから実行を再開する手段がないので、一時停止は記録だけしてすぐに再開する
+        while runtime.is_paused() {
+            runtime.continue_execution();
+        }
+        warnings
+    }
+
+    // importされたモジュールを取得してパースし、その文をprogramの前に連結する
+    // モジュールのURLはページのURLを基準に解決する
+    // 読み込み済みのモジュールは連結しないので、循環したimportでも止まる
+    fn link_modules(&mut self, mut program: Program, loaded_modules: &mut Vec<String>) -> Program {
+        let mut modules = Vec::new();
+
+        for import in program.imports() {
+            let (specifiers, source, location) = match import.as_ref() {
+                JsNode::ImportDeclaration {
+                    specifiers,
+                    source,
+                    location,
+                } => (specifiers, source, location),
+                _ => continue,
+            };
+
+            let url = self.resolve_url(source);
+            if loaded_modules.contains(&url) {
+                continue;
+            }
+            loaded_modules.push(url.clone());
+
+            if !self.allows_script(&url) {
+                self.report_csp_violation(url);
+                continue;
+            }
+            let js = match self.fetch_resource(&url) {
+                Some(response) => response.body(),
+                None => {
+                    self.script_errors
+                        .push(format!("Failed to load module {} ({})", url, location));
+                    continue;
+                }
+            };
+
+            let lexer = JsLexer::new(js);
+            let mut parser = JsParser::new(lexer);
+            let module = match parser.parse_program() {
+                Ok(module) => module,
+                Err(error) => {
+                    self.script_errors
+                        .push(format!("{} in module {}", error, url));
+                    continue;
+                }
+            };
+            for name in specifiers {
+                if !module.exports().contains_key(name) {
+                    self.script_errors.push(format!(
+                        "SyntaxError: {} does not export {} ({})",
+                        source, name, location
+                    ));
+                }
+            }
+            modules.push(self.link_modules(module, loaded_modules));
+        }
+
+        // 先に書かれたimportのモジュールから順に実行されるように、後ろから連結する
+        for module in modules.into_iter().rev() {
+            program.import_module(module);
+        }
+        program
+    }
+
+    // `<link rel="preload">`で指定されたリソースを先に取得しておく
+    fn prefetch_resources(&mut self, dom: Rc<RefCell<Node>>) {
+        if self.transport.is_none() {
+            return;
+        }
+
+        for link in get_element_nodes(Some(dom), ElementKind::Link) {
+            let element = match link.borrow().get_element() {
+                Some(e) => e,
+                None => continue,
+            };
+            if element.get_attribute("rel").as_deref() != Some("preload") {
+                continue;
+            }
+            let href = match element.get_attribute("href") {
+                Some(href) => href,
+                None => continue,
+            };
+
+            let url = self.resolve_url(&href);
+            if self.prefetch_cache.contains_key(&url) {
+                continue;
+            }
+            let allowed = match element.get_attribute("as").as_deref() {
+                Some("script") if !self.js_enabled => continue,
+                Some("script") => self.allows_script(&url),
+                Some("style") => self.allows_style(&url),
+                _ => true,
+            };
+            if !allowed {
+                self.report_csp_violation(url);
+                continue;
+            }
+            if let Some(response) = self.request_resource(url.clone()) {
+                self.prefetch_cache.insert(url, response);
+            }
+        }
+    }
+
+    // プリロード済みのリソースがあればそれを使い、なければリクエストを送る
+    fn fetch_resource(&mut self, href: &str) -> Option<HttpResponse> {
+        let url = self.resolve_url(href);
+        if let Some(response) = self.prefetch_cache.get(&url) {
+            return Some(response.clone());
+        }
+
+        self.request_resource(url)
+    }
+
+    // 通信層でサブリソースを取得し、その情報を記録する
+    // リダイレクトでは、移動先のURLに合わせてリクエストを作り直して送り直す
+    fn request_resource(&mut self, url: String) -> Option<HttpResponse> {
+        let transport = self.transport.clone()?;
+        let mut request_url = url.clone();
+        for _ in 0..=MAX_REDIRECTS {
+            let response = transport
+                .request(self.create_request(request_url.clone()))
+                .ok()?;
+            match response.redirect_location(&request_url) {
+                Some(location) => request_url = location,
+                None => {
+                    self.resource_stats.push(ResourceStat::new(url, &response));
+                    return Some(response);
+                }
+            }
+        }
+        None
+    }
+
+    // サブリソースのリクエストには、ページの移動と同じようにこのページのURLをRefererとして付ける
+    fn create_request(&self, url: String) -> HttpRequest {
+        let mut builder = HttpRequest::builder(url);
+        if let Some(referrer) = self.url.as_deref().and_then(referrer) {
+            builder = builder.header("Referer", referrer);
+        }
+        builder.build()
+    }
+
+    fn resolve_url(&self, href: &str) -> String {
+        match self.url.clone().map(|url| Url::new(url).parse()) {
+            Some(Ok(base)) => base.resolve(href),
+            _ => href.to_string(),
+        }
+    }
+
+    fn external_style_content(&mut self, dom: Rc<RefCell<Node>>) -> String {
+        let mut style = String::new();
+
+        for link in get_element_nodes(Some(dom), ElementKind::Link) {
+            let element = match link.borrow().get_element() {
+                Some(e) => e,
+                None => continue,
+            };
+            if element.get_attribute("rel").as_deref() != Some("stylesheet") {
+                continue;
+            }
+            let href = match element.get_attribute("href") {
+                Some(href) => href,
+                None => continue,
+            };
+            let url = self.resolve_url(&href);
+            if !self.allows_style(&url) {
+                self.report_csp_violation(url);
+                continue;
+            }
+            if let Some(response) = self.fetch_resource(&url) {
+                if let Some(body) = self.check_integrity(&link, &url, response.body()) {
+                    style.push_str(&body);
+                }
+            }
+        }
+
+        style
+    }
+
+    // src属性があれば外部ファイルを、なければ要素内のテキストをスクリプトとして使う
+    // CSPで許可されていないスクリプトはNoneを返す
+    fn script_content(&mut self, script: &Rc<RefCell<Node>>) -> Option<String> {
+        let src = script
+            .borrow()
+            .get_element()
+            .and_then(|e| e.get_attribute("src"));
+
+        match src {
+            Some(src) => {
+                let url = self.resolve_url(&src);
+                if !self.allows_script(&url) {
+                    self.report_csp_violation(url);
+                    return None;
+                }
+                let body = self.fetch_resource(&url)?.body();
+                self.check_integrity(script, &url, body)
+            }
+            None => {
+                let allows_inline_script = match &self.csp_policy {
+                    Some(csp) => csp.allows_inline_script(),
+                    None => true,
+                };
+                if !allows_inline_script {
+                    self.report_csp_violation("inline script".to_string());
+                    return None;
+                }
+                Some(get_text_content(script))
+            }
+        }
+    }
+
+    // integrity属性のハッシュと一致しないリソースは、ネットワークエラーとして扱って使わない
+    fn check_integrity(
+        &mut self,
+        node: &Rc<RefCell<Node>>,
+        url: &str,
+        body: String,
+    ) -> Option<String> {
+        let integrity = node
+            .borrow()
+            .get_element()
+            .and_then(|e| e.get_attribute("integrity"));
+
+        match integrity {
+            Some(integrity) if !matches_integrity(body.as_bytes(), &integrity) => {
+                self.network_errors.push(format!(
+                    "Failed to find a valid digest in the 'integrity' attribute for {}",
+                    url
+                ));
+                None
+            }
+            _ => Some(body),
+        }
+    }
+
+    fn allows_script(&self, url: &str) -> bool {
+        match &self.csp_policy {
+            Some(csp) => csp.allows_script(url, self.url.as_deref()),
+            None => true,
+        }
+    }
+
+    fn allows_style(&self, url: &str) -> bool {
+        match &self.csp_policy {
+            Some(csp) => csp.allows_style(url, self.url.as_deref()),
+            None => true,
+        }
+    }
+
+    fn report_csp_violation(&mut self, resource: String) {
+        self.csp_violations.push(format!(
+            "Refused to load {} because it violates the Content-Security-Policy",
+            resource
+        ));
     }
 
     fn set_layout_view(&mut self) {
+        // レイアウトし直すと位置が変わるため、開いている選択肢の一覧は閉じる
+        self.open_select = None;
+
         let dom = match &self.frame {
             Some(frame) => frame.borrow().document(),
             None => return,
@@ -89,41 +739,1639 @@ impl Page {
             None => return,
         };
 
-        let layout_view = LayoutView::new(dom, &style);
+        // 同じ文書をレイアウトし直すときは、解決済みのスタイルを使い回す
+        if let Some(layout_view) = &mut self.layout_view {
+            layout_view.relayout(dom, &style);
+            return;
+        }
+
+        let mut layout_view = LayoutView::new(dom, &style);
+        if self.zoom != 100 {
+            layout_view.set_zoom(self.zoom);
+        }
 
         self.layout_view = Some(layout_view);
     }
 
     fn paint_tree(&mut self) {
         if let Some(layout_view) = &self.layout_view {
-            self.display_items = layout_view.paint();
+            let mut display_items = layout_view.paint();
+            // 選択肢の一覧は、ページの上に重ねて描画する
+            if let Some(select) = &self.open_select {
+                display_items.extend(paint_select_options(&select.borrow()));
+            }
+            self.dirty_rect = dirty_rect(&self.display_items, &display_items);
+            self.display_items = display_items;
+            self.paint_count += 1;
         }
     }
 
-    pub fn display_items(&self) -> Vec<DisplayItem> {
-        self.display_items.clone()
+    pub fn paint_count(&self) -> usize {
+        self.paint_count
+    }
+
+    // 再描画のたびにコピーしないよう、借用で返す。表示項目はページが変わったときだけ作り直す
+    pub fn display_items(&self) -> &[DisplayItem] {
+        &self.display_items
     }
 
     pub fn clear_display_items(&mut self) {
         self.display_items = Vec::new();
     }
 
-    pub fn clicked(&self, position: (i64, i64)) -> Option<String> {
+    // 前回の描画から変化した範囲。UIはこの範囲だけを描画し直せばよい
+    pub fn dirty_rect(&self) -> Option<(LayoutPoint, LayoutSize)> {
+        self.dirty_rect
+    }
+
+    pub fn clear_dirty_rect(&mut self) {
+        self.dirty_rect = None;
+    }
+
+    pub fn alert_messages(&self) -> Vec<String> {
+        self.alert_messages.clone()
+    }
+
+    pub fn clear_alert_messages(&mut self) {
+        self.alert_messages = Vec::new();
+    }
+
+    pub fn console_messages(&self) -> Vec<String> {
+        self.console_messages.clone()
+    }
+
+    pub fn title(&self) -> Option<String> {
+        let dom = self.frame.as_ref()?.borrow().document();
+        get_title(dom)
+    }
+
+    // スクリーンリーダーなどに渡すアクセシビリティツリー
+    // ルートのDocumentノードだけを含む。ページを読み込んでいないときは空
+    pub fn accessibility_tree(&self) -> Vec<AccessibilityNode> {
+        match &self.frame {
+            Some(frame) => [build_accessibility_tree(&frame.borrow().document())].to_vec(),
+            None => Vec::new(),
+        }
+    }
+
+    // 文書内の順番で、aタグのhrefを絶対URLにして返す
+    pub fn links(&self) -> Vec<String> {
+        let dom = match &self.frame {
+            Some(frame) => frame.borrow().document(),
+            None => return Vec::new(),
+        };
+
+        get_element_nodes(Some(dom), ElementKind::A)
+            .iter()
+            .filter_map(|a| a.borrow().get_element()?.get_attribute("href"))
+            .map(|href| self.resolve_url(&href))
+            .collect()
+    }
+
+    // JavaScriptによる変更を反映した、現在のDOMツリーのHTML
+    pub fn serialize_dom(&self) -> String {
+        match &self.frame {
+            Some(frame) => to_html(&frame.borrow().document()),
+            None => String::new(),
+        }
+    }
+
+    // 現在のDOMツリーから、id属性が一致する要素を探す
+    pub fn find_element_by_id(&self, id: &str) -> Option<Rc<RefCell<Node>>> {
+        self.frame
+            .as_ref()
+            .and_then(|frame| find_element_by_id(&frame.borrow().document(), id))
+    }
+
+    pub fn csp_violations(&self) -> Vec<String> {
+        self.csp_violations.clone()
+    }
+
+    // 次に読み込むページのスクリプトをプロファイルするかどうか
+    pub fn set_js_profiling(&mut self, js_profiling: bool) {
+        self.js_profiling = js_profiling;
+    }
+
+    // 次に読み込むページのスクリプトを実行するかどうか
+    pub fn set_js_enabled(&mut self, js_enabled: bool) {
+        self.js_enabled = js_enabled;
+    }
+
+    pub fn js_enabled(&self) -> bool {
+        self.js_enabled
+    }
+
+    // 次に読み込むページのスクリプトに設定するブレークポイント(行, 列)
+    pub fn add_js_breakpoint(&mut self, line: u32, column: u32) {
+        self.js_breakpoints.push((line, column));
+    }
+
+    // ブラウザの既定のスタイルシートのルールを、作者のルールの前に加える
+    fn with_default_stylesheet(&mut self, author: StyleSheet) -> StyleSheet {
+        if self.default_stylesheet.is_empty() {
+            return author;
+        }
+
+        let mut sheet = self.parse_stylesheet(self.default_stylesheet.clone());
+        sheet.set_origin(CascadeOrigin::UserAgent);
+        sheet.rules.extend(author.rules);
+        sheet
+    }
+
+    // 同じ内容のCSSは、前にパースした結果を使い回す
+    // ハッシュが衝突した別のCSSの結果を使わないように、テキストも比べる
+    fn parse_stylesheet(&mut self, style: String) -> StyleSheet {
+        let key = content_hash(&style);
+        if let Some((source, sheet)) = self.stylesheet_cache.get(&key) {
+            if *source == style {
+                return sheet.clone();
+            }
+        }
+
+        let css_tokenizer = CssTokenizer::new(style.clone());
+        // 不正な宣言は読み飛ばし、残りのスタイルで描画する
+        let (sheet, _) = CssParser::new(css_tokenizer).parse_stylesheet_with_errors();
+        self.stylesheet_parse_count += 1;
+
+        // 上限に達したら、キャッシュを作り直す
+        if self.stylesheet_cache.len() >= MAX_STYLESHEET_CACHE_ENTRIES {
+            self.stylesheet_cache.clear();
+        }
+        self.stylesheet_cache.insert(key, (style, sheet.clone()));
+        sheet
+    }
+
+    // スタイルシートのlintで見つかった問題。デバッグビルドでだけ報告する
+    pub fn css_lint_warnings(&self) -> Vec<LintWarning> {
+        self.css_lint_warnings.clone()
+    }
+
+    // スクリプトのlintで見つかった問題。位置はそれぞれのスクリプトの中での位置
+    pub fn js_lint_warnings(&self) -> Vec<JsLintWarning> {
+        self.js_lint_warnings.clone()
+    }
+
+    // CSSをパースした回数。キャッシュに当たったときは数えない
+    pub fn stylesheet_parse_count(&self) -> usize {
+        self.stylesheet_parse_count
+    }
+
+    // 前回の読み込みでブレークポイントに当たったときの状態
+    pub fn js_pause_events(&self) -> Vec<DebugPause> {
+        self.js_pause_events.clone()
+    }
+
+    // 次に読み込むページのスクリプトで、文を実行するたびに評価する式
+    pub fn add_js_watch(&mut self, expr: String) {
+        self.js_watches.push(expr);
+    }
+
+    // ウォッチ式と、スクリプトをすべて実行した後の値
+    pub fn js_watches(&self) -> Vec<(String, Option<RuntimeValue>)> {
+        self.js_watches
+            .iter()
+            .cloned()
+            .zip(self.js_watch_values.iter().cloned())
+            .collect()
+    }
+
+    // 開発者コンソールに入力された式を、ページのスクリプトと同じスコープで評価する
+    pub fn eval_js_expression(&self, expr: &str) -> Result<Option<RuntimeValue>, JsError> {
+        match &self.js_runtime {
+            Some(runtime) => runtime.borrow_mut().eval_expression(expr),
+            None => Err(JsError::new(
+                "no page is loaded".to_string(),
+                SourceLocation::new(1, 1),
+            )),
+        }
+    }
+
+    pub fn js_profile_report(&self) -> &BTreeMap<&'static str, u64> {
+        &self.js_profile
+    }
+
+    pub fn layout_metrics(&self) -> LayoutMetrics {
+        let (node_count, max_depth) = match &self.layout_view {
+            Some(layout_view) => (layout_view.node_count(), layout_view.max_depth()),
+            None => (0, 0),
+        };
+        LayoutMetrics {
+            node_count,
+            max_depth,
+            paint_item_count: self.display_items.len(),
+        }
+    }
+
+    // このページのために取得したリソース。プリロード済みのものは1回だけ数える
+    pub fn resource_stats(&self) -> &[ResourceStat] {
+        &self.resource_stats
+    }
+
+    // JavaScriptのfetchで失敗したリクエスト
+    pub fn network_errors(&self) -> Vec<String> {
+        self.network_errors.clone()
+    }
+
+    pub fn script_errors(&self) -> Vec<String> {
+        self.script_errors.clone()
+    }
+
+    // Content-Typeか、なければボディから推測した文書のメディアタイプ
+    pub fn mime_type(&self) -> String {
+        self.mime_type.clone()
+    }
+
+    // HTMLのパースで行った回復処理
+    pub fn parse_warnings(&self) -> Vec<ParseWarning> {
+        self.parse_warnings.clone()
+    }
+
+    pub fn x_frame_options(&self) -> Option<XFrameOptions> {
+        self.x_frame_options.clone()
+    }
+
+    // iframeに対応したときに、このページを埋め込めるかどうかの判定に使う
+    pub fn allows_embedding_by(&self, embedder_url: &str) -> bool {
+        let page_url = self.url.as_deref();
+        if let Some(options) = &self.x_frame_options {
+            if !options.allows_embedding(embedder_url, page_url) {
+                return false;
+            }
+        }
+        match &self.csp_policy {
+            Some(csp) => csp.allows_frame_ancestor(embedder_url, page_url),
+            None => true,
+        }
+    }
+
+    // 指定した位置にある、最も内側の要素を返す
+    // テキストの上の場合は、そのテキストを含む要素を返す
+    pub fn element_at(&self, position: (i64, i64)) -> Option<ElementDescription> {
+        let view = self.layout_view.as_ref()?;
+
+        let mut node = view.find_node_by_position(position);
+        while let Some(n) = node {
+            if let NodeKind::Element(e) = n.borrow().node_kind() {
+                return Some(ElementDescription {
+                    tag: e.kind().to_string(),
+                    id: e.get_attribute("id"),
+                    classes: e
+                        .get_attribute("class")
+                        .map(|class| class.split_whitespace().map(|c| c.to_string()).collect())
+                        .unwrap_or_default(),
+                    point: n.borrow().point(),
+                    size: n.borrow().size(),
+                });
+            }
+            node = n.borrow().parent().upgrade();
+        }
+
+        None
+    }
+
+    // クリックした位置にあるリンクか送信ボタンを、内側から探す
+    // 文字を入力できるinputをクリックしたときは、そのinputにフォーカスする
+    // selectをクリックしたときは選択肢の一覧を開き、一覧の上をクリックしたときはその選択肢を選ぶ
+    pub fn clicked(&mut self, position: (i64, i64)) -> Option<ClickAction> {
+        self.focused_input = None;
+
+        if let Some(select) = self.open_select.take() {
+            // 一覧の外をクリックしたときは、一覧を閉じるだけ
+            let index = option_at(&select.borrow(), position);
+            if let Some(index) = index {
+                let node = select.borrow().node();
+                let mut state = SelectState::from_node(&node);
+                if state.select(index) {
+                    state.apply_to(&node);
+                    if let Some(layout_view) = &mut self.layout_view {
+                        layout_view.invalidate_node_style(&node);
+                    }
+                    self.set_layout_view();
+                }
+            }
+            self.paint_tree();
+            return None;
+        }
+
         let view = match &self.layout_view {
             Some(v) => v,
             None => return None,
         };
 
-        if let Some(n) = view.find_node_by_position(position) {
-            if let Some(parent) = n.borrow().parent().upgrade() {
-                if let NodeKind::Element(e) = parent.borrow().node_kind() {
-                    if e.kind() == ElementKind::A {
-                        return e.get_attribute("href");
+        let target = view.find_node_by_position(position);
+        if let Some(t) = &target {
+            let element = t.borrow().node().borrow().get_element();
+            if element
+                .is_some_and(|e| e.kind() == ElementKind::Select && !e.has_attribute("disabled"))
+            {
+                self.open_select = Some(t.clone());
+                self.paint_tree();
+                return None;
+            }
+        }
+
+        let mut node = target.map(|n| n.borrow().node());
+        while let Some(n) = node {
+            if let Some(e) = n.borrow().get_element() {
+                if e.kind() == ElementKind::A {
+                    if let Some(href) = e.get_attribute("href") {
+                        return Some(ClickAction::Link(href));
                     }
                 }
+                if is_submit_control(&e) {
+                    // formの外にある送信ボタンは何もしない
+                    let form = enclosing_form(&n)?;
+                    return self
+                        .form_submission(&form, Some(&n))
+                        .map(ClickAction::Submit);
+                }
+                if is_text_input(&e) {
+                    self.focused_input = Some(n.clone());
+                    return None;
+                }
             }
+            node = n.borrow().parent().upgrade();
+        }
+
+        None
+    }
+
+    pub fn focused_input(&self) -> Option<Rc<RefCell<Node>>> {
+        self.focused_input.clone()
+    }
+
+    pub fn open_select(&self) -> Option<Rc<RefCell<Node>>> {
+        self.open_select.as_ref().map(|s| s.borrow().node())
+    }
+
+    // フォーカスしているinputにキー入力を渡し、value属性を書き換える
+    // Enterキーではフォームを送信し、既定のボタンがあればそれをクリックしたときと同じ内容を返す
+    pub fn key_pressed(&mut self, c: char) -> Option<FormSubmission> {
+        let input = self.focused_input.clone()?;
+
+        if c == 0x0A as char {
+            let form = enclosing_form(&input)?;
+            let submitter = default_button(&form);
+            return self.form_submission(&form, submitter.as_ref());
         }
 
+        let mut value = input
+            .borrow()
+            .get_element()
+            .and_then(|e| e.get_attribute("value"))
+            .unwrap_or_default();
+        if c == 0x7F as char || c == 0x08 as char {
+            // DelキーまたはBackspaceキーで最後の文字を削除する
+            value.pop();
+        } else if !c.is_control() {
+            value.push(c);
+        } else {
+            return None;
+        }
+        if let NodeKind::Element(ref mut e) = input.borrow_mut().kind {
+            e.set_attribute("value", &value);
+        }
+
+        if let Some(layout_view) = &mut self.layout_view {
+            layout_view.invalidate_node_style(&input);
+        }
+        self.set_layout_view();
+        self.paint_tree();
         None
     }
+
+    // submitterを押してformを送信するときの内容
+    fn form_submission(
+        &self,
+        form: &Rc<RefCell<Node>>,
+        submitter: Option<&Rc<RefCell<Node>>>,
+    ) -> Option<FormSubmission> {
+        let element = form.borrow().get_element()?;
+
+        // actionがなければ、今のページに送信する
+        let action = match element.get_attribute("action") {
+            Some(action) if !action.is_empty() => self.resolve_url(&action),
+            _ => self.url.clone().unwrap_or_default(),
+        };
+        Some(FormSubmission::new(
+            form_method(&element),
+            action,
+            collect_form_fields(form, submitter),
+        ))
+    }
+}
+
+// FNV-1aによる64ビットのハッシュ
+fn content_hash(text: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in text.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+// 開いているselectの選択肢の一覧で、それぞれの選択肢の位置と大きさ
+// selectの箱のすぐ下から、箱と同じ大きさで縦に並べる
+fn select_option_rects(select: &LayoutObject) -> Vec<(LayoutPoint, LayoutSize)> {
+    let count = SelectState::from_node(&select.node()).options().len() as i64;
+    let point = select.point();
+    let size = select.size();
+    (1..=count)
+        .map(|i| {
+            (
+                LayoutPoint::new(point.x(), point.y() + size.height() * i),
+                size,
+            )
+        })
+        .collect()
+}
+
+// 選択肢の一覧のうち、positionにある選択肢の位置
+fn option_at(select: &LayoutObject, position: (i64, i64)) -> Option<usize> {
+    select_option_rects(select)
+        .iter()
+        .position(|(point, size)| {
+            point.x() <= position.0
+                && position.0 < point.x() + size.width()
+                && point.y() <= position.1
+                && position.1 < point.y() + size.height()
+        })
+}
+
+fn paint_select_options(select: &LayoutObject) -> Vec<DisplayItem> {
+    let state = SelectState::from_node(&select.node());
+    let mut items = Vec::new();
+    for (i, (point, size)) in select_option_rects(select).into_iter().enumerate() {
+        // 選ばれている選択肢は、背景の色を変えて示す
+        let mut style = select.style();
+        let background = if Some(i) == state.selected_index() {
+            "lightgray"
+        } else {
+            "white"
+        };
+        style
+            .set_background_color(Color::from_name(background).expect("color should be supported"));
+        items.push(DisplayItem::Rect {
+            style: style.clone(),
+            layout_point: point,
+            layout_size: size,
+        });
+        items.push(DisplayItem::Text {
+            text: state.options()[i].label(),
+            style,
+            layout_point: point,
+        });
+    }
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{CHAR_HEIGHT_WITH_PADDING, CHAR_WIDTH, CONTENT_AREA_WIDTH};
+    use crate::renderer::dom::accessibility::AriaRole;
+    use crate::renderer::dom::form::FormMethod;
+    use crate::renderer::js::runtime::JsNumber;
+    use crate::renderer::layout::computed_style::Color;
+    use crate::test_support::{body_response, StubTransport};
+
+    fn create_page(html: &str) -> Page {
+        let response = HttpResponse::builder().body(html.to_string()).build();
+        let mut page = Page::new();
+        page.receive_response(response);
+        page
+    }
+
+    fn create_page_with_content_type(content_type: Option<&str>, body: &str) -> Page {
+        let mut builder = HttpResponse::builder();
+        if let Some(content_type) = content_type {
+            builder = builder.header("Content-Type", content_type);
+        }
+        let mut page = Page::new();
+        page.receive_response(builder.body(body.to_string()).build());
+        page
+    }
+
+    fn body_children(page: &Page) -> Vec<NodeKind> {
+        let document = page.frame.as_ref().expect("no frame").borrow().document();
+        let body = get_element_nodes(Some(document), ElementKind::Body)[0].clone();
+        let mut children = Vec::new();
+        let mut child = body.borrow().first_child();
+        while let Some(node) = child {
+            children.push(node.borrow().kind());
+            child = node.borrow().next_sibling();
+        }
+        children
+    }
+
+    #[test]
+    fn test_sniffed_html_is_parsed() {
+        let page = create_page_with_content_type(
+            None,
+            "<!DOCTYPE html><html><head></head><body><p>text</p></body></html>",
+        );
+        assert_eq!("text/html", page.mime_type());
+        assert!(matches!(
+            body_children(&page).as_slice(),
+            [NodeKind::Element(e)] if e.kind() == ElementKind::P
+        ));
+    }
+
+    #[test]
+    fn test_sniffed_json_and_text_are_not_parsed() {
+        for (body, mime_type) in [
+            (r#"{"a": "<p>"}"#, "application/json"),
+            ("hello <p>world</p>", "text/plain"),
+        ] {
+            let page = create_page_with_content_type(None, body);
+            assert_eq!(mime_type, page.mime_type());
+            assert_eq!(
+                [NodeKind::Text(body.to_string())].to_vec(),
+                body_children(&page)
+            );
+        }
+    }
+
+    #[test]
+    fn test_content_type_header_wins_over_sniffing() {
+        let html = "<html><head></head><body><p>text</p></body></html>";
+        let page = create_page_with_content_type(Some("text/plain; charset=utf-8"), html);
+        assert_eq!("text/plain", page.mime_type());
+        assert_eq!(
+            [NodeKind::Text(html.to_string())].to_vec(),
+            body_children(&page)
+        );
+    }
+
+    #[test]
+    fn test_deferred_scripts_run_after_regular_scripts() {
+        let page = create_page(
+            r#"<html><head>
+<script type="module">alert("module")</script>
+<script defer>alert("defer")</script>
+<script>alert("regular")</script>
+</head><body></body></html>"#,
+        );
+
+        assert_eq!(
+            [
+                "regular".to_string(),
+                "module".to_string(),
+                "defer".to_string()
+            ]
+            .to_vec(),
+            page.alert_messages()
+        );
+    }
+
+    #[test]
+    fn test_deferred_script_sees_full_dom() {
+        let page = create_page(
+            r#"<html><head>
+<script defer>document.getElementById("target").textContent = "deferred"</script>
+</head><body><p id="target">original</p></body></html>"#,
+        );
+
+        let texts = page
+            .display_items()
+            .iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text { text, .. } => Some(text.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(["deferred".to_string()].to_vec(), texts);
+    }
+
+    #[test]
+    fn test_serialize_dom_reflects_js_mutation() {
+        let page = create_page(
+            r#"<html><head><script defer>document.getElementById("target").textContent = "a & b"</script></head><body><p id="target" class="a&b">original</p></body></html>"#,
+        );
+
+        assert_eq!(
+            r#"<html><head><script defer="">document.getElementById("target").textContent = "a & b"</script></head><body><p id="target" class="a&amp;b">a &amp; b</p></body></html>"#,
+            page.serialize_dom()
+        );
+    }
+
+    #[test]
+    fn test_outer_html() {
+        let page = create_page(
+            r#"<html><head><script defer>document.getElementById("target").textContent = "changed"; alert(document.documentElement.outerHTML)</script></head><body><p id="target">original</p></body></html>"#,
+        );
+
+        assert_eq!([page.serialize_dom()].to_vec(), page.alert_messages());
+        assert!(page.alert_messages()[0].contains(r#"<p id="target">changed</p>"#));
+    }
+
+    #[test]
+    fn test_find_element_by_id() {
+        let page = create_page(
+            r#"<html><head><script defer>document.getElementById("target").textContent = "changed"</script></head><body><div><p id="target">original</p></div></body></html>"#,
+        );
+
+        let target = page
+            .find_element_by_id("target")
+            .expect("target should exist");
+        assert_eq!(Some(ElementKind::P), target.borrow().element_kind());
+        assert_eq!("changed", get_text_content(&target));
+        assert!(page.find_element_by_id("missing").is_none());
+    }
+
+    #[test]
+    fn test_click_submit_button_in_get_form() {
+        let response = HttpResponse::builder()
+            .body(r#"<html><head></head><body><form action="/search?old=1"><input name="q" value="saba browser"><input type="checkbox" name="safe" checked><button name="go" value="1">search</button></form></body></html>"#.to_string())
+            .build();
+        let mut page = Page::new();
+        page.set_url("http://example.com/index.html".to_string());
+        page.receive_response(response);
+
+        // 2つのinputの下の、ボタンのテキストの上
+        let action = page
+            .clicked((CHAR_WIDTH, CHAR_HEIGHT_WITH_PADDING * 2 + 1))
+            .expect("the button should be hit");
+        let submission = match action {
+            ClickAction::Submit(submission) => submission,
+            ClickAction::Link(href) => panic!("unexpected link: {}", href),
+        };
+        assert_eq!(FormMethod::Get, submission.method());
+        assert_eq!(
+            "http://example.com:80/search?q=saba+browser&safe=on&go=1",
+            submission.url()
+        );
+    }
+
+    #[test]
+    fn test_accessibility_tree() {
+        assert!(Page::new().accessibility_tree().is_empty());
+
+        let page = create_page(
+            r#"<html><head><title>Top</title></head><body><h1>Welcome</h1><p>Go <a href="/next">next</a></p></body></html>"#,
+        );
+        let tree = page.accessibility_tree();
+        assert_eq!(1, tree.len());
+        assert_eq!(AriaRole::Document, tree[0].role());
+        assert_eq!("Top", tree[0].label());
+        let roles: Vec<(AriaRole, String)> = tree[0]
+            .children()
+            .iter()
+            .map(|n| (n.role(), n.label()))
+            .collect();
+        assert_eq!(
+            [
+                (AriaRole::Heading(1), "Welcome".to_string()),
+                (AriaRole::Paragraph, "Go next".to_string()),
+            ]
+            .to_vec(),
+            roles
+        );
+        assert_eq!(AriaRole::Link, tree[0].children()[1].children()[0].role());
+    }
+
+    #[test]
+    fn test_choosing_option_changes_submitted_value() {
+        let response = HttpResponse::builder()
+            .body(r#"<html><head></head><body><form action="/order"><select name="size"><option value="s">small</option><option value="m">medium</option><option value="l">large</option></select><input type="submit"></form></body></html>"#.to_string())
+            .build();
+        let mut page = Page::new();
+        page.set_url("http://example.com/index.html".to_string());
+        page.receive_response(response);
+        let line = CHAR_HEIGHT_WITH_PADDING;
+        let has_text = |page: &Page, s: &str| {
+            page.display_items()
+                .iter()
+                .any(|item| matches!(item, DisplayItem::Text { text, .. } if text == s))
+        };
+
+        // selectの箱には、選ばれている選択肢だけを表示する
+        assert!(has_text(&page, "small"));
+        assert!(!has_text(&page, "medium"));
+
+        // selectをクリックすると、選択肢の一覧が箱の下に開く
+        assert_eq!(None, page.clicked((CHAR_WIDTH, 1)));
+        assert!(page.open_select().is_some());
+        assert!(has_text(&page, "medium"));
+        assert!(has_text(&page, "large"));
+
+        // 2番目の選択肢をクリックすると、一覧が閉じて箱の表示が変わる
+        assert_eq!(None, page.clicked((CHAR_WIDTH, line * 2 + 1)));
+        assert!(page.open_select().is_none());
+        assert!(has_text(&page, "medium"));
+        assert!(!has_text(&page, "small"));
+
+        // 一覧が閉じているので、selectの下の送信ボタンを押せる
+        let submission = match page.clicked((CHAR_WIDTH, line + 1)) {
+            Some(ClickAction::Submit(submission)) => submission,
+            action => panic!("unexpected action: {:?}", action),
+        };
+        assert_eq!("http://example.com:80/order?size=m", submission.url());
+    }
+
+    #[test]
+    fn test_enter_in_focused_input_submits_form() {
+        let response = HttpResponse::builder()
+            .body(r#"<html><head></head><body><form action="/search"><input name="q" value="sab"><input type="submit" name="go" value="Go"><input type="submit" name="other"></form></body></html>"#.to_string())
+            .build();
+        let mut page = Page::new();
+        page.set_url("http://example.com/index.html".to_string());
+        page.receive_response(response);
+
+        // 入力欄をクリックしてフォーカスする
+        assert_eq!(None, page.clicked((CHAR_WIDTH, 1)));
+        assert!(page.focused_input().is_some());
+
+        // 入力した文字は描画にも反映される
+        for c in ['x', 0x7F as char, 'a'] {
+            assert_eq!(None, page.key_pressed(c));
+        }
+        assert!(page
+            .display_items()
+            .iter()
+            .any(|item| matches!(item, DisplayItem::Text { text, .. } if text == "saba")));
+
+        // 既定のボタン(最初の送信ボタン)を押したときと同じ内容を送信する
+        let submission = page
+            .key_pressed(0x0A as char)
+            .expect("form should be submitted");
+        assert_eq!(
+            "http://example.com:80/search?q=saba&go=Go",
+            submission.url()
+        );
+
+        // 入力欄の外をクリックすると、フォーカスが外れてキー入力は無視される
+        page.clicked((CONTENT_AREA_WIDTH - 1, CHAR_HEIGHT_WITH_PADDING * 10));
+        assert!(page.focused_input().is_none());
+        assert_eq!(None, page.key_pressed(0x0A as char));
+    }
+
+    #[test]
+    fn test_input_value_and_placeholder() {
+        let mut page = create_page(
+            r#"<html><head></head><body><form action="http://example.com/search"><input name="q" value="saba" placeholder="unused"><input name="n" placeholder="name"><input type="submit"></form></body></html>"#,
+        );
+        let text_color = |page: &Page, s: &str| {
+            page.display_items().iter().find_map(|item| match item {
+                DisplayItem::Text { text, style, .. } if text == s => Some(style.color()),
+                _ => None,
+            })
+        };
+
+        // 値があればplaceholderは表示せず、空の入力欄にはplaceholderを灰色で表示する
+        assert_eq!(Some(Color::black()), text_color(&page, "saba"));
+        assert_eq!(None, text_color(&page, "unused"));
+        assert_eq!(
+            Some(Color::from_name("gray").expect("gray should be supported")),
+            text_color(&page, "name")
+        );
+
+        // 初期値は送信するが、placeholderは送信しない
+        let submission = match page.clicked((CHAR_WIDTH, CHAR_HEIGHT_WITH_PADDING * 2 + 1)) {
+            Some(ClickAction::Submit(submission)) => submission,
+            action => panic!("unexpected action: {:?}", action),
+        };
+        assert_eq!("http://example.com/search?q=saba&n=", submission.url());
+
+        // 文字を入力すると、placeholderの代わりに値を表示する
+        page.clicked((CHAR_WIDTH, CHAR_HEIGHT_WITH_PADDING + 1));
+        page.key_pressed('x');
+        assert_eq!(None, text_color(&page, "name"));
+        assert_eq!(Some(Color::black()), text_color(&page, "x"));
+    }
+
+    #[test]
+    fn test_relayout_reuses_resolved_styles() {
+        let mut page = create_page(
+            r#"<html><head><style>input[value="ab"] { color: red; }</style></head><body><p>text</p><form><input name="q" value="a"></form></body></html>"#,
+        );
+        let resolve_count = |page: &Page| {
+            page.layout_view
+                .as_ref()
+                .expect("layout view should exist")
+                .style_resolve_count()
+        };
+        let text_color = |page: &Page, s: &str| {
+            page.display_items().iter().find_map(|item| match item {
+                DisplayItem::Text { text, style, .. } if text == s => Some(style.color()),
+                _ => None,
+            })
+        };
+        let initial = resolve_count(&page);
+
+        // 書き換えたinputのスタイルだけを解決し直し、属性セレクタの一致も反映する
+        page.clicked((CHAR_WIDTH, CHAR_HEIGHT_WITH_PADDING + 1));
+        assert!(page.focused_input().is_some());
+        page.key_pressed('b');
+        assert_eq!(initial + 1, resolve_count(&page));
+        assert_eq!(
+            Some(Color::from_name("red").expect("red should be supported")),
+            text_color(&page, "ab")
+        );
+
+        // 既定のスタイルシートが変わると、すべて解決し直す
+        page.restyle_with_default_stylesheet(String::new());
+        assert_eq!(initial * 2 + 1, resolve_count(&page));
+    }
+
+    #[test]
+    fn test_click_link_and_submit_input() {
+        let mut page = create_page(
+            r#"<html><head></head><body><p><a href="/next"><b>link</b></a></p><form method="post"><input type="submit"></form><input type="submit"></body></html>"#,
+        );
+
+        // リンクの中の要素をクリックしても、リンクをたどる
+        assert_eq!(
+            Some(ClickAction::Link("/next".to_string())),
+            page.clicked((CHAR_WIDTH, 1))
+        );
+
+        let action = page.clicked((CHAR_WIDTH, CHAR_HEIGHT_WITH_PADDING + 1));
+        match action {
+            Some(ClickAction::Submit(submission)) => {
+                assert_eq!(FormMethod::Post, submission.method());
+                assert!(submission.fields().is_empty());
+            }
+            _ => panic!("unexpected action: {:?}", action),
+        }
+
+        // formの外の送信ボタンは何もしない
+        assert_eq!(
+            None,
+            page.clicked((CHAR_WIDTH, CHAR_HEIGHT_WITH_PADDING * 2 + 1))
+        );
+    }
+
+    #[test]
+    fn test_element_at() {
+        let page = create_page(
+            r#"<html><head></head><body><p>first</p><div id="target" class="box  main"><a href="/next">link</a></div></body></html>"#,
+        );
+
+        // 2行目のリンクのテキストの上
+        let link = page
+            .element_at((CHAR_WIDTH, CHAR_HEIGHT_WITH_PADDING + 1))
+            .expect("an element should be hit");
+        assert_eq!("a", link.tag());
+        assert_eq!(None, link.id());
+        assert!(link.classes().is_empty());
+
+        // リンクの右側は、div要素の範囲
+        let div = page
+            .element_at((CONTENT_AREA_WIDTH - 1, CHAR_HEIGHT_WITH_PADDING + 1))
+            .expect("an element should be hit");
+        assert_eq!("div", div.tag());
+        assert_eq!(Some("target".to_string()), div.id());
+        assert_eq!(
+            ["box".to_string(), "main".to_string()].to_vec(),
+            div.classes()
+        );
+        assert_eq!(LayoutPoint::new(0, CHAR_HEIGHT_WITH_PADDING), div.point());
+        assert_eq!(
+            LayoutSize::new(CONTENT_AREA_WIDTH, CHAR_HEIGHT_WITH_PADDING),
+            div.size()
+        );
+    }
+
+    fn display_texts(page: &Page) -> Vec<String> {
+        page.display_items()
+            .iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text { text, .. } => Some(text.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_closed_details_shows_only_summary() {
+        let page = create_page(
+            "<html><head></head><body><details><summary>more</summary><p>hidden</p></details></body></html>",
+        );
+        assert_eq!(["more".to_string()].to_vec(), display_texts(&page));
+    }
+
+    #[test]
+    fn test_open_details_via_js() {
+        let page = create_page(
+            r#"<html><head><script defer>document.getElementById("d").open = true</script></head><body><details id="d"><summary>more</summary><p>shown</p></details></body></html>"#,
+        );
+        assert_eq!(
+            ["more".to_string(), "shown".to_string()].to_vec(),
+            display_texts(&page)
+        );
+    }
+
+    #[test]
+    fn test_close_details_via_js() {
+        let page = create_page(
+            r#"<html><head><script defer>document.getElementById("d").open = false</script></head><body><details id="d" open><summary>more</summary><p>hidden</p></details></body></html>"#,
+        );
+        assert_eq!(["more".to_string()].to_vec(), display_texts(&page));
+    }
+
+    #[test]
+    fn test_details_set_and_remove_attribute() {
+        let page = create_page(
+            r#"<html><head><script defer>var a = document.getElementById("a"); a.setAttribute("open", ""); alert(a.hasAttribute("open")); var b = document.getElementById("b"); b.removeAttribute("open"); alert(b.hasAttribute("open"))</script></head><body><details id="a"><summary>a</summary><p>a-body</p></details><details id="b" open><summary>b</summary><p>b-body</p></details></body></html>"#,
+        );
+        assert_eq!(
+            ["true".to_string(), "false".to_string()].to_vec(),
+            page.alert_messages()
+        );
+        assert_eq!(
+            ["a".to_string(), "a-body".to_string(), "b".to_string()].to_vec(),
+            display_texts(&page)
+        );
+    }
+
+    #[test]
+    fn test_display_items_are_not_recomputed_on_repaint() {
+        let mut page = create_page("<html><head></head><body><p>first</p></body></html>");
+        let paint_count = page.paint_count();
+
+        // UIの再描画と同じく、変化した範囲を取り出して表示項目を借用する
+        // 再描画では表示項目を作り直さない
+        for _ in 0..2 {
+            page.dirty_rect();
+            page.clear_dirty_rect();
+            assert!(!page.display_items().is_empty());
+        }
+        assert_eq!(paint_count, page.paint_count());
+
+        let html = "<html><head></head><body><p>second</p></body></html>";
+        page.receive_response(HttpResponse::builder().body(html.to_string()).build());
+        assert_eq!(paint_count + 1, page.paint_count());
+        assert!(page.display_items().iter().any(|item| match item {
+            DisplayItem::Text { text, .. } => text == "second",
+            _ => false,
+        }));
+    }
+
+    // http://example.com/index.htmlとしてresponseを読み込む。サブリソースはtransportから取得する
+    fn load_page(transport: &Rc<StubTransport>, response: HttpResponse) -> Page {
+        let mut page = Page::new();
+        page.set_url("http://example.com/index.html".to_string());
+        page.set_transport(transport.clone());
+        page.receive_response(response);
+        page
+    }
+
+    fn create_site_page(html: &str) -> Page {
+        load_page(&example_site(), body_response(html))
+    }
+
+    fn response_with(name: &str, value: &str, body: &str) -> HttpResponse {
+        HttpResponse::builder()
+            .header(name, value)
+            .body(body.to_string())
+            .build()
+    }
+
+    // テストで読み込むページのサブリソース
+    fn example_site() -> Rc<StubTransport> {
+        let style = "p { background-color: red; }";
+        Rc::new(StubTransport::new(
+            [
+                ("http://example.com:80/css/style.css", body_response(style)),
+                ("http://example.com:80/style.css", body_response(style)),
+                (
+                    "http://example.com:80/old.css",
+                    HttpResponse::builder()
+                        .status(301)
+                        .header("Location", "/css/style.css")
+                        .build(),
+                ),
+                (
+                    "http://example.com:80/same.js",
+                    body_response(r#"alert("same")"#),
+                ),
+                (
+                    "http://other.com/other.js",
+                    body_response(r#"alert("other")"#),
+                ),
+                (
+                    "http://example.com:80/ok.js",
+                    response_with("Content-Type", "text/plain", r#"alert("ok")"#),
+                ),
+                (
+                    "http://example.com:80/module.js",
+                    response_with(
+                        "Content-Type",
+                        "text/javascript",
+                        "import { base } from \"./base.js\"\nexport function foo() { return base + 1; }\nexport const x = 5",
+                    ),
+                ),
+                (
+                    "http://example.com:80/base.js",
+                    response_with("Content-Type", "text/javascript", "export const base = 41"),
+                ),
+            ]
+            .to_vec(),
+        ))
+    }
+
+    #[test]
+    fn test_preload_is_fetched_once() {
+        let html = r#"<html><head>
+<link rel="preload" href="css/style.css" as="style">
+<link rel="stylesheet" href="/css/style.css">
+</head><body><p>text</p></body></html>"#;
+        let transport = example_site();
+        let page = load_page(&transport, body_response(html));
+
+        let url = "http://example.com:80/css/style.css";
+        assert_eq!(1, transport.request_count(url));
+        let request = transport
+            .requests()
+            .into_iter()
+            .find(|r| r.url() == url)
+            .expect("stylesheet should be requested");
+        assert_eq!(
+            Some("http://example.com/index.html".to_string()),
+            request.header_value("Referer")
+        );
+        assert!(has_red_background(&page));
+    }
+
+    // page_urlのページから/style.cssを読み込んだときのRefererの値
+    fn stylesheet_referer(page_url: &str) -> Option<String> {
+        let html = r#"<html><head>
+<link rel="stylesheet" href="http://example.com:80/style.css">
+</head><body><p>text</p></body></html>"#;
+        let transport = example_site();
+        let mut page = Page::new();
+        page.set_url(page_url.to_string());
+        page.set_transport(transport.clone());
+        page.receive_response(body_response(html));
+
+        let requests = transport.requests();
+        assert_eq!(1, requests.len());
+        requests[0].header_value("Referer")
+    }
+
+    #[test]
+    fn test_subresource_referer_drops_fragment() {
+        assert_eq!(
+            Some("http://example.com/index.html".to_string()),
+            stylesheet_referer("http://example.com/index.html#top")
+        );
+    }
+
+    #[test]
+    fn test_no_subresource_referer_from_local_page() {
+        assert_eq!(None, stylesheet_referer("file:///index.html"));
+        assert_eq!(None, stylesheet_referer("data:text/html,hello"));
+        assert_eq!(None, stylesheet_referer("about:blank"));
+    }
+
+    fn create_page_with_csp(csp: &str, html: &str) -> Page {
+        load_page(
+            &example_site(),
+            response_with("Content-Security-Policy", csp, html),
+        )
+    }
+
+    static CSP_HTML: &str = r#"<html><head>
+<script>alert("inline")</script>
+<script src="/same.js"></script>
+<script src="http://other.com/other.js"></script>
+</head><body></body></html>"#;
+
+    #[test]
+    fn test_csp_script_src_none() {
+        let page = create_page_with_csp("script-src 'none'", CSP_HTML);
+        assert!(page.alert_messages().is_empty());
+        assert_eq!(3, page.csp_violations().len());
+    }
+
+    #[test]
+    fn test_csp_script_src_self() {
+        let page = create_page_with_csp("script-src 'self'", CSP_HTML);
+        assert_eq!(
+            ["inline".to_string(), "same".to_string()].to_vec(),
+            page.alert_messages()
+        );
+        assert_eq!(1, page.csp_violations().len());
+    }
+
+    #[test]
+    fn test_csp_script_src_with_host() {
+        let page = create_page_with_csp("default-src 'self' other.com", CSP_HTML);
+        assert_eq!(
+            [
+                "inline".to_string(),
+                "same".to_string(),
+                "other".to_string()
+            ]
+            .to_vec(),
+            page.alert_messages()
+        );
+        assert!(page.csp_violations().is_empty());
+    }
+
+    #[test]
+    fn test_redirected_stylesheet() {
+        let html = r#"<html><head>
+<link rel="stylesheet" href="/old.css">
+</head><body><p>text</p></body></html>"#;
+        let transport = example_site();
+        let page = load_page(&transport, body_response(html));
+
+        // 移動先のスタイルシートを取得して適用する
+        assert_eq!(1, transport.request_count("http://example.com:80/old.css"));
+        assert_eq!(
+            1,
+            transport.request_count("http://example.com:80/css/style.css")
+        );
+        assert!(page.display_items().iter().any(|item| match item {
+            DisplayItem::Rect { style, .. } => {
+                style.background_color().code_u32() == 0xff0000
+            }
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn test_csp_style_src() {
+        let html = r#"<html><head>
+<link rel="stylesheet" href="/style.css">
+</head><body><p>text</p></body></html>"#;
+        let page = create_page_with_csp("style-src 'none'; script-src 'self'", html);
+        assert!(!page.display_items().iter().any(|item| match item {
+            DisplayItem::Rect { style, .. } => {
+                style.background_color().code_u32() == 0xff0000
+            }
+            _ => false,
+        }));
+        assert_eq!(2, page.csp_violations().len());
+    }
+
+    #[test]
+    fn test_head_content_is_not_rendered() {
+        let page = create_page(
+            r#"<html><head><title>Page Title</title><meta charset="utf-8">
+<style>p { color: red; }</style></head><body><p>text</p></body></html>"#,
+        );
+
+        assert_eq!(Some("Page Title".to_string()), page.title());
+
+        let texts = page
+            .display_items()
+            .iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text { text, style, .. } => Some((text.clone(), style.color())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            [(
+                "text".to_string(),
+                Color::from_name("red").expect("red should be supported")
+            )]
+            .to_vec(),
+            texts
+        );
+    }
+
+    #[test]
+    fn test_x_frame_options() {
+        let response = HttpResponse::builder()
+            .header("X-Frame-Options", "SAMEORIGIN")
+            .body("<html><head></head><body></body></html>".to_string())
+            .build();
+        let mut page = Page::new();
+        page.set_url("http://example.com/index.html".to_string());
+        page.receive_response(response);
+
+        assert_eq!(Some(XFrameOptions::SameOrigin), page.x_frame_options());
+        assert!(page.allows_embedding_by("http://example.com/top.html"));
+        assert!(!page.allows_embedding_by("http://other.com/"));
+    }
+
+    #[test]
+    fn test_frame_ancestors_none() {
+        let page = create_page_with_csp(
+            "frame-ancestors 'none'",
+            "<html><head></head><body></body></html>",
+        );
+        assert_eq!(None, page.x_frame_options());
+        assert!(!page.allows_embedding_by("http://example.com/top.html"));
+    }
+
+    #[test]
+    fn test_links() {
+        let html = r#"<html><head></head><body>
+<p><a href="/about.html">about</a></p>
+<a>no href</a>
+<a href="docs/index.html">docs</a>
+<a href="http://other.com/">other</a>
+</body></html>"#;
+        let mut page = Page::new();
+        page.set_url("http://example.com/blog/index.html".to_string());
+        page.receive_response(HttpResponse::builder().body(html.to_string()).build());
+
+        assert_eq!(
+            [
+                "http://example.com:80/about.html".to_string(),
+                "http://example.com:80/blog/docs/index.html".to_string(),
+                "http://other.com/".to_string()
+            ]
+            .to_vec(),
+            page.links()
+        );
+    }
+
+    fn has_red_background(page: &Page) -> bool {
+        page.display_items().iter().any(|item| match item {
+            DisplayItem::Rect { style, .. } => style.background_color().code_u32() == 0xff0000,
+            _ => false,
+        })
+    }
+
+    #[test]
+    fn test_integrity_matches() {
+        let page = create_site_page(
+            r#"<html><head>
+<link rel="stylesheet" href="/style.css" integrity="sha384-vaucsqk+C2CseWZpNBb//iBU02kW0J/jgLnNTj2ZPHO84rWfSHeg2A+t7TRvu1mA">
+<script src="/ok.js" integrity="sha256-6B96Nt3Gt7a7ZTidcRLjXLiA6Kit6pM7WThjkV75Ujo="></script>
+</head><body><p>text</p></body></html>"#,
+        );
+
+        assert_eq!(["ok".to_string()].to_vec(), page.alert_messages());
+        assert!(has_red_background(&page));
+        assert!(page.network_errors().is_empty());
+    }
+
+    #[test]
+    fn test_integrity_mismatch() {
+        let page = create_site_page(
+            r#"<html><head>
+<link rel="stylesheet" href="/style.css" integrity="sha384-ywB1P0WjXou1oD1pmsZQBycsMqsO3tFjGotgWkP/W+2AhgcroefMI1i67KE0yCWn">
+<script src="/ok.js" integrity="sha256-ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0="></script>
+</head><body><p>text</p></body></html>"#,
+        );
+
+        assert!(page.alert_messages().is_empty());
+        assert!(!has_red_background(&page));
+        assert_eq!(2, page.network_errors().len());
+    }
+
+    #[test]
+    fn test_resource_stats() {
+        let page = create_site_page(
+            r#"<html><head>
+<link rel="preload" href="/ok.js" as="script">
+<link rel="stylesheet" href="/style.css">
+<script src="/ok.js"></script>
+<script src="/missing.js"></script>
+</head><body></body></html>"#,
+        );
+
+        let stats = page.resource_stats();
+        assert_eq!(3, stats.len());
+        assert_eq!("http://example.com/index.html", stats[0].url());
+        assert_eq!(200, stats[0].status_code());
+        assert_eq!("http://example.com:80/ok.js", stats[1].url());
+        assert_eq!("text/plain", stats[1].content_type());
+        assert_eq!(r#"alert("ok")"#.len(), stats[1].raw_size());
+        assert_eq!(r#"alert("ok")"#.len(), stats[1].decompressed_size());
+        assert_eq!("http://example.com:80/style.css", stats[2].url());
+    }
+
+    #[test]
+    fn test_layout_metrics() {
+        assert_eq!(
+            LayoutMetrics {
+                node_count: 0,
+                max_depth: 0,
+                paint_item_count: 0
+            },
+            Page::new().layout_metrics()
+        );
+
+        let page = create_page("<html><head></head><body><p>a</p><p>b</p></body></html>");
+        let metrics = page.layout_metrics();
+        assert_eq!(5, metrics.node_count());
+        assert_eq!(3, metrics.max_depth());
+        assert_eq!(page.display_items().len(), metrics.paint_item_count());
+    }
+
+    #[test]
+    fn test_js_profile_report() {
+        let html =
+            "<html><head><script>var a = 1; alert(a + 2)</script></head><body></body></html>";
+        let mut page = Page::new();
+        page.set_js_profiling(true);
+        page.receive_response(HttpResponse::builder().body(html.to_string()).build());
+
+        let report = page.js_profile_report();
+        assert_eq!(Some(&1), report.get("CallExpression"));
+        assert_eq!(Some(&1), report.get("AdditiveExpression"));
+        assert_eq!(Some(&2), report.get("NumericLiteral"));
+    }
+
+    #[test]
+    fn test_parse_warnings() {
+        let page = create_page("<html><head></head><body><p>a</div></p></body></html>");
+        let warnings = page.parse_warnings();
+        assert_eq!(1, warnings.len());
+        assert_eq!(
+            "unexpected end tag </div> is ignored",
+            warnings[0].message()
+        );
+        assert_eq!(30, warnings[0].column());
+
+        let page = create_page("<html><head></head><body><p>a</p></body></html>");
+        assert!(page.parse_warnings().is_empty());
+    }
+
+    #[test]
+    fn test_js_state_is_reset_when_js_is_disabled() {
+        let html = "<html><head><script>var a = 1;\nconsole.log(a);\nalert(a);\nif (a = 2) {}</script></head><body></body></html>";
+        let mut page = Page::new();
+        page.set_js_profiling(true);
+        page.add_js_breakpoint(2, 1);
+        page.add_js_watch("a".to_string());
+        page.receive_response(HttpResponse::builder().body(html.to_string()).build());
+        assert!(!page.alert_messages().is_empty());
+        assert!(!page.console_messages().is_empty());
+        assert!(!page.js_lint_warnings().is_empty());
+        assert!(!page.js_pause_events().is_empty());
+        assert!(!page.js_profile_report().is_empty());
+        assert_eq!(
+            Some(RuntimeValue::Number(JsNumber::from(2))),
+            page.js_watches()[0].1
+        );
+
+        // JavaScriptを無効にして次の文書を読み込むと、前の文書の結果は残らない
+        page.set_js_enabled(false);
+        page.receive_response(
+            HttpResponse::builder()
+                .body("<html><head></head><body><p>next</p></body></html>".to_string())
+                .build(),
+        );
+        assert!(page.alert_messages().is_empty());
+        assert!(page.console_messages().is_empty());
+        assert!(page.js_lint_warnings().is_empty());
+        assert!(page.js_pause_events().is_empty());
+        assert!(page.js_profile_report().is_empty());
+        assert_eq!(None, page.js_watches()[0].1);
+    }
+
+    #[test]
+    fn test_scripts_are_not_fetched_when_js_is_disabled() {
+        let html = r#"<html><head>
+<link rel="preload" href="/same.js" as="script">
+<script src="/ok.js" defer></script>
+<script type="module" src="/module.js"></script>
+<link rel="stylesheet" href="/style.css">
+</head><body><p>text</p></body></html>"#;
+        let transport = example_site();
+        let mut page = Page::new();
+        page.set_url("http://example.com/index.html".to_string());
+        page.set_transport(transport.clone());
+        page.set_js_enabled(false);
+        page.receive_response(body_response(html));
+
+        // スタイルシートだけを取得する
+        assert_eq!(
+            ["http://example.com:80/style.css".to_string()].to_vec(),
+            transport
+                .requests()
+                .iter()
+                .map(|r| r.url())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_js_pause_events() {
+        let html = "<html><head><script>var a = 1;\nalert(a);\nvar b = 2;</script></head><body></body></html>";
+        let mut page = Page::new();
+        page.add_js_breakpoint(2, 1);
+        page.receive_response(HttpResponse::builder().body(html.to_string()).build());
+
+        let events = page.js_pause_events();
+        assert_eq!(1, events.len());
+        assert_eq!(
+            "paused at ExpressionStatement (line 2, column 1) a=1",
+            events[0].to_string()
+        );
+        // 一時停止の後も、スクリプトは最後まで実行される
+        assert_eq!(["1".to_string()].to_vec(), page.alert_messages());
+    }
+
+    #[test]
+    fn test_js_watches() {
+        let html = "<html><head><script>var count = 1;\ncount = count + 1;</script></head><body></body></html>";
+        let mut page = Page::new();
+        page.add_js_watch("count".to_string());
+        page.receive_response(HttpResponse::builder().body(html.to_string()).build());
+
+        assert_eq!(
+            [(
+                "count".to_string(),
+                Some(RuntimeValue::Number(JsNumber::from(2)))
+            )]
+            .to_vec(),
+            page.js_watches()
+        );
+    }
+
+    #[test]
+    fn test_stylesheet_is_parsed_once() {
+        let html =
+            "<html><head><style>p { color: red; }</style></head><body><p>a</p></body></html>";
+        let mut page = Page::new();
+        for _ in 0..3 {
+            page.receive_response(HttpResponse::builder().body(html.to_string()).build());
+        }
+        assert_eq!(1, page.stylesheet_parse_count());
+
+        let other =
+            "<html><head><style>p { color: blue; }</style></head><body><p>a</p></body></html>";
+        page.receive_response(HttpResponse::builder().body(other.to_string()).build());
+        assert_eq!(2, page.stylesheet_parse_count());
+    }
+
+    #[test]
+    fn test_stylesheet_cache_checks_source_on_hash_collision() {
+        let mut page = Page::new();
+        let red = "p { color: red; }".to_string();
+        let blue = "p { color: blue; }".to_string();
+        let blue_sheet = page.parse_stylesheet(blue.clone());
+
+        // 赤のCSSと同じハッシュで、青のCSSの結果が入っている状態にする
+        page.stylesheet_cache
+            .insert(content_hash(&red), (blue, blue_sheet.clone()));
+        let red_sheet = page.parse_stylesheet(red.clone());
+        assert_ne!(blue_sheet, red_sheet);
+        assert_eq!(2, page.stylesheet_parse_count());
+
+        // 入れ直した赤のCSSの結果は使い回す
+        assert_eq!(red_sheet, page.parse_stylesheet(red));
+        assert_eq!(2, page.stylesheet_parse_count());
+    }
+
+    #[test]
+    fn test_content_hash() {
+        assert_eq!(0xcbf29ce484222325, content_hash(""));
+        assert_eq!(0xaf63dc4c8601ec8c, content_hash("a"));
+        assert_ne!(
+            content_hash("p { color: red; }"),
+            content_hash("p { color: blue; }")
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn test_css_lint_warnings() {
+        let page = create_page(
+            "<html><head><style>p { color: red; color: blue; }</style></head><body><p>a</p></body></html>",
+        );
+        assert_eq!(
+            ["duplicate declaration of \"color\" (rule 0)".to_string()].to_vec(),
+            page.css_lint_warnings()
+                .iter()
+                .map(|w| w.to_string())
+                .collect::<Vec<String>>()
+        );
+    }
+
+    #[test]
+    fn test_js_lint_warnings() {
+        let page = create_page(
+            "<html><head><script>var a = 1;\nmissing(a);</script></head><body></body></html>",
+        );
+        assert_eq!(
+            ["call to undefined function missing (line 2, column 1)".to_string()].to_vec(),
+            page.js_lint_warnings()
+                .iter()
+                .map(|w| w.to_string())
+                .collect::<Vec<String>>()
+        );
+        // 実行時には、呼び出せなかったことがエラーとして報告される
+        assert_eq!(
+            ["ReferenceError: missing is not defined (line 2, column 1)".to_string()].to_vec(),
+            page.script_errors()
+        );
+    }
+
+    #[test]
+    fn test_dead_code_is_not_executed() {
+        let page = create_page(
+            "<html><head><script>function f() {\n  return 1;\n  alert(2);\n}\nalert(f());</script></head><body></body></html>",
+        );
+        assert_eq!(["1".to_string()].to_vec(), page.alert_messages());
+        assert_eq!(
+            ["unreachable code after return (line 3, column 3)".to_string()].to_vec(),
+            page.js_lint_warnings()
+                .iter()
+                .map(|w| w.to_string())
+                .collect::<Vec<String>>()
+        );
+    }
+
+    #[test]
+    fn test_eval_js_expression_in_page_scope() {
+        let page = create_page(
+            "<html><head><script>var greeting = \"hello\";</script></head><body></body></html>",
+        );
+        assert_eq!(
+            Ok(Some(RuntimeValue::StringLiteral("hello world".to_string()))),
+            page.eval_js_expression(r#"greeting + " world""#)
+        );
+        assert_eq!(Ok(None), page.eval_js_expression("var n = 1"));
+        assert_eq!(
+            Ok(Some(RuntimeValue::Number(JsNumber::from(2)))),
+            page.eval_js_expression("n + 1")
+        );
+    }
+
+    #[test]
+    fn test_eval_js_expression_without_page() {
+        assert!(Page::new().eval_js_expression("1").is_err());
+    }
+
+    #[test]
+    fn test_folded_constant_condition_is_removed() {
+        let page = create_page(
+            "<html><head><script>if (1 - 1) { alert(\"never\"); }\nalert(\"a\" + 1);</script></head><body></body></html>",
+        );
+        assert_eq!(["a1".to_string()].to_vec(), page.alert_messages());
+        assert_eq!(
+            ["condition is always false (line 1, column 1)".to_string()].to_vec(),
+            page.js_lint_warnings()
+                .iter()
+                .map(|w| w.to_string())
+                .collect::<Vec<String>>()
+        );
+    }
+
+    #[test]
+    fn test_import_module() {
+        let page = create_site_page(
+            r#"<html><head><script>
+import { foo, x } from "./module.js"
+alert(foo())
+alert(x)
+</script></head><body></body></html>"#,
+        );
+
+        assert_eq!(
+            ["42".to_string(), "5".to_string()].to_vec(),
+            page.alert_messages()
+        );
+        assert!(page.script_errors().is_empty());
+    }
+
+    #[test]
+    fn test_import_missing_export() {
+        let page = create_site_page(
+            r#"<html><head><script>import { bar } from "./module.js"</script></head><body></body></html>"#,
+        );
+
+        assert_eq!(
+            ["SyntaxError: ./module.js does not export bar (line 1, column 1)".to_string()]
+                .to_vec(),
+            page.script_errors()
+        );
+    }
+
+    #[test]
+    fn test_import_missing_module() {
+        let page = create_site_page(
+            r#"<html><head><script>import { foo } from "./missing.js"</script></head><body></body></html>"#,
+        );
+
+        assert_eq!(
+            [
+                "Failed to load module http://example.com:80/missing.js (line 1, column 1)"
+                    .to_string()
+            ]
+            .to_vec(),
+            page.script_errors()
+        );
+    }
+
+    #[test]
+    fn test_script_with_syntax_error_is_not_run() {
+        let page = create_page(
+            r#"<html><head><script>alert("before")
+if (1</script><script>alert("next")</script></head><body></body></html>"#,
+        );
+
+        // 構文エラーのあるスクリプトは、エラーの前の文も実行しない
+        assert_eq!(["next".to_string()].to_vec(), page.alert_messages());
+        assert_eq!(
+            [
+                "SyntaxError: if statement should have `)` but got None (line 2, column 5)"
+                    .to_string()
+            ]
+            .to_vec(),
+            page.script_errors()
+        );
+    }
 }
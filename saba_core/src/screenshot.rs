@@ -0,0 +1,122 @@
+use crate::display_item::DisplayItem;
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::{max, min};
+
+// スクリーンショットの背景色
+static BACKGROUND: u32 = 0xffffff;
+
+// 表示項目を、0xRRGGBBの画素が横width×縦heightに並んだ画像に描画する
+// フォントを使わないため、テキストは文字色で塗りつぶした、テキストと同じ大きさの四角として描く
+pub fn rasterize(items: &[DisplayItem], width: i64, height: i64) -> Vec<u32> {
+    let mut pixels = vec![BACKGROUND; (max(width, 0) * max(height, 0)) as usize];
+
+    for item in items {
+        let color = match item {
+            DisplayItem::Rect { style, .. } => style.background_color().code_u32(),
+            DisplayItem::Text { style, .. } => style.color().code_u32(),
+        };
+
+        // 画像からはみ出す部分は描画しない
+        let (point, size) = item.bounds();
+        let left = max(point.x(), 0);
+        let top = max(point.y(), 0);
+        let right = min(point.x() + size.width(), width);
+        let bottom = min(point.y() + size.height(), height);
+        for y in top..bottom {
+            for x in left..right {
+                pixels[(y * width + x) as usize] = color;
+            }
+        }
+    }
+
+    pixels
+}
+
+// 画素をバイナリ形式のPPM(P6)にする
+// http://netpbm.sourceforge.net/doc/ppm.html
+pub fn encode_ppm(pixels: &[u32], width: i64, height: i64) -> Vec<u8> {
+    let mut ppm = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    for pixel in pixels {
+        ppm.push((pixel >> 16) as u8);
+        ppm.push((pixel >> 8) as u8);
+        ppm.push(*pixel as u8);
+    }
+    ppm
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{CHAR_HEIGHT_WITH_PADDING, CHAR_WIDTH};
+    use crate::renderer::dom::node::{Node, NodeKind};
+    use crate::renderer::layout::computed_style::{Color, ComputedStyle};
+    use crate::renderer::layout::layout_object::{LayoutPoint, LayoutSize};
+    use alloc::rc::Rc;
+    use alloc::string::ToString;
+    use core::cell::RefCell;
+
+    fn create_style(color: &str, background_color: &str) -> ComputedStyle {
+        let node = Rc::new(RefCell::new(Node::new(NodeKind::Text("".to_string()))));
+        let mut style = ComputedStyle::new();
+        style.defaulting(&node, None);
+        style.set_color(Color::from_name(color).expect("color should be supported"));
+        style.set_background_color(
+            Color::from_name(background_color).expect("color should be supported"),
+        );
+        style
+    }
+
+    #[test]
+    fn test_rasterize() {
+        let width = 100;
+        let items = [
+            DisplayItem::Rect {
+                style: create_style("black", "red"),
+                layout_point: LayoutPoint::new(10, 10),
+                layout_size: LayoutSize::new(20, 5),
+            },
+            // 後の項目が上に重なる
+            DisplayItem::Text {
+                text: "ab".to_string(),
+                style: create_style("blue", "white"),
+                layout_point: LayoutPoint::new(20, 12),
+            },
+            // 画像からはみ出す部分は切り取る
+            DisplayItem::Rect {
+                style: create_style("black", "green"),
+                layout_point: LayoutPoint::new(90, -5),
+                layout_size: LayoutSize::new(50, 10),
+            },
+        ];
+        let pixels = rasterize(&items, width, 50);
+        let at = |x: i64, y: i64| pixels[(y * width + x) as usize];
+
+        assert_eq!(100 * 50, pixels.len());
+        assert_eq!(0xffffff, at(0, 0));
+        assert_eq!(0xff0000, at(10, 10));
+        assert_eq!(0xff0000, at(29, 11));
+        assert_eq!(0xffffff, at(30, 10));
+        // テキストは、2文字分の幅と1行の高さの四角になる
+        assert_eq!(0x0000ff, at(20, 12));
+        assert_eq!(0x0000ff, at(20 + CHAR_WIDTH * 2 - 1, 12));
+        assert_eq!(0xffffff, at(20 + CHAR_WIDTH * 2, 12));
+        assert_eq!(0x0000ff, at(20, 12 + CHAR_HEIGHT_WITH_PADDING - 1));
+        assert_eq!(0xffffff, at(20, 12 + CHAR_HEIGHT_WITH_PADDING));
+        assert_eq!(0x008000, at(99, 0));
+        assert_eq!(0x008000, at(90, 4));
+        assert_eq!(0xffffff, at(90, 5));
+    }
+
+    #[test]
+    fn test_encode_ppm() {
+        let ppm = encode_ppm(&[0xff0000, 0x00ff00, 0x0000ff, 0x123456], 2, 2);
+        let header = b"P6\n2 2\n255\n";
+        assert_eq!(header, &ppm[..header.len()]);
+        assert_eq!(
+            [255, 0, 0, 0, 255, 0, 0, 0, 255, 0x12, 0x34, 0x56],
+            ppm[header.len()..]
+        );
+    }
+}
@@ -0,0 +1,58 @@
+// テストで共有する部品
+use crate::error::Error;
+use crate::http::{HttpRequest, HttpResponse, HttpTransport};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+// URLごとに用意したレスポンスを返し、受け取ったリクエストを記録する通信層
+// 用意していないURLへのリクエストはネットワークのエラーにする
+#[derive(Debug)]
+pub struct StubTransport {
+    responses: Vec<(String, HttpResponse)>,
+    requests: RefCell<Vec<HttpRequest>>,
+}
+
+impl StubTransport {
+    pub fn new(responses: Vec<(&str, HttpResponse)>) -> Self {
+        Self {
+            responses: responses
+                .into_iter()
+                .map(|(url, response)| (url.to_string(), response))
+                .collect(),
+            requests: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.requests.borrow().clone()
+    }
+
+    // urlへのリクエストを受け取った回数
+    pub fn request_count(&self, url: &str) -> usize {
+        self.requests
+            .borrow()
+            .iter()
+            .filter(|request| request.url() == url)
+            .count()
+    }
+}
+
+impl HttpTransport for StubTransport {
+    fn request(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+        let url = request.url();
+        self.requests.borrow_mut().push(request);
+        match self.responses.iter().find(|(u, _)| *u == url) {
+            Some((_, response)) => Ok(response.clone()),
+            None => Err(Error::Network {
+                url,
+                message: "not found".to_string(),
+            }),
+        }
+    }
+}
+
+// ボディだけを持つレスポンス
+pub fn body_response(body: &str) -> HttpResponse {
+    HttpResponse::builder().body(body.to_string()).build()
+}
@@ -113,6 +113,69 @@ impl Url {
     pub fn searchpart(&self) -> String {
         self.searchpart.clone()
     }
+
+    // Originヘッダーなどで使うオリジン。デフォルトのポートは省略する
+    pub fn origin(&self) -> String {
+        if self.port == "80" {
+            format!("http://{}", self.host)
+        } else {
+            format!("http://{}:{}", self.host, self.port)
+        }
+    }
+
+    // パース済みのURLを基準に、href属性などの相対URLを絶対URLに変換する
+    pub fn resolve(&self, href: &str) -> String {
+        if href.starts_with("http://") {
+            return href.to_string();
+        }
+
+        if let Some(absolute_path) = href.strip_prefix('/') {
+            return format!("http://{}:{}/{}", self.host, self.port, absolute_path);
+        }
+
+        // "./a.js"は"a.js"と同じ
+        let href = href.strip_prefix("./").unwrap_or(href);
+        let directory = match self.path.rfind('/') {
+            Some(index) => &self.path[..index + 1],
+            None => "",
+        };
+        format!("http://{}:{}/{}{}", self.host, self.port, directory, href)
+    }
+}
+
+// http://しか扱わないので、ホストとポートが同じなら同一オリジンとする
+pub fn is_same_origin(page_url: &Url, request_url: &Url) -> bool {
+    page_url.host() == request_url.host() && page_url.port() == request_url.port()
+}
+
+// Urlはhttp://しか解析できないので、https://のURLからもホストやパスを取り出せるようにする
+fn strip_scheme(url: &str) -> &str {
+    url.strip_prefix("http://")
+        .or_else(|| url.strip_prefix("https://"))
+        .unwrap_or(url)
+}
+
+pub fn host_of(url: &str) -> String {
+    let authority = strip_scheme(url).split(['/', '?']).next().unwrap_or("");
+    authority.split(':').next().unwrap_or("").to_string()
+}
+
+// 先頭の'/'を含むパスを返す。パスがない場合は"/"
+pub fn path_of(url: &str) -> String {
+    let rest = strip_scheme(url);
+    let path = match rest.find('/') {
+        Some(index) => &rest[index..],
+        None => return "/".to_string(),
+    };
+    path.split('?').next().unwrap_or("/").to_string()
+}
+
+// '#'以降のフラグメントを取り除いたURL
+pub fn strip_fragment(url: &str) -> &str {
+    match url.find('#') {
+        Some(index) => &url[..index],
+        None => url,
+    }
 }
 
 #[cfg(test)]
@@ -197,4 +260,69 @@ mod tests {
             Err("Only HTTP scheme is supported. https://example.com:8888/index.html".to_string());
         assert_eq!(expected, Url::new(url).parse());
     }
+
+    #[test]
+    fn test_resolve() {
+        let url = Url::new("http://example.com:8888/dir/index.html".to_string())
+            .parse()
+            .expect("failed to parse url");
+        assert_eq!(
+            "http://example.com:8888/dir/style.css",
+            url.resolve("style.css")
+        );
+        assert_eq!(
+            "http://example.com:8888/style.css",
+            url.resolve("/style.css")
+        );
+        assert_eq!(
+            "http://other.com/style.css",
+            url.resolve("http://other.com/style.css")
+        );
+        assert_eq!(
+            "http://example.com:8888/dir/module.js",
+            url.resolve("./module.js")
+        );
+    }
+
+    #[test]
+    fn test_host_of_and_path_of() {
+        assert_eq!("example.com", host_of("http://example.com:8000/a?b"));
+        assert_eq!("example.com", host_of("https://example.com"));
+        assert_eq!("/a/b.html", path_of("http://example.com:8000/a/b.html?c"));
+        assert_eq!("/", path_of("https://example.com"));
+    }
+
+    #[test]
+    fn test_strip_fragment() {
+        assert_eq!(
+            "http://example.com/a",
+            strip_fragment("http://example.com/a#section")
+        );
+        assert_eq!(
+            "http://example.com/a",
+            strip_fragment("http://example.com/a")
+        );
+    }
+
+    #[test]
+    fn test_same_origin() {
+        let page = Url::new("http://example.com/index.html".to_string())
+            .parse()
+            .expect("failed to parse url");
+        let same = Url::new("http://example.com:80/data".to_string())
+            .parse()
+            .expect("failed to parse url");
+        let other_port = Url::new("http://example.com:8000/data".to_string())
+            .parse()
+            .expect("failed to parse url");
+        let other_host = Url::new("http://other.com/data".to_string())
+            .parse()
+            .expect("failed to parse url");
+
+        assert!(is_same_origin(&page, &same));
+        assert!(!is_same_origin(&page, &other_port));
+        assert!(!is_same_origin(&page, &other_host));
+        assert_eq!("http://example.com", page.origin());
+        assert_eq!("http://example.com:8000", other_port.origin());
+    }
 }
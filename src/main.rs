@@ -3,27 +3,24 @@
 
 extern crate alloc;
 
-use crate::alloc::string::ToString;
-use alloc::format;
 use alloc::rc::Rc;
-use alloc::string::String;
 use core::cell::RefCell;
 use net_wasabi::http::HttpClient;
 use noli::*;
 use saba_core::browser::Browser;
-use saba_core::error::Error;
-use saba_core::http::HttpResponse;
-use saba_core::url::Url;
 use ui_wasabi::app::WasabiUI;
 
 fn main() -> u64 {
     let browser = Browser::new();
+    browser
+        .borrow_mut()
+        .set_transport(Rc::new(HttpClient::new()));
 
     let ui = Rc::new(RefCell::new(WasabiUI::new(browser)));
-    match ui.borrow_mut().start(handle_url) {
+    match ui.borrow_mut().start() {
         Ok(_) => {}
         Err(e) => {
-            println!("browser fails to start {:?}", e);
+            println!("browser fails to start: {}", e);
             return 1;
         }
     }
@@ -31,59 +28,4 @@ fn main() -> u64 {
     0
 }
 
-fn handle_url(url: String) -> Result<HttpResponse, Error> {
-    let parsed_url = match Url::new(url.to_string()).parse() {
-        Ok(url) => url,
-        Err(e) => {
-            return Err(Error::UnexpectedInput(format!(
-                "input html is not supported: {:?}",
-                e
-            )));
-        }
-    };
-
-    let client = HttpClient::new();
-    let response = match client.get(
-        parsed_url.host(),
-        parsed_url.port().parse::<u16>().expect(&format!(
-            "port number should be u16 but got {}",
-            parsed_url.port()
-        )),
-        parsed_url.path(),
-    ) {
-        Ok(res) => {
-            if res.status_code() == 302 {
-                let location = match res.header_value("Location") {
-                    Ok(value) => value,
-                    Err(_) => return Ok(res),
-                };
-                let redirect_parsed_url = Url::new(location);
-
-                let redirect_res = match client.get(
-                    redirect_parsed_url.host(),
-                    redirect_parsed_url.port().parse::<u16>().expect(&format!(
-                        "port number should be u16 but got {}",
-                        redirect_parsed_url.port()
-                    )),
-                    redirect_parsed_url.path(),
-                ) {
-                    Ok(res) => res,
-                    Err(e) => return Err(Error::Network(format!("{:?}", e))),
-                };
-
-                redirect_res
-            } else {
-                res
-            }
-        }
-        Err(e) => {
-            return Err(Error::Network(format!(
-                "failed to get http response: {:?}",
-                e
-            )));
-        }
-    };
-    Ok(response)
-}
-
 entry_point!(main);
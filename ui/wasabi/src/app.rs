@@ -2,6 +2,7 @@ use crate::cursor::Cursor;
 use alloc::format;
 use alloc::rc::Rc;
 use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 use core::cell::RefCell;
 use noli::error::Result as OsResult;
 use noli::prelude::SystemApi;
@@ -11,12 +12,19 @@ use noli::sys::api::MouseEvent;
 use noli::sys::wasabi::Api;
 use noli::window::StringSize;
 use noli::window::Window;
-use saba_core::browser::Browser;
+use saba_core::browser::{Browser, LinkAction, MouseButton};
+use saba_core::clipboard::{paste_into, Clipboard, NoClipboard};
 use saba_core::constants::*;
-use saba_core::display_item::DisplayItem;
+use saba_core::context_menu::ContextMenu;
+use saba_core::display_item::{intersect_rect, DisplayItem};
 use saba_core::error::Error;
-use saba_core::http::HttpResponse;
-use saba_core::renderer::layout::computed_style::{FontSize, TextDecoration};
+use saba_core::file::{FileWriter, NoFileWriter};
+use saba_core::flip::{apply_flip, flip_states, FlipState};
+use saba_core::renderer::dom::form::{FormMethod, FormSubmission};
+use saba_core::renderer::layout::computed_style::{FontSize, FontWeight, TextDecoration};
+use saba_core::renderer::layout::layout_object::{LayoutPoint, LayoutSize};
+use saba_core::renderer::page::ClickAction;
+use saba_core::screenshot::{encode_ppm, rasterize};
 
 #[derive(Debug)]
 pub struct WasabiUI {
@@ -25,6 +33,11 @@ pub struct WasabiUI {
     input_mode: InputMode,
     window: Window,
     cursor: Cursor,
+    flip_states: Vec<FlipState>,
+    clipboard: Rc<dyn Clipboard>,
+    // 右クリックで開いているメニュー
+    context_menu: Option<ContextMenu>,
+    file_writer: Rc<dyn FileWriter>,
 }
 
 impl WasabiUI {
@@ -43,6 +56,12 @@ impl WasabiUI {
             )
             .unwrap(),
             cursor: Cursor::new(),
+            flip_states: Vec::new(),
+            // noliはクリップボードのAPIを提供していないので、貼り付けは何もしない
+            clipboard: Rc::new(NoClipboard),
+            context_menu: None,
+            // noliはファイルシステムのAPIを提供していないので、書き出しは常に失敗する
+            file_writer: Rc::new(NoFileWriter),
         }
     }
 
@@ -87,40 +106,37 @@ impl WasabiUI {
 
     fn setup(&mut self) -> Result<(), Error> {
         if let Err(error) = self.setup_toolbar() {
-            return Err(Error::InvalidUI(format!(
-                "failed to initialize a toolbar with error: {:#?}",
-                error
-            )));
+            return Err(Error::InvalidUI {
+                component: "toolbar".to_string(),
+                message: format!("failed to initialize with error: {:#?}", error),
+            });
         }
         self.window.flush();
         Ok(())
     }
 
-    pub fn start(
-        &mut self,
-        handle_url: fn(String) -> Result<HttpResponse, Error>,
-    ) -> Result<(), Error> {
+    pub fn start(&mut self) -> Result<(), Error> {
         self.setup()?;
 
-        self.run_app(handle_url)?;
+        // 起動時にホームページを開く。開けなくてもブラウザは使えるようにする
+        let home_url = self.browser.borrow().home_url();
+        if let Err(e) = self.start_navigation(home_url) {
+            println!("failed to open the home page: {}", e);
+        }
+
+        self.run_app()?;
 
         Ok(())
     }
 
-    fn run_app(
-        &mut self,
-        handle_url: fn(String) -> Result<HttpResponse, Error>,
-    ) -> Result<(), Error> {
+    fn run_app(&mut self) -> Result<(), Error> {
         loop {
-            self.handle_mouse_input(handle_url)?;
-            self.handle_key_input(handle_url)?;
+            self.handle_mouse_input()?;
+            self.handle_key_input()?;
         }
     }
 
-    fn handle_mouse_input(
-        &mut self,
-        handle_url: fn(String) -> Result<HttpResponse, Error>,
-    ) -> Result<(), Error> {
+    fn handle_mouse_input(&mut self) -> Result<(), Error> {
         if let Some(MouseEvent { button, position }) = Api::get_mouse_cursor_info() {
             self.window.flush_area(self.cursor.rect());
             self.cursor.set_position(position.x, position.y);
@@ -143,6 +159,27 @@ impl WasabiUI {
                     return Ok(());
                 }
 
+                let position_in_content_area = (
+                    relative_pos.0,
+                    relative_pos.1 - TITLE_BAR_HEIGHT - TOOLBAR_HEIGHT,
+                );
+
+                // メニューが開いているときのクリックは、項目を選ぶか、メニューを閉じるだけにする
+                if let Some(menu) = self.context_menu.take() {
+                    self.close_context_menu(&menu)?;
+                    if let Some(item) = menu.item_at(position_in_content_area) {
+                        let result = menu.select(
+                            item,
+                            &mut self.browser.borrow_mut(),
+                            self.clipboard.as_ref(),
+                        );
+                        if let Err(e) = result {
+                            println!("{}: {}", item.label(), e);
+                        }
+                    }
+                    return Ok(());
+                }
+
                 if relative_pos.1 < TOOLBAR_HEIGHT + TITLE_BAR_HEIGHT
                     && relative_pos.1 >= TITLE_BAR_HEIGHT
                 {
@@ -155,17 +192,51 @@ impl WasabiUI {
 
                 self.input_mode = InputMode::Normal;
 
-                let position_in_content_area = (
-                    relative_pos.0,
-                    relative_pos.1 - TITLE_BAR_HEIGHT - TOOLBAR_HEIGHT,
-                );
                 let page = self.browser.borrow().current_page();
-                let next_destination = page.borrow_mut().clicked(position_in_content_area);
+                let action = page.borrow_mut().clicked(position_in_content_area);
+                // selectの選択肢の一覧を開閉したときなど、ページの表示だけが変わることがある
+                if action.is_none() {
+                    return self.update_ui();
+                }
+
+                if let Some(ClickAction::Submit(submission)) = action {
+                    return self.submit_form(submission);
+                }
 
-                if let Some(url) = next_destination {
-                    self.input_url = url.clone();
-                    self.update_address_bar()?;
-                    self.start_navigation(handle_url, url)?;
+                if let Some(ClickAction::Link(href)) = action {
+                    if button.r() {
+                        let url = self.browser.borrow().resolve_link(&href);
+                        return self.open_context_menu(ContextMenu::for_link(
+                            url,
+                            position_in_content_area,
+                        ));
+                    }
+
+                    let button = if button.c() {
+                        MouseButton::Middle
+                    } else {
+                        MouseButton::Left
+                    };
+                    let action = self.browser.borrow().link_action(&href, button);
+                    match action {
+                        LinkAction::Navigate(url) => {
+                            self.input_url = url.clone();
+                            self.update_address_bar()?;
+                            self.start_navigation(url)?;
+                        }
+                        LinkAction::OpenInBackgroundTab(url) => {
+                            // タブの一覧はまだ描画しないので、開いたことだけを表示する
+                            // 読み込みに失敗しても、表示中のタブはそのまま使い続ける
+                            let result = self
+                                .browser
+                                .borrow_mut()
+                                .open_in_background_tab(url.clone());
+                            match result {
+                                Ok(()) => println!("opened {} in a new tab", url),
+                                Err(e) => println!("failed to open {} in a new tab: {}", url, e),
+                            }
+                        }
+                    }
                 }
             }
         }
@@ -173,20 +244,110 @@ impl WasabiUI {
         Ok(())
     }
 
-    fn handle_key_input(
-        &mut self,
-        handle_url: fn(String) -> Result<HttpResponse, Error>,
-    ) -> Result<(), Error> {
+    // 表示中のページのコンテンツ領域を、PPM形式の画像にする
+    pub fn screenshot_to_ppm(&self) -> Vec<u8> {
+        let page = self.browser.borrow().current_page();
+        let pixels = rasterize(
+            page.borrow().display_items(),
+            CONTENT_AREA_WIDTH,
+            CONTENT_AREA_HEIGHT,
+        );
+        encode_ppm(&pixels, CONTENT_AREA_WIDTH, CONTENT_AREA_HEIGHT)
+    }
+
+    pub fn save_screenshot(&self, path: &str) -> Result<(), Error> {
+        self.file_writer.write(path, &self.screenshot_to_ppm())
+    }
+
+    fn submit_form(&mut self, submission: FormSubmission) -> Result<(), Error> {
+        // HttpRequestにはメソッドとボディがないので、POSTではまだ送信できない
+        if submission.method() == FormMethod::Post {
+            println!("form: POST to {} is not supported yet", submission.action());
+            return Ok(());
+        }
+        let url = submission.url();
+        self.input_url = url.clone();
+        self.update_address_bar()?;
+        self.start_navigation(url)
+    }
+
+    fn handle_key_input(&mut self) -> Result<(), Error> {
         match self.input_mode {
+            // ページ内の入力欄にフォーカスしているときは、キー入力をその入力欄に渡す
+            InputMode::Normal
+                if self
+                    .browser
+                    .borrow()
+                    .current_page()
+                    .borrow()
+                    .focused_input()
+                    .is_some() =>
+            {
+                if let Some(c) = Api::read_key() {
+                    let page = self.browser.borrow().current_page();
+                    let submission = page.borrow_mut().key_pressed(c);
+                    match submission {
+                        Some(submission) => self.submit_form(submission)?,
+                        None => self.update_ui()?,
+                    }
+                }
+            }
             InputMode::Normal => {
-                // InputModeがNormalのとき、キー入力を無視する
-                let _ = Api::read_key();
+                // InputModeがNormalのとき、開発者コンソールを開くキーと拡大・縮小のキー以外は無視する
+                // noliのキー入力には修飾キーの状態がないので、Ctrlなしの+と-で拡大・縮小する
+                match Api::read_key() {
+                    Some('`') => {
+                        self.input_url = String::new();
+                        self.input_mode = InputMode::Console;
+                        self.update_address_bar()?;
+                    }
+                    Some('+') | Some('=') => {
+                        self.browser.borrow_mut().zoom_in();
+                        self.update_ui()?;
+                    }
+                    Some('-') => {
+                        self.browser.borrow_mut().zoom_out();
+                        self.update_ui()?;
+                    }
+                    Some('0') => {
+                        self.browser.borrow_mut().set_zoom(100);
+                        self.update_ui()?;
+                    }
+                    _ => {}
+                }
+            }
+            InputMode::Console => {
+                if let Some(c) = Api::read_key() {
+                    if c == 0x0A as char {
+                        // Enterキーが押されたので、入力された式を表示中のページで評価する
+                        let expr = core::mem::take(&mut self.input_url);
+                        let page = self.browser.borrow().current_page();
+                        println!("js> {}", expr);
+                        match page.borrow().eval_js_expression(&expr) {
+                            Ok(Some(value)) => println!("{}", value),
+                            Ok(None) => println!("undefined"),
+                            Err(e) => println!("error: {}", e),
+                        }
+                        self.update_address_bar()?;
+                    } else if c == 0x1B as char {
+                        // Escキーが押されたのでコンソールを閉じる
+                        self.input_url = String::new();
+                        self.input_mode = InputMode::Normal;
+                        self.update_address_bar()?;
+                    } else if c == 0x7F as char || c == 0x08 as char {
+                        self.input_url.pop();
+                        self.update_address_bar()?;
+                    } else {
+                        self.input_url.push(c);
+                        self.update_address_bar()?;
+                    }
+                }
             }
             InputMode::Editing => {
                 if let Some(c) = Api::read_key() {
                     if c == 0x0A as char {
                         // Enterキーが押されたのでナビゲーションを開始
-                        self.start_navigation(handle_url, self.input_url.clone())?;
+                        self.start_navigation(self.input_url.clone())?;
 
                         self.input_url = String::new();
                         self.input_mode = InputMode::Normal;
@@ -194,6 +355,11 @@ impl WasabiUI {
                         // DelキーまたはBackspaceキーが押されたので最後の文字を削除
                         self.input_url.pop();
                         self.update_address_bar()?;
+                    } else if c == 0x16 as char {
+                        // Ctrl+Vが押されたのでクリップボードのテキストを貼り付ける
+                        if paste_into(&mut self.input_url, self.clipboard.as_ref()) {
+                            self.update_address_bar()?;
+                        }
                     } else {
                         self.input_url.push(c);
                         self.update_address_bar()?;
@@ -206,14 +372,21 @@ impl WasabiUI {
     }
 
     fn update_address_bar(&mut self) -> Result<(), Error> {
+        // 開発者コンソールの入力中は、アドレスバーを入力欄として使う
+        let text = match self.input_mode {
+            InputMode::Console => format!("js> {}", self.input_url),
+            _ => self.input_url.clone(),
+        };
+
         if self
             .window
             .fill_rect(WHITE, 72, 4, WINDOW_WIDTH - 76, ADDRESSBAR_HEIGHT - 2)
             .is_err()
         {
-            return Err(Error::InvalidUI(
-                "failed to clear an address bar".to_string(),
-            ));
+            return Err(Error::InvalidUI {
+                component: "address bar".to_string(),
+                message: "failed to clear".to_string(),
+            });
         }
 
         if self
@@ -222,13 +395,16 @@ impl WasabiUI {
                 BLACK,
                 74,
                 6,
-                &self.input_url,
+                &text,
                 StringSize::Medium,
                 /*underline=*/ false,
             )
             .is_err()
         {
-            return Err(Error::InvalidUI("failed to update address bar".to_string()));
+            return Err(Error::InvalidUI {
+                component: "address bar".to_string(),
+                message: "failed to update".to_string(),
+            });
         }
 
         // アドレスバーの部分の画面を更新する
@@ -251,9 +427,10 @@ impl WasabiUI {
             .fill_rect(WHITE, 72, 4, WINDOW_WIDTH - 76, 2)
             .is_err()
         {
-            return Err(Error::InvalidUI(
-                "failed to clear an address bar".to_string(),
-            ));
+            return Err(Error::InvalidUI {
+                component: "address bar".to_string(),
+                message: "failed to clear".to_string(),
+            });
         }
 
         self.window.flush_area(
@@ -269,103 +446,408 @@ impl WasabiUI {
         Ok(())
     }
 
-    fn start_navigation(
-        &mut self,
-        handle_url: fn(String) -> Result<HttpResponse, Error>,
-        destination: String,
-    ) -> Result<(), Error> {
-        self.clear_content_area()?;
+    fn start_navigation(&mut self, destination: String) -> Result<(), Error> {
+        // 遷移前の表示項目の位置を記録しておく(First)
+        let first = self
+            .browser
+            .borrow()
+            .current_page()
+            .borrow()
+            .display_items()
+            .to_vec();
+
+        // 読み込めなかったときは、理由を説明するページを代わりに表示する
+        let result = self.browser.borrow_mut().navigate(destination.clone());
+        if let Err(e) = result {
+            println!("failed to load {}: {}", destination, e);
+            self.browser.borrow_mut().show_error_page(destination, &e);
+        }
 
-        match handle_url(destination) {
-            Ok(response) => {
-                let page = self.browser.borrow().current_page();
-                page.borrow_mut().receive_response(response);
-            }
-            Err(e) => {
-                return Err(e);
+        // 遷移後の位置(Last)と比べて、動いたテキストを遷移前の位置から動かす
+        let page = self.browser.borrow().current_page();
+        self.flip_states = flip_states(&first, page.borrow().display_items(), FLIP_FRAMES);
+
+        for message in page.borrow().console_messages() {
+            println!("console: {}", message);
+        }
+        for warning in page.borrow().parse_warnings() {
+            println!("html: {}", warning);
+        }
+        for pause in page.borrow().js_pause_events() {
+            println!("js: {}", pause);
+        }
+        for (watch, value) in page.borrow().js_watches() {
+            match value {
+                Some(value) => println!("js: watch {} = {}", watch, value),
+                None => println!("js: watch {} = undefined", watch),
             }
         }
+        for warning in page.borrow().css_lint_warnings() {
+            println!("css: {}", warning);
+        }
+        for warning in page.borrow().js_lint_warnings() {
+            println!("js: {}", warning);
+        }
+        for violation in page.borrow().csp_violations() {
+            println!("{}", violation);
+        }
+        for error in page.borrow().network_errors() {
+            println!("{}", error);
+        }
+        for error in page.borrow().script_errors() {
+            println!("{}", error);
+        }
+        let metrics = page.borrow().layout_metrics();
+        println!(
+            "layout: {} nodes, depth {}, {} paint items",
+            metrics.node_count(),
+            metrics.max_depth(),
+            metrics.paint_item_count()
+        );
+        for (kind, steps) in page.borrow().js_profile_report() {
+            println!("js profile: {} {}", kind, steps);
+        }
+        for stat in page.borrow().resource_stats() {
+            println!(
+                "{} {} {} {}B ({}B raw)",
+                stat.status_code(),
+                stat.url(),
+                stat.content_type(),
+                stat.decompressed_size(),
+                stat.raw_size()
+            );
+        }
+        if let Some(options) = page.borrow().x_frame_options() {
+            println!(
+                "warning: X-Frame-Options {:?} is ignored because frames are not supported",
+                options
+            );
+        }
 
         self.update_ui()?;
 
+        let page = self.browser.borrow().current_page();
+        let alert_messages = page.borrow().alert_messages();
+        page.borrow_mut().clear_alert_messages();
+        for message in alert_messages {
+            self.show_alert(&message)?;
+        }
+
+        Ok(())
+    }
+
+    fn open_context_menu(&mut self, menu: ContextMenu) -> Result<(), Error> {
+        if self.draw_context_menu(&menu).is_err() {
+            return Err(Error::InvalidUI {
+                component: "context menu".to_string(),
+                message: "failed to draw".to_string(),
+            });
+        }
+        self.window.flush();
+        self.context_menu = Some(menu);
+        Ok(())
+    }
+
+    fn draw_context_menu(&mut self, menu: &ContextMenu) -> OsResult<()> {
+        let (x, y) = menu.position();
+        let (width, height) = menu.size();
+
+        // 枠線と背景を描画
+        self.window.fill_rect(
+            DARKGREY,
+            x + WINDOW_PADDING,
+            y + WINDOW_PADDING + TOOLBAR_HEIGHT,
+            width,
+            height,
+        )?;
+        self.window.fill_rect(
+            WHITE,
+            x + WINDOW_PADDING + 1,
+            y + WINDOW_PADDING + TOOLBAR_HEIGHT + 1,
+            width - 2,
+            height - 2,
+        )?;
+
+        for (i, item) in menu.items().iter().enumerate() {
+            let (item_x, item_y) = menu.item_position(i);
+            self.window.draw_string(
+                BLACK,
+                item_x + WINDOW_PADDING + 4,
+                item_y + WINDOW_PADDING + TOOLBAR_HEIGHT + 2,
+                item.label(),
+                StringSize::Medium,
+                /*underline=*/ false,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    // メニューが表示されていた範囲だけページを描画し直す
+    fn close_context_menu(&mut self, menu: &ContextMenu) -> Result<(), Error> {
+        let (x, y) = menu.position();
+        let (width, height) = menu.size();
+        self.repaint_area(LayoutPoint::new(x, y), LayoutSize::new(width, height))
+    }
+
+    fn alert_position(&self) -> (i64, i64) {
+        (
+            (WINDOW_WIDTH - ALERT_WIDTH) / 2,
+            TOOLBAR_HEIGHT + (CONTENT_AREA_HEIGHT - ALERT_HEIGHT) / 2,
+        )
+    }
+
+    fn alert_button_position(&self) -> (i64, i64) {
+        let (x, y) = self.alert_position();
+        (
+            x + ALERT_WIDTH - ALERT_BUTTON_WIDTH - 10,
+            y + ALERT_HEIGHT - ALERT_BUTTON_HEIGHT - 10,
+        )
+    }
+
+    fn show_alert(&mut self, message: &str) -> Result<(), Error> {
+        if self.draw_alert(message).is_err() {
+            return Err(Error::InvalidUI {
+                component: "alert".to_string(),
+                message: "failed to draw".to_string(),
+            });
+        }
+        self.window.flush();
+
+        // OKボタンがクリックされるかEnterキーが押されるまで待つ
+        loop {
+            if let Some(c) = Api::read_key() {
+                if c == 0x0A as char {
+                    break;
+                }
+            }
+
+            if let Some(MouseEvent { button, position }) = Api::get_mouse_cursor_info() {
+                self.window.flush_area(self.cursor.rect());
+                self.cursor.set_position(position.x, position.y);
+                self.window.flush_area(self.cursor.rect());
+                self.cursor.flush();
+
+                if button.l() {
+                    let relative_pos = (
+                        position.x - WINDOW_INIT_X_POS,
+                        position.y - WINDOW_INIT_Y_POS - TITLE_BAR_HEIGHT,
+                    );
+                    let (button_x, button_y) = self.alert_button_position();
+                    if button_x <= relative_pos.0
+                        && relative_pos.0 <= button_x + ALERT_BUTTON_WIDTH
+                        && button_y <= relative_pos.1
+                        && relative_pos.1 <= button_y + ALERT_BUTTON_HEIGHT
+                    {
+                        break;
+                    }
+                }
+            }
+        }
+
+        // アラートが表示されていた範囲だけページを描画し直す
+        let (x, y) = self.alert_position();
+        self.repaint_area(
+            LayoutPoint::new(x - WINDOW_PADDING, y - TOOLBAR_HEIGHT - WINDOW_PADDING),
+            LayoutSize::new(ALERT_WIDTH, ALERT_HEIGHT),
+        )
+    }
+
+    fn draw_alert(&mut self, message: &str) -> OsResult<()> {
+        let (x, y) = self.alert_position();
+
+        // 枠線と背景を描画
+        self.window
+            .fill_rect(DARKGREY, x, y, ALERT_WIDTH, ALERT_HEIGHT)?;
+        self.window
+            .fill_rect(WHITE, x + 1, y + 1, ALERT_WIDTH - 2, ALERT_HEIGHT - 2)?;
+
+        // 1行に収まらないメッセージは切り詰める
+        let max_chars = ((ALERT_WIDTH - 20) / CHAR_WIDTH) as usize;
+        let text: String = message.chars().take(max_chars).collect();
+        self.window.draw_string(
+            BLACK,
+            x + 10,
+            y + 10,
+            &text,
+            StringSize::Medium,
+            /*underline=*/ false,
+        )?;
+
+        let (button_x, button_y) = self.alert_button_position();
+        self.window.fill_rect(
+            LIGHTGREY,
+            button_x,
+            button_y,
+            ALERT_BUTTON_WIDTH,
+            ALERT_BUTTON_HEIGHT,
+        )?;
+        self.window.draw_string(
+            BLACK,
+            button_x + 12,
+            button_y + 2,
+            "OK",
+            StringSize::Medium,
+            /*underline=*/ false,
+        )?;
+
         Ok(())
     }
 
-    fn clear_content_area(&mut self) -> Result<(), Error> {
+    // 前回の描画から変化した範囲だけを描画し直す
+    fn update_ui(&mut self) -> Result<(), Error> {
+        let page = self.browser.borrow().current_page();
+        let dirty_rect = page.borrow().dirty_rect();
+        page.borrow_mut().clear_dirty_rect();
+
+        if !self.flip_states.is_empty() {
+            return self.play_flip();
+        }
+
+        match dirty_rect {
+            Some((point, size)) => self.repaint_area(point, size),
+            None => Ok(()),
+        }
+    }
+
+    // FLIPアニメーションの各フレームを描画する(Play)
+    // タイマーがないので、フレームの間隔は描画にかかる時間に任せる
+    fn play_flip(&mut self) -> Result<(), Error> {
+        let page = self.browser.borrow().current_page();
+        let items = page.borrow().display_items().to_vec();
+        let content_area = (
+            LayoutPoint::new(0, 0),
+            LayoutSize::new(CONTENT_AREA_WIDTH, CONTENT_AREA_HEIGHT),
+        );
+
+        while !self.flip_states.is_empty() {
+            let frame_items = apply_flip(&items, &self.flip_states);
+            self.paint_items(&frame_items, content_area.0, content_area.1)?;
+
+            for state in self.flip_states.iter_mut() {
+                state.advance();
+            }
+            self.flip_states.retain(|state| !state.is_finished());
+        }
+
+        // 最後に、ずれのない本来の位置で描画する
+        self.paint_items(&items, content_area.0, content_area.1)
+    }
+
+    fn repaint_area(&mut self, point: LayoutPoint, size: LayoutSize) -> Result<(), Error> {
+        let page = self.browser.borrow().current_page();
+        let page = page.borrow();
+        self.paint_items(page.display_items(), point, size)
+    }
+
+    fn paint_items(
+        &mut self,
+        items: &[DisplayItem],
+        point: LayoutPoint,
+        size: LayoutSize,
+    ) -> Result<(), Error> {
+        let area = match intersect_rect(
+            (point, size),
+            (
+                LayoutPoint::new(0, 0),
+                LayoutSize::new(CONTENT_AREA_WIDTH, CONTENT_AREA_HEIGHT),
+            ),
+        ) {
+            Some(area) => area,
+            None => return Ok(()),
+        };
+        let (point, size) = area;
+
         if self
             .window
             .fill_rect(
                 WHITE,
-                0,
-                TOOLBAR_HEIGHT + 2,
-                CONTENT_AREA_WIDTH,
-                CONTENT_AREA_HEIGHT - 2,
+                point.x() + WINDOW_PADDING,
+                point.y() + WINDOW_PADDING + TOOLBAR_HEIGHT,
+                size.width(),
+                size.height(),
             )
             .is_err()
         {
-            return Err(Error::InvalidUI(
-                "failed to clear a content area".to_string(),
-            ));
+            return Err(Error::InvalidUI {
+                component: "content area".to_string(),
+                message: "failed to clear".to_string(),
+            });
         }
 
-        self.window.flush();
-
-        Ok(())
-    }
-
-    fn update_ui(&mut self) -> Result<(), Error> {
-        let display_items = self
-            .browser
-            .borrow()
-            .current_page()
-            .borrow()
-            .display_items();
+        for item in items {
+            // 再描画する範囲と重ならない項目は描画しない
+            let bounds = match intersect_rect(item.bounds(), area) {
+                Some(bounds) => bounds,
+                None => continue,
+            };
 
-        for item in display_items {
             match item {
                 DisplayItem::Text {
                     text,
                     style,
                     layout_point,
                 } => {
-                    if self
-                        .window
-                        .draw_string(
-                            style.color().code_u32(),
-                            layout_point.x() + WINDOW_PADDING,
-                            layout_point.y() + WINDOW_PADDING + TOOLBAR_HEIGHT,
-                            &text,
-                            convert_font_size(style.font_size()),
-                            style.text_decoration() == TextDecoration::Underline,
-                        )
-                        .is_err()
-                    {
-                        return Err(Error::InvalidUI("failed to draw a string".to_string()));
+                    // 太字のフォントがないので、1pxずらして重ね書きする
+                    // イタリック体のフォントもなく、文字を傾けて描画できないため、
+                    // font-style: italicのテキストは通常の字体で描画する
+                    let strokes = match style.font_weight() {
+                        FontWeight::Bold => 2,
+                        FontWeight::Normal => 1,
+                    };
+                    for dx in 0..strokes {
+                        if self
+                            .window
+                            .draw_string(
+                                style.color().code_u32(),
+                                layout_point.x() + WINDOW_PADDING + dx,
+                                layout_point.y() + WINDOW_PADDING + TOOLBAR_HEIGHT,
+                                text,
+                                convert_font_size(style.font_size()),
+                                style.text_decoration() == TextDecoration::Underline,
+                            )
+                            .is_err()
+                        {
+                            return Err(Error::InvalidUI {
+                                component: "content area".to_string(),
+                                message: "failed to draw a string".to_string(),
+                            });
+                        }
                     }
                 }
-                DisplayItem::Rect {
-                    style,
-                    layout_point,
-                    layout_size,
-                } => {
+                DisplayItem::Rect { style, .. } => {
+                    // 範囲外を塗りつぶさないよう、重なっている部分だけを描画する
+                    let (rect_point, rect_size) = bounds;
                     if self
                         .window
                         .fill_rect(
                             style.background_color().code_u32(),
-                            layout_point.x() + WINDOW_PADDING,
-                            layout_point.y() + WINDOW_PADDING + TOOLBAR_HEIGHT,
-                            layout_size.width(),
-                            layout_size.height(),
+                            rect_point.x() + WINDOW_PADDING,
+                            rect_point.y() + WINDOW_PADDING + TOOLBAR_HEIGHT,
+                            rect_size.width(),
+                            rect_size.height(),
                         )
                         .is_err()
                     {
-                        return Err(Error::InvalidUI("failed to draw a rect".to_string()));
+                        return Err(Error::InvalidUI {
+                            component: "content area".to_string(),
+                            message: "failed to draw a rect".to_string(),
+                        });
                     }
                 }
             }
         }
 
-        self.window.flush();
+        self.window.flush_area(
+            Rect::new(
+                WINDOW_INIT_X_POS + WINDOW_PADDING + point.x(),
+                WINDOW_INIT_Y_POS + TITLE_BAR_HEIGHT + TOOLBAR_HEIGHT + WINDOW_PADDING + point.y(),
+                size.width(),
+                size.height(),
+            )
+            .expect("failed to create a rect for the content area"),
+        );
+
         Ok(())
     }
 }
@@ -374,6 +856,8 @@ impl WasabiUI {
 enum InputMode {
     Normal,
     Editing,
+    // 開発者コンソールに式を入力している
+    Console,
 }
 
 fn convert_font_size(size: FontSize) -> StringSize {